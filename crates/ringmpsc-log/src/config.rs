@@ -0,0 +1,123 @@
+//! Configuration for the logging backend.
+
+use log::LevelFilter;
+
+/// What a logging thread does when its ring is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// Drop the new record and count it (INV-LOG-03). Logging never blocks.
+    #[default]
+    DropNewest,
+    /// Spin, yield, then park until the writer thread frees space. No record
+    /// is lost while the logger is running, at the cost of stalling the caller.
+    Block,
+}
+
+/// Configuration for [`RingLogger`](crate::RingLogger).
+#[derive(Debug, Clone)]
+pub struct LogConfig {
+    /// Most verbose level that is recorded.
+    ///
+    /// Default: `LevelFilter::Info`
+    pub level: LevelFilter,
+
+    /// Per-thread ring size as a power of 2.
+    ///
+    /// Bounds memory: at most `(max_threads + 1) << ring_bits` formatted
+    /// records are buffered at any time (INV-LOG-02).
+    ///
+    /// Default: 12 (4096 records per thread)
+    pub ring_bits: u8,
+
+    /// Number of threads that get a dedicated ring.
+    ///
+    /// A thread's ring is handed back when it exits and reused by the next
+    /// thread that logs, so this bounds the threads logging *at the same
+    /// time*. Threads beyond the limit share one fallback ring behind a mutex.
+    ///
+    /// Default: 32
+    pub max_threads: usize,
+
+    /// Behavior when a thread's ring is full.
+    ///
+    /// Default: [`OverflowPolicy::DropNewest`]
+    pub overflow: OverflowPolicy,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            level: LevelFilter::Info,
+            ring_bits: 12,
+            max_threads: 32,
+            overflow: OverflowPolicy::DropNewest,
+        }
+    }
+}
+
+impl LogConfig {
+    /// Sets the most verbose level that is recorded.
+    #[must_use]
+    pub fn with_level(mut self, level: LevelFilter) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Sets the per-thread ring size as a power of 2.
+    #[must_use]
+    pub fn with_ring_bits(mut self, ring_bits: u8) -> Self {
+        self.ring_bits = ring_bits;
+        self
+    }
+
+    /// Sets the number of threads that get a dedicated ring.
+    #[must_use]
+    pub fn with_max_threads(mut self, max_threads: usize) -> Self {
+        self.max_threads = max_threads;
+        self
+    }
+
+    /// Sets the overflow policy.
+    #[must_use]
+    pub fn with_overflow(mut self, overflow: OverflowPolicy) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Checks the bounds imposed by the underlying ringmpsc channel.
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        if self.ring_bits == 0 || self.ring_bits > 20 {
+            return Err(format!(
+                "ring_bits must be between 1 and 20, got {}",
+                self.ring_bits
+            ));
+        }
+        // One extra ring is reserved for the shared fallback producer.
+        if self.max_threads == 0 || self.max_threads > 127 {
+            return Err(format!(
+                "max_threads must be between 1 and 127, got {}",
+                self.max_threads
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config_is_valid() {
+        assert!(LogConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_rejects_out_of_range() {
+        assert!(LogConfig::default().with_ring_bits(0).validate().is_err());
+        assert!(LogConfig::default().with_ring_bits(21).validate().is_err());
+        assert!(LogConfig::default().with_max_threads(0).validate().is_err());
+        assert!(LogConfig::default().with_max_threads(128).validate().is_err());
+        assert!(LogConfig::default().with_max_threads(127).validate().is_ok());
+    }
+}
@@ -0,0 +1,22 @@
+//! Error types for ringmpsc-log.
+
+use thiserror::Error;
+
+/// Errors that can occur while setting up the logging backend.
+///
+/// Errors that happen while writing records are not surfaced to the logging
+/// thread; they are counted in [`LogStats::write_errors`](crate::LogStats).
+#[derive(Debug, Error)]
+pub enum LogError {
+    /// The configuration is outside the bounds supported by ringmpsc.
+    #[error("invalid configuration: {0}")]
+    InvalidConfig(String),
+
+    /// Opening the sink or spawning the writer thread failed.
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// A global logger was already installed.
+    #[error("failed to install logger: {0}")]
+    SetLogger(#[from] log::SetLoggerError),
+}
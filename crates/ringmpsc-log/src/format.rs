@@ -0,0 +1,96 @@
+//! Line formatting, performed on the logging thread.
+//!
+//! Every record becomes one self-contained line:
+//!
+//! ```text
+//! 2026-01-02T03:04:05.678Z INFO  [worker-1] my_crate::module: message
+//! ```
+
+use std::fmt::{self, Write};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Formats a complete record line, including the trailing newline.
+pub(crate) fn format_line(
+    now: SystemTime,
+    level: &str,
+    target: &str,
+    message: fmt::Arguments<'_>,
+) -> String {
+    let mut line = String::with_capacity(64 + target.len());
+    write_timestamp(&mut line, now);
+
+    // Writing into a String cannot fail.
+    let current = thread::current();
+    let _ = match current.name() {
+        Some(name) => write!(line, " {level:<5} [{name}] {target}: "),
+        None => write!(line, " {level:<5} [{:?}] {target}: ", current.id()),
+    };
+    let _ = line.write_fmt(message);
+    line.push('\n');
+    line
+}
+
+/// Appends `now` as an RFC 3339 UTC timestamp with millisecond precision.
+///
+/// Times before the Unix epoch are clamped to the epoch.
+fn write_timestamp(out: &mut String, now: SystemTime) {
+    let since_epoch = now.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let secs_of_day = secs % 86_400;
+
+    let _ = write!(
+        out,
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        secs_of_day / 3600,
+        (secs_of_day / 60) % 60,
+        secs_of_day % 60,
+        since_epoch.subsec_millis(),
+    );
+}
+
+/// Converts days since 1970-01-01 to a proleptic Gregorian `(year, month, day)`.
+///
+/// Howard Hinnant's `civil_from_days` algorithm.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_civil_from_days() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(59), (1970, 3, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(20_454), (2026, 1, 1));
+    }
+
+    #[test]
+    fn test_format_line() {
+        let now = UNIX_EPOCH + Duration::from_millis(1_767_322_245_678);
+        let line = thread::Builder::new()
+            .name("worker-1".into())
+            .spawn(move || format_line(now, "INFO", "app::db", format_args!("opened {}", 3)))
+            .unwrap()
+            .join()
+            .unwrap();
+        assert_eq!(
+            line,
+            "2026-01-02T02:50:45.678Z INFO  [worker-1] app::db: opened 3\n"
+        );
+    }
+}
@@ -0,0 +1,103 @@
+//! Debug assertion macros for logging backend invariants.
+//!
+//! These macros provide runtime checks for the invariants documented in `spec.md`.
+//! They are only active in debug builds (`#[cfg(debug_assertions)]`), so there is
+//! zero overhead in release builds.
+
+// =============================================================================
+// INV-LOG-01: Per-Thread Ordering
+// =============================================================================
+
+// **Invariant**: records from one thread are written in emission order.
+//
+// Structural: a thread resolves its ring once (own ring, or the shared fallback
+// ring if none was free) and caches that choice in its thread-local, so all of
+// its records go through a single SPSC ring (ringmpsc INV-CH-03). A ring is
+// only reused after the thread that held it has exited.
+
+// =============================================================================
+// INV-LOG-02: Bounded Memory
+// =============================================================================
+
+// **Invariant**: buffered records ≤ (max_threads + 1) << ring_bits
+//
+// Structural: rings are allocated once at construction and never grow; a full
+// ring triggers the overflow policy instead of an allocation.
+
+// =============================================================================
+// INV-LOG-03: Drop Accounting
+// =============================================================================
+
+/// Assert that the in-band drop report never runs ahead of the drop counter.
+///
+/// **Invariant**: `reported_drops ≤ dropped` (both monotonic)
+///
+/// Used in: writer thread, before emitting a "records dropped" notice
+macro_rules! debug_assert_drop_report {
+    ($reported:expr, $dropped:expr) => {
+        debug_assert!(
+            $reported <= $dropped,
+            "INV-LOG-03 violated: reported {} drops but only {} recorded",
+            $reported,
+            $dropped
+        )
+    };
+}
+
+// =============================================================================
+// INV-LOG-04: Flush-on-Shutdown
+// =============================================================================
+
+/// Assert that the writer's final drain runs only after the channel is closed.
+///
+/// **Invariant**: `shutdown() → channel closed → drain until empty → sink flushed`
+///
+/// Once every ring is closed no new reservation can succeed, so draining until
+/// empty collects every record whose `log()` call returned before shutdown.
+///
+/// Used in: writer thread, after the final drain
+macro_rules! debug_assert_drained_on_shutdown {
+    ($channel_closed:expr) => {
+        debug_assert!(
+            $channel_closed,
+            "INV-LOG-04 violated: writer finished its final drain before the channel was closed"
+        )
+    };
+}
+
+// =============================================================================
+// INV-LOG-05: Flush Completion
+// =============================================================================
+
+/// Assert that the writer never acknowledges a flush that was not requested.
+///
+/// **Invariant**: `completed ≤ requested`
+///
+/// Used in: writer thread, when acknowledging `Log::flush()`
+macro_rules! debug_assert_flush_progress {
+    ($completed:expr, $requested:expr) => {
+        debug_assert!(
+            $completed <= $requested,
+            "INV-LOG-05 violated: flush epoch {} acknowledged but only {} requested",
+            $completed,
+            $requested
+        )
+    };
+}
+
+// =============================================================================
+// External: ringmpsc INV-WAKE-01 (No Lost Wakeup)
+// =============================================================================
+
+// Not a local invariant. The writer thread and `OverflowPolicy::Block`
+// callers park in ringmpsc waker slots and re-check after registering; the
+// protocol is ringmpsc's, checked by its loom tests (`waker::loom_tests`), so
+// there is no macro here.
+
+// =============================================================================
+// Re-exports for crate-internal use
+// =============================================================================
+
+pub(crate) use debug_assert_drained_on_shutdown;
+pub(crate) use debug_assert_drop_report;
+pub(crate) use debug_assert_flush_progress;
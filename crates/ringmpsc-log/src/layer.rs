@@ -0,0 +1,92 @@
+//! `tracing_subscriber::Layer` that writes formatted events through the same
+//! rings as [`RingLogger`](crate::RingLogger).
+
+use crate::format::format_line;
+use crate::logger::Shared;
+use std::fmt::{self, Write};
+use std::sync::Arc;
+use std::time::SystemTime;
+use tracing_core::field::{Field, Visit};
+use tracing_core::{Event, Level, Metadata, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+/// A [`Layer`] that formats each event on the calling thread and commits it to
+/// that thread's ring. Obtain one with [`RingLogger::layer`](crate::RingLogger::layer).
+///
+/// Events are rendered as `message key=value ...`; span context is not
+/// included.
+///
+/// ```ignore
+/// use tracing_subscriber::prelude::*;
+///
+/// let (logger, guard) = RingLogger::new(LogConfig::default(), Sink::Stdout)?;
+/// tracing_subscriber::registry().with(logger.layer()).init();
+/// ```
+pub struct RingLayer {
+    shared: Arc<Shared>,
+}
+
+impl RingLayer {
+    pub(crate) fn new(shared: Arc<Shared>) -> Self {
+        Self { shared }
+    }
+}
+
+fn to_log_level(level: Level) -> log::Level {
+    match level {
+        Level::ERROR => log::Level::Error,
+        Level::WARN => log::Level::Warn,
+        Level::INFO => log::Level::Info,
+        Level::DEBUG => log::Level::Debug,
+        _ => log::Level::Trace,
+    }
+}
+
+impl<S: Subscriber> Layer<S> for RingLayer {
+    fn enabled(&self, metadata: &Metadata<'_>, _ctx: Context<'_, S>) -> bool {
+        self.shared.level_enabled(to_log_level(*metadata.level()))
+    }
+
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let level = to_log_level(*metadata.level());
+        if !self.shared.level_enabled(level) {
+            return;
+        }
+
+        let mut visitor = EventVisitor::default();
+        event.record(&mut visitor);
+        let line = format_line(
+            SystemTime::now(),
+            level.as_str(),
+            metadata.target(),
+            format_args!("{}{}", visitor.message, visitor.fields),
+        );
+        self.shared.submit(line);
+    }
+}
+
+/// Collects the `message` field and renders the rest as ` key=value`.
+#[derive(Default)]
+struct EventVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for EventVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{value:?}");
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+}
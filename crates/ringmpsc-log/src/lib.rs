@@ -0,0 +1,51 @@
+//! Asynchronous `log` / `tracing` backend built on ringmpsc-rs
+//!
+//! Logging threads format each record and commit the line into their own
+//! SPSC ring; a background writer thread drains every ring and batch-writes to
+//! stdout, stderr, a file or a size-rotated file. The caller never touches the
+//! sink, so a slow disk stalls the writer, not the application.
+//!
+//! # Features
+//!
+//! - **Per-thread rings**: Registered lazily on a thread's first record via `Channel::register`
+//! - **Per-thread ordering**: A thread's records are written in the order it logged them
+//! - **Bounded memory**: Fixed ring capacity with a [`OverflowPolicy`] (drop and count, or block)
+//! - **Batched writes**: The writer drains with `consume_all_owned` and writes once per drain
+//! - **Flush-on-shutdown**: Dropping the [`LoggerGuard`] drains, flushes and joins the writer
+//! - **`tracing` support**: `RingLayer` behind the `tracing` feature
+//!
+//! # Example
+//!
+//! ```ignore
+//! use ringmpsc_log::{LogConfig, OverflowPolicy, Sink};
+//!
+//! fn main() -> Result<(), ringmpsc_log::LogError> {
+//!     let _guard = ringmpsc_log::init(
+//!         LogConfig::default().with_overflow(OverflowPolicy::Block),
+//!         Sink::RollingFile {
+//!             path: "app.log".into(),
+//!             max_bytes: 64 << 20,
+//!             max_files: 4,
+//!         },
+//!     )?;
+//!
+//!     log::info!("service started");
+//!     Ok(())
+//! } // guard dropped: remaining records are written and the file flushed
+//! ```
+
+mod config;
+mod error;
+mod format;
+mod invariants;
+#[cfg(feature = "tracing")]
+mod layer;
+mod logger;
+mod sink;
+
+pub use config::{LogConfig, OverflowPolicy};
+pub use error::LogError;
+#[cfg(feature = "tracing")]
+pub use layer::RingLayer;
+pub use logger::{init, LogStats, LoggerGuard, RingLogger};
+pub use sink::Sink;
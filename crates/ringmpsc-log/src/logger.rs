@@ -0,0 +1,530 @@
+//! The [`log::Log`] implementation and its writer thread.
+//!
+//! Logging threads format on their own stack and commit the finished line into
+//! a dedicated ring, registered lazily on first use and handed back to the
+//! logger when the thread exits, so a later thread reuses it. A single writer
+//! thread drains all rings with `consume_all_owned` and hands each drained
+//! batch to the sink in one write.
+
+use crate::config::{LogConfig, OverflowPolicy};
+use crate::error::LogError;
+use crate::format::format_line;
+#[cfg(debug_assertions)]
+use crate::invariants::{
+    debug_assert_drained_on_shutdown, debug_assert_drop_report, debug_assert_flush_progress,
+};
+use crate::sink::{Sink, SinkWriter};
+use log::{Log, Metadata, Record};
+use ringmpsc_rs::{Backoff, Channel, Config, Producer};
+use std::cell::RefCell;
+use std::mem::MaybeUninit;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, OnceLock, Weak};
+use std::task::{Wake, Waker};
+use std::thread::{self, JoinHandle, Thread};
+use std::time::SystemTime;
+
+static NEXT_LOGGER_ID: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    /// This thread's producer for each logger it has used.
+    static PRODUCERS: RefCell<Vec<ThreadProducer>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A thread's ring for one logger.
+///
+/// `producer` is `None` if no ring was free, in which case the thread uses
+/// the fallback ring; either way it keeps a single ring for its lifetime
+/// (INV-LOG-01). On thread exit the producer goes back to the logger's free
+/// list (INV-LOG-02).
+struct ThreadProducer {
+    logger_id: u64,
+    producer: Option<Rc<Producer<String>>>,
+    shared: Weak<Shared>,
+}
+
+impl Drop for ThreadProducer {
+    fn drop(&mut self) {
+        let Some(shared) = self.shared.upgrade() else {
+            return;
+        };
+        if let Some(producer) = self.producer.take().and_then(|p| Rc::try_unwrap(p).ok()) {
+            shared.release(producer);
+        }
+    }
+}
+
+/// Counters describing what the logger did with the records it was given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LogStats {
+    /// Records handed to the sink.
+    pub written: u64,
+    /// Records discarded because a ring was full or the logger was shut down.
+    pub dropped: u64,
+    /// Failed sink writes or flushes. Records in a failed write are lost.
+    pub write_errors: u64,
+}
+
+struct FlushState {
+    requested: u64,
+    completed: u64,
+    stopped: bool,
+}
+
+/// State shared by logger handles, layers and the writer thread.
+pub(crate) struct Shared {
+    id: u64,
+    config: LogConfig,
+    channel: Channel<String>,
+    /// Producer for threads that could not get a ring of their own.
+    fallback: Mutex<Producer<String>>,
+    /// Rings released by exited threads, reused before registering new ones.
+    free: Mutex<Vec<Producer<String>>>,
+    dropped: AtomicU64,
+    written: AtomicU64,
+    write_errors: AtomicU64,
+    stopping: AtomicBool,
+    flush: Mutex<FlushState>,
+    flushed: Condvar,
+    writer: OnceLock<Thread>,
+}
+
+impl Shared {
+    pub(crate) fn level_enabled(&self, level: log::Level) -> bool {
+        level <= self.config.level
+    }
+
+    /// Commits a formatted line into the calling thread's ring.
+    pub(crate) fn submit(self: &Arc<Self>, line: String) {
+        let mut line = Some(line);
+
+        // The borrow only covers the lookup, so a record logged while this
+        // one is being pushed still finds the thread's own ring.
+        let producer = PRODUCERS
+            .try_with(|cell| {
+                let mut producers = cell.try_borrow_mut().ok()?;
+                if let Some(p) = producers.iter().find(|p| p.logger_id == self.id) {
+                    return p.producer.clone();
+                }
+                // Forget producers of loggers that have shut down.
+                producers.retain(|p| p.producer.as_ref().is_none_or(|p| !p.is_closed()));
+                let producer = self.acquire().map(Rc::new);
+                producers.push(ThreadProducer {
+                    logger_id: self.id,
+                    producer: producer.clone(),
+                    shared: Arc::downgrade(self),
+                });
+                producer
+            })
+            // Thread-local already destroyed (logging from a TLS destructor).
+            .ok()
+            .flatten();
+
+        match producer {
+            Some(producer) => self.push(&producer, &mut line),
+            None => {
+                let producer = self.fallback.lock().unwrap();
+                self.push(&producer, &mut line);
+            }
+        }
+    }
+
+    /// Hands out a released ring, or registers a new one if none is free.
+    fn acquire(&self) -> Option<Producer<String>> {
+        if let Some(producer) = self.free.lock().unwrap().pop() {
+            return Some(producer);
+        }
+        self.channel.register().ok()
+    }
+
+    /// Returns an exited thread's ring for reuse. Records it left behind are
+    /// still drained in order.
+    fn release(&self, producer: Producer<String>) {
+        if !producer.is_closed() {
+            self.free.lock().unwrap().push(producer);
+        }
+    }
+
+    /// Pushes `line` according to the overflow policy, counting a drop if it
+    /// could not be committed.
+    fn push(&self, producer: &Producer<String>, line: &mut Option<String>) {
+        if try_commit(producer, line) {
+            return;
+        }
+        if self.config.overflow == OverflowPolicy::Block && block_until_committed(producer, line) {
+            return;
+        }
+        // INV-LOG-03: every record that is not committed is counted.
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Blocks until every record committed before this call is written and
+    /// the sink is flushed (INV-LOG-05).
+    fn flush(&self) {
+        let Some(writer) = self.writer.get() else {
+            return;
+        };
+        let mut state = self.flush.lock().unwrap();
+        if state.stopped {
+            return;
+        }
+        state.requested += 1;
+        let target = state.requested;
+        writer.unpark();
+        while state.completed < target && !state.stopped {
+            state = self.flushed.wait(state).unwrap();
+        }
+    }
+
+    pub(crate) fn stats(&self) -> LogStats {
+        LogStats {
+            written: self.written.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            write_errors: self.write_errors.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Drains every ring into one buffer and writes it to the sink.
+    ///
+    /// Appends a notice when records were dropped since the last drain.
+    /// Returns the number of records drained.
+    fn drain(&self, sink: &mut SinkWriter, buf: &mut Vec<u8>, reported_drops: &mut u64) -> usize {
+        buf.clear();
+        let drained = self
+            .channel
+            .consume_all_owned(|line| buf.extend_from_slice(line.as_bytes()));
+
+        let dropped = self.dropped.load(Ordering::Relaxed);
+        #[cfg(debug_assertions)]
+        debug_assert_drop_report!(*reported_drops, dropped);
+        if dropped > *reported_drops {
+            let notice = format_line(
+                SystemTime::now(),
+                "WARN",
+                "ringmpsc_log",
+                format_args!("{} records dropped", dropped - *reported_drops),
+            );
+            buf.extend_from_slice(notice.as_bytes());
+            *reported_drops = dropped;
+        }
+
+        if !buf.is_empty() {
+            match sink.write_lines(buf) {
+                Ok(()) => {
+                    self.written.fetch_add(drained as u64, Ordering::Relaxed);
+                }
+                Err(_) => {
+                    self.write_errors.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+        drained
+    }
+
+    fn flush_sink(&self, sink: &mut SinkWriter) {
+        if sink.flush().is_err() {
+            self.write_errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn ack_flush(&self, epoch: u64) {
+        let mut state = self.flush.lock().unwrap();
+        #[cfg(debug_assertions)]
+        debug_assert_flush_progress!(epoch, state.requested);
+        state.completed = epoch;
+        self.flushed.notify_all();
+    }
+
+    fn shutdown(&self) {
+        // Close first: once `stopping` is visible every ring is closed, so the
+        // writer's final drain cannot miss a record committed before shutdown.
+        self.channel.close();
+        self.stopping.store(true, Ordering::Release);
+        if let Some(writer) = self.writer.get() {
+            writer.unpark();
+        }
+    }
+}
+
+/// Commits `line` if the ring is open and has space. Leaves it in place
+/// otherwise.
+fn try_commit(producer: &Producer<String>, line: &mut Option<String>) -> bool {
+    if producer.is_closed() {
+        return false;
+    }
+    let Some(mut reservation) = producer.reserve(1) else {
+        return false;
+    };
+    if let Some(value) = line.take() {
+        reservation.as_mut_slice()[0] = MaybeUninit::new(value);
+        reservation.commit();
+    }
+    true
+}
+
+/// Waits for the writer to free space: spins and yields first, then parks
+/// in the ring's producer waker slot. Returns false if the ring closed.
+fn block_until_committed(producer: &Producer<String>, line: &mut Option<String>) -> bool {
+    let mut backoff = Backoff::new();
+    let mut waker = None;
+    while !producer.is_closed() {
+        if backoff.is_completed() {
+            let waker = waker
+                .get_or_insert_with(|| Waker::from(Arc::new(ThreadWaker(thread::current()))));
+            // Register, then re-check (ringmpsc INV-WAKE-01); a drain or
+            // close unparks the thread.
+            producer.register_waker(waker);
+            if try_commit(producer, line) {
+                return true;
+            }
+            if producer.is_closed() {
+                break;
+            }
+            thread::park();
+        } else {
+            backoff.snooze();
+        }
+        if try_commit(producer, line) {
+            return true;
+        }
+    }
+    false
+}
+
+/// Unparks a thread from a ring's waker slot: the writer from the consumer
+/// slots, a blocked logging thread from its producer slot.
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+fn run_writer(shared: Arc<Shared>, mut sink: SinkWriter) {
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut buf = Vec::new();
+    let mut reported_drops = 0;
+    let mut acked = 0;
+
+    loop {
+        let stopping = shared.stopping.load(Ordering::Acquire);
+        let flush_target = shared.flush.lock().unwrap().requested;
+        let drained = shared.drain(&mut sink, &mut buf, &mut reported_drops);
+
+        if stopping {
+            break;
+        }
+        if flush_target > acked {
+            shared.flush_sink(&mut sink);
+            acked = flush_target;
+            shared.ack_flush(acked);
+            continue;
+        }
+        if drained > 0 {
+            continue;
+        }
+
+        // Idle: register in every ring's consumer slot, re-check, then park
+        // until a commit, a flush request or shutdown unparks us.
+        shared.channel.register_consumer_waker(&waker);
+        if shared.drain(&mut sink, &mut buf, &mut reported_drops) > 0 {
+            continue;
+        }
+        shared.flush_sink(&mut sink);
+        thread::park();
+    }
+
+    // INV-LOG-04: the rings are closed; drain whatever is left and flush.
+    while shared.drain(&mut sink, &mut buf, &mut reported_drops) > 0 {}
+    #[cfg(debug_assertions)]
+    debug_assert_drained_on_shutdown!(shared.channel.is_closed());
+    shared.flush_sink(&mut sink);
+
+    let mut state = shared.flush.lock().unwrap();
+    state.stopped = true;
+    shared.flushed.notify_all();
+}
+
+/// A [`log::Log`] implementation that hands formatted records to a writer
+/// thread through per-thread ringmpsc rings.
+///
+/// Handles are cheap to clone and all feed the same writer thread. The
+/// writer runs until the [`LoggerGuard`] returned alongside the logger is
+/// dropped or [shut down](LoggerGuard::shutdown).
+///
+/// # Example
+///
+/// ```ignore
+/// use ringmpsc_log::{LogConfig, RingLogger, Sink};
+///
+/// let (logger, guard) = RingLogger::new(LogConfig::default(), Sink::Stdout)?;
+/// log::set_boxed_logger(Box::new(logger))?;
+/// log::set_max_level(log::LevelFilter::Info);
+///
+/// log::info!("ready");
+/// drop(guard); // drains and flushes
+/// ```
+#[derive(Clone)]
+pub struct RingLogger {
+    shared: Arc<Shared>,
+}
+
+impl RingLogger {
+    /// Opens `sink`, starts the writer thread and returns a logger handle
+    /// plus the guard that owns the writer.
+    pub fn new(config: LogConfig, sink: Sink) -> Result<(Self, LoggerGuard), LogError> {
+        config.validate().map_err(LogError::InvalidConfig)?;
+        let sink = SinkWriter::open(sink)?;
+
+        // Ring 0 is the shared fallback; the rest are handed out per thread.
+        let channel =
+            Channel::new(Config::new(config.ring_bits, config.max_threads + 1, false)).with_wakers();
+        let fallback = channel
+            .register()
+            .expect("fresh channel has room for the fallback producer");
+
+        let shared = Arc::new(Shared {
+            id: NEXT_LOGGER_ID.fetch_add(1, Ordering::Relaxed),
+            config,
+            channel,
+            fallback: Mutex::new(fallback),
+            free: Mutex::new(Vec::new()),
+            dropped: AtomicU64::new(0),
+            written: AtomicU64::new(0),
+            write_errors: AtomicU64::new(0),
+            stopping: AtomicBool::new(false),
+            flush: Mutex::new(FlushState {
+                requested: 0,
+                completed: 0,
+                stopped: false,
+            }),
+            flushed: Condvar::new(),
+            writer: OnceLock::new(),
+        });
+
+        let worker = Arc::clone(&shared);
+        let handle = thread::Builder::new()
+            .name("ringmpsc-log".into())
+            .spawn(move || run_writer(worker, sink))?;
+        let _ = shared.writer.set(handle.thread().clone());
+
+        let guard = LoggerGuard {
+            shared: Arc::clone(&shared),
+            handle: Some(handle),
+        };
+        Ok((Self { shared }, guard))
+    }
+
+    /// Returns a snapshot of the logger's counters.
+    pub fn stats(&self) -> LogStats {
+        self.shared.stats()
+    }
+
+    /// Returns a `tracing_subscriber` layer feeding the same writer thread.
+    #[cfg(feature = "tracing")]
+    pub fn layer(&self) -> crate::RingLayer {
+        crate::RingLayer::new(Arc::clone(&self.shared))
+    }
+}
+
+impl Log for RingLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        self.shared.level_enabled(metadata.level())
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = format_line(
+            SystemTime::now(),
+            record.level().as_str(),
+            record.target(),
+            *record.args(),
+        );
+        self.shared.submit(line);
+    }
+
+    fn flush(&self) {
+        self.shared.flush();
+    }
+}
+
+/// Owns the writer thread. Dropping it shuts the logger down.
+///
+/// Shutdown closes every ring, drains what was committed, flushes the sink
+/// and joins the writer thread (INV-LOG-04). Records logged afterwards are
+/// counted as dropped.
+pub struct LoggerGuard {
+    shared: Arc<Shared>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl LoggerGuard {
+    /// Returns a snapshot of the logger's counters.
+    pub fn stats(&self) -> LogStats {
+        self.shared.stats()
+    }
+
+    /// Shuts the logger down and returns the final counters.
+    pub fn shutdown(mut self) -> LogStats {
+        self.stop();
+        self.shared.stats()
+    }
+
+    fn stop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            self.shared.shutdown();
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for LoggerGuard {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Creates a [`RingLogger`], installs it as the global `log` logger and sets
+/// the global max level to `config.level`.
+///
+/// Keep the returned guard alive for as long as the program logs; dropping
+/// it flushes and stops the writer.
+pub fn init(config: LogConfig, sink: Sink) -> Result<LoggerGuard, LogError> {
+    let level = config.level;
+    let (logger, guard) = RingLogger::new(config, sink)?;
+    log::set_boxed_logger(Box::new(logger))?;
+    log::set_max_level(level);
+    Ok(guard)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// INV-LOG-02: a thread's ring is reused once the thread has exited.
+    #[test]
+    fn test_exited_thread_ring_is_recycled() {
+        let config = LogConfig::default().with_max_threads(1);
+        let (logger, guard) = RingLogger::new(config, Sink::Writer(Box::new(std::io::sink()))).unwrap();
+
+        for i in 0..8 {
+            let logger = logger.clone();
+            thread::spawn(move || logger.shared.submit(format!("thread {i}\n")))
+                .join()
+                .unwrap();
+        }
+
+        // The fallback ring plus the single recycled per-thread ring.
+        assert_eq!(logger.shared.channel.producer_count(), 2);
+        assert_eq!(logger.shared.free.lock().unwrap().len(), 1);
+        assert_eq!(guard.shutdown().written, 8);
+    }
+}
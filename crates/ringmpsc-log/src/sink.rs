@@ -0,0 +1,258 @@
+//! Output destinations for the writer thread.
+
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Where the writer thread sends formatted records.
+pub enum Sink {
+    /// Standard output.
+    Stdout,
+    /// Standard error.
+    Stderr,
+    /// A file, opened in append mode.
+    File(PathBuf),
+    /// A size-rotated file.
+    ///
+    /// Before a write would grow `path` beyond `max_bytes`, the file is
+    /// renamed to `path.1` (shifting `path.1` to `path.2`, and so on) and a
+    /// fresh file is started. At most `max_files` rotated files are kept;
+    /// with `max_files == 0` the file is truncated instead.
+    RollingFile {
+        /// Path of the active log file.
+        path: PathBuf,
+        /// Size threshold that triggers rotation.
+        max_bytes: u64,
+        /// Number of rotated files to keep.
+        max_files: usize,
+    },
+    /// Any writer, e.g. a socket or an in-memory buffer in tests.
+    Writer(Box<dyn Write + Send>),
+}
+
+impl fmt::Debug for Sink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Stdout => f.write_str("Stdout"),
+            Self::Stderr => f.write_str("Stderr"),
+            Self::File(path) => f.debug_tuple("File").field(path).finish(),
+            Self::RollingFile {
+                path,
+                max_bytes,
+                max_files,
+            } => f
+                .debug_struct("RollingFile")
+                .field("path", path)
+                .field("max_bytes", max_bytes)
+                .field("max_files", max_files)
+                .finish(),
+            Self::Writer(_) => f.write_str("Writer(..)"),
+        }
+    }
+}
+
+/// Writer-thread side of a [`Sink`].
+pub(crate) enum SinkWriter {
+    Stdout,
+    Stderr,
+    File(File),
+    Rolling(RollingFile),
+    Writer(Box<dyn Write + Send>),
+}
+
+impl SinkWriter {
+    /// Opens the sink. Called before the writer thread starts so that a bad
+    /// path is reported to the caller rather than swallowed.
+    pub(crate) fn open(sink: Sink) -> io::Result<Self> {
+        Ok(match sink {
+            Sink::Stdout => Self::Stdout,
+            Sink::Stderr => Self::Stderr,
+            Sink::File(path) => Self::File(open_append(&path)?),
+            Sink::RollingFile {
+                path,
+                max_bytes,
+                max_files,
+            } => Self::Rolling(RollingFile::open(path, max_bytes, max_files)?),
+            Sink::Writer(writer) => Self::Writer(writer),
+        })
+    }
+
+    /// Writes one batch of complete lines.
+    pub(crate) fn write_lines(&mut self, buf: &[u8]) -> io::Result<()> {
+        match self {
+            Self::Stdout => io::stdout().lock().write_all(buf),
+            Self::Stderr => io::stderr().lock().write_all(buf),
+            Self::File(file) => file.write_all(buf),
+            Self::Rolling(rolling) => rolling.write_lines(buf),
+            Self::Writer(writer) => writer.write_all(buf),
+        }
+    }
+
+    pub(crate) fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Stdout => io::stdout().flush(),
+            Self::Stderr => io::stderr().flush(),
+            Self::File(file) => file.flush(),
+            Self::Rolling(rolling) => rolling.file.flush(),
+            Self::Writer(writer) => writer.flush(),
+        }
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+pub(crate) struct RollingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    file: File,
+    size: u64,
+}
+
+impl RollingFile {
+    fn open(path: PathBuf, max_bytes: u64, max_files: usize) -> io::Result<Self> {
+        let file = open_append(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path,
+            max_bytes,
+            max_files,
+            file,
+            size,
+        })
+    }
+
+    /// Writes `buf`, rotating at line boundaries so no line is split across
+    /// files. A single line longer than `max_bytes` gets a file of its own.
+    fn write_lines(&mut self, mut buf: &[u8]) -> io::Result<()> {
+        while !buf.is_empty() {
+            let room = self.max_bytes.saturating_sub(self.size) as usize;
+            let fits = fitting_prefix(buf, room);
+            if fits == 0 && self.size > 0 {
+                self.rotate()?;
+                continue;
+            }
+            // Empty file and the first line alone is too long: write it whole.
+            let len = if fits == 0 {
+                buf.iter().position(|&b| b == b'\n').map_or(buf.len(), |i| i + 1)
+            } else {
+                fits
+            };
+            self.file.write_all(&buf[..len])?;
+            self.size += len as u64;
+            buf = &buf[len..];
+        }
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        if self.max_files > 0 {
+            for i in (1..self.max_files).rev() {
+                let from = rotated_path(&self.path, i);
+                if from.exists() {
+                    fs::rename(&from, rotated_path(&self.path, i + 1))?;
+                }
+            }
+            fs::rename(&self.path, rotated_path(&self.path, 1))?;
+            self.file = open_append(&self.path)?;
+        } else {
+            self.file = OpenOptions::new()
+                .create(true)
+                .write(true)
+                .truncate(true)
+                .open(&self.path)?;
+        }
+        self.size = 0;
+        Ok(())
+    }
+}
+
+/// Length of the longest run of whole lines at the start of `buf` that fits
+/// in `room` bytes.
+fn fitting_prefix(buf: &[u8], room: usize) -> usize {
+    if buf.len() <= room {
+        return buf.len();
+    }
+    buf[..room]
+        .iter()
+        .rposition(|&b| b == b'\n')
+        .map_or(0, |i| i + 1)
+}
+
+/// `app.log` → `app.log.1`
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{index}"));
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "ringmpsc-log-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("app.log")
+    }
+
+    #[test]
+    fn test_fitting_prefix() {
+        assert_eq!(fitting_prefix(b"ab\ncd\n", 6), 6);
+        assert_eq!(fitting_prefix(b"ab\ncd\n", 5), 3);
+        assert_eq!(fitting_prefix(b"ab\ncd\n", 3), 3);
+        assert_eq!(fitting_prefix(b"ab\ncd\n", 2), 0);
+    }
+
+    #[test]
+    fn test_rolling_file_rotates_at_line_boundaries() {
+        let path = temp_path("rotate");
+        let mut writer = SinkWriter::open(Sink::RollingFile {
+            path: path.clone(),
+            max_bytes: 8,
+            max_files: 2,
+        })
+        .unwrap();
+
+        writer.write_lines(b"one\ntwo\n").unwrap();
+        writer.write_lines(b"three\nfour\n").unwrap();
+        writer.write_lines(b"five\n").unwrap();
+        writer.flush().unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "five\n");
+        assert_eq!(fs::read_to_string(rotated_path(&path, 1)).unwrap(), "four\n");
+        assert_eq!(fs::read_to_string(rotated_path(&path, 2)).unwrap(), "three\n");
+        // `one\ntwo\n` was rotated out past max_files.
+        assert!(!rotated_path(&path, 3).exists());
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_rolling_file_without_history_truncates() {
+        let path = temp_path("truncate");
+        let mut writer = SinkWriter::open(Sink::RollingFile {
+            path: path.clone(),
+            max_bytes: 4,
+            max_files: 0,
+        })
+        .unwrap();
+
+        writer.write_lines(b"aaa\nbbb\n").unwrap();
+        writer.flush().unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "bbb\n");
+        assert!(!rotated_path(&path, 1).exists());
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
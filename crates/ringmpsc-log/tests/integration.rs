@@ -0,0 +1,269 @@
+//! Integration tests for ringmpsc-log.
+//!
+//! Tests drive `RingLogger` through the `log::Log` trait directly rather than
+//! installing a global logger, so they can run in parallel.
+
+use log::{Level, LevelFilter, Log, Record};
+use ringmpsc_log::{LogConfig, OverflowPolicy, RingLogger, Sink};
+use std::io::{self, Write};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// In-memory sink shared between the writer thread and the test.
+#[derive(Clone, Default)]
+struct Capture(Arc<Mutex<Vec<u8>>>);
+
+impl Capture {
+    fn lines(&self) -> Vec<String> {
+        String::from_utf8(self.0.lock().unwrap().clone())
+            .unwrap()
+            .lines()
+            .map(str::to_owned)
+            .collect()
+    }
+}
+
+impl Write for Capture {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Sink whose first write blocks until the test releases it, so the rings
+/// can be filled while the writer thread is stuck.
+struct Gated {
+    gate: Option<(mpsc::Sender<()>, mpsc::Receiver<()>)>,
+    inner: Capture,
+}
+
+impl Write for Gated {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Some((entered, release)) = self.gate.take() {
+            entered.send(()).unwrap();
+            release.recv().unwrap();
+        }
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn emit(logger: &RingLogger, level: Level, message: &str) {
+    logger.log(
+        &Record::builder()
+            .level(level)
+            .target("test")
+            .args(format_args!("{message}"))
+            .build(),
+    );
+}
+
+fn capture_logger(config: LogConfig) -> (RingLogger, ringmpsc_log::LoggerGuard, Capture) {
+    let capture = Capture::default();
+    let (logger, guard) = RingLogger::new(config, Sink::Writer(Box::new(capture.clone()))).unwrap();
+    (logger, guard, capture)
+}
+
+#[test]
+fn test_records_written_on_shutdown() {
+    let (logger, guard, capture) = capture_logger(LogConfig::default());
+
+    for i in 0..100 {
+        emit(&logger, Level::Info, &format!("record {i}"));
+    }
+    let stats = guard.shutdown();
+
+    assert_eq!(stats.written, 100);
+    assert_eq!(stats.dropped, 0);
+    let lines = capture.lines();
+    assert_eq!(lines.len(), 100);
+    assert!(lines[0].contains(" INFO  ["));
+    assert!(lines[0].ends_with("test: record 0"));
+}
+
+#[test]
+fn test_flush_makes_records_visible() {
+    let (logger, _guard, capture) = capture_logger(LogConfig::default());
+
+    emit(&logger, Level::Warn, "before flush");
+    logger.flush();
+
+    let lines = capture.lines();
+    assert_eq!(lines.len(), 1);
+    assert!(lines[0].ends_with("test: before flush"));
+}
+
+#[test]
+fn test_level_filter() {
+    let (logger, guard, capture) =
+        capture_logger(LogConfig::default().with_level(LevelFilter::Warn));
+
+    emit(&logger, Level::Error, "kept");
+    emit(&logger, Level::Info, "filtered");
+    emit(&logger, Level::Debug, "filtered");
+    guard.shutdown();
+
+    let lines = capture.lines();
+    assert_eq!(lines.len(), 1);
+    assert!(lines[0].ends_with("kept"));
+}
+
+/// INV-LOG-01: each thread's records are written in the order it logged them,
+/// including threads that overflow onto the shared fallback ring.
+#[test]
+fn test_per_thread_ordering() {
+    const THREADS: usize = 6;
+    const PER_THREAD: usize = 2_000;
+
+    // Fewer dedicated rings than threads: two threads share the fallback.
+    let (logger, guard, capture) = capture_logger(
+        LogConfig::default()
+            .with_max_threads(4)
+            .with_ring_bits(6)
+            .with_overflow(OverflowPolicy::Block),
+    );
+
+    let handles: Vec<_> = (0..THREADS)
+        .map(|t| {
+            let logger = logger.clone();
+            thread::spawn(move || {
+                for i in 0..PER_THREAD {
+                    emit(&logger, Level::Info, &format!("t{t} {i}"));
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    let stats = guard.shutdown();
+
+    assert_eq!(stats.written, (THREADS * PER_THREAD) as u64);
+    assert_eq!(stats.dropped, 0);
+
+    let mut next = [0usize; THREADS];
+    for line in capture.lines() {
+        let message = line.rsplit("test: ").next().unwrap();
+        let (thread_tag, seq) = message.split_once(' ').unwrap();
+        let t: usize = thread_tag[1..].parse().unwrap();
+        let seq: usize = seq.parse().unwrap();
+        assert_eq!(seq, next[t], "thread {t} out of order");
+        next[t] += 1;
+    }
+    assert_eq!(next, [PER_THREAD; THREADS]);
+}
+
+/// INV-LOG-03: with `DropNewest`, records that do not fit are counted and the
+/// writer reports them in-band.
+#[test]
+fn test_drop_newest_counts_and_reports_drops() {
+    let (entered_tx, entered_rx) = mpsc::channel();
+    let (release_tx, release_rx) = mpsc::channel();
+    let capture = Capture::default();
+    let sink = Gated {
+        gate: Some((entered_tx, release_rx)),
+        inner: capture.clone(),
+    };
+    let (logger, guard) =
+        RingLogger::new(LogConfig::default().with_ring_bits(2), Sink::Writer(Box::new(sink)))
+            .unwrap();
+
+    emit(&logger, Level::Info, "first");
+    // Writer is now stuck inside the sink; the 4-slot ring fills up.
+    entered_rx.recv().unwrap();
+    for i in 0..20 {
+        emit(&logger, Level::Info, &format!("burst {i}"));
+    }
+    assert_eq!(logger.stats().dropped, 16);
+
+    release_tx.send(()).unwrap();
+    let stats = guard.shutdown();
+
+    assert_eq!(stats.written, 5);
+    assert_eq!(stats.dropped, 16);
+    let lines = capture.lines();
+    assert!(lines.iter().any(|l| l.ends_with("ringmpsc_log: 16 records dropped")));
+    assert!(lines.iter().any(|l| l.ends_with("burst 3")));
+    assert!(!lines.iter().any(|l| l.ends_with("burst 4")));
+}
+
+#[test]
+fn test_records_after_shutdown_are_dropped() {
+    let (logger, guard, capture) = capture_logger(LogConfig::default());
+    guard.shutdown();
+
+    emit(&logger, Level::Error, "too late");
+
+    assert_eq!(logger.stats().dropped, 1);
+    assert!(capture.lines().is_empty());
+}
+
+#[test]
+fn test_rolling_file_sink() {
+    let dir = std::env::temp_dir().join(format!("ringmpsc-log-it-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("app.log");
+
+    let (logger, guard) = RingLogger::new(
+        LogConfig::default(),
+        Sink::RollingFile {
+            path: path.clone(),
+            max_bytes: 256,
+            max_files: 3,
+        },
+    )
+    .unwrap();
+    for i in 0..20 {
+        emit(&logger, Level::Info, &format!("line {i}"));
+    }
+    guard.shutdown();
+
+    let mut total = 0;
+    for name in ["app.log", "app.log.1", "app.log.2", "app.log.3"] {
+        let content = std::fs::read_to_string(dir.join(name)).unwrap();
+        assert!(content.len() <= 256);
+        assert!(content.ends_with('\n'));
+        total += content.lines().count();
+    }
+    // ~60 bytes per line: 20 lines need more than 4 files, the oldest are gone.
+    assert!(total < 20);
+    let newest = std::fs::read_to_string(&path).unwrap();
+    assert!(newest.trim_end().ends_with("line 19"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_invalid_config_rejected() {
+    let result = RingLogger::new(LogConfig::default().with_max_threads(0), Sink::Stdout);
+    assert!(matches!(result, Err(ringmpsc_log::LogError::InvalidConfig(_))));
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn test_tracing_layer_formats_fields() {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    let (logger, guard, capture) = capture_logger(LogConfig::default());
+    let subscriber = tracing_subscriber::registry().with(logger.layer());
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info!(target: "svc", user = 7, "logged in");
+        tracing::debug!(target: "svc", "filtered out");
+    });
+    guard.shutdown();
+
+    let lines = capture.lines();
+    assert_eq!(lines.len(), 1);
+    assert!(lines[0].contains(" INFO  ["));
+    assert!(lines[0].ends_with("svc: logged in user=7"));
+}
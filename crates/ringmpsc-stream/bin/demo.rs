@@ -235,11 +235,11 @@ async fn demo_configuration_presets() -> Result<(), Box<dyn std::error::Error>>
 
     let ring_config = Config::new(8, 2, false);
 
-    // Low-latency configuration (1ms poll, batch 16)
+    // Low-latency configuration (batch 16)
     let low_latency = StreamConfig::low_latency();
     println!(
-        "  Low-latency: poll_interval={:?}, batch_hint={}",
-        low_latency.poll_interval, low_latency.batch_hint
+        "  Low-latency: batch_hint={}",
+        low_latency.batch_hint
     );
 
     let (factory, mut rx) = channel_with_stream_config::<u64>(ring_config, low_latency);
@@ -250,12 +250,12 @@ async fn demo_configuration_presets() -> Result<(), Box<dyn std::error::Error>>
         println!("  Received {v} with low-latency config");
     }
 
-    // High-throughput configuration (50ms poll, batch 256)
+    // High-throughput configuration (batch 256)
     let ring_config = Config::new(8, 2, false);
     let high_throughput = StreamConfig::high_throughput();
     println!(
-        "  High-throughput: poll_interval={:?}, batch_hint={}",
-        high_throughput.poll_interval, high_throughput.batch_hint
+        "  High-throughput: batch_hint={}",
+        high_throughput.batch_hint
     );
 
     let (factory, mut rx) = channel_with_stream_config::<u64>(ring_config, high_throughput);
@@ -273,14 +273,8 @@ async fn demo_configuration_presets() -> Result<(), Box<dyn std::error::Error>>
     println!("  Received {count} items with high-throughput config");
 
     // Custom configuration
-    let custom = StreamConfig {
-        poll_interval: Duration::from_millis(25),
-        batch_hint: 128,
-    };
-    println!(
-        "  Custom: poll_interval={:?}, batch_hint={}",
-        custom.poll_interval, custom.batch_hint
-    );
+    let custom = StreamConfig::default().with_batch_hint(128);
+    println!("  Custom: batch_hint={}", custom.batch_hint);
 
     println!("  ✓ Configuration presets complete\n");
     Ok(())
@@ -1,13 +1,14 @@
 //! Channel construction and sender factory.
 
-use crate::config::StreamConfig;
+use crate::config::{SendPolicy, StreamConfig};
 use crate::error::StreamError;
 use crate::receiver::RingReceiver;
 use crate::sender::RingSender;
 use crate::shutdown::ShutdownState;
+use crate::timer::Timer;
+use crate::weight::{WeightGauge, WeightStats, Weigher};
 use ringmpsc_rs::{Channel, Config};
 use std::sync::Arc;
-use tokio::sync::Notify;
 
 #[cfg(debug_assertions)]
 use crate::invariants::debug_assert_explicit_registration;
@@ -42,30 +43,89 @@ pub fn channel<T: Send + 'static>(config: Config) -> (SenderFactory<T>, RingRece
 /// # Arguments
 ///
 /// * `config` - The ringmpsc configuration (ring size, max producers, etc.)
-/// * `stream_config` - The stream-specific configuration (poll interval, batch hint)
+/// * `stream_config` - The stream-specific configuration (batch hint,
+///   weigher and weight budgets)
+///
+/// Ring waker slots are always enabled (`Channel::with_wakers`): the receiver
+/// and senders park on them instead of polling (INV-STREAM-02).
+///
+/// The channel weighs items if `stream_config` carries a weigher for `T`
+/// ([`StreamConfig::with_weigher`]) or sets a weight budget; a budget
+/// without a weigher counts one unit per item. See [`channel_with_weigher`].
 #[must_use] 
 pub fn channel_with_stream_config<T: Send + 'static>(
     config: Config,
     stream_config: StreamConfig,
 ) -> (SenderFactory<T>, RingReceiver<T>) {
-    let channel = Arc::new(Channel::new(config));
-    let data_notify = Arc::new(Notify::new());
-    let backpressure_notify = Arc::new(Notify::new());
-    let shutdown_state = Arc::new(ShutdownState::new());
+    let weigher = stream_config.weigher_for::<T>().or_else(|| {
+        let budgeted =
+            stream_config.max_weight.is_some() || stream_config.max_sender_weight.is_some();
+        budgeted.then(|| Arc::new(|_: &T| 1_usize) as Arc<dyn Weigher<T>>)
+    });
+    let weights =
+        weigher.map(|weigher| Arc::new(WeightGauge::new(weigher, &stream_config, &config)));
+    build(config, stream_config, weights)
+}
 
+/// Creates a channel whose capacity is also bounded by item weight.
+///
+/// Each item is weighed once, when it is committed: `weigher.weigh(&item)`
+/// is charged to its sender and to the channel, stored with the item, and
+/// released when the receiver drains it from the ring. [`StreamConfig::max_weight`] and
+/// [`StreamConfig::max_sender_weight`] bound the outstanding weight: a send
+/// that would exceed either is treated like a send to a full ring under the
+/// sender's [`SendPolicy`], even if the ring has free slots. An item heavier
+/// than a budget is still admitted once nothing else is outstanding.
+///
+/// Without budgets the channel only measures; see
+/// [`SenderFactory::weight_stats`] and [`RingReceiver::weight_stats`].
+///
+/// Shorthand for [`channel_with_stream_config`] with
+/// `stream_config.with_weigher(weigher)`.
+///
+/// ```ignore
+/// let stream_config = StreamConfig::default().with_max_weight(64 << 20);
+/// let (factory, rx) =
+///     channel_with_weigher(Config::default(), stream_config, |buf: &Vec<u8>| buf.len());
+/// ```
+#[must_use]
+pub fn channel_with_weigher<T: Send + 'static>(
+    config: Config,
+    stream_config: StreamConfig,
+    weigher: impl Weigher<T> + 'static,
+) -> (SenderFactory<T>, RingReceiver<T>) {
+    channel_with_stream_config(config, stream_config.with_weigher(weigher))
+}
+
+fn build<T: Send + 'static>(
+    config: Config,
+    stream_config: StreamConfig,
+    weights: Option<Arc<WeightGauge<T>>>,
+) -> (SenderFactory<T>, RingReceiver<T>) {
+    let channel = Arc::new(Channel::new(config).with_wakers());
+    let closer = Arc::clone(&channel);
+    let budget = weights.clone();
+    // INV-SHUT-02: senders parked on a weight budget wake on close as well.
+    let shutdown_state = Arc::new(ShutdownState::new(move || {
+        closer.close();
+        if let Some(budget) = &budget {
+            budget.wake_all();
+        }
+    }));
+
+    let timer = Arc::clone(&stream_config.timer);
     let receiver = RingReceiver::new(
         Arc::clone(&channel),
-        Arc::clone(&data_notify),
-        Arc::clone(&backpressure_notify),
         Arc::clone(&shutdown_state),
         stream_config,
+        weights.clone(),
     );
 
     let factory = SenderFactory {
         channel,
-        data_notify,
-        backpressure_notify,
         shutdown_state,
+        timer,
+        weights,
     };
 
     (factory, receiver)
@@ -81,12 +141,23 @@ pub fn channel_with_stream_config<T: Send + 'static>(
 ///
 /// `SenderFactory` is `Clone`, allowing it to be shared across threads.
 /// Each cloned factory can register its own senders.
-#[derive(Clone)]
 pub struct SenderFactory<T> {
     channel: Arc<Channel<T>>,
-    data_notify: Arc<Notify>,
-    backpressure_notify: Arc<Notify>,
     shutdown_state: Arc<ShutdownState>,
+    timer: Arc<dyn Timer>,
+    weights: Option<Arc<WeightGauge<T>>>,
+}
+
+// Manual impl: cloning shares the channel, so `T: Clone` is not needed.
+impl<T> Clone for SenderFactory<T> {
+    fn clone(&self) -> Self {
+        Self {
+            channel: Arc::clone(&self.channel),
+            shutdown_state: Arc::clone(&self.shutdown_state),
+            timer: Arc::clone(&self.timer),
+            weights: self.weights.clone(),
+        }
+    }
 }
 
 impl<T: Send + 'static> SenderFactory<T> {
@@ -105,6 +176,20 @@ impl<T: Send + 'static> SenderFactory<T> {
     /// let tx = factory.register().expect("registration failed");
     /// ```
     pub fn register(&self) -> Result<RingSender<T>, StreamError> {
+        self.register_with(SendPolicy::default())
+    }
+
+    /// Registers a new sender with the given full-ring [`SendPolicy`].
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// // Live telemetry: never wait, count what does not fit
+    /// let tx = factory.register_with(SendPolicy::DropNewest)?;
+    /// tx.send(sample).await?;
+    /// println!("dropped {}", tx.stats().dropped);
+    /// ```
+    pub fn register_with(&self, policy: SendPolicy) -> Result<RingSender<T>, StreamError> {
         if self.shutdown_state.is_closed() {
             return Err(StreamError::Closed);
         }
@@ -115,11 +200,15 @@ impl<T: Send + 'static> SenderFactory<T> {
         #[cfg(debug_assertions)]
         debug_assert_explicit_registration!(true);
 
+        // INV-STREAM-08: a per-producer split picks up the new ring
+        self.shutdown_state.notify_registered();
+
         Ok(RingSender::new(
             producer,
-            Arc::clone(&self.data_notify),
-            Arc::clone(&self.backpressure_notify),
             Arc::clone(&self.shutdown_state),
+            policy,
+            Arc::clone(&self.timer),
+            self.weights.clone(),
         ))
     }
 
@@ -130,7 +219,6 @@ impl<T: Send + 'static> SenderFactory<T> {
     /// `StreamError::Closed`.
     pub fn close(&self) {
         self.shutdown_state.close();
-        self.channel.close();
     }
 
     /// Returns `true` if the channel is closed for new registrations.
@@ -144,4 +232,13 @@ impl<T: Send + 'static> SenderFactory<T> {
     pub fn producer_count(&self) -> usize {
         self.channel.producer_count()
     }
+
+    /// Returns the outstanding weight, or `None` unless the channel weighs
+    /// items (a weigher or a weight budget in its [`StreamConfig`]).
+    #[must_use]
+    pub fn weight_stats(&self) -> Option<WeightStats> {
+        self.weights
+            .as_ref()
+            .map(|weights| weights.stats(self.channel.producer_count()))
+    }
 }
@@ -1,16 +1,21 @@
 //! Configuration for stream behavior.
 
+use crate::timer::{default_timer, Timer};
+use crate::weight::{ErasedWeigher, Weigher};
+use std::sync::Arc;
 use std::time::Duration;
 
 /// Configuration for async stream behavior.
 #[derive(Debug, Clone)]
 pub struct StreamConfig {
-    /// Poll interval for hybrid polling strategy.
-    /// 
-    /// Even with event-driven notify, this interval acts as a safety net
-    /// to catch missed notifications and batch small bursts.
-    /// 
+    /// Poll interval of the former hybrid polling strategy.
+    ///
+    /// The receiver is now purely event-driven (ring waker slots), so this
+    /// value no longer drives any timer. It is retained so existing
+    /// configurations keep compiling.
+    ///
     /// Default: 10ms
+    #[deprecated(note = "the receiver is event-driven; this value is ignored")]
     pub poll_interval: Duration,
 
     /// Target batch size hint for consumption.
@@ -20,38 +25,100 @@ pub struct StreamConfig {
     /// 
     /// Default: 64
     pub batch_hint: usize,
+
+    /// How long `recv_batch` waits for a partial batch to fill.
+    ///
+    /// The linger window opens when the first item of a batch arrives; the
+    /// batch is returned when it is full, the window closes, or the channel
+    /// is closed or shut down. Zero returns as soon as any item is available.
+    /// Only the batch API uses this; `poll_next` never lingers. A non-zero
+    /// value sleeps on [`timer`](Self::timer).
+    ///
+    /// Default: 0
+    pub batch_linger: Duration,
+
+    /// Timer for `batch_linger` and `SendPolicy::BlockWithTimeout`.
+    ///
+    /// Default: [`AutoTimer`](crate::AutoTimer) with the `tokio` feature
+    /// (Tokio's timer inside a Tokio runtime, [`ThreadTimer`](crate::ThreadTimer)
+    /// elsewhere), `ThreadTimer` without it
+    pub timer: Arc<dyn Timer>,
+
+    /// Measures each item against the weight budgets; set by
+    /// [`with_weigher`](Self::with_weigher).
+    ///
+    /// Ignored by channels of another item type. With a budget but no
+    /// weigher, every item weighs one unit, so the budgets count items.
+    ///
+    /// Default: `None`
+    pub weigher: Option<ErasedWeigher>,
+
+    /// Budget for the weight of all queued items, in the
+    /// [`weigher`](Self::weigher)'s unit (typically bytes).
+    ///
+    /// Senders wait (or drop, or fail, per [`SendPolicy`]) once it is
+    /// exhausted, even if their ring has free slots.
+    ///
+    /// Default: `None` (unbounded)
+    pub max_weight: Option<usize>,
+
+    /// Budget for the weight queued by any single sender, in the same unit
+    /// as [`max_weight`](Self::max_weight).
+    ///
+    /// Default: `None` (unbounded)
+    pub max_sender_weight: Option<usize>,
 }
 
 impl Default for StreamConfig {
+    #[allow(deprecated)]
     fn default() -> Self {
         Self {
             poll_interval: Duration::from_millis(10),
             batch_hint: 64,
+            batch_linger: Duration::ZERO,
+            timer: default_timer(),
+            weigher: None,
+            max_weight: None,
+            max_sender_weight: None,
         }
     }
 }
 
 impl StreamConfig {
-    /// Creates a low-latency configuration with shorter poll interval.
+    /// Creates a low-latency configuration with smaller batches.
     #[must_use] 
+    #[allow(deprecated)]
     pub fn low_latency() -> Self {
         Self {
             poll_interval: Duration::from_millis(1),
             batch_hint: 16,
+            batch_linger: Duration::ZERO,
+            timer: default_timer(),
+            weigher: None,
+            max_weight: None,
+            max_sender_weight: None,
         }
     }
 
     /// Creates a high-throughput configuration with larger batches.
     #[must_use] 
+    #[allow(deprecated)]
     pub fn high_throughput() -> Self {
         Self {
             poll_interval: Duration::from_millis(50),
             batch_hint: 256,
+            batch_linger: Duration::ZERO,
+            timer: default_timer(),
+            weigher: None,
+            max_weight: None,
+            max_sender_weight: None,
         }
     }
 
-    /// Sets the poll interval.
+    /// Sets the poll interval (unused by the event-driven receiver).
     #[must_use] 
+    #[deprecated(note = "the receiver is event-driven; the poll interval is ignored")]
+    #[allow(deprecated)]
     pub fn with_poll_interval(mut self, interval: Duration) -> Self {
         self.poll_interval = interval;
         self
@@ -63,4 +130,87 @@ impl StreamConfig {
         self.batch_hint = hint;
         self
     }
+
+    /// Sets the linger time used by `recv_batch` to fill partial batches.
+    #[must_use]
+    pub fn with_batch_linger(mut self, linger: Duration) -> Self {
+        self.batch_linger = linger;
+        self
+    }
+
+    /// Sets the timer behind linger windows and send timeouts.
+    #[must_use]
+    pub fn with_timer(mut self, timer: impl Timer) -> Self {
+        self.timer = Arc::new(timer);
+        self
+    }
+
+    /// Sets the weigher for channels of `T`.
+    ///
+    /// Each item is weighed once, when it is committed; the weight counts
+    /// against [`max_weight`](Self::max_weight) and
+    /// [`max_sender_weight`](Self::max_sender_weight) until the receiver
+    /// drains the item. Without budgets the channel only measures.
+    #[must_use]
+    pub fn with_weigher<T: 'static>(mut self, weigher: impl Weigher<T> + 'static) -> Self {
+        self.weigher = Some(ErasedWeigher::new(weigher));
+        self
+    }
+
+    /// Returns the weigher if it weighs `T`.
+    pub(crate) fn weigher_for<T: 'static>(&self) -> Option<Arc<dyn Weigher<T>>> {
+        self.weigher.as_ref().and_then(ErasedWeigher::get)
+    }
+
+    /// Sets the channel-wide weight budget.
+    #[must_use]
+    pub fn with_max_weight(mut self, max: usize) -> Self {
+        self.max_weight = Some(max);
+        self
+    }
+
+    /// Sets the per-sender weight budget.
+    #[must_use]
+    pub fn with_max_sender_weight(mut self, max: usize) -> Self {
+        self.max_sender_weight = Some(max);
+        self
+    }
+}
+
+/// What a [`RingSender`](crate::RingSender) does when its ring is full (or a
+/// [weight budget](StreamConfig::max_weight) is exhausted).
+///
+/// Chosen per sender via
+/// [`SenderFactory::register_with`](crate::SenderFactory::register_with) and
+/// applied by `send`, `send_cloned` and the `Sink` impl alike. `try_send` is
+/// unaffected: it always fails right away. Drops and timeouts are counted in
+/// [`SendStats`](crate::SendStats).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SendPolicy {
+    /// Wait for the receiver to drain the ring (default).
+    #[default]
+    Block,
+
+    /// Wait at most this long; on timeout the item is handed back
+    /// (`SendError::Timeout`, or [`RingSender::take_pending`](crate::RingSender::take_pending)
+    /// after a `Sink` returns `StreamError::Timeout`).
+    BlockWithTimeout(Duration),
+
+    /// Discard the item being sent and report success.
+    DropNewest,
+
+    /// Keep the newest item in the sender's one-item overflow slot; an item
+    /// already waiting there is discarded and counted.
+    ///
+    /// Items committed to the ring are never evicted (they belong to the
+    /// receiver, and only a drain frees ring slots), so this is "keep latest",
+    /// not drop-oldest: under sustained overflow the ring holds the oldest
+    /// items and the slot the newest. The slot is committed ahead of the next
+    /// send, or by [`RingSender::flush_pending`](crate::RingSender::flush_pending) / `Sink::poll_flush`,
+    /// or when the sender is dropped if the ring has room; otherwise it is
+    /// counted in [`RingReceiver::abandoned`](crate::RingReceiver::abandoned).
+    KeepLatest,
+
+    /// Fail with `SendError::Full` (`StreamError::Full` from `poll_ready`).
+    Fail,
 }
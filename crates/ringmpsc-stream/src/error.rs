@@ -14,6 +14,10 @@ pub enum StreamError {
     #[error("channel is closed")]
     Closed,
 
+    /// `SendPolicy::BlockWithTimeout` expired before the ring had space.
+    #[error("timed out waiting for ring space")]
+    Timeout,
+
     /// Failed to register a new producer.
     #[error("registration failed: {0}")]
     RegistrationFailed(#[from] ChannelError),
@@ -23,12 +27,24 @@ pub enum StreamError {
     ShutDown,
 }
 
+#[cfg(feature = "stack-ring")]
+impl From<ringmpsc_rs::StackChannelError> for StreamError {
+    fn from(err: ringmpsc_rs::StackChannelError) -> Self {
+        match err {
+            ringmpsc_rs::StackChannelError::TooManyProducers { max } => {
+                Self::RegistrationFailed(ChannelError::TooManyProducers { max })
+            }
+            ringmpsc_rs::StackChannelError::Closed => Self::Closed,
+        }
+    }
+}
+
 impl StreamError {
-    /// Returns `true` if this is a recoverable error (e.g., `Full`).
+    /// Returns `true` if this is a recoverable error (`Full` or `Timeout`).
     #[inline]
     #[must_use] 
     pub fn is_recoverable(&self) -> bool {
-        matches!(self, Self::Full)
+        matches!(self, Self::Full | Self::Timeout)
     }
 
     /// Returns `true` if this error indicates the channel is permanently unusable.
@@ -38,3 +54,46 @@ impl StreamError {
         matches!(self, Self::Closed | Self::ShutDown)
     }
 }
+
+/// Error returned by [`RingSender::send`](crate::RingSender::send), handing
+/// the unsent item back (INV-SINK-01).
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum SendError<T> {
+    /// The ring was full and the sender's policy is `SendPolicy::Fail`.
+    #[error("ring buffer is full")]
+    Full(T),
+
+    /// `SendPolicy::BlockWithTimeout` expired before the ring had space.
+    #[error("timed out waiting for ring space")]
+    Timeout(T),
+
+    /// The channel has been closed.
+    #[error("channel is closed")]
+    Closed(T),
+}
+
+impl<T> SendError<T> {
+    /// Returns the item that could not be sent.
+    #[must_use]
+    pub fn into_inner(self) -> T {
+        match self {
+            Self::Full(item) | Self::Timeout(item) | Self::Closed(item) => item,
+        }
+    }
+
+    /// Returns the corresponding [`StreamError`], dropping the item.
+    #[must_use]
+    pub fn kind(&self) -> StreamError {
+        match self {
+            Self::Full(_) => StreamError::Full,
+            Self::Timeout(_) => StreamError::Timeout,
+            Self::Closed(_) => StreamError::Closed,
+        }
+    }
+}
+
+impl<T> From<SendError<T>> for StreamError {
+    fn from(err: SendError<T>) -> Self {
+        err.kind()
+    }
+}
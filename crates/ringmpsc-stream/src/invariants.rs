@@ -8,20 +8,11 @@
 // INV-STREAM-03: Backpressure Relief Signaling
 // =============================================================================
 
-/// Assert that backpressure was signaled after draining items.
-///
-/// **Invariant**: `consume_count > 0 → backpressure_notify.notify_waiters()`
-///
-/// Used in: `RingReceiver::poll_next()` after consuming items
-macro_rules! debug_assert_backpressure_signaled {
-    ($drained:expr, $signaled:expr) => {
-        debug_assert!(
-            $drained == 0 || $signaled,
-            "INV-STREAM-03 violated: drained {} items but did not signal backpressure relief",
-            $drained
-        )
-    };
-}
+// **Invariant**: `consume_count > 0 → drained ring's producer waker notified`
+//
+// Structural: every ringmpsc `consume_*` call advances the ring head, and with
+// `Channel::with_wakers` the advance notifies that ring's producer waker slot
+// (ringmpsc INV-WAKE-01). No runtime macro is needed.
 
 // =============================================================================
 // INV-STREAM-04: Graceful Shutdown Drain
@@ -47,14 +38,14 @@ macro_rules! debug_assert_shutdown_drained {
 
 /// Assert that the post-registration re-drain caught items in the race window.
 ///
-/// **Invariant**: After registering our waker via `data_notified.poll(cx)`,
-/// we must re-drain the ring buffer. Any items found were pushed between our
-/// last drain and the waker registration — the classic lost-wakeup window.
+/// **Invariant**: After registering our waker in the rings' consumer waker
+/// slots, we must re-drain the ring buffer. Any items found were pushed between
+/// our last drain and the waker registration — the classic lost-wakeup window.
 ///
 /// This assertion fires only when items ARE found, confirming the recheck
 /// was necessary and caught real data. Zero items means no race occurred.
 ///
-/// Used in: `RingReceiver::poll_next()` after `data_notified.poll()` → Pending
+/// Used in: `RingReceiver::poll_next()` after `Channel::register_consumer_waker()`
 macro_rules! debug_assert_recheck_after_register {
     ($recheck_count:expr) => {
         debug_assert!(
@@ -65,31 +56,6 @@ macro_rules! debug_assert_recheck_after_register {
     };
 }
 
-// =============================================================================
-// INV-STREAM-06: Timer Tick Loop (Waker Registration Guarantee)
-// =============================================================================
-
-/// Assert that the timer `poll_tick` loop consumed at least one tick before
-/// reaching the Pending state that registers the waker.
-///
-/// **Invariant**: `poll_tick()` is called in a loop until it returns `Pending`.
-/// `Interval::poll_tick()` only registers the waker when returning `Pending`.
-/// If only called once and it returns `Ready` (first tick fires immediately),
-/// the waker is never registered and the timer is permanently dead.
-///
-/// This is a structural/documentation assertion — the loop in `receiver.rs`
-/// guarantees this by construction. The assert documents the requirement.
-///
-/// Used in: `RingReceiver::poll_next()` timer polling loop
-#[allow(unused_macros)]
-macro_rules! debug_assert_timer_pending_reached {
-    () => {
-        // Structural assertion: this macro is placed at the `Pending` branch
-        // of the poll_tick loop, confirming the waker was registered.
-        // The loop structure guarantees we always reach here.
-    };
-}
-
 // =============================================================================
 // INV-SINK-01: No Item Loss on Backpressure
 // =============================================================================
@@ -122,16 +88,29 @@ macro_rules! debug_assert_item_preserved {
 // INV-SINK-03: Data Arrival Notification
 // =============================================================================
 
-/// Assert that data arrival was notified after successful send.
+// **Invariant**: `successful_send → receiver's consumer waker notified`
+//
+// Structural: every send goes through `Reservation::commit()`, and with
+// `Channel::with_wakers` the commit notifies the ring's consumer waker slot
+// (ringmpsc INV-WAKE-01). No runtime macro is needed.
+
+// =============================================================================
+// INV-SINK-06: Weight Budget
+// =============================================================================
+
+/// Assert that a drain releases no more weight than its sender was charged.
 ///
-/// **Invariant**: `successful_send → data_notify.notify_one()`
+/// **Invariant**: `released(ring) ≤ charged(ring)` (each item releases its stored weight)
 ///
-/// Used in: `RingSender::try_send()`, `RingSender::send()`, Sink impl
-macro_rules! debug_assert_data_notified {
-    ($send_success:expr, $notified:expr) => {
+/// Used in: `WeightGauge::release()`
+macro_rules! debug_assert_weight_released {
+    ($outstanding:expr, $released:expr) => {
         debug_assert!(
-            !$send_success || $notified,
-            "INV-SINK-03 violated: send succeeded but data_notify was not called"
+            $outstanding >= $released,
+            "INV-SINK-06 violated: released {} but only {} was outstanding \
+             (stored weight out of step with the ring?)",
+            $released,
+            $outstanding
         )
     };
 }
@@ -169,9 +148,9 @@ macro_rules! debug_assert_explicit_registration {
 // INV-SHUT-01: Shutdown Signaled
 // =============================================================================
 
-/// Assert that shutdown was signaled via oneshot.
+/// Assert that shutdown was signaled to the receiver.
 ///
-/// **Invariant**: `shutdown() → shutdown_tx.send(())`
+/// **Invariant**: `shutdown() → shutdown_initiated = true (observed by the receiver)`
 macro_rules! debug_assert_shutdown_signaled {
     ($shutdown_called:expr, $signal_sent:expr) => {
         debug_assert!(
@@ -187,7 +166,7 @@ macro_rules! debug_assert_shutdown_signaled {
 
 /// Assert that blocked senders were woken during shutdown.
 ///
-/// **Invariant**: `shutdown → backpressure_notify.notify_waiters()`
+/// **Invariant**: `shutdown → close every ring → producer waker slots notified`
 macro_rules! debug_assert_senders_woken {
     ($shutdown:expr, $woken:expr) => {
         debug_assert!(
@@ -197,16 +176,32 @@ macro_rules! debug_assert_senders_woken {
     };
 }
 
+// =============================================================================
+// INV-SHUT-04: Deadline-Bounded Shutdown
+// =============================================================================
+
+/// Assert that a shutdown which settled before its deadline left every ring empty.
+///
+/// **Invariant**: `every sender dropped → final drain empties every ring`
+macro_rules! debug_assert_shutdown_report_complete {
+    ($timed_out:expr, $rings_empty:expr) => {
+        debug_assert!(
+            $timed_out || $rings_empty,
+            "INV-SHUT-04 violated: shutdown settled with no live sender but a ring still held items"
+        )
+    };
+}
+
 // =============================================================================
 // Re-exports for crate-internal use
 // =============================================================================
 
-pub(crate) use debug_assert_backpressure_signaled;
-pub(crate) use debug_assert_data_notified;
 pub(crate) use debug_assert_recheck_after_register;
 pub(crate) use debug_assert_explicit_registration;
 pub(crate) use debug_assert_item_preserved;
 pub(crate) use debug_assert_senders_woken;
 pub(crate) use debug_assert_shutdown_drained;
+pub(crate) use debug_assert_shutdown_report_complete;
 pub(crate) use debug_assert_shutdown_signaled;
+pub(crate) use debug_assert_weight_released;
 // debug_assert_single_producer is not exported - it's compile-time enforced via !Clone
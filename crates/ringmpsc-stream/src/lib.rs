@@ -5,10 +5,28 @@
 //!
 //! # Features
 //!
-//! - **Hybrid polling**: Event-driven via `Notify` + configurable poll interval as safety net
-//! - **Backpressure**: Senders await when ring is full, woken when space available
-//! - **Graceful shutdown**: Internal oneshot + composable with `take_until` for flexibility
+//! - **Event-driven**: Receiver and senders park in the rings' waker slots — no poll timer
+//! - **Backpressure**: Senders await when ring is full, woken when their ring is drained
+//! - **Send policies**: Per-sender [`SendPolicy`] — block, block with timeout, drop newest, keep latest, or fail
+//! - **Weight budgets**: [`StreamConfig::with_weigher`] and [`channel_with_weigher`] bound queued bytes, not just slots
+//! - **Per-producer substreams**: [`RingReceiver::into_per_producer`] yields one stream per sender's ring
+//! - **Batch receive**: [`RingReceiver::recv_batch`] and [`RingReceiver::into_batch_stream`] with an optional linger
+//! - **Graceful shutdown**: Drains before ending; [`ShutdownSignal::wait`] composes with `take_until`;
+//!   [`RingReceiver::shutdown_with_deadline`] returns leftovers in a [`ShutdownReport`]
+//! - **Request/reply**: [`rpc`] pairs [`RpcClient::call`] with an [`RpcServer`] stream of requests and [`Responder`]s
 //! - **Zero-copy path**: Inherits ringmpsc's ownership transfer semantics
+//! - **Runtime-agnostic**: Only `futures` wakers plus a pluggable [`Timer`]
+//!
+//! # Cargo Features
+//!
+//! - `tokio` (default): [`TokioTimer`], the default [`AutoTimer`] (Tokio's
+//!   timer inside a Tokio runtime, [`ThreadTimer`] elsewhere) and the
+//!   `tokio_stream::StreamExt` re-export. Without it the crate has no Tokio
+//!   dependency and defaults to [`ThreadTimer`], so it runs on smol,
+//!   async-std or `futures::executor`.
+//! - `stack-ring`: [`stack_channel`] — `StackRingReceiver`/`StackRingSender`
+//!   over a caller-owned `StackChannel` (e.g. a `static`), for pipelines that
+//!   allocate nothing per item.
 //!
 //! # Example
 //!
@@ -40,16 +58,34 @@ mod channel;
 mod config;
 mod error;
 mod invariants;
+mod per_producer;
 mod receiver;
+mod rpc;
 mod sender;
 mod shutdown;
+#[cfg(feature = "stack-ring")]
+mod stack;
+mod timer;
+mod weight;
 
-pub use channel::{channel, channel_with_stream_config, SenderFactory};
-pub use config::StreamConfig;
-pub use error::StreamError;
+pub use channel::{channel, channel_with_stream_config, channel_with_weigher, SenderFactory};
+pub use config::{SendPolicy, StreamConfig};
+pub use error::{SendError, StreamError};
+pub use per_producer::{PerProducer, ProducerStream};
 pub use receiver::RingReceiver;
-pub use sender::RingSender;
-pub use shutdown::ShutdownSignal;
+pub use rpc::{rpc, rpc_with_config, Responder, RpcClient, RpcClientFactory, RpcConfig, RpcServer};
+pub use sender::{RingSender, SendPermit, SendStats};
+pub use shutdown::{ShutdownReport, ShutdownSignal};
+#[cfg(feature = "stack-ring")]
+pub use stack::{
+    stack_channel, stack_channel_with_stream_config, StackRingReceiver, StackRingSender,
+    StackSenderFactory,
+};
+#[cfg(feature = "tokio")]
+pub use timer::{AutoTimer, TokioTimer};
+pub use timer::{Sleep, ThreadTimer, Timer};
+pub use weight::{ErasedWeigher, WeightStats, Weigher};
 
 // Re-export useful stream combinators
+#[cfg(feature = "tokio")]
 pub use tokio_stream::StreamExt;
@@ -0,0 +1,196 @@
+//! Per-producer substreams: one `Stream` per sender's ring.
+
+use crate::shutdown::ShutdownState;
+use crate::weight::{consume_ring, WeightGauge};
+use ringmpsc_rs::Channel;
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+
+/// Stream of per-producer substreams, from
+/// [`RingReceiver::into_per_producer`](crate::RingReceiver::into_per_producer).
+///
+/// Yields `(producer_id, ProducerStream<T>)` once for every sender, in
+/// registration order, including senders registered later. Ends once the
+/// channel is closed (`SenderFactory::close` or shutdown) and every
+/// registered sender has been handed out.
+pub struct PerProducer<T> {
+    channel: Arc<Channel<T>>,
+    shutdown_state: Arc<ShutdownState>,
+    batch_hint: usize,
+    next_id: usize,
+    weights: Option<Arc<WeightGauge<T>>>,
+}
+
+impl<T: Send + 'static> PerProducer<T> {
+    pub(crate) fn new(
+        channel: Arc<Channel<T>>,
+        shutdown_state: Arc<ShutdownState>,
+        batch_hint: usize,
+        weights: Option<Arc<WeightGauge<T>>>,
+    ) -> Self {
+        Self {
+            channel,
+            shutdown_state,
+            batch_hint,
+            next_id: 0,
+            weights,
+        }
+    }
+
+    /// Returns the number of substreams handed out so far.
+    #[must_use]
+    pub fn yielded(&self) -> usize {
+        self.next_id
+    }
+
+    fn next_substream(&mut self) -> Option<(usize, ProducerStream<T>)> {
+        if self.next_id >= self.channel.producer_count() {
+            return None;
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        let substream = ProducerStream::new(
+            Arc::clone(&self.channel),
+            id,
+            self.batch_hint,
+            self.weights.clone(),
+        );
+        Some((id, substream))
+    }
+}
+
+impl<T: Send + 'static> Stream for PerProducer<T> {
+    type Item = (usize, ProducerStream<T>);
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        let mut registered = false;
+        loop {
+            // Read `closed` first: a sender registered before the close is
+            // then visible to `next_substream` below.
+            let closed = this.shutdown_state.is_closed();
+            if let Some(substream) = this.next_substream() {
+                return Poll::Ready(Some(substream));
+            }
+            if closed {
+                return Poll::Ready(None);
+            }
+            if registered {
+                return Poll::Pending;
+            }
+            // Register-then-recheck (INV-STREAM-05): woken by the next
+            // registration or by close.
+            this.shutdown_state.register_registration(cx.waker());
+            registered = true;
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for PerProducer<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PerProducer")
+            .field("next_id", &self.next_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Items of a single sender, read from that sender's ring only.
+///
+/// Yields the sender's items in FIFO order (INV-STREAM-01). Draining wakes
+/// only this sender, so each substream applies its own backpressure.
+/// Ends once the ring is closed — by `RingSender::close`, dropping the
+/// sender, or closing the channel — and drained.
+pub struct ProducerStream<T> {
+    channel: Arc<Channel<T>>,
+    id: usize,
+    batch_hint: usize,
+    buffer: VecDeque<T>,
+    done: bool,
+    weights: Option<Arc<WeightGauge<T>>>,
+}
+
+impl<T: Send + 'static> ProducerStream<T> {
+    fn new(
+        channel: Arc<Channel<T>>,
+        id: usize,
+        batch_hint: usize,
+        weights: Option<Arc<WeightGauge<T>>>,
+    ) -> Self {
+        Self {
+            channel,
+            id,
+            batch_hint,
+            buffer: VecDeque::with_capacity(batch_hint),
+            done: false,
+            weights,
+        }
+    }
+
+    /// Returns the producer id (the sender's [`RingSender::id`](crate::RingSender::id)).
+    #[must_use]
+    pub fn producer_id(&self) -> usize {
+        self.id
+    }
+}
+
+// Items are never pinned in place; the buffer only moves them.
+impl<T> Unpin for ProducerStream<T> {}
+
+impl<T: Send + 'static> Stream for ProducerStream<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if let Some(item) = this.buffer.pop_front() {
+            return Poll::Ready(Some(item));
+        }
+        if this.done {
+            return Poll::Ready(None);
+        }
+
+        let mut registered = false;
+        loop {
+            let ring = this
+                .channel
+                .get_ring(this.id)
+                .expect("substream id is below producer_count");
+            // Read `closed` before draining, so the drain sees every item
+            // committed before the close.
+            let closed = ring.is_closed();
+            let buffer = &mut this.buffer;
+            let weights = this.weights.as_deref();
+            let drained = consume_ring(ring, this.id, weights, this.batch_hint, |item| {
+                buffer.push_back(item);
+            });
+            if drained > 0 {
+                return Poll::Ready(this.buffer.pop_front());
+            }
+            if closed {
+                this.done = true;
+                return Poll::Ready(None);
+            }
+            if registered {
+                return Poll::Pending;
+            }
+            // INV-STREAM-05: woken by the next commit or the ring's close
+            ring.register_consumer_waker(cx.waker());
+            registered = true;
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for ProducerStream<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProducerStream")
+            .field("id", &self.id)
+            .field("buffered", &self.buffer.len())
+            .field("done", &self.done)
+            .finish_non_exhaustive()
+    }
+}
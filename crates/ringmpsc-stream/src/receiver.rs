@@ -3,17 +3,20 @@
 use crate::config::StreamConfig;
 #[cfg(debug_assertions)]
 use crate::invariants::{
-    debug_assert_backpressure_signaled, debug_assert_recheck_after_register,
-    debug_assert_shutdown_drained,
+    debug_assert_recheck_after_register, debug_assert_shutdown_drained,
+    debug_assert_shutdown_report_complete,
 };
-use crate::shutdown::{ShutdownHandle, ShutdownSignal, ShutdownState};
+use crate::per_producer::PerProducer;
+use crate::shutdown::{ShutdownReport, ShutdownSignal, ShutdownState};
+use crate::timer::{timeout, Sleep};
+use crate::weight::{consume_ring, consume_up_to, WeightGauge, WeightStats};
 use ringmpsc_rs::Channel;
 use std::collections::VecDeque;
+use std::future::poll_fn;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
-use tokio::sync::{oneshot, Notify};
-use tokio::time::{interval, Interval};
+use std::time::Duration;
 
 use futures_core::{Future, Stream};
 use pin_project_lite::pin_project;
@@ -21,13 +24,16 @@ use pin_project_lite::pin_project;
 pin_project! {
     /// Async stream receiver wrapping a ringmpsc `Channel`.
     ///
-    /// Implements `futures::Stream` with hybrid event-driven + polling strategy.
-    /// Items are yielded in per-producer FIFO order (inherits from ringmpsc).
+    /// Implements `futures::Stream` with purely event-driven wakeups: when the
+    /// channel is empty the receiver parks in every ring's consumer waker slot
+    /// and is woken by the next commit, or by close/shutdown. There is no
+    /// poll timer. Items are yielded in per-producer FIFO order (inherits from
+    /// ringmpsc).
     ///
     /// # Backpressure
     ///
-    /// After draining items, the receiver calls `notify_waiters()` on the
-    /// backpressure notify to wake any blocked senders.
+    /// Draining a ring advances its head, which wakes that ring's sender if it
+    /// is parked waiting for space.
     ///
     /// # Shutdown
     ///
@@ -36,17 +42,12 @@ pin_project! {
     /// for external cancellation control.
     pub struct RingReceiver<T> {
         channel: Arc<Channel<T>>,
-        data_notify: Arc<Notify>,
-        backpressure_notify: Arc<Notify>,
         shutdown_state: Arc<ShutdownState>,
-        shutdown_rx: Option<oneshot::Receiver<()>>,
-        shutdown_handle: Option<ShutdownHandle>,
         config: StreamConfig,
-        #[pin]
-        poll_timer: Interval,
         buffer: VecDeque<T>,
-        data_pending: bool,
         drain_complete: bool,
+        // Released as items leave the rings (`StreamConfig::weigher`).
+        weights: Option<Arc<WeightGauge<T>>>,
     }
 }
 
@@ -54,30 +55,17 @@ impl<T: Send + 'static> RingReceiver<T> {
     /// Creates a new receiver wrapping the given channel.
     pub(crate) fn new(
         channel: Arc<Channel<T>>,
-        data_notify: Arc<Notify>,
-        backpressure_notify: Arc<Notify>,
         shutdown_state: Arc<ShutdownState>,
         config: StreamConfig,
+        weights: Option<Arc<WeightGauge<T>>>,
     ) -> Self {
-        let (shutdown_tx, shutdown_rx) = oneshot::channel();
-        let shutdown_handle = ShutdownHandle {
-            shutdown_tx: Some(shutdown_tx),
-            state: Arc::clone(&shutdown_state),
-            backpressure_notify: Arc::clone(&backpressure_notify),
-        };
-
         Self {
             channel,
-            data_notify,
-            backpressure_notify,
             shutdown_state,
-            shutdown_rx: Some(shutdown_rx),
-            shutdown_handle: Some(shutdown_handle),
-            config: config.clone(),
-            poll_timer: interval(config.poll_interval),
             buffer: VecDeque::with_capacity(config.batch_hint),
-            data_pending: false,
+            config,
             drain_complete: false,
+            weights,
         }
     }
 
@@ -94,11 +82,85 @@ impl<T: Send + 'static> RingReceiver<T> {
     /// After calling this, continue polling the stream to receive
     /// remaining items until it returns `None`.
     pub fn shutdown(&mut self) {
-        if let Some(ref mut handle) = self.shutdown_handle {
-            handle.trigger();
+        self.shutdown_state.shutdown();
+    }
+
+    /// Shuts down and hands back everything not yet yielded, waiting at
+    /// most `deadline`.
+    ///
+    /// This will:
+    /// 1. Close the channel for new registrations
+    /// 2. Close every ring, so blocked senders wake and fail with
+    ///    `Closed` (their item is returned to them)
+    /// 3. Drain the receiver's buffer and every ring into the report, again
+    ///    and again until a pass finds every ring empty or `deadline` elapses
+    ///
+    /// Unlike [`shutdown`](Self::shutdown), items are not yielded through
+    /// the stream; they are returned in the [`ShutdownReport`] so the caller
+    /// can persist them. Senders are not waited for: once the rings are
+    /// closed no new send can commit, so an idle sender held by a long-lived
+    /// task does not delay the shutdown. Senders still alive are counted in
+    /// `senders_outstanding`.
+    pub async fn shutdown_with_deadline(mut self, deadline: Duration) -> ShutdownReport<T> {
+        self.shutdown_state.shutdown();
+
+        let channel = &self.channel;
+        let weights = self.weights.as_deref();
+        let mut per_producer: Vec<Vec<T>> =
+            (0..channel.producer_count()).map(|_| Vec::new()).collect();
+        // A commit that reserved just before the close can still land after
+        // a pass, so keep draining until a pass leaves every ring empty.
+        // INV-STREAM-05: Register-then-drain. Between passes the task parks
+        // in every ring's consumer waker slot, and a late commit wakes it
+        // (ringmpsc INV-WAKE-01); the deadline fires if none ever lands.
+        let drained = poll_fn(|cx| {
+            channel.register_consumer_waker(cx.waker());
+            for (id, items) in per_producer.iter_mut().enumerate() {
+                if let Some(ring) = channel.get_ring(id) {
+                    consume_ring(ring, id, weights, usize::MAX, |item| items.push(item));
+                }
+            }
+            if (0..channel.producer_count())
+                .filter_map(|id| channel.get_ring(id))
+                .all(ringmpsc_rs::Ring::is_empty)
+            {
+                return Poll::Ready(());
+            }
+            Poll::Pending
+        });
+        let timed_out = timeout(&*self.config.timer, deadline, drained)
+            .await
+            .is_none();
+        self.drain_complete = true;
+
+        let senders_outstanding = self.shutdown_state.live_senders();
+        // INV-SHUT-04: Verify a settled shutdown left nothing behind
+        #[cfg(debug_assertions)]
+        debug_assert_shutdown_report_complete!(
+            timed_out || senders_outstanding > 0,
+            (0..self.channel.producer_count())
+                .filter_map(|id| self.channel.get_ring(id))
+                .all(ringmpsc_rs::Ring::is_empty)
+        );
+
+        ShutdownReport {
+            buffered: self.buffer.drain(..).collect(),
+            per_producer,
+            timed_out,
+            senders_outstanding,
+            abandoned: self.shutdown_state.abandoned(),
         }
     }
 
+    /// Returns the number of items lost because their sender was dropped
+    /// while still holding them — a `KeepLatest` overflow slot or a `Sink`'s
+    /// pending item — and its ring was full or closed. A dropped sender
+    /// commits such an item if its ring has room.
+    #[must_use]
+    pub fn abandoned(&self) -> u64 {
+        self.shutdown_state.abandoned()
+    }
+
     /// Returns `true` if the stream has been shut down.
     #[must_use] 
     pub fn is_shutdown(&self) -> bool {
@@ -125,10 +187,7 @@ impl<T: Send + 'static> RingReceiver<T> {
     /// ```
     #[must_use] 
     pub fn shutdown_signal(&self) -> ShutdownSignal {
-        ShutdownSignal::new(
-            Arc::clone(&self.shutdown_state),
-            Arc::clone(&self.backpressure_notify),
-        )
+        ShutdownSignal::new(Arc::clone(&self.shutdown_state))
     }
 
     /// Returns the number of items currently buffered.
@@ -136,13 +195,222 @@ impl<T: Send + 'static> RingReceiver<T> {
     pub fn buffered_count(&self) -> usize {
         self.buffer.len()
     }
+
+    /// Returns the weight still queued in the rings, or `None` unless the
+    /// channel weighs items (see [`StreamConfig::weigher`]).
+    ///
+    /// Buffered items (`buffered_count`) have already been released.
+    #[must_use]
+    pub fn weight_stats(&self) -> Option<WeightStats> {
+        self.weights
+            .as_ref()
+            .map(|weights| weights.stats(self.channel.producer_count()))
+    }
+
+    /// Receives up to `max` items into `out`, waiting until at least one is
+    /// available.
+    ///
+    /// Items move straight from the rings into `out` (appended, existing
+    /// contents untouched), without the per-item `poll_next` path. Once the
+    /// first item arrives, the call keeps collecting until `max` items are
+    /// gathered or [`StreamConfig::batch_linger`] elapses; with the default
+    /// zero linger it returns whatever one drain produced.
+    ///
+    /// Returns the number of items appended. `0` means the stream has ended
+    /// (shutdown drained, or channel closed and empty), exactly where
+    /// `poll_next` would return `None`.
+    ///
+    /// # Cancel safety
+    ///
+    /// Items are appended to `out` as they are drained, so dropping the
+    /// future (e.g. in `tokio::select!`) loses nothing: whatever was received
+    /// is already in `out`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is zero.
+    pub fn recv_batch<'a>(
+        &'a mut self,
+        out: &'a mut Vec<T>,
+        max: usize,
+    ) -> impl Future<Output = usize> + 'a {
+        assert!(max > 0, "recv_batch: max must be non-zero");
+        let start = out.len();
+        let mut linger = None;
+        poll_fn(move |cx| self.poll_recv_batch(cx, out, start, max, &mut linger))
+    }
+
+    /// Converts the receiver into a stream of batches of at most `max` items.
+    ///
+    /// Each batch is produced by [`recv_batch`](Self::recv_batch), so it
+    /// honours [`StreamConfig::batch_linger`] and ends after the shutdown
+    /// drain. Batches are never empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is zero.
+    pub fn into_batch_stream(self, max: usize) -> impl Stream<Item = Vec<T>> {
+        assert!(max > 0, "into_batch_stream: max must be non-zero");
+        futures_util::stream::unfold(self, move |mut rx| async move {
+            let mut batch = Vec::with_capacity(max);
+            (rx.recv_batch(&mut batch, max).await > 0).then_some((batch, rx))
+        })
+    }
+
+    /// Splits the receiver into one substream per sender.
+    ///
+    /// The returned stream yields `(producer_id, ProducerStream<T>)` for every
+    /// registered sender and for each sender registered later. Each substream
+    /// reads only its own ring (FIFO, independent backpressure) and ends when
+    /// its sender closes or is dropped, or the channel is closed. The id
+    /// matches [`RingSender::id`](crate::RingSender::id).
+    ///
+    /// ```ignore
+    /// let mut producers = rx.into_per_producer();
+    /// while let Some((id, substream)) = producers.next().await {
+    ///     tokio::spawn(async move { handle_tenant(id, substream).await });
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if items are buffered from the merged stream
+    /// ([`buffered_count`](Self::buffered_count) > 0), since their producer
+    /// is unknown. Split before consuming, or take them first with
+    /// `recv_batch(&mut out, rx.buffered_count())`.
+    #[must_use]
+    pub fn into_per_producer(self) -> PerProducer<T> {
+        assert!(
+            self.buffer.is_empty(),
+            "into_per_producer: {} merged items still buffered",
+            self.buffer.len()
+        );
+        PerProducer::new(
+            self.channel,
+            self.shutdown_state,
+            self.config.batch_hint,
+            self.weights,
+        )
+    }
+
+    /// Poll body of [`recv_batch`](Self::recv_batch).
+    ///
+    /// `out[start..]` holds what this call has received so far; `linger` is
+    /// the window opened by the first item.
+    fn poll_recv_batch(
+        &mut self,
+        cx: &mut Context<'_>,
+        out: &mut Vec<T>,
+        start: usize,
+        max: usize,
+        linger: &mut Option<Sleep>,
+    ) -> Poll<usize> {
+        let target = start + max;
+
+        // Items buffered by an earlier `poll_next` come first.
+        while out.len() < target {
+            match self.buffer.pop_front() {
+                Some(item) => out.push(item),
+                None => break,
+            }
+        }
+        if out.len() == target || self.drain_complete {
+            return Poll::Ready(out.len() - start);
+        }
+
+        // Shutdown: everything committed so far ends up in `out` or the buffer.
+        if self.shutdown_state.is_shutdown_initiated() {
+            self.final_drain_into(out, target);
+            self.drain_complete = true;
+
+            // INV-STREAM-04: Verify drain complete before the stream ends
+            #[cfg(debug_assertions)]
+            debug_assert_shutdown_drained!(true, self.drain_complete);
+
+            return Poll::Ready(out.len() - start);
+        }
+
+        let mut registered = false;
+        loop {
+            let drained = consume_up_to(
+                &self.channel,
+                self.weights.as_deref(),
+                target - out.len(),
+                |item| out.push(item),
+            );
+            if registered && drained > 0 {
+                // INV-STREAM-05: Verify recheck caught items
+                #[cfg(debug_assertions)]
+                debug_assert_recheck_after_register!(drained);
+            }
+            if out.len() == target {
+                return Poll::Ready(max);
+            }
+
+            if out.len() > start {
+                if self.config.batch_linger.is_zero() {
+                    return Poll::Ready(out.len() - start);
+                }
+                let window =
+                    linger.get_or_insert_with(|| self.config.timer.sleep(self.config.batch_linger));
+                if window.as_mut().poll(cx).is_ready() {
+                    return Poll::Ready(out.len() - start);
+                }
+            }
+
+            if registered {
+                // INV-STREAM-07: Closed — take what was committed, stop lingering
+                if self.shutdown_state.is_closed() {
+                    self.final_drain_into(out, target);
+                    return Poll::Ready(out.len() - start);
+                }
+                return Poll::Pending;
+            }
+
+            // INV-STREAM-05: Register-then-recheck, as in `poll_next`.
+            self.shutdown_state.register_receiver(cx.waker());
+            self.channel.register_consumer_waker(cx.waker());
+            registered = true;
+        }
+    }
+
+    /// Drains every ring: up to `target` items into `out`, the rest into the
+    /// internal buffer for the next call.
+    fn final_drain_into(&mut self, out: &mut Vec<T>, target: usize) {
+        let buffer = &mut self.buffer;
+        consume_up_to(&self.channel, self.weights.as_deref(), usize::MAX, |item| {
+            if out.len() < target {
+                out.push(item);
+            } else {
+                buffer.push_back(item);
+            }
+        });
+    }
+}
+
+/// Moves up to `batch_hint - buffer.len()` items from the channel into `buffer`.
+///
+/// Consuming advances each drained ring's head, which wakes that ring's sender
+/// if it is parked on a full ring (INV-STREAM-03), and releases the drained
+/// weight (INV-SINK-06).
+fn drain_batch<T>(
+    channel: &Channel<T>,
+    weights: Option<&WeightGauge<T>>,
+    buffer: &mut VecDeque<T>,
+    batch_hint: usize,
+) -> usize {
+    let batch_limit = batch_hint.saturating_sub(buffer.len());
+    if batch_limit == 0 {
+        return 0;
+    }
+    consume_up_to(channel, weights, batch_limit, |item| buffer.push_back(item))
 }
 
 impl<T: Send + 'static> Stream for RingReceiver<T> {
     type Item = T;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        let mut this = self.project();
+        let this = self.project();
 
         // If we have buffered items, yield one
         if let Some(item) = this.buffer.pop_front() {
@@ -155,162 +423,59 @@ impl<T: Send + 'static> Stream for RingReceiver<T> {
         }
 
         // Check for shutdown signal
-        if let Some(ref mut rx) = this.shutdown_rx {
-            match Pin::new(rx).poll(cx) {
-                Poll::Ready(_) => {
-                    // Shutdown signaled - perform final drain
-                    *this.shutdown_rx = None;
-
-                    // Drain all remaining items
-                    let mut drained = 0usize;
-                    this.channel.consume_all_owned(|item| {
-                        this.buffer.push_back(item);
-                        drained += 1;
-                    });
-                    this.backpressure_notify.notify_waiters();
-                    *this.drain_complete = true;
-
-                    // INV-STREAM-03: Verify backpressure was signaled
-                    #[cfg(debug_assertions)]
-                    debug_assert_backpressure_signaled!(drained, true);
-
-                    // INV-STREAM-04: Verify drain complete before returning None
-                    #[cfg(debug_assertions)]
-                    debug_assert_shutdown_drained!(true, *this.drain_complete);
-
-                    // Yield buffered item if any
-                    if let Some(item) = this.buffer.pop_front() {
-                        return Poll::Ready(Some(item));
-                    }
-                    return Poll::Ready(None);
-                }
-                Poll::Pending => {}
-            }
-        }
+        if this.shutdown_state.is_shutdown_initiated() {
+            // Shutdown signaled - perform final drain
+            consume_up_to(this.channel, this.weights.as_deref(), usize::MAX, |item| {
+                this.buffer.push_back(item);
+            });
+            *this.drain_complete = true;
 
-        // Hybrid polling: check data notify
-        if *this.data_pending {
-            *this.data_pending = false;
-            let batch_limit = this.config.batch_hint.saturating_sub(this.buffer.len());
-            if batch_limit > 0 {
-                let mut drained = 0usize;
-                this.channel.consume_all_up_to_owned(batch_limit, |item| {
-                    this.buffer.push_back(item);
-                    drained += 1;
-                });
-                let signaled = !this.buffer.is_empty();
-                if signaled {
-                    this.backpressure_notify.notify_waiters();
-                }
-                // INV-STREAM-03: Verify backpressure signaled after drain
-                #[cfg(debug_assertions)]
-                debug_assert_backpressure_signaled!(drained, signaled || drained == 0);
-            }
-        }
+            // INV-STREAM-04: Verify drain complete before returning None
+            #[cfg(debug_assertions)]
+            debug_assert_shutdown_drained!(true, *this.drain_complete);
 
-        // Yield buffered item if any
-        if let Some(item) = this.buffer.pop_front() {
-            return Poll::Ready(Some(item));
+            return Poll::Ready(this.buffer.pop_front());
         }
 
-        // Register for data notification
-        let data_notified = this.data_notify.notified();
-        tokio::pin!(data_notified);
-
-        // Check if data arrived while we were setting up
-        match data_notified.as_mut().poll(cx) {
-            Poll::Ready(()) => {
-                *this.data_pending = true;
-                // Re-poll to process the notification
-                cx.waker().wake_by_ref();
-                return Poll::Pending;
-            }
-            Poll::Pending => {}
+        // Drain whatever is already committed
+        let drained = drain_batch(
+            this.channel,
+            this.weights.as_deref(),
+            this.buffer,
+            this.config.batch_hint,
+        );
+        if drained > 0 {
+            return Poll::Ready(this.buffer.pop_front());
         }
 
-        // INV-STREAM-05: Register-then-recheck — drain ring after waker
-        // registration to close the race window where a producer pushes
-        // an item + calls notify_one() between our last drain and the
-        // notified().poll() call above. Without this re-drain, the
-        // notification would wake our task, but on re-entry data_pending
-        // is false so we'd skip the drain and wait for the timer.
-        {
-            let batch_limit = this.config.batch_hint.saturating_sub(this.buffer.len());
-            if batch_limit > 0 {
-                let mut recheck_count = 0;
-                this.channel.consume_all_up_to_owned(batch_limit, |item| {
-                    this.buffer.push_back(item);
-                    recheck_count += 1;
-                });
-                if recheck_count > 0 {
-                    this.backpressure_notify.notify_waiters();
-                    // INV-STREAM-03: Verify backpressure signaled
-                    #[cfg(debug_assertions)]
-                    debug_assert_backpressure_signaled!(recheck_count, true);
-                    // INV-STREAM-05: Verify recheck caught items
-                    #[cfg(debug_assertions)]
-                    debug_assert_recheck_after_register!(recheck_count);
-                    if let Some(item) = this.buffer.pop_front() {
-                        return Poll::Ready(Some(item));
-                    }
-                }
-            }
-        }
+        // INV-STREAM-02 / INV-STREAM-05: Register-then-recheck. Park in every
+        // ring's consumer waker slot (and the close waker), then drain again.
+        // A commit that raced with registration is either seen by the re-drain
+        // or wakes the slot we just registered (ringmpsc INV-WAKE-01). Unlike a
+        // stack-local `Notified`, the slots stay registered after we return
+        // Pending, so commits between polls also wake the task.
+        this.shutdown_state.register_receiver(cx.waker());
+        this.channel.register_consumer_waker(cx.waker());
 
-        // Poll interval timer as safety net.
-        // IMPORTANT: poll_tick only registers the waker when it returns Pending.
-        // If it returns Ready (tick fired), we must loop to consume all elapsed
-        // ticks and eventually get Pending, which registers for the next tick.
-        // Without this loop, after the first Ready the timer is effectively dead
-        // and the task is never re-woken by the timer.
-        #[allow(clippy::while_let_loop)]
-        loop {
-            match this.poll_timer.as_mut().poll_tick(cx) {
-                Poll::Ready(_) => {
-                    // Timer fired - try to drain even without notification
-                    let batch_limit =
-                        this.config.batch_hint.saturating_sub(this.buffer.len());
-                    if batch_limit > 0 {
-                        let mut count = 0;
-                        this.channel.consume_all_up_to_owned(batch_limit, |item| {
-                            this.buffer.push_back(item);
-                            count += 1;
-                        });
-                        if count > 0 {
-                            this.backpressure_notify.notify_waiters();
-                            // INV-STREAM-03: Verify backpressure signaled
-                            #[cfg(debug_assertions)]
-                            debug_assert_backpressure_signaled!(count, true);
-                            if let Some(item) = this.buffer.pop_front() {
-                                return Poll::Ready(Some(item));
-                            }
-                        }
-                    }
-                    // Continue loop to consume more elapsed ticks and register
-                    // for the next one (poll_tick must return Pending to register).
-                }
-                Poll::Pending => break, // Waker registered for next tick (INV-STREAM-06)
-            }
+        let recheck_count = drain_batch(
+            this.channel,
+            this.weights.as_deref(),
+            this.buffer,
+            this.config.batch_hint,
+        );
+        if recheck_count > 0 {
+            // INV-STREAM-05: Verify recheck caught items
+            #[cfg(debug_assertions)]
+            debug_assert_recheck_after_register!(recheck_count);
+            return Poll::Ready(this.buffer.pop_front());
         }
 
-        // Check if channel is closed
+        // INV-STREAM-07: Closed — drain anything committed before close, then end
         if this.shutdown_state.is_closed() {
-            // Try one more drain to ensure we got everything
-            let mut found_any = false;
-            this.channel.consume_all_owned(|item| {
+            consume_up_to(this.channel, this.weights.as_deref(), usize::MAX, |item| {
                 this.buffer.push_back(item);
-                found_any = true;
             });
-            if found_any {
-                this.backpressure_notify.notify_waiters();
-                // INV-STREAM-03: Verify backpressure signaled
-                #[cfg(debug_assertions)]
-                debug_assert_backpressure_signaled!(1, true);
-                if let Some(item) = this.buffer.pop_front() {
-                    return Poll::Ready(Some(item));
-                }
-            }
-            return Poll::Ready(None);
+            return Poll::Ready(this.buffer.pop_front());
         }
 
         Poll::Pending
@@ -0,0 +1,459 @@
+//! Request/reply (RPC) over a stream channel.
+//!
+//! Many tasks call into one owner task and await its response. Each
+//! [`RpcClient`] owns one sender (one ring); the [`RpcServer`] is the
+//! receiver and yields every request together with a [`Responder`] for its
+//! reply.
+
+use crate::channel::{channel_with_stream_config, SenderFactory};
+use crate::config::StreamConfig;
+use crate::error::StreamError;
+use crate::receiver::RingReceiver;
+use crate::sender::RingSender;
+use crate::shutdown::ShutdownSignal;
+use crate::timer::{timeout, Timer};
+use crate::weight::WeightStats;
+use ringmpsc_rs::Config;
+use std::future::{poll_fn, Future};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{ready, Context, Poll, Waker};
+use std::time::Duration;
+
+use futures_channel::oneshot;
+use futures_core::Stream;
+use pin_project_lite::pin_project;
+
+/// A request in flight: the payload and the caller's reply slot.
+struct Call<Req, Resp> {
+    request: Req,
+    reply: oneshot::Sender<Resp>,
+}
+
+/// Configuration for [`rpc_with_config`].
+#[derive(Debug, Clone)]
+pub struct RpcConfig {
+    /// Upper bound for a whole [`RpcClient::call`]: waiting for an in-flight
+    /// slot, for ring space, and for the reply. `None` waits indefinitely.
+    ///
+    /// Default: `None`
+    pub call_timeout: Option<Duration>,
+
+    /// Calls a single client may have outstanding at once; further calls
+    /// wait for one to finish.
+    ///
+    /// Default: 16
+    pub max_in_flight: usize,
+}
+
+impl Default for RpcConfig {
+    fn default() -> Self {
+        Self {
+            call_timeout: None,
+            max_in_flight: 16,
+        }
+    }
+}
+
+impl RpcConfig {
+    /// Sets the default timeout of every call.
+    #[must_use]
+    pub fn with_call_timeout(mut self, limit: Duration) -> Self {
+        self.call_timeout = Some(limit);
+        self
+    }
+
+    /// Sets the per-client bound on outstanding calls (at least 1).
+    #[must_use]
+    pub fn with_max_in_flight(mut self, max: usize) -> Self {
+        self.max_in_flight = max.max(1);
+        self
+    }
+}
+
+/// Creates an RPC channel with default configuration.
+///
+/// # Example
+///
+/// ```ignore
+/// let (clients, mut server) = rpc::<Query, Answer>(Config::default());
+///
+/// tokio::spawn(async move {
+///     while let Some((query, responder)) = server.next().await {
+///         let _ = responder.respond(answer(query));
+///     }
+/// });
+///
+/// let client = clients.register()?;
+/// let answer = client.call(query).await?;
+/// ```
+#[must_use]
+pub fn rpc<Req, Resp>(config: Config) -> (RpcClientFactory<Req, Resp>, RpcServer<Req, Resp>)
+where
+    Req: Send + 'static,
+    Resp: Send + 'static,
+{
+    rpc_with_config(config, StreamConfig::default(), RpcConfig::default())
+}
+
+/// Creates an RPC channel with custom stream and RPC configuration.
+///
+/// `stream_config.timer` drives the call timeouts. Weight budgets bound the
+/// queued requests: a [`StreamConfig::with_weigher`] weigher for `Req`
+/// weighs each request, and without one every call weighs one unit.
+#[must_use]
+pub fn rpc_with_config<Req, Resp>(
+    config: Config,
+    stream_config: StreamConfig,
+    rpc_config: RpcConfig,
+) -> (RpcClientFactory<Req, Resp>, RpcServer<Req, Resp>)
+where
+    Req: Send + 'static,
+    Resp: Send + 'static,
+{
+    let timer = Arc::clone(&stream_config.timer);
+    let stream_config = match stream_config.weigher_for::<Req>() {
+        Some(weigher) => stream_config
+            .with_weigher(move |call: &Call<Req, Resp>| weigher.weigh(&call.request)),
+        None => stream_config,
+    };
+    let (senders, receiver) = channel_with_stream_config(config, stream_config);
+    let factory = RpcClientFactory {
+        senders,
+        timer,
+        config: rpc_config,
+    };
+    (factory, RpcServer::new(receiver))
+}
+
+/// Factory for [`RpcClient`]s; each client gets its own ring.
+///
+/// Like `SenderFactory`, it is `Clone` and clients are not.
+pub struct RpcClientFactory<Req, Resp> {
+    senders: SenderFactory<Call<Req, Resp>>,
+    timer: Arc<dyn Timer>,
+    config: RpcConfig,
+}
+
+impl<Req, Resp> Clone for RpcClientFactory<Req, Resp> {
+    fn clone(&self) -> Self {
+        Self {
+            senders: self.senders.clone(),
+            timer: Arc::clone(&self.timer),
+            config: self.config.clone(),
+        }
+    }
+}
+
+impl<Req: Send + 'static, Resp: Send + 'static> RpcClientFactory<Req, Resp> {
+    /// Registers a new client.
+    ///
+    /// # Errors
+    ///
+    /// `StreamError::Closed` once the factory is closed or the server shut
+    /// down, `StreamError::RegistrationFailed` when every ring is taken.
+    pub fn register(&self) -> Result<RpcClient<Req, Resp>, StreamError> {
+        Ok(RpcClient {
+            sender: self.senders.register()?,
+            enqueue: futures_util::lock::Mutex::new(()),
+            in_flight: InFlight::new(self.config.max_in_flight),
+            timer: Arc::clone(&self.timer),
+            call_timeout: self.config.call_timeout,
+        })
+    }
+
+    /// Closes the factory for new clients. Existing clients keep calling.
+    pub fn close(&self) {
+        self.senders.close();
+    }
+
+    /// Returns `true` if the factory is closed for new clients.
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.senders.is_closed()
+    }
+}
+
+impl<Req, Resp> std::fmt::Debug for RpcClientFactory<Req, Resp> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RpcClientFactory")
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Calling side of an RPC channel.
+///
+/// [`call`](Self::call) takes `&self`, so one client can be shared (e.g.
+/// behind an `Arc`) by tasks issuing concurrent calls; at most
+/// `RpcConfig::max_in_flight` of them are outstanding at a time. Calls enter
+/// the client's ring one at a time, keeping it single-producer.
+///
+/// # Cancellation
+///
+/// Dropping a call future cancels the call. A request not yet enqueued is
+/// dropped with the future. An enqueued one is skipped by the server if it
+/// has not been yielded yet. Otherwise its [`Responder`] reports
+/// [`is_canceled`](Responder::is_canceled), so the server can abandon the
+/// work.
+pub struct RpcClient<Req, Resp> {
+    sender: RingSender<Call<Req, Resp>>,
+    // Serializes enqueueing: `RingSender::send_ref` must not run concurrently.
+    enqueue: futures_util::lock::Mutex<()>,
+    in_flight: InFlight,
+    timer: Arc<dyn Timer>,
+    call_timeout: Option<Duration>,
+}
+
+impl<Req: Send + 'static, Resp: Send + 'static> RpcClient<Req, Resp> {
+    /// Sends `req` to the server and waits for its response, bounded by
+    /// `RpcConfig::call_timeout`.
+    ///
+    /// # Errors
+    ///
+    /// - `StreamError::Timeout` if the call timeout expired
+    /// - `StreamError::Closed` if the server is gone, or dropped the request
+    ///   without replying
+    pub async fn call(&self, req: Req) -> Result<Resp, StreamError> {
+        match self.call_timeout {
+            Some(limit) => self.call_with_timeout(req, limit).await,
+            None => self.call_inner(req).await,
+        }
+    }
+
+    /// Like [`call`](Self::call), with `limit` instead of the configured
+    /// timeout.
+    ///
+    /// # Errors
+    ///
+    /// As for [`call`](Self::call).
+    pub async fn call_with_timeout(&self, req: Req, limit: Duration) -> Result<Resp, StreamError> {
+        timeout(&*self.timer, limit, self.call_inner(req))
+            .await
+            .unwrap_or(Err(StreamError::Timeout))
+    }
+
+    async fn call_inner(&self, req: Req) -> Result<Resp, StreamError> {
+        let _permit = self.in_flight.acquire().await;
+
+        let (reply, response) = oneshot::channel();
+        let mut slot = Some(Call {
+            request: req,
+            reply,
+        });
+        {
+            let _enqueue = self.enqueue.lock().await;
+            self.sender.send_ref(&mut slot).await?;
+        }
+
+        // Canceled: the server dropped the `Responder` or shut down.
+        response.await.map_err(|_| StreamError::Closed)
+    }
+
+    /// Returns the number of calls currently outstanding.
+    #[must_use]
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.current()
+    }
+
+    /// Returns the client's producer id.
+    #[must_use]
+    pub fn id(&self) -> usize {
+        self.sender.id()
+    }
+
+    /// Returns `true` if the server can no longer receive calls.
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+}
+
+impl<Req, Resp> std::fmt::Debug for RpcClient<Req, Resp> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RpcClient")
+            .field("in_flight", &self.in_flight.current())
+            .field("call_timeout", &self.call_timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+pin_project! {
+    /// Serving side of an RPC channel.
+    ///
+    /// Yields `(request, responder)` pairs in per-client FIFO order
+    /// (INV-STREAM-01). Requests whose caller already gave up are skipped
+    /// and counted in [`canceled`](Self::canceled). Ends after
+    /// [`shutdown`](Self::shutdown) once drained; requests still queued when
+    /// the server is dropped fail their callers with `StreamError::Closed`.
+    pub struct RpcServer<Req, Resp> {
+        #[pin]
+        receiver: RingReceiver<Call<Req, Resp>>,
+        canceled: u64,
+    }
+}
+
+impl<Req: Send + 'static, Resp: Send + 'static> RpcServer<Req, Resp> {
+    fn new(receiver: RingReceiver<Call<Req, Resp>>) -> Self {
+        Self {
+            receiver,
+            canceled: 0,
+        }
+    }
+
+    /// Initiates graceful shutdown: no new clients or calls, queued calls
+    /// are still yielded.
+    pub fn shutdown(&mut self) {
+        self.receiver.shutdown();
+    }
+
+    /// Returns a cloneable signal that shuts the server down.
+    #[must_use]
+    pub fn shutdown_signal(&self) -> ShutdownSignal {
+        self.receiver.shutdown_signal()
+    }
+
+    /// Returns the number of requests skipped because their caller dropped
+    /// the call before the server reached them.
+    #[must_use]
+    pub fn canceled(&self) -> u64 {
+        self.canceled
+    }
+
+    /// Returns the weight of the requests not yet read, or `None` unless
+    /// the channel weighs requests (see [`rpc_with_config`]).
+    #[must_use]
+    pub fn weight_stats(&self) -> Option<WeightStats> {
+        self.receiver.weight_stats()
+    }
+}
+
+impl<Req: Send + 'static, Resp: Send + 'static> Stream for RpcServer<Req, Resp> {
+    type Item = (Req, Responder<Resp>);
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        loop {
+            match ready!(this.receiver.as_mut().poll_next(cx)) {
+                Some(call) if call.reply.is_canceled() => *this.canceled += 1,
+                Some(call) => {
+                    return Poll::Ready(Some((call.request, Responder { reply: call.reply })));
+                }
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+impl<Req, Resp> std::fmt::Debug for RpcServer<Req, Resp> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RpcServer")
+            .field("canceled", &self.canceled)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Reply handle for one request yielded by [`RpcServer`].
+///
+/// Dropping it without responding fails the call with `StreamError::Closed`.
+pub struct Responder<Resp> {
+    reply: oneshot::Sender<Resp>,
+}
+
+impl<Resp> Responder<Resp> {
+    /// Sends the response to the caller.
+    ///
+    /// # Errors
+    ///
+    /// Returns `resp` if the caller has dropped the call.
+    pub fn respond(self, resp: Resp) -> Result<(), Resp> {
+        self.reply.send(resp)
+    }
+
+    /// Returns `true` if the caller has dropped the call (or timed out).
+    #[must_use]
+    pub fn is_canceled(&self) -> bool {
+        self.reply.is_canceled()
+    }
+
+    /// Completes once the caller drops the call, for abandoning work in a
+    /// `select!`.
+    pub fn cancelled(&mut self) -> impl Future<Output = ()> + '_ {
+        poll_fn(|cx| self.reply.poll_canceled(cx))
+    }
+}
+
+impl<Resp> std::fmt::Debug for Responder<Resp> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Responder")
+            .field("canceled", &self.reply.is_canceled())
+            .finish()
+    }
+}
+
+/// Counting bound on a client's outstanding calls.
+///
+/// Every release wakes all waiters, so a waiter dropped after being woken
+/// cannot swallow the wakeup another one needed.
+struct InFlight {
+    max: usize,
+    state: Mutex<InFlightState>,
+}
+
+struct InFlightState {
+    current: usize,
+    waiters: Vec<Waker>,
+}
+
+impl InFlight {
+    fn new(max: usize) -> Self {
+        Self {
+            max: max.max(1),
+            state: Mutex::new(InFlightState {
+                current: 0,
+                waiters: Vec::new(),
+            }),
+        }
+    }
+
+    fn current(&self) -> usize {
+        self.state.lock().unwrap().current
+    }
+
+    /// Waits for a free slot; the slot is held until the permit drops.
+    async fn acquire(&self) -> InFlightPermit<'_> {
+        poll_fn(|cx| {
+            let mut state = self.state.lock().unwrap();
+            if state.current < self.max {
+                state.current += 1;
+                return Poll::Ready(InFlightPermit { bound: self });
+            }
+            if !state.waiters.iter().any(|w| w.will_wake(cx.waker())) {
+                state.waiters.push(cx.waker().clone());
+            }
+            Poll::Pending
+        })
+        .await
+    }
+
+    fn release(&self) {
+        let waiters = {
+            let mut state = self.state.lock().unwrap();
+            state.current -= 1;
+            std::mem::take(&mut state.waiters)
+        };
+        for waker in waiters {
+            waker.wake();
+        }
+    }
+}
+
+struct InFlightPermit<'a> {
+    bound: &'a InFlight,
+}
+
+impl Drop for InFlightPermit<'_> {
+    fn drop(&mut self) {
+        self.bound.release();
+    }
+}
@@ -1,16 +1,20 @@
 //! Async sender implementing `futures::Sink`.
 
-use crate::error::StreamError;
+use crate::config::SendPolicy;
+use crate::error::{SendError, StreamError};
 #[cfg(debug_assertions)]
-use crate::invariants::{debug_assert_data_notified, debug_assert_item_preserved};
+use crate::invariants::debug_assert_item_preserved;
 use crate::shutdown::ShutdownState;
+use crate::timer::{timeout, Sleep, Timer};
+use crate::weight::WeightGauge;
 use ringmpsc_rs::Producer;
+use std::future::poll_fn;
+use std::mem::MaybeUninit;
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll};
-use tokio::sync::Notify;
 
-use futures_core::Future;
 use futures_sink::Sink;
 use pin_project_lite::pin_project;
 
@@ -19,46 +23,251 @@ pin_project! {
     ///
     /// Implements `futures::Sink` with backpressure support.
     /// When the ring is full, `poll_ready` will return `Pending`
-    /// until space becomes available.
+    /// until space becomes available. The sender parks in its ring's producer
+    /// waker slot, so only a drain of *this* sender's ring wakes it.
     ///
     /// # Note
     ///
     /// `RingSender` does NOT implement `Clone`. This is intentional
     /// to preserve the single-producer-per-ring invariant. To create
     /// multiple senders, call `SenderFactory::register()` for each.
+    ///
+    /// What happens on a full ring is set by the sender's [`SendPolicy`]
+    /// (`SenderFactory::register_with`); the default waits. On a channel with
+    /// a [weight budget](crate::StreamConfig::max_weight), an exhausted
+    /// budget counts as a full ring under every policy, and a sender
+    /// waiting on the budget is woken when the receiver drains any ring.
+    ///
+    /// # Cancel safety
+    ///
+    /// [`send_ref`](Self::send_ref) and [`reserve`](Self::reserve) are
+    /// cancel-safe; [`send`](Self::send) is not. Through the `Sink` impl,
+    /// an item accepted by `start_send` is held in the sender, not in the
+    /// future driving it: dropping a `SinkExt::send`/`flush` future leaves it
+    /// pending, to be committed by the next `poll_ready`/`poll_flush` or
+    /// reclaimed with [`take_pending`](Self::take_pending).
     pub struct RingSender<T> {
         producer: Producer<T>,
-        data_notify: Arc<Notify>,
-        backpressure_notify: Arc<Notify>,
         shutdown_state: Arc<ShutdownState>,
-        pending_item: Option<T>,
+        policy: SendPolicy,
+        // Accepted but not yet committed: the `Sink`'s pending item, or the
+        // `KeepLatest` overflow slot. Locked by `send`, `get_mut` elsewhere.
+        pending_item: Mutex<Option<T>>,
+        // Sleeps for `BlockWithTimeout` (`StreamConfig::timer`).
+        timer: Arc<dyn Timer>,
+        // Running `BlockWithTimeout` deadline of a parked `Sink` poll. Only
+        // touched through `&mut self` (`get_mut`); the lock keeps the sender
+        // `Sync` without requiring `Sync` sleep futures.
+        deadline: Mutex<Option<Sleep>>,
+        dropped: AtomicU64,
+        timed_out: AtomicU64,
+        // Weight budget shared with the receiver (`StreamConfig::weigher`).
+        weights: Option<Arc<WeightGauge<T>>>,
+    }
+
+    impl<T> PinnedDrop for RingSender<T> {
+        /// Commits the item the sender still holds (the `KeepLatest` slot, or
+        /// a `Sink`'s pending item) if its ring has room, counting it in
+        /// `RingReceiver::abandoned` if not. Then closes the sender's ring, so
+        /// a per-producer substream (`RingReceiver::into_per_producer`) ends
+        /// once it is drained, and drops out of
+        /// `ShutdownReport::senders_outstanding`.
+        fn drop(this: Pin<&mut Self>) {
+            let this = this.project();
+            if let Ok(pending) = this.pending_item.get_mut() {
+                if !try_commit(this.producer, this.weights.as_deref(), pending) {
+                    this.shutdown_state.item_abandoned();
+                }
+            }
+            this.producer.close();
+            this.shutdown_state.sender_dropped();
+        }
     }
 }
 
+/// Per-sender counters for items affected by the [`SendPolicy`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SendStats {
+    /// Items discarded by `DropNewest` / `KeepLatest` while the sender was
+    /// alive. An item still uncommitted when the sender is dropped is
+    /// counted in [`RingReceiver::abandoned`](crate::RingReceiver::abandoned).
+    pub dropped: u64,
+    /// Sends that gave up under `BlockWithTimeout`.
+    pub timed_out: u64,
+    /// Weight of this sender's items not yet drained by the receiver
+    /// (always 0 unless the channel weighs items).
+    pub weight: usize,
+}
+
+/// Why an item could not be committed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Blocked {
+    /// The ring has no free slot; a drain of this ring frees one.
+    Ring,
+    /// A weight budget is exhausted; a drain of any ring may free room.
+    Weight,
+}
+
+/// Commits `item` into the producer's ring if there is space.
+///
+/// Returns `Ok` once `item` is empty. Leaves `item` untouched when the ring
+/// is full or a weight budget is exhausted (INV-SINK-01, INV-SINK-06). The
+/// commit itself wakes a parked receiver (INV-SINK-03).
+fn commit_or_block<T>(
+    producer: &Producer<T>,
+    weights: Option<&WeightGauge<T>>,
+    item: &mut Option<T>,
+) -> Result<(), Blocked> {
+    let Some(value) = item.as_ref() else {
+        return Ok(());
+    };
+    let Some(mut reservation) = producer.reserve(1) else {
+        return Err(Blocked::Ring);
+    };
+    // Charged only once a slot is held; the unused reservation is released.
+    // The weight is stored with the slot, so the drain releases it without
+    // weighing the item again.
+    if let Some(weights) = weights {
+        let weight = weights.weigh(value);
+        if !weights.try_acquire(producer.id(), weight) {
+            return Err(Blocked::Weight);
+        }
+        weights.record(producer.id(), weight);
+    }
+    if let Some(value) = item.take() {
+        reservation.as_mut_slice()[0] = MaybeUninit::new(value);
+        reservation.commit();
+    }
+    Ok(())
+}
+
+/// [`commit_or_block`] for callers that do not wait.
+fn try_commit<T>(
+    producer: &Producer<T>,
+    weights: Option<&WeightGauge<T>>,
+    item: &mut Option<T>,
+) -> bool {
+    commit_or_block(producer, weights, item).is_ok()
+}
+
+/// Registers `cx` where the event that clears `blocked` is signalled: the
+/// ring's producer waker slot, or the weight budget's waiter list.
+fn register_blocked<T>(
+    blocked: Blocked,
+    producer: &Producer<T>,
+    weights: Option<&WeightGauge<T>>,
+    cx: &Context<'_>,
+) {
+    match (blocked, weights) {
+        (Blocked::Weight, Some(weights)) => weights.register(cx.waker()),
+        _ => producer.register_waker(cx.waker()),
+    }
+}
+
+/// Polls until `item` is committed or the channel is closed.
+///
+/// When blocked, registers the task where the blocker is cleared (the ring's
+/// producer waker slot for a full ring, the weight budget for an exhausted
+/// one) and retries (register-then-recheck, INV-STREAM-05), so an advance or
+/// release racing with registration is never missed. A sender blocked only
+/// on its own ring is not woken by drains of other rings. On `Err(Closed)`
+/// the item stays in `item`.
+fn poll_commit<T>(
+    producer: &Producer<T>,
+    weights: Option<&WeightGauge<T>>,
+    shutdown_state: &ShutdownState,
+    item: &mut Option<T>,
+    cx: &Context<'_>,
+) -> Poll<Result<(), StreamError>> {
+    if shutdown_state.is_closed() || producer.is_closed() {
+        return Poll::Ready(Err(StreamError::Closed));
+    }
+    let Err(mut blocked) = commit_or_block(producer, weights, item) else {
+        return Poll::Ready(Ok(()));
+    };
+
+    // Ring is full - INV-SINK-01: item preserved (still in Option)
+    #[cfg(debug_assertions)]
+    debug_assert_item_preserved!(true, item.is_some());
+
+    // The recheck can hit the other blocker (a drain freed the slot but the
+    // budget is exhausted): register for that one too, then recheck again.
+    let (mut on_ring, mut on_weight) = (false, false);
+    loop {
+        let registered = match blocked {
+            Blocked::Ring => &mut on_ring,
+            Blocked::Weight => &mut on_weight,
+        };
+        if *registered {
+            break;
+        }
+        *registered = true;
+        register_blocked(blocked, producer, weights, cx);
+        match commit_or_block(producer, weights, item) {
+            Ok(()) => return Poll::Ready(Ok(())),
+            Err(again) => blocked = again,
+        }
+    }
+    // Ring close wakes the slot too, but re-check in case it raced.
+    if shutdown_state.is_closed() || producer.is_closed() {
+        return Poll::Ready(Err(StreamError::Closed));
+    }
+    Poll::Pending
+}
+
+/// Makes `item` the sender's newest uncommitted item (`KeepLatest`).
+///
+/// The slot is committed first if there is room; otherwise `item` displaces
+/// it and the displaced item is counted as dropped. Either way the slot is
+/// never committed after `item`, so per-producer order holds (INV-STREAM-01).
+fn push_latest<T>(
+    producer: &Producer<T>,
+    weights: Option<&WeightGauge<T>>,
+    slot: &mut Option<T>,
+    item: T,
+    dropped: &AtomicU64,
+) {
+    try_commit(producer, weights, slot);
+    if slot.replace(item).is_some() {
+        dropped.fetch_add(1, Ordering::Relaxed);
+    }
+    try_commit(producer, weights, slot);
+}
+
+/// Takes back an item that `poll_commit` left in place (INV-SINK-01).
+fn unsent<T>(item: &mut Option<T>) -> T {
+    item.take().expect("INV-SINK-01 violated: unsent item was not preserved")
+}
+
 impl<T: Send + 'static> RingSender<T> {
     /// Creates a new sender wrapping the given producer.
     pub(crate) fn new(
         producer: Producer<T>,
-        data_notify: Arc<Notify>,
-        backpressure_notify: Arc<Notify>,
         shutdown_state: Arc<ShutdownState>,
+        policy: SendPolicy,
+        timer: Arc<dyn Timer>,
+        weights: Option<Arc<WeightGauge<T>>>,
     ) -> Self {
+        shutdown_state.sender_created();
         Self {
             producer,
-            data_notify,
-            backpressure_notify,
             shutdown_state,
-            pending_item: None,
+            policy,
+            pending_item: Mutex::new(None),
+            timer,
+            deadline: Mutex::new(None),
+            dropped: AtomicU64::new(0),
+            timed_out: AtomicU64::new(0),
+            weights,
         }
     }
 
     /// Attempts to send an item without blocking.
     ///
     /// Returns `Ok(())` if the item was sent, or `Err(item)` if the
-    /// ring is full or closed. The item is returned on failure.
+    /// ring is full, a weight budget is exhausted, or the channel is closed.
+    /// The item is returned on failure.
     pub fn try_send(&self, item: T) -> Result<(), T> {
-        use std::mem::MaybeUninit;
-
         if self.shutdown_state.is_closed() || self.producer.is_closed() {
             // INV-SINK-01: Item preserved on closed channel
             #[cfg(debug_assertions)]
@@ -66,83 +275,386 @@ impl<T: Send + 'static> RingSender<T> {
             return Err(item);
         }
 
-        if let Some(mut reservation) = self.producer.reserve(1) {
-            reservation.as_mut_slice()[0] = MaybeUninit::new(item);
-            reservation.commit();
-            self.data_notify.notify_one();
-            // INV-SINK-03: Verify data notification sent
-            #[cfg(debug_assertions)]
-            debug_assert_data_notified!(true, true);
+        // INV-SINK-03: the commit wakes a parked receiver
+        let mut item = Some(item);
+        if try_commit(&self.producer, self.weights.as_deref(), &mut item) {
             Ok(())
         } else {
-            // Ring is full - item is NOT consumed since reserve() returned None
+            // Full - item is NOT consumed since try_commit left it in place
             // INV-SINK-01: Verify item preserved on backpressure
             #[cfg(debug_assertions)]
-            debug_assert_item_preserved!(true, true);
-            Err(item)
+            debug_assert_item_preserved!(true, item.is_some());
+            Err(unsent(&mut item))
         }
     }
 
-    /// Sends an item, waiting for space if the ring is full.
+    /// Sends an item, applying the sender's [`SendPolicy`] if the ring is full.
     ///
     /// This is a convenience method for simple async sending.
     /// Uses reserve/commit internally to ensure no item loss.
-    pub async fn send(&self, item: T) -> Result<(), StreamError> {
-        use std::mem::MaybeUninit;
-
+    ///
+    /// Under `Block` and `BlockWithTimeout`, while the ring is full the task
+    /// parks in the ring's producer waker slot and is woken when the receiver
+    /// drains this ring, or when the channel is closed. The other policies
+    /// never wait.
+    ///
+    /// # Errors
+    ///
+    /// Returns the item in `SendError::Closed` if the channel is closed,
+    /// `SendError::Timeout` if a `BlockWithTimeout` wait expires, and
+    /// `SendError::Full` if the ring is full under `Fail`. `DropNewest` and
+    /// `KeepLatest` report success for dropped items and count them in
+    /// [`stats`](Self::stats).
+    ///
+    /// # Cancel safety
+    ///
+    /// Not cancel-safe: the item lives inside the future, so dropping it
+    /// while it waits (e.g. a losing `tokio::select!` branch) drops the
+    /// item. Use [`send_ref`](Self::send_ref) or [`reserve`](Self::reserve)
+    /// in select loops.
+    pub async fn send(&self, item: T) -> Result<(), SendError<T>> {
+        if self.is_closed() {
+            return Err(SendError::Closed(item));
+        }
         let mut item = Some(item);
+        match self.policy {
+            SendPolicy::Block => {
+                let committed = poll_fn(|cx| {
+                    poll_commit(
+                        &self.producer,
+                        self.weights.as_deref(),
+                        &self.shutdown_state,
+                        &mut item,
+                        cx,
+                    )
+                })
+                .await;
+                committed.map_err(|_| SendError::Closed(unsent(&mut item)))
+            }
+            SendPolicy::BlockWithTimeout(limit) => {
+                let wait = poll_fn(|cx| {
+                    poll_commit(
+                        &self.producer,
+                        self.weights.as_deref(),
+                        &self.shutdown_state,
+                        &mut item,
+                        cx,
+                    )
+                });
+                match timeout(&*self.timer, limit, wait).await {
+                    Some(Ok(())) => Ok(()),
+                    Some(Err(_)) => Err(SendError::Closed(unsent(&mut item))),
+                    None => {
+                        self.timed_out.fetch_add(1, Ordering::Relaxed);
+                        Err(SendError::Timeout(unsent(&mut item)))
+                    }
+                }
+            }
+            SendPolicy::DropNewest => {
+                if !try_commit(&self.producer, self.weights.as_deref(), &mut item) {
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+                Ok(())
+            }
+            SendPolicy::KeepLatest => {
+                let mut slot = self.pending_item.lock().unwrap();
+                push_latest(
+                    &self.producer,
+                    self.weights.as_deref(),
+                    &mut slot,
+                    unsent(&mut item),
+                    &self.dropped,
+                );
+                Ok(())
+            }
+            SendPolicy::Fail => {
+                if try_commit(&self.producer, self.weights.as_deref(), &mut item) {
+                    Ok(())
+                } else {
+                    Err(SendError::Full(unsent(&mut item)))
+                }
+            }
+        }
+    }
 
-        loop {
-            if self.shutdown_state.is_closed() || self.producer.is_closed() {
-                return Err(StreamError::Closed);
+    /// Same as [`send`](Self::send); kept for callers of the former
+    /// clone-per-retry API.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`send`](Self::send).
+    pub async fn send_cloned(&self, item: T) -> Result<(), SendError<T>> {
+        self.send(item).await
+    }
+
+    /// Sends the item in `slot`, leaving it there until it is committed.
+    ///
+    /// Behaves like [`send`](Self::send) under the sender's [`SendPolicy`],
+    /// except that the item is never owned by the future: on success `slot`
+    /// is `None` (the item was committed, or dropped and counted under a drop
+    /// policy); on error the item is still in `slot`. An empty `slot` returns
+    /// `Ok(())` at once.
+    ///
+    /// # Cancel safety
+    ///
+    /// Cancel-safe. If the future is dropped before completing, the item is
+    /// still in `slot` unless it was committed, so a select loop can retry it
+    /// or take it back:
+    ///
+    /// ```ignore
+    /// let mut slot = Some(item);
+    /// tokio::select! {
+    ///     res = tx.send_ref(&mut slot) => res?,
+    ///     () = token.cancelled() => {
+    ///         if let Some(item) = slot.take() { requeue(item) }
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// `StreamError::Closed`, `StreamError::Timeout` (`BlockWithTimeout`) or
+    /// `StreamError::Full` (`Fail`), with the item left in `slot`.
+    pub async fn send_ref(&self, slot: &mut Option<T>) -> Result<(), StreamError> {
+        if slot.is_none() {
+            return Ok(());
+        }
+        if self.is_closed() {
+            return Err(StreamError::Closed);
+        }
+        match self.policy {
+            SendPolicy::Block => {
+                poll_fn(|cx| {
+                    poll_commit(
+                        &self.producer,
+                        self.weights.as_deref(),
+                        &self.shutdown_state,
+                        slot,
+                        cx,
+                    )
+                })
+                .await
             }
+            SendPolicy::BlockWithTimeout(limit) => {
+                let wait = poll_fn(|cx| {
+                    poll_commit(
+                        &self.producer,
+                        self.weights.as_deref(),
+                        &self.shutdown_state,
+                        slot,
+                        cx,
+                    )
+                });
+                timeout(&*self.timer, limit, wait).await.unwrap_or_else(|| {
+                    self.timed_out.fetch_add(1, Ordering::Relaxed);
+                    Err(StreamError::Timeout)
+                })
+            }
+            SendPolicy::DropNewest => {
+                if !try_commit(&self.producer, self.weights.as_deref(), slot) {
+                    slot.take();
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+                Ok(())
+            }
+            SendPolicy::KeepLatest => {
+                let mut pending = self.pending_item.lock().unwrap();
+                push_latest(
+                    &self.producer,
+                    self.weights.as_deref(),
+                    &mut pending,
+                    unsent(slot),
+                    &self.dropped,
+                );
+                Ok(())
+            }
+            SendPolicy::Fail => {
+                if try_commit(&self.producer, self.weights.as_deref(), slot) {
+                    Ok(())
+                } else {
+                    Err(StreamError::Full)
+                }
+            }
+        }
+    }
 
-            // Try to reserve space
-            if let Some(mut reservation) = self.producer.reserve(1) {
-                // Safety: item is Some here
-                reservation.as_mut_slice()[0] = MaybeUninit::new(item.take().unwrap());
-                reservation.commit();
-                self.data_notify.notify_one();
-                // INV-SINK-03: Verify data notification sent
-                #[cfg(debug_assertions)]
-                debug_assert_data_notified!(true, true);
-                return Ok(());
+    /// Waits for space in the ring and returns a permit to send one item.
+    ///
+    /// Modeled on Tokio's `mpsc::Sender::reserve`: the wait happens without
+    /// an item, and [`SendPermit::send`] then commits one synchronously. The
+    /// permit borrows the sender mutably, so nothing else can take the slot
+    /// it found. The sender's overflow slot (`KeepLatest`) is committed first.
+    ///
+    /// `BlockWithTimeout` bounds the wait and `Fail` fails at once on a full
+    /// ring; the other policies wait, since there is no item to drop.
+    ///
+    /// With a weight budget the permit is issued once the budget has room;
+    /// the item is charged when sent, even if it overshoots the budget.
+    ///
+    /// # Cancel safety
+    ///
+    /// Cancel-safe: no item is involved until the permit is used.
+    ///
+    /// ```ignore
+    /// tokio::select! {
+    ///     permit = tx.reserve() => permit?.send(next_item())?,
+    ///     () = token.cancelled() => break,
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// `StreamError::Closed`, `StreamError::Timeout` (`BlockWithTimeout`) or
+    /// `StreamError::Full` (`Fail`).
+    pub async fn reserve(&mut self) -> Result<SendPermit<'_, T>, StreamError> {
+        let this = &*self;
+        let wait = poll_fn(|cx| {
+            let mut pending = this.pending_item.lock().unwrap();
+            match poll_commit(
+                &this.producer,
+                this.weights.as_deref(),
+                &this.shutdown_state,
+                &mut pending,
+                cx,
+            ) {
+                Poll::Ready(Ok(())) => {}
+                other => return other,
+            }
+            let Err(blocked) = this.room() else {
+                return Poll::Ready(Ok(()));
+            };
+            if this.policy == SendPolicy::Fail {
+                return Poll::Ready(Err(StreamError::Full));
+            }
+            register_blocked(blocked, &this.producer, this.weights.as_deref(), cx);
+            match this.room() {
+                Ok(()) => return Poll::Ready(Ok(())),
+                // Blocked on the other one now: park there too and poll again.
+                Err(again) if again != blocked => cx.waker().wake_by_ref(),
+                Err(_) => {}
+            }
+            if this.is_closed() {
+                return Poll::Ready(Err(StreamError::Closed));
             }
+            Poll::Pending
+        });
+        match self.policy {
+            SendPolicy::BlockWithTimeout(limit) => {
+                timeout(&*self.timer, limit, wait).await.unwrap_or_else(|| {
+                    self.timed_out.fetch_add(1, Ordering::Relaxed);
+                    Err(StreamError::Timeout)
+                })?;
+            }
+            _ => wait.await?,
+        }
+        Ok(SendPermit { sender: self })
+    }
 
-            // Ring is full - wait for backpressure relief
-            // INV-SINK-01: Item preserved (still in Option)
-            #[cfg(debug_assertions)]
-            debug_assert_item_preserved!(true, item.is_some());
+    /// Waits until the sender's uncommitted item, if any, is in the ring.
+    ///
+    /// Only `KeepLatest` leaves an item behind after `send` returns; call this
+    /// to deliver it without sending another. Honors `BlockWithTimeout`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `StreamError::Closed` if the channel is closed and
+    /// `StreamError::Timeout` if a `BlockWithTimeout` wait expires.
+    pub async fn flush_pending(&self) -> Result<(), StreamError> {
+        let wait = poll_fn(|cx| {
+            let mut slot = self.pending_item.lock().unwrap();
+            poll_commit(
+                &self.producer,
+                self.weights.as_deref(),
+                &self.shutdown_state,
+                &mut slot,
+                cx,
+            )
+        });
+        let SendPolicy::BlockWithTimeout(limit) = self.policy else {
+            return wait.await;
+        };
+        timeout(&*self.timer, limit, wait).await.unwrap_or_else(|| {
+            self.timed_out.fetch_add(1, Ordering::Relaxed);
+            Err(StreamError::Timeout)
+        })
+    }
 
-            // BACKPRESSURE FLOW:
-            // ==================
-            // Both RingSender and RingReceiver hold `Arc<Notify>` pointing to the SAME
-            // heap-allocated Notify instance (created once in channel.rs, then Arc::clone'd).
-            //
-            // Thread-safe coordination:
-            // 1. SENDER (here): Calls `notified().await` which:
-            //    - Atomically registers this task as a waiter inside the Notify
-            //    - Suspends until a permit is available (returns Poll::Pending)
-            //
-            // 2. RECEIVER (in poll_next): After draining items from the ring, calls
-            //    `backpressure_notify.notify_waiters()` which:
-            //    - Atomically grants permits to ALL currently registered waiters
-            //    - Wakes their wakers so the executor re-polls them
-            //
-            // 3. SENDER (resumed): `notified().await` completes (Poll::Ready), we retry
-            //    the reserve() since space should now be available.
-            //
-            // The Notify internally uses atomic operations (no mutex) to manage the
-            // waiter list, making this coordination lock-free and safe across threads.
-            self.backpressure_notify.notified().await;
-
-            // After waking, check if we should give up
-            if self.shutdown_state.is_closed() {
-                return Err(StreamError::Closed);
-            }
+    /// Removes the item the sender accepted but has not committed yet.
+    ///
+    /// After the `Sink` impl returns `StreamError::Timeout`, or a future
+    /// flushing it is cancelled, the item is still held here; take it back to
+    /// retry or reroute it.
+    pub fn take_pending(&mut self) -> Option<T> {
+        *self.deadline.get_mut().unwrap() = None;
+        self.pending_item.get_mut().unwrap().take()
+    }
 
-            // Retry - item is still owned by us
+    /// Returns the sender's [`SendPolicy`].
+    #[must_use]
+    pub fn policy(&self) -> SendPolicy {
+        self.policy
+    }
+
+    /// Returns the sender's drop and timeout counters.
+    #[must_use]
+    pub fn stats(&self) -> SendStats {
+        SendStats {
+            dropped: self.dropped.load(Ordering::Relaxed),
+            timed_out: self.timed_out.load(Ordering::Relaxed),
+            weight: self
+                .weights
+                .as_ref()
+                .map_or(0, |weights| weights.sender_weight(self.producer.id())),
+        }
+    }
+
+    /// Polls the pending item into the ring, bounded by the
+    /// `BlockWithTimeout` deadline.
+    fn poll_pending(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), StreamError>> {
+        let pending = self.pending_item.get_mut().unwrap();
+        let result = poll_commit(
+            &self.producer,
+            self.weights.as_deref(),
+            &self.shutdown_state,
+            pending,
+            cx,
+        );
+        let SendPolicy::BlockWithTimeout(limit) = self.policy else {
+            return result;
+        };
+        let deadline = self.deadline.get_mut().unwrap();
+        if result.is_ready() {
+            *deadline = None;
+            return result;
+        }
+        let sleep = deadline.get_or_insert_with(|| self.timer.sleep(limit));
+        if sleep.as_mut().poll(cx).is_ready() {
+            *deadline = None;
+            self.timed_out.fetch_add(1, Ordering::Relaxed);
+            return Poll::Ready(Err(StreamError::Timeout));
         }
+        Poll::Pending
+    }
+
+    /// Returns `Ok` if the ring has a free slot and no weight budget is
+    /// exhausted. A probe reservation is released uncommitted when dropped.
+    fn room(&self) -> Result<(), Blocked> {
+        if self.producer.reserve(1).is_none() {
+            return Err(Blocked::Ring);
+        }
+        match &self.weights {
+            Some(weights) if !weights.has_room(self.producer.id()) => Err(Blocked::Weight),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the id of the sender's ring.
+    ///
+    /// Matches the id paired with its substream by
+    /// [`RingReceiver::into_per_producer`](crate::RingReceiver::into_per_producer).
+    #[must_use]
+    pub fn id(&self) -> usize {
+        self.producer.id()
     }
 
     /// Returns `true` if the sender's ring is closed.
@@ -151,6 +663,9 @@ impl<T: Send + 'static> RingSender<T> {
     }
 
     /// Closes the sender's ring.
+    ///
+    /// Dropping the sender also closes its ring. Items already committed are
+    /// still delivered.
     pub fn close(&self) {
         self.producer.close();
     }
@@ -181,43 +696,33 @@ impl<T: Send + 'static> Sink<T> for RingSender<T> {
     ///
     /// If a previous `start_send()` stored a pending item (ring was full), this
     /// method attempts to flush it before declaring readiness.
+    ///
+    /// # Send policy
+    ///
+    /// - `Block`: waits for the pending item as above
+    /// - `BlockWithTimeout`: same, but fails with `StreamError::Timeout` when
+    ///   the wait expires; the item stays pending (`take_pending`)
+    /// - `DropNewest` / `KeepLatest`: always ready; `start_send` drops on overflow
+    /// - `Fail`: fails with `StreamError::Full` while the ring is full
     fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        let this = self.project();
-
-        // Check for shutdown/closed state
-        if this.shutdown_state.is_closed() || this.producer.is_closed() {
-            return Poll::Ready(Err(StreamError::Closed));
-        }
-
-        // If we have a pending item, try to send it first
-        if let Some(item) = this.pending_item.take() {
-            use std::mem::MaybeUninit;
-
-            if let Some(mut reservation) = this.producer.reserve(1) {
-                reservation.as_mut_slice()[0] = MaybeUninit::new(item);
-                reservation.commit();
-                this.data_notify.notify_one();
-                return Poll::Ready(Ok(()));
-            } else {
-                // Still full - re-store the item and wait
-                *this.pending_item = Some(item);
-
-                // Register for backpressure notification
-                let notified = this.backpressure_notify.notified();
-                tokio::pin!(notified);
-                match notified.poll(cx) {
-                    Poll::Ready(()) => {
-                        // Notification received - re-poll
-                        cx.waker().wake_by_ref();
-                        return Poll::Pending;
-                    }
-                    Poll::Pending => return Poll::Pending,
-                }
+        let this = self.get_mut();
+
+        match this.policy {
+            // Fails on a closed channel; commits a pending item first (parks on a full ring)
+            SendPolicy::Block | SendPolicy::BlockWithTimeout(_) => this.poll_pending(cx),
+            _ if this.is_closed() => Poll::Ready(Err(StreamError::Closed)),
+            SendPolicy::DropNewest => Poll::Ready(Ok(())),
+            SendPolicy::KeepLatest => {
+                try_commit(
+                    &this.producer,
+                    this.weights.as_deref(),
+                    this.pending_item.get_mut().unwrap(),
+                );
+                Poll::Ready(Ok(()))
             }
+            SendPolicy::Fail if this.room().is_err() => Poll::Ready(Err(StreamError::Full)),
+            SendPolicy::Fail => Poll::Ready(Ok(())),
         }
-
-        // Ready to accept an item
-        Poll::Ready(Ok(()))
     }
 
     /// Begins the process of sending an item to the sink.
@@ -235,8 +740,9 @@ impl<T: Send + 'static> Sink<T> for RingSender<T> {
     ///
     /// # Behavior
     ///
-    /// - If ring has space: reserves, commits item, notifies receiver via `data_notify`
+    /// - If ring has space: reserves and commits item (the commit wakes the receiver)
     /// - If ring is full: stores item in `pending_item` for later flush
+    ///   (`DropNewest` drops it, `KeepLatest` drops the previous pending item)
     /// - Never blocks - always returns immediately
     ///
     /// # Note
@@ -244,24 +750,40 @@ impl<T: Send + 'static> Sink<T> for RingSender<T> {
     /// The item is NOT guaranteed to be in the ring after this call returns.
     /// It may be buffered in `pending_item`. Call `poll_flush()` to ensure delivery.
     fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
-        let this = self.project();
+        let this = self.get_mut();
 
-        if this.shutdown_state.is_closed() || this.producer.is_closed() {
+        if this.is_closed() {
             return Err(StreamError::Closed);
         }
 
-        use std::mem::MaybeUninit;
-
-        if let Some(mut reservation) = this.producer.reserve(1) {
-            reservation.as_mut_slice()[0] = MaybeUninit::new(item);
-            reservation.commit();
-            this.data_notify.notify_one();
-            Ok(())
-        } else {
-            // Ring is full - store as pending
-            *this.pending_item = Some(item);
-            Ok(())
+        let pending = this.pending_item.get_mut().unwrap();
+        match this.policy {
+            // Commit now if there is space; otherwise the item stays pending
+            // until poll_ready/poll_flush can commit it
+            SendPolicy::Block | SendPolicy::BlockWithTimeout(_) => {
+                *pending = Some(item);
+                try_commit(&this.producer, this.weights.as_deref(), pending);
+            }
+            SendPolicy::DropNewest => {
+                if !try_commit(&this.producer, this.weights.as_deref(), &mut Some(item)) {
+                    this.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            SendPolicy::KeepLatest => push_latest(
+                &this.producer,
+                this.weights.as_deref(),
+                pending,
+                item,
+                &this.dropped,
+            ),
+            // Only reachable without a successful poll_ready
+            SendPolicy::Fail => {
+                if !try_commit(&this.producer, this.weights.as_deref(), &mut Some(item)) {
+                    return Err(StreamError::Full);
+                }
+            }
         }
+        Ok(())
     }
 
     /// Flushes any pending item to the ring buffer.
@@ -285,46 +807,17 @@ impl<T: Send + 'static> Sink<T> for RingSender<T> {
     ///
     /// - If no pending item: returns `Poll::Ready(Ok(()))` immediately
     /// - If pending item exists and ring has space: commits it, returns Ready
-    /// - If pending item exists and ring is full: waits on `backpressure_notify`
+    /// - If pending item exists and ring is full: parks in the ring's producer waker slot
     ///
     /// # Backpressure
     ///
-    /// When the ring is full, this method registers for backpressure notification
-    /// and returns `Poll::Pending`. The receiver will call `notify_waiters()` after
-    /// draining items, which wakes this task to retry.
+    /// When the ring is full, this method registers the task in the ring's
+    /// producer waker slot and returns `Poll::Pending`. The receiver's next
+    /// drain of this ring advances its head, which wakes this task to retry.
+    /// Under `BlockWithTimeout` the wait fails with `StreamError::Timeout`
+    /// when it expires.
     fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        let this = self.project();
-
-        if this.shutdown_state.is_closed() || this.producer.is_closed() {
-            return Poll::Ready(Err(StreamError::Closed));
-        }
-
-        // If we have a pending item, need to flush it
-        if let Some(item) = this.pending_item.take() {
-            use std::mem::MaybeUninit;
-
-            if let Some(mut reservation) = this.producer.reserve(1) {
-                reservation.as_mut_slice()[0] = MaybeUninit::new(item);
-                reservation.commit();
-                this.data_notify.notify_one();
-                return Poll::Ready(Ok(()));
-            } else {
-                // Still full - re-store and wait
-                *this.pending_item = Some(item);
-
-                let notified = this.backpressure_notify.notified();
-                tokio::pin!(notified);
-                match notified.poll(cx) {
-                    Poll::Ready(()) => {
-                        cx.waker().wake_by_ref();
-                        return Poll::Pending;
-                    }
-                    Poll::Pending => return Poll::Pending,
-                }
-            }
-        }
-
-        Poll::Ready(Ok(()))
+        self.get_mut().poll_pending(cx)
     }
 
     /// Closes the sink, flushing any pending item first.
@@ -359,42 +852,53 @@ impl<T: Send + 'static> Sink<T> for RingSender<T> {
             Poll::Ready(Ok(())) => {}
         }
 
-        let this = self.project();
-        this.producer.close();
+        self.producer.close();
         Poll::Ready(Ok(()))
     }
 }
 
-/// Extension trait for `RingSender` providing async convenience methods.
-impl<T: Send + Clone + 'static> RingSender<T> {
-    /// Sends an item, waiting for space if the ring is full.
-    ///
-    /// This version uses clone for compatibility with the simpler push API.
-    /// For zero-copy sending, use `send()` which uses reserve/commit internally.
-    pub async fn send_cloned(&self, item: T) -> Result<(), StreamError> {
-        use std::mem::MaybeUninit;
-
-        loop {
-            if self.shutdown_state.is_closed() || self.producer.is_closed() {
-                return Err(StreamError::Closed);
-            }
-
-            if let Some(mut reservation) = self.producer.reserve(1) {
-                reservation.as_mut_slice()[0] = MaybeUninit::new(item);
-                reservation.commit();
-                self.data_notify.notify_one();
-                return Ok(());
-            }
-
-            // Ring is full - wait for backpressure relief
-            self.backpressure_notify.notified().await;
-
-            // After waking, check if we should give up
-            if self.shutdown_state.is_closed() {
-                return Err(StreamError::Closed);
-            }
+/// Permission to send one item, obtained from [`RingSender::reserve`].
+///
+/// The ring had room when the permit was issued, and only the receiver
+/// touches the ring in the meantime, so [`send`](Self::send) commits without
+/// waiting. Dropping the permit gives the space back.
+#[must_use = "a permit does nothing unless used to send"]
+pub struct SendPermit<'a, T> {
+    sender: &'a mut RingSender<T>,
+}
 
-            // Retry - item is cloned on each attempt
+impl<T: Send + 'static> SendPermit<'_, T> {
+    /// Commits `item` into the reserved space.
+    ///
+    /// # Errors
+    ///
+    /// Returns the item in `SendError::Closed` if the channel was closed
+    /// after the permit was issued.
+    pub fn send(self, item: T) -> Result<(), SendError<T>> {
+        if self.sender.is_closed() {
+            return Err(SendError::Closed(item));
+        }
+        let producer = &self.sender.producer;
+        let Some(mut reservation) = producer.reserve(1) else {
+            debug_assert!(false, "permit space was taken by another send");
+            return Err(SendError::Full(item));
+        };
+        // The permit already holds the slot: its item is charged even past
+        // a weight budget (INV-SINK-06). Charged before the commit, since the
+        // receiver may drain and release the item as soon as it is committed.
+        if let Some(weights) = self.sender.weights.as_deref() {
+            let weight = weights.weigh(&item);
+            weights.acquire(producer.id(), weight);
+            weights.record(producer.id(), weight);
         }
+        reservation.as_mut_slice()[0] = MaybeUninit::new(item);
+        reservation.commit();
+        Ok(())
+    }
+}
+
+impl<T> std::fmt::Debug for SendPermit<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SendPermit").finish_non_exhaustive()
     }
 }
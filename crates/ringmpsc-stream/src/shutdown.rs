@@ -2,90 +2,172 @@
 
 #[cfg(debug_assertions)]
 use crate::invariants::{debug_assert_senders_woken, debug_assert_shutdown_signaled};
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
-use tokio::sync::{oneshot, Notify};
+use futures_util::task::AtomicWaker;
+use std::future::{poll_fn, Future};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
 
 /// Shared shutdown state between sender factory and receiver.
-#[derive(Debug)]
 pub(crate) struct ShutdownState {
     /// Flag indicating the channel is closed for new registrations.
     closed: AtomicBool,
     /// Flag indicating shutdown has been initiated.
     shutdown_initiated: AtomicBool,
+    /// Receiver parked in `poll_next`, woken on close (INV-STREAM-07).
+    receiver_waker: AtomicWaker,
+    /// `PerProducer` stream parked waiting for a new sender.
+    registration_waker: AtomicWaker,
+    /// `RingSender`s not yet dropped, reported by `shutdown_with_deadline`.
+    live_senders: AtomicUsize,
+    /// Items dropped senders still held and could not commit.
+    abandoned: AtomicU64,
+    /// Tasks awaiting `ShutdownSignal::wait`. `shutdown_initiated` is set
+    /// under this lock, so a waiter either sees it or gets woken.
+    shutdown_waiters: Mutex<Vec<Waker>>,
+    /// Closes every ring of the channel, waking senders parked on a full
+    /// ring (INV-SHUT-02).
+    close_rings: Box<dyn Fn() + Send + Sync>,
 }
 
 impl ShutdownState {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new<F>(close_rings: F) -> Self
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
         Self {
             closed: AtomicBool::new(false),
             shutdown_initiated: AtomicBool::new(false),
+            receiver_waker: AtomicWaker::new(),
+            registration_waker: AtomicWaker::new(),
+            live_senders: AtomicUsize::new(0),
+            abandoned: AtomicU64::new(0),
+            shutdown_waiters: Mutex::new(Vec::new()),
+            close_rings: Box::new(close_rings),
         }
     }
 
     /// Marks the channel as closed for new registrations.
+    ///
+    /// Closes the underlying rings and wakes the receiver, so every parked
+    /// task re-polls and observes the closed state.
     #[inline]
     pub(crate) fn close(&self) {
         self.closed.store(true, Ordering::Release);
+        (self.close_rings)();
+        self.receiver_waker.wake();
+        self.registration_waker.wake();
     }
 
-    /// Returns `true` if closed for new registrations.
+    /// Registers the receiver's waker to be woken by [`close`](Self::close).
+    ///
+    /// The caller must re-check [`is_closed`](Self::is_closed) afterwards.
     #[inline]
-    pub(crate) fn is_closed(&self) -> bool {
-        self.closed.load(Ordering::Acquire)
+    pub(crate) fn register_receiver(&self, waker: &Waker) {
+        self.receiver_waker.register(waker);
     }
 
-    /// Initiates shutdown sequence.
+    /// Wakes a task waiting for new senders; call after registering one.
     #[inline]
-    pub(crate) fn initiate_shutdown(&self) {
-        self.shutdown_initiated.store(true, Ordering::Release);
+    pub(crate) fn notify_registered(&self) {
+        self.registration_waker.wake();
     }
 
-    /// Returns `true` if shutdown has been initiated.
+    /// Registers a waker for [`notify_registered`](Self::notify_registered).
+    ///
+    /// The caller must re-check the producer count afterwards. `close` also
+    /// wakes it, so a parked task observes the end of registrations.
     #[inline]
-    pub(crate) fn is_shutdown_initiated(&self) -> bool {
-        self.shutdown_initiated.load(Ordering::Acquire)
+    pub(crate) fn register_registration(&self, waker: &Waker) {
+        self.registration_waker.register(waker);
     }
-}
 
-/// Handle for triggering shutdown from the receiver side.
-pub(crate) struct ShutdownHandle {
-    /// Oneshot sender to signal the consumer loop to drain and exit.
-    pub(crate) shutdown_tx: Option<oneshot::Sender<()>>,
-    /// Shared state for coordinating shutdown.
-    pub(crate) state: Arc<ShutdownState>,
-    /// Notify to wake blocked senders during shutdown.
-    pub(crate) backpressure_notify: Arc<Notify>,
-}
+    /// Counts a new `RingSender`.
+    #[inline]
+    pub(crate) fn sender_created(&self) {
+        self.live_senders.fetch_add(1, Ordering::AcqRel);
+    }
 
-impl ShutdownHandle {
-    /// Triggers graceful shutdown.
-    /// 
-    /// This will:
-    /// 1. Mark the channel as closed
-    /// 2. Signal the consumer loop to drain remaining items
-    /// 3. Wake any blocked senders so they can observe the closed state
-    pub(crate) fn trigger(&mut self) {
-        self.state.initiate_shutdown();
-        self.state.close();
+    /// Uncounts a dropped `RingSender` and wakes the receiver, so a
+    /// deadline-bounded shutdown re-checks [`live_senders`](Self::live_senders).
+    #[inline]
+    pub(crate) fn sender_dropped(&self) {
+        self.live_senders.fetch_sub(1, Ordering::AcqRel);
+        self.receiver_waker.wake();
+    }
+
+    /// Counts an item a dropped `RingSender` could not commit.
+    #[inline]
+    pub(crate) fn item_abandoned(&self) {
+        self.abandoned.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the number of items dropped senders could not commit.
+    #[inline]
+    pub(crate) fn abandoned(&self) -> u64 {
+        self.abandoned.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of `RingSender`s not yet dropped.
+    #[inline]
+    pub(crate) fn live_senders(&self) -> usize {
+        self.live_senders.load(Ordering::Acquire)
+    }
 
-        // Signal consumer loop to drain
-        let signal_sent = if let Some(tx) = self.shutdown_tx.take() {
-            tx.send(()).is_ok()
-        } else {
-            false
-        };
+    /// Returns `true` if closed for new registrations.
+    #[inline]
+    pub(crate) fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+
+    /// Initiates graceful shutdown; only the first call has effect.
+    ///
+    /// This will:
+    /// 1. Mark shutdown as initiated, so the receiver performs its final
+    ///    drain on its next poll (INV-SHUT-01)
+    /// 2. Close the channel, waking the receiver and any blocked senders so
+    ///    they observe the closed state (INV-SHUT-02)
+    /// 3. Wake tasks awaiting [`ShutdownSignal::wait`]
+    pub(crate) fn shutdown(&self) {
+        let mut waiters = self.shutdown_waiters.lock().unwrap();
+        if self.shutdown_initiated.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let waiters = std::mem::take(&mut *waiters);
+        self.close();
 
         // INV-SHUT-01: Verify shutdown was signaled
         #[cfg(debug_assertions)]
-        debug_assert_shutdown_signaled!(true, signal_sent || self.shutdown_tx.is_none());
-
-        // Wake blocked senders so they can observe closed state
-        self.backpressure_notify.notify_waiters();
+        debug_assert_shutdown_signaled!(true, self.is_shutdown_initiated());
 
         // INV-SHUT-02: Verify senders were woken
         #[cfg(debug_assertions)]
-        debug_assert_senders_woken!(true, true);
+        debug_assert_senders_woken!(true, self.is_closed());
+
+        for waker in waiters {
+            waker.wake();
+        }
+    }
+
+    /// Returns `true` if shutdown has been initiated.
+    #[inline]
+    pub(crate) fn is_shutdown_initiated(&self) -> bool {
+        self.shutdown_initiated.load(Ordering::Acquire)
+    }
+
+    /// Polls for shutdown, registering `cx`'s waker if it has not happened.
+    fn poll_shutdown(&self, cx: &Context<'_>) -> Poll<()> {
+        if self.is_shutdown_initiated() {
+            return Poll::Ready(());
+        }
+        let mut waiters = self.shutdown_waiters.lock().unwrap();
+        if self.is_shutdown_initiated() {
+            return Poll::Ready(());
+        }
+        if !waiters.iter().any(|w| w.will_wake(cx.waker())) {
+            waiters.push(cx.waker().clone());
+        }
+        Poll::Pending
     }
 }
 
@@ -96,35 +178,24 @@ impl ShutdownHandle {
 #[derive(Clone)]
 pub struct ShutdownSignal {
     state: Arc<ShutdownState>,
-    backpressure_notify: Arc<Notify>,
 }
 
 impl ShutdownSignal {
-    pub(crate) fn new(
-        state: Arc<ShutdownState>,
-        backpressure_notify: Arc<Notify>,
-    ) -> Self {
-        Self {
-            state,
-            backpressure_notify,
-        }
+    pub(crate) fn new(state: Arc<ShutdownState>) -> Self {
+        Self { state }
     }
 
     /// Triggers graceful shutdown.
     ///
     /// Calling this will:
     /// 1. Close the channel for new registrations
-    /// 2. Signal the consumer to drain remaining items
+    /// 2. Wake the receiver so it drains remaining items
     /// 3. Wake any blocked senders so they can observe the closed state
     ///
     /// This method is idempotent - calling it multiple times has no
     /// additional effect after the first call.
     pub fn shutdown(&self) {
-        if !self.state.is_shutdown_initiated() {
-            self.state.initiate_shutdown();
-            self.state.close();
-            self.backpressure_notify.notify_waiters();
-        }
+        self.state.shutdown();
     }
 
     /// Returns `true` if shutdown has been initiated.
@@ -132,4 +203,66 @@ impl ShutdownSignal {
     pub fn is_shutdown(&self) -> bool {
         self.state.is_shutdown_initiated()
     }
+
+    /// Returns a future that completes once shutdown has been initiated.
+    ///
+    /// Runtime-neutral (plain wakers), so it works as a `select!` arm or with
+    /// `StreamExt::take_until` on any executor.
+    pub fn wait(&self) -> impl Future<Output = ()> + Send + 'static {
+        let state = Arc::clone(&self.state);
+        poll_fn(move |cx| state.poll_shutdown(cx))
+    }
+}
+
+/// Items left over by [`RingReceiver::shutdown_with_deadline`](crate::RingReceiver::shutdown_with_deadline).
+///
+/// Holds everything the receiver had not yielded yet, so the caller can
+/// persist it instead of dropping it on exit.
+#[derive(Debug)]
+pub struct ShutdownReport<T> {
+    /// Items already moved into the receiver's buffer by earlier polls. Their
+    /// producer is no longer known; they precede every item in `per_producer`.
+    pub buffered: Vec<T>,
+    /// Items still in each ring, indexed by producer id, in FIFO order
+    /// (INV-STREAM-01).
+    pub per_producer: Vec<Vec<T>>,
+    /// `true` if the deadline elapsed before a drain pass found every ring
+    /// empty; items committed after that may remain in the rings.
+    pub timed_out: bool,
+    /// Senders still alive when the drain ended. They can no longer commit;
+    /// the shutdown does not wait for them to be dropped.
+    pub senders_outstanding: usize,
+    /// Items lost because their sender was dropped still holding them (see
+    /// [`RingReceiver::abandoned`](crate::RingReceiver::abandoned)).
+    pub abandoned: u64,
+}
+
+impl<T> ShutdownReport<T> {
+    /// Returns the total number of leftover items.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.buffered.len() + self.per_producer.iter().map(Vec::len).sum::<usize>()
+    }
+
+    /// Returns `true` if nothing was left over.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of ring leftovers per producer id.
+    #[must_use]
+    pub fn counts(&self) -> Vec<usize> {
+        self.per_producer.iter().map(Vec::len).collect()
+    }
+
+    /// Flattens the report: `buffered`, then each producer's items in id order.
+    #[must_use]
+    pub fn into_items(self) -> Vec<T> {
+        let mut items = self.buffered;
+        for ring in self.per_producer {
+            items.extend(ring);
+        }
+        items
+    }
 }
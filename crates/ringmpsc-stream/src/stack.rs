@@ -0,0 +1,518 @@
+//! Async adapters for the stack-allocated [`StackChannel`].
+//!
+//! The channel is owned by the caller — typically a `static`, or an `Arc` —
+//! and shared with the adapters through any cloneable handle that derefs to
+//! it. Receiving and sending then allocate nothing per item.
+
+use crate::config::StreamConfig;
+use crate::error::StreamError;
+#[cfg(debug_assertions)]
+use crate::invariants::{
+    debug_assert_explicit_registration, debug_assert_item_preserved,
+    debug_assert_recheck_after_register, debug_assert_shutdown_drained,
+};
+use crate::shutdown::{ShutdownSignal, ShutdownState};
+use ringmpsc_rs::{StackChannel, StackRing};
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::future::poll_fn;
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+use futures_sink::Sink;
+
+/// Creates async adapters over a [`StackChannel`] built with
+/// [`StackChannel::with_wakers`].
+///
+/// `channel` is any cloneable handle to the channel: `&'static StackChannel`
+/// (e.g. a reference to a `static`) or an `Arc<StackChannel>` /
+/// `Pin<Arc<StackChannel>>` owner. Create one pair per channel, and register
+/// producers only through the returned factory.
+///
+/// # Panics
+///
+/// Panics if the channel was not built with `StackChannel::with_wakers`:
+/// without waker slots the adapters could never be woken.
+///
+/// # Example
+///
+/// ```ignore
+/// static CHANNEL: StackChannel<u64, 1024, 4> = StackChannel::with_wakers();
+///
+/// let (factory, mut rx) = stack_channel(&CHANNEL);
+/// let tx = factory.register()?;
+/// tx.send(42).await?;
+/// assert_eq!(rx.next().await, Some(42));
+/// ```
+#[must_use]
+pub fn stack_channel<T, const N: usize, const P: usize, C>(
+    channel: C,
+) -> (StackSenderFactory<T, N, P, C>, StackRingReceiver<T, N, P, C>)
+where
+    T: Send + 'static,
+    C: Deref<Target = StackChannel<T, N, P>> + Clone + Send + Sync + 'static,
+{
+    stack_channel_with_stream_config(channel, StreamConfig::default())
+}
+
+/// Creates stack channel adapters with custom stream configuration.
+///
+/// Only `stream_config.batch_hint` applies: it bounds both a drain and the
+/// receiver's buffer, which is allocated once here.
+///
+/// # Panics
+///
+/// As for [`stack_channel`].
+#[must_use]
+pub fn stack_channel_with_stream_config<T, const N: usize, const P: usize, C>(
+    channel: C,
+    stream_config: StreamConfig,
+) -> (StackSenderFactory<T, N, P, C>, StackRingReceiver<T, N, P, C>)
+where
+    T: Send + 'static,
+    C: Deref<Target = StackChannel<T, N, P>> + Clone + Send + Sync + 'static,
+{
+    assert!(
+        channel.wakers_enabled(),
+        "stack_channel: build the channel with StackChannel::with_wakers()"
+    );
+
+    let closer = channel.clone();
+    let shutdown_state = Arc::new(ShutdownState::new(move || closer.close()));
+
+    let receiver = StackRingReceiver {
+        channel: channel.clone(),
+        shutdown_state: Arc::clone(&shutdown_state),
+        buffer: VecDeque::with_capacity(stream_config.batch_hint),
+        batch_hint: stream_config.batch_hint,
+        drain_complete: false,
+    };
+    let factory = StackSenderFactory {
+        channel,
+        shutdown_state,
+    };
+
+    (factory, receiver)
+}
+
+/// Factory for [`StackRingSender`]s; the stack counterpart of
+/// [`SenderFactory`](crate::SenderFactory).
+///
+/// Each sender gets one of the channel's `P` rings.
+pub struct StackSenderFactory<T, const N: usize, const P: usize, C = &'static StackChannel<T, N, P>>
+where
+    C: Deref<Target = StackChannel<T, N, P>>,
+{
+    channel: C,
+    shutdown_state: Arc<ShutdownState>,
+}
+
+impl<T, const N: usize, const P: usize, C> Clone for StackSenderFactory<T, N, P, C>
+where
+    C: Deref<Target = StackChannel<T, N, P>> + Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            channel: self.channel.clone(),
+            shutdown_state: Arc::clone(&self.shutdown_state),
+        }
+    }
+}
+
+impl<T, const N: usize, const P: usize, C> StackSenderFactory<T, N, P, C>
+where
+    T: Send + 'static,
+    C: Deref<Target = StackChannel<T, N, P>> + Clone + Send + Sync + 'static,
+{
+    /// Registers a new sender on the next free ring.
+    ///
+    /// # Errors
+    ///
+    /// `StreamError::Closed` if the channel is closed, or
+    /// `StreamError::RegistrationFailed` once all `P` rings are taken.
+    pub fn register(&self) -> Result<StackRingSender<T, N, P, C>, StreamError> {
+        if self.shutdown_state.is_closed() {
+            return Err(StreamError::Closed);
+        }
+        let id = self.channel.register()?.id();
+
+        // INV-CH-01: Explicit registration creates unique sender per ring
+        #[cfg(debug_assertions)]
+        debug_assert_explicit_registration!(true);
+
+        Ok(StackRingSender {
+            channel: self.channel.clone(),
+            id,
+            shutdown_state: Arc::clone(&self.shutdown_state),
+            pending_item: None,
+            _not_sync: PhantomData,
+        })
+    }
+
+    /// Closes the channel for new registrations; see
+    /// [`SenderFactory::close`](crate::SenderFactory::close).
+    pub fn close(&self) {
+        self.shutdown_state.close();
+    }
+
+    /// Returns `true` if the channel is closed for new registrations.
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.shutdown_state.is_closed()
+    }
+
+    /// Returns the number of registered producers.
+    #[must_use]
+    pub fn producer_count(&self) -> usize {
+        self.channel.producer_count()
+    }
+}
+
+impl<T, const N: usize, const P: usize, C> std::fmt::Debug for StackSenderFactory<T, N, P, C>
+where
+    C: Deref<Target = StackChannel<T, N, P>>,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StackSenderFactory")
+            .field("closed", &self.shutdown_state.is_closed())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Commits `item` into `ring` if there is space.
+///
+/// Returns `true` once `item` is empty; leaves it untouched when the ring is
+/// full or closed (INV-SINK-01).
+fn try_commit<T, const N: usize>(ring: &StackRing<T, N>, item: &mut Option<T>) -> bool {
+    let Some(value) = item.take() else {
+        return true;
+    };
+    // SAFETY: the sender owning this ring is its only producer (INV-SINK-02),
+    // and the single reserved slot is written before `commit(1)`.
+    unsafe {
+        let Some((ptr, _)) = ring.reserve(1) else {
+            *item = Some(value);
+            return false;
+        };
+        ptr.write(value);
+    }
+    ring.commit(1);
+    true
+}
+
+/// Async sender for one ring of a [`StackChannel`]; the stack counterpart of
+/// [`RingSender`](crate::RingSender) with its default `Block` policy.
+///
+/// When the ring is full, sending parks in the ring's producer waker slot
+/// until the receiver drains this ring or the channel is closed. Like
+/// `StackProducer` it is `Send` but neither `Clone` nor `Sync`, so the ring
+/// keeps a single producer. Dropping the sender closes its ring.
+pub struct StackRingSender<T, const N: usize, const P: usize, C = &'static StackChannel<T, N, P>>
+where
+    C: Deref<Target = StackChannel<T, N, P>>,
+{
+    channel: C,
+    id: usize,
+    shutdown_state: Arc<ShutdownState>,
+    // Item accepted by `Sink::start_send`, not yet committed.
+    pending_item: Option<T>,
+    _not_sync: PhantomData<Cell<()>>,
+}
+
+// Items are never pinned in place; the pending slot only moves them.
+impl<T, const N: usize, const P: usize, C> Unpin for StackRingSender<T, N, P, C> where
+    C: Deref<Target = StackChannel<T, N, P>> + Unpin
+{
+}
+
+impl<T, const N: usize, const P: usize, C> StackRingSender<T, N, P, C>
+where
+    T: Send + 'static,
+    C: Deref<Target = StackChannel<T, N, P>>,
+{
+    fn ring(&self) -> &StackRing<T, N> {
+        self.channel
+            .get_ring(self.id)
+            .expect("sender id is below P")
+    }
+
+    /// Polls until `item` is committed or the channel is closed; see the
+    /// heap sender's register-then-recheck (INV-STREAM-05).
+    fn poll_commit(&self, item: &mut Option<T>, cx: &Context<'_>) -> Poll<Result<(), StreamError>> {
+        if self.is_closed() {
+            return Poll::Ready(Err(StreamError::Closed));
+        }
+        let ring = self.ring();
+        if try_commit(ring, item) {
+            return Poll::Ready(Ok(()));
+        }
+
+        // Ring is full - INV-SINK-01: item preserved (still in Option)
+        #[cfg(debug_assertions)]
+        debug_assert_item_preserved!(true, item.is_some());
+
+        ring.register_producer_waker(cx.waker());
+        if try_commit(ring, item) {
+            return Poll::Ready(Ok(()));
+        }
+        if self.is_closed() {
+            return Poll::Ready(Err(StreamError::Closed));
+        }
+        Poll::Pending
+    }
+
+    /// Attempts to send an item without waiting, returning it if the ring
+    /// is full or closed.
+    ///
+    /// # Errors
+    ///
+    /// Returns the item if it could not be committed.
+    pub fn try_send(&self, item: T) -> Result<(), T> {
+        let mut item = Some(item);
+        if !self.is_closed() && try_commit(self.ring(), &mut item) {
+            return Ok(());
+        }
+        Err(item.take().expect("uncommitted item is still in the slot"))
+    }
+
+    /// Sends an item, waiting while the ring is full.
+    ///
+    /// # Errors
+    ///
+    /// Returns the item in `SendError::Closed` if the channel is closed.
+    ///
+    /// # Cancel safety
+    ///
+    /// Not cancel-safe, as [`RingSender::send`](crate::RingSender::send);
+    /// use [`send_ref`](Self::send_ref) in select loops.
+    pub async fn send(&self, item: T) -> Result<(), crate::SendError<T>> {
+        let mut slot = Some(item);
+        match self.send_ref(&mut slot).await {
+            Ok(()) => Ok(()),
+            Err(_) => Err(crate::SendError::Closed(
+                slot.take().expect("unsent item is still in the slot"),
+            )),
+        }
+    }
+
+    /// Sends the item in `slot`, leaving it there until it is committed.
+    ///
+    /// # Errors
+    ///
+    /// `StreamError::Closed`, with the item left in `slot`.
+    ///
+    /// # Cancel safety
+    ///
+    /// Cancel-safe: the item is never owned by the future.
+    pub async fn send_ref(&self, slot: &mut Option<T>) -> Result<(), StreamError> {
+        poll_fn(|cx| self.poll_commit(slot, cx)).await
+    }
+
+    /// Returns the sender's producer id (its ring index).
+    #[must_use]
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Returns `true` if the sender's ring or the channel is closed.
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.shutdown_state.is_closed() || self.ring().is_closed()
+    }
+
+    /// Closes the sender's ring. Items already committed can still be
+    /// received.
+    pub fn close(&self) {
+        self.ring().close();
+    }
+}
+
+impl<T, const N: usize, const P: usize, C> Drop for StackRingSender<T, N, P, C>
+where
+    C: Deref<Target = StackChannel<T, N, P>>,
+{
+    fn drop(&mut self) {
+        if let Some(ring) = self.channel.get_ring(self.id) {
+            ring.close();
+        }
+    }
+}
+
+impl<T, const N: usize, const P: usize, C> Sink<T> for StackRingSender<T, N, P, C>
+where
+    T: Send + 'static,
+    C: Deref<Target = StackChannel<T, N, P>> + Unpin,
+{
+    type Error = StreamError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        let mut pending = this.pending_item.take();
+        let result = this.poll_commit(&mut pending, cx);
+        this.pending_item = pending;
+        result
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+        let this = self.get_mut();
+        if this.is_closed() {
+            return Err(StreamError::Closed);
+        }
+        debug_assert!(this.pending_item.is_none(), "start_send without poll_ready");
+        this.pending_item = Some(item);
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        if self.pending_item.is_none() {
+            return Poll::Ready(Ok(()));
+        }
+        self.poll_ready(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let flushed = std::task::ready!(self.as_mut().poll_flush(cx));
+        self.close();
+        Poll::Ready(flushed)
+    }
+}
+
+impl<T, const N: usize, const P: usize, C> std::fmt::Debug for StackRingSender<T, N, P, C>
+where
+    C: Deref<Target = StackChannel<T, N, P>>,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StackRingSender")
+            .field("id", &self.id)
+            .field("pending", &self.pending_item.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Async stream over a [`StackChannel`]; the stack counterpart of
+/// [`RingReceiver`](crate::RingReceiver).
+///
+/// Event-driven like `RingReceiver`: parks in every ring's consumer waker
+/// slot, drains up to `batch_hint` items per poll into a buffer allocated
+/// once, and ends after [`shutdown`](Self::shutdown) (or close) once drained.
+pub struct StackRingReceiver<T, const N: usize, const P: usize, C = &'static StackChannel<T, N, P>>
+{
+    channel: C,
+    shutdown_state: Arc<ShutdownState>,
+    buffer: VecDeque<T>,
+    batch_hint: usize,
+    drain_complete: bool,
+}
+
+// Items are never pinned in place; the buffer only moves them.
+impl<T, const N: usize, const P: usize, C: Unpin> Unpin for StackRingReceiver<T, N, P, C> {}
+
+impl<T, const N: usize, const P: usize, C> StackRingReceiver<T, N, P, C>
+where
+    T: Send + 'static,
+    C: Deref<Target = StackChannel<T, N, P>>,
+{
+    /// Initiates graceful shutdown; see [`RingReceiver::shutdown`](crate::RingReceiver::shutdown).
+    pub fn shutdown(&mut self) {
+        self.shutdown_state.shutdown();
+    }
+
+    /// Returns `true` if the stream has been shut down.
+    #[must_use]
+    pub fn is_shutdown(&self) -> bool {
+        self.shutdown_state.is_shutdown_initiated()
+    }
+
+    /// Returns a cloneable shutdown signal.
+    #[must_use]
+    pub fn shutdown_signal(&self) -> ShutdownSignal {
+        ShutdownSignal::new(Arc::clone(&self.shutdown_state))
+    }
+
+    /// Returns the number of items currently buffered.
+    #[must_use]
+    pub fn buffered_count(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Closed through the factory, shutdown, or `StackChannel::close` directly.
+    fn is_closed(&self) -> bool {
+        self.shutdown_state.is_closed() || self.channel.is_closed()
+    }
+
+    fn drain_batch(&mut self) -> usize {
+        let limit = self.batch_hint.saturating_sub(self.buffer.len()).max(1);
+        let buffer = &mut self.buffer;
+        self.channel
+            .consume_all_up_to_owned(limit, |item| buffer.push_back(item))
+    }
+}
+
+impl<T, const N: usize, const P: usize, C> Stream for StackRingReceiver<T, N, P, C>
+where
+    T: Send + 'static,
+    C: Deref<Target = StackChannel<T, N, P>> + Unpin,
+{
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if let Some(item) = this.buffer.pop_front() {
+            return Poll::Ready(Some(item));
+        }
+        if this.drain_complete {
+            return Poll::Ready(None);
+        }
+
+        if this.shutdown_state.is_shutdown_initiated() {
+            let buffer = &mut this.buffer;
+            this.channel.consume_all_owned(|item| buffer.push_back(item));
+            this.drain_complete = true;
+
+            // INV-STREAM-04: Verify drain complete before returning None
+            #[cfg(debug_assertions)]
+            debug_assert_shutdown_drained!(true, this.drain_complete);
+
+            return Poll::Ready(this.buffer.pop_front());
+        }
+
+        if this.drain_batch() > 0 {
+            return Poll::Ready(this.buffer.pop_front());
+        }
+
+        // INV-STREAM-05: Register-then-recheck, as in `RingReceiver`.
+        this.shutdown_state.register_receiver(cx.waker());
+        this.channel.register_consumer_waker(cx.waker());
+
+        let recheck_count = this.drain_batch();
+        if recheck_count > 0 {
+            // INV-STREAM-05: Verify recheck caught items
+            #[cfg(debug_assertions)]
+            debug_assert_recheck_after_register!(recheck_count);
+            return Poll::Ready(this.buffer.pop_front());
+        }
+
+        // INV-STREAM-07: Closed — drain anything committed before close, then end
+        if this.is_closed() {
+            let buffer = &mut this.buffer;
+            this.channel.consume_all_owned(|item| buffer.push_back(item));
+            return Poll::Ready(this.buffer.pop_front());
+        }
+
+        Poll::Pending
+    }
+}
+
+impl<T, const N: usize, const P: usize, C> std::fmt::Debug for StackRingReceiver<T, N, P, C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StackRingReceiver")
+            .field("buffered", &self.buffer.len())
+            .field("drain_complete", &self.drain_complete)
+            .finish_non_exhaustive()
+    }
+}
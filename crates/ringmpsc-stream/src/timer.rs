@@ -0,0 +1,297 @@
+//! Pluggable timers for batch linger windows and send timeouts.
+//!
+//! The `Stream`/`Sink` implementations only need wakers; the two timed
+//! features (`StreamConfig::batch_linger`, `SendPolicy::BlockWithTimeout`) go
+//! through the [`Timer`] in [`StreamConfig::timer`](crate::StreamConfig::timer).
+//! [`TokioTimer`] (feature `tokio`) uses the Tokio time driver;
+//! [`ThreadTimer`] works on any executor. [`AutoTimer`], the default with the
+//! `tokio` feature, picks between them each time it creates a sleep.
+
+use futures_util::task::AtomicWaker;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::future::{poll_fn, Future};
+use std::pin::{pin, Pin};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::task::{Context, Poll};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A boxed future returned by [`Timer::sleep`].
+pub type Sleep = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+/// Source of sleep futures for the timed parts of the crate.
+///
+/// Implement this to plug in another runtime's timer, e.g. for smol:
+///
+/// ```ignore
+/// #[derive(Debug)]
+/// struct SmolTimer;
+///
+/// impl Timer for SmolTimer {
+///     fn sleep(&self, duration: Duration) -> Sleep {
+///         Box::pin(async move { smol::Timer::after(duration).await; })
+///     }
+/// }
+///
+/// let config = StreamConfig::default().with_timer(SmolTimer);
+/// ```
+pub trait Timer: fmt::Debug + Send + Sync + 'static {
+    /// Returns a future that completes once `duration` has elapsed.
+    fn sleep(&self, duration: Duration) -> Sleep;
+}
+
+/// Timer backed by `tokio::time::sleep`.
+///
+/// Sleeps must be polled inside a Tokio runtime with the time driver
+/// enabled; they follow Tokio's paused clock in tests.
+#[cfg(feature = "tokio")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioTimer;
+
+#[cfg(feature = "tokio")]
+impl Timer for TokioTimer {
+    fn sleep(&self, duration: Duration) -> Sleep {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// Timer that uses [`TokioTimer`] when a sleep is created inside a Tokio
+/// runtime and [`ThreadTimer`] otherwise.
+///
+/// Sleeps are created by the task that waits on them, so a channel shared
+/// between Tokio and another executor sleeps correctly on both. Inside Tokio
+/// the runtime needs its time driver enabled.
+#[cfg(feature = "tokio")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AutoTimer;
+
+#[cfg(feature = "tokio")]
+impl Timer for AutoTimer {
+    fn sleep(&self, duration: Duration) -> Sleep {
+        match tokio::runtime::Handle::try_current() {
+            Ok(_) => TokioTimer.sleep(duration),
+            Err(_) => ThreadTimer.sleep(duration),
+        }
+    }
+}
+
+/// Minimal runtime-independent timer.
+///
+/// Deadlines are kept in an ordered queue served by one process-wide
+/// background thread, started on first use, which wakes each sleep when it
+/// expires. Dropping a pending sleep removes its deadline from the queue.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThreadTimer;
+
+impl Timer for ThreadTimer {
+    fn sleep(&self, duration: Duration) -> Sleep {
+        Box::pin(ThreadSleep {
+            deadline: Instant::now() + duration,
+            scheduled: None,
+        })
+    }
+}
+
+/// The timer used when none is configured: [`AutoTimer`] with the `tokio`
+/// feature, [`ThreadTimer`] without it.
+pub(crate) fn default_timer() -> Arc<dyn Timer> {
+    #[cfg(feature = "tokio")]
+    return Arc::new(AutoTimer);
+    #[cfg(not(feature = "tokio"))]
+    return Arc::new(ThreadTimer);
+}
+
+/// Runs `future` for at most `limit`; `None` if the timer fired first.
+pub(crate) async fn timeout<F: Future>(
+    timer: &dyn Timer,
+    limit: Duration,
+    future: F,
+) -> Option<F::Output> {
+    let mut future = pin!(future);
+    let mut sleep = timer.sleep(limit);
+    poll_fn(|cx| {
+        if let Poll::Ready(output) = future.as_mut().poll(cx) {
+            return Poll::Ready(Some(output));
+        }
+        sleep.as_mut().poll(cx).map(|()| None)
+    })
+    .await
+}
+
+/// Position of a scheduled sleep in the timer thread's queue.
+type Key = (Instant, u64);
+
+struct ThreadSleep {
+    deadline: Instant,
+    // Scheduled on first poll, so an unpolled sleep costs nothing.
+    scheduled: Option<(Key, Arc<SleepState>)>,
+}
+
+#[derive(Default)]
+struct SleepState {
+    fired: AtomicBool,
+    waker: AtomicWaker,
+}
+
+impl Future for ThreadSleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if Instant::now() >= self.deadline {
+            return Poll::Ready(());
+        }
+        let deadline = self.deadline;
+        let (_, state) = self
+            .scheduled
+            .get_or_insert_with(|| TimerThread::get().schedule(deadline));
+        // Register before checking, so a concurrent fire is never missed.
+        state.waker.register(cx.waker());
+        if state.fired.load(Ordering::Acquire) {
+            return Poll::Ready(());
+        }
+        Poll::Pending
+    }
+}
+
+impl Drop for ThreadSleep {
+    fn drop(&mut self) {
+        if let Some((key, state)) = &self.scheduled {
+            if !state.fired.load(Ordering::Acquire) {
+                TimerThread::get().cancel(key);
+            }
+        }
+    }
+}
+
+struct TimerThread {
+    queue: Mutex<BTreeMap<Key, Arc<SleepState>>>,
+    changed: Condvar,
+    next_seq: AtomicU64,
+}
+
+impl TimerThread {
+    fn get() -> &'static Self {
+        static TIMER: OnceLock<TimerThread> = OnceLock::new();
+        TIMER.get_or_init(|| {
+            thread::Builder::new()
+                .name("ringmpsc-stream-timer".into())
+                .spawn(|| TimerThread::get().run())
+                .expect("failed to spawn timer thread");
+            TimerThread {
+                queue: Mutex::new(BTreeMap::new()),
+                changed: Condvar::new(),
+                next_seq: AtomicU64::new(0),
+            }
+        })
+    }
+
+    fn schedule(&self, deadline: Instant) -> (Key, Arc<SleepState>) {
+        let state = Arc::new(SleepState::default());
+        let key = (deadline, self.next_seq.fetch_add(1, Ordering::Relaxed));
+        let mut queue = self.queue.lock().unwrap();
+        let earliest = queue.first_key_value().is_none_or(|(head, _)| key < *head);
+        queue.insert(key, Arc::clone(&state));
+        drop(queue);
+        if earliest {
+            self.changed.notify_one();
+        }
+        (key, state)
+    }
+
+    /// Removes a sleep that was dropped before its deadline.
+    fn cancel(&self, key: &Key) {
+        self.queue.lock().unwrap().remove(key);
+    }
+
+    fn run(&self) {
+        let mut queue = self.queue.lock().unwrap();
+        loop {
+            let Some(((deadline, _), _)) = queue.first_key_value() else {
+                queue = self.changed.wait(queue).unwrap();
+                continue;
+            };
+            let now = Instant::now();
+            if *deadline > now {
+                let wait = *deadline - now;
+                queue = self.changed.wait_timeout(queue, wait).unwrap().0;
+                continue;
+            }
+            if let Some((_, state)) = queue.pop_first() {
+                state.fired.store(true, Ordering::Release);
+                state.waker.wake();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thread_timer_sleeps() {
+        let started = Instant::now();
+        futures::executor::block_on(ThreadTimer.sleep(Duration::from_millis(20)));
+        assert!(started.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[test]
+    fn test_thread_timer_orders_deadlines() {
+        let started = Instant::now();
+        let long = ThreadTimer.sleep(Duration::from_millis(60));
+        let short = ThreadTimer.sleep(Duration::from_millis(10));
+        let first = futures::executor::block_on(async {
+            matches!(
+                futures::future::select(long, short).await,
+                futures::future::Either::Right(_)
+            )
+        });
+        assert!(first, "shorter sleep must fire first");
+        assert!(started.elapsed() < Duration::from_millis(60));
+    }
+
+    #[test]
+    fn test_thread_timer_dropped_sleep_leaves_queue() {
+        let waker = futures::task::noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        let mut sleep = ThreadTimer.sleep(Duration::from_hours(1));
+        assert!(sleep.as_mut().poll(&mut cx).is_pending());
+
+        let queued = |deadline: Instant| {
+            TimerThread::get()
+                .queue
+                .lock()
+                .unwrap()
+                .keys()
+                .any(|(d, _)| *d >= deadline)
+        };
+        let far = Instant::now() + Duration::from_mins(50);
+        assert!(queued(far));
+        drop(sleep);
+        assert!(!queued(far));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn test_auto_timer_outside_tokio_uses_thread_timer() {
+        // No runtime on this thread: a TokioTimer sleep would panic here.
+        let started = Instant::now();
+        futures::executor::block_on(AutoTimer.sleep(Duration::from_millis(10)));
+        assert!(started.elapsed() >= Duration::from_millis(10));
+    }
+
+    #[test]
+    fn test_timeout_helper() {
+        let timer = ThreadTimer;
+        let expired = futures::executor::block_on(timeout(
+            &timer,
+            Duration::from_millis(10),
+            std::future::pending::<()>(),
+        ));
+        assert_eq!(expired, None);
+        let done = futures::executor::block_on(timeout(&timer, Duration::from_secs(5), async { 7 }));
+        assert_eq!(done, Some(7));
+    }
+}
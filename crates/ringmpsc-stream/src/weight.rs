@@ -0,0 +1,319 @@
+//! Byte-weighted capacity accounting for [`StreamConfig::weigher`] and weight budgets.
+
+use crate::config::StreamConfig;
+#[cfg(debug_assertions)]
+use crate::invariants::debug_assert_weight_released;
+use ringmpsc_rs::{Channel, Config, Ring};
+use std::any::Any;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::task::Waker;
+
+/// Measures an item, typically its size in bytes.
+///
+/// Called once, when a sender commits the item; the weight is stored
+/// alongside it and released when the receiver drains it.
+/// Implemented for every `Fn(&T) -> usize + Send + Sync + 'static`.
+pub trait Weigher<T>: Send + Sync {
+    /// Returns the weight charged against the budget while `item` is queued.
+    fn weigh(&self, item: &T) -> usize;
+}
+
+impl<T, F> Weigher<T> for F
+where
+    F: Fn(&T) -> usize + Send + Sync + 'static,
+{
+    fn weigh(&self, item: &T) -> usize {
+        self(item)
+    }
+}
+
+/// A [`Weigher`] held by [`StreamConfig::weigher`].
+///
+/// The item type is erased so that `StreamConfig` stays non-generic; a
+/// channel uses the weigher only if it was built for the channel's item
+/// type. Created by [`StreamConfig::with_weigher`].
+#[derive(Clone)]
+pub struct ErasedWeigher {
+    item_type: &'static str,
+    // An `Arc<dyn Weigher<T>>`.
+    weigher: Arc<dyn Any + Send + Sync>,
+}
+
+impl ErasedWeigher {
+    pub(crate) fn new<T: 'static>(weigher: impl Weigher<T> + 'static) -> Self {
+        let weigher: Arc<dyn Weigher<T>> = Arc::new(weigher);
+        Self {
+            item_type: std::any::type_name::<T>(),
+            weigher: Arc::new(weigher),
+        }
+    }
+
+    /// Returns the weigher if it weighs `T`.
+    pub(crate) fn get<T: 'static>(&self) -> Option<Arc<dyn Weigher<T>>> {
+        self.weigher.downcast_ref::<Arc<dyn Weigher<T>>>().cloned()
+    }
+}
+
+impl fmt::Debug for ErasedWeigher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ErasedWeigher")
+            .field("item_type", &self.item_type)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Outstanding weight of a weighted channel, from
+/// [`RingReceiver::weight_stats`](crate::RingReceiver::weight_stats) or
+/// [`SenderFactory::weight_stats`](crate::SenderFactory::weight_stats).
+///
+/// Weight is outstanding from the commit until the receiver drains the item
+/// from its ring.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct WeightStats {
+    /// Weight of every queued item.
+    pub total: usize,
+    /// Weight queued per sender, indexed by [`RingSender::id`](crate::RingSender::id).
+    pub per_sender: Vec<usize>,
+    /// [`StreamConfig::max_weight`] the channel was built with.
+    pub max_weight: Option<usize>,
+    /// [`StreamConfig::max_sender_weight`] the channel was built with.
+    pub max_sender_weight: Option<usize>,
+}
+
+/// Weight charged by senders and released by the receiver's drains.
+///
+/// Only senders whose budget is exhausted park here (a full ring parks in
+/// the ring's producer slot instead); any release wakes all of them, since
+/// weight freed on one ring can admit a sender on another.
+pub(crate) struct WeightGauge<T> {
+    weigher: Arc<dyn Weigher<T>>,
+    max_total: Option<usize>,
+    max_per_sender: Option<usize>,
+    total: AtomicUsize,
+    per_sender: Box<[AtomicUsize]>,
+    queued: Box<[QueuedWeights]>,
+    capacity: usize,
+    waiters: Mutex<Vec<Waker>>,
+    parked: AtomicBool,
+}
+
+/// Weights of the items in one ring, in ring order.
+///
+/// Slot `n & mask` holds the weight of the ring's `n`th item. It is written
+/// by the sender before the item is committed and read by the consumer
+/// before the item's slot is released, so the ring's own commit/consume
+/// ordering covers it, exactly like the item slot it shadows.
+struct QueuedWeights {
+    // Allocated by the sender's first commit.
+    slots: OnceLock<Box<[AtomicUsize]>>,
+    // Items committed (sender only) and drained (consumer only).
+    pushed: AtomicUsize,
+    popped: AtomicUsize,
+}
+
+impl<T> WeightGauge<T> {
+    pub(crate) fn new(
+        weigher: Arc<dyn Weigher<T>>,
+        stream_config: &StreamConfig,
+        config: &Config,
+    ) -> Self {
+        Self {
+            weigher,
+            max_total: stream_config.max_weight,
+            max_per_sender: stream_config.max_sender_weight,
+            total: AtomicUsize::new(0),
+            per_sender: (0..config.max_producers).map(|_| AtomicUsize::new(0)).collect(),
+            queued: (0..config.max_producers)
+                .map(|_| QueuedWeights {
+                    slots: OnceLock::new(),
+                    pushed: AtomicUsize::new(0),
+                    popped: AtomicUsize::new(0),
+                })
+                .collect(),
+            capacity: config.capacity(),
+            waiters: Mutex::new(Vec::new()),
+            parked: AtomicBool::new(false),
+        }
+    }
+
+    pub(crate) fn weigh(&self, item: &T) -> usize {
+        self.weigher.weigh(item)
+    }
+
+    /// Charges `weight` to sender `id` if both budgets have room.
+    ///
+    /// A budget with nothing outstanding always admits, so an item heavier
+    /// than the budget is delivered alone instead of blocking forever.
+    pub(crate) fn try_acquire(&self, id: usize, weight: usize) -> bool {
+        let sender = &self.per_sender[id];
+        if !admit(sender, weight, self.max_per_sender) {
+            return false;
+        }
+        if !admit(&self.total, weight, self.max_total) {
+            sender.fetch_sub(weight, Ordering::SeqCst);
+            return false;
+        }
+        true
+    }
+
+    /// Charges `weight` to sender `id` regardless of the budgets (a used
+    /// `SendPermit`).
+    pub(crate) fn acquire(&self, id: usize, weight: usize) {
+        self.per_sender[id].fetch_add(weight, Ordering::SeqCst);
+        self.total.fetch_add(weight, Ordering::SeqCst);
+    }
+
+    /// Stores the weight of the item sender `id` is about to commit. Must be
+    /// followed by that commit, while the sender still holds its slot.
+    pub(crate) fn record(&self, id: usize, weight: usize) {
+        let queued = &self.queued[id];
+        let slots = queued
+            .slots
+            .get_or_init(|| (0..self.capacity).map(|_| AtomicUsize::new(0)).collect());
+        let n = queued.pushed.load(Ordering::Relaxed);
+        slots[n & (self.capacity - 1)].store(weight, Ordering::Relaxed);
+        queued.pushed.store(n.wrapping_add(1), Ordering::Relaxed);
+    }
+
+    /// Takes the stored weight of the next item drained from ring `id`.
+    /// Must be called before the item's slot is released.
+    fn take_recorded(&self, id: usize) -> usize {
+        let queued = &self.queued[id];
+        let slots = queued
+            .slots
+            .get()
+            .expect("a committed item of a weighted ring has a recorded weight");
+        let n = queued.popped.load(Ordering::Relaxed);
+        queued.popped.store(n.wrapping_add(1), Ordering::Relaxed);
+        slots[n & (self.capacity - 1)].load(Ordering::Relaxed)
+    }
+
+    /// Returns `true` if sender `id` could commit an item of weight 1.
+    pub(crate) fn has_room(&self, id: usize) -> bool {
+        let fits = |current: usize, max: Option<usize>| {
+            max.is_none_or(|max| current == 0 || current < max)
+        };
+        fits(
+            self.per_sender[id].load(Ordering::SeqCst),
+            self.max_per_sender,
+        ) && fits(self.total.load(Ordering::SeqCst), self.max_total)
+    }
+
+    /// Returns `weight` drained from sender `id`'s ring to the budgets and
+    /// wakes every parked sender.
+    pub(crate) fn release(&self, id: usize, weight: usize) {
+        if weight == 0 {
+            return;
+        }
+        let _previous = self.per_sender[id].fetch_sub(weight, Ordering::SeqCst);
+        // INV-SINK-06: Verify only charged weight is released
+        #[cfg(debug_assertions)]
+        debug_assert_weight_released!(_previous, weight);
+        self.total.fetch_sub(weight, Ordering::SeqCst);
+        self.wake_all();
+    }
+
+    /// Wakes every parked sender, on a release or when the channel closes.
+    pub(crate) fn wake_all(&self) {
+        // SeqCst pairs with `register`: either the waiter's recheck sees the
+        // release (or the close), or this load sees it parked.
+        if self.parked.load(Ordering::SeqCst) {
+            let waiters = {
+                let mut waiters = self.waiters.lock().unwrap();
+                self.parked.store(false, Ordering::SeqCst);
+                std::mem::take(&mut *waiters)
+            };
+            for waker in waiters {
+                waker.wake();
+            }
+        }
+    }
+
+    /// Parks `waker` until the next release. Callers recheck their budget
+    /// afterwards (INV-STREAM-05).
+    pub(crate) fn register(&self, waker: &Waker) {
+        let mut waiters = self.waiters.lock().unwrap();
+        if !waiters.iter().any(|w| w.will_wake(waker)) {
+            waiters.push(waker.clone());
+        }
+        self.parked.store(true, Ordering::SeqCst);
+    }
+
+    /// Outstanding weight of sender `id`.
+    pub(crate) fn sender_weight(&self, id: usize) -> usize {
+        self.per_sender[id].load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn stats(&self, producer_count: usize) -> WeightStats {
+        WeightStats {
+            total: self.total.load(Ordering::Relaxed),
+            per_sender: (0..producer_count)
+                .map(|id| self.sender_weight(id))
+                .collect(),
+            max_weight: self.max_total,
+            max_sender_weight: self.max_per_sender,
+        }
+    }
+}
+
+/// Adds `weight` to `counter` unless that would exceed a non-empty budget.
+fn admit(counter: &AtomicUsize, weight: usize, max: Option<usize>) -> bool {
+    let Some(max) = max else {
+        counter.fetch_add(weight, Ordering::SeqCst);
+        return true;
+    };
+    counter
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |current| {
+            current
+                .checked_add(weight)
+                .filter(|&next| current == 0 || next <= max)
+        })
+        .is_ok()
+}
+
+/// Drains up to `max` items of ring `id`, releasing their weight once the
+/// batch is out.
+pub(crate) fn consume_ring<T>(
+    ring: &Ring<T>,
+    id: usize,
+    weights: Option<&WeightGauge<T>>,
+    max: usize,
+    mut handler: impl FnMut(T),
+) -> usize {
+    let Some(weights) = weights else {
+        return ring.consume_up_to_owned(max, handler);
+    };
+    let mut weight = 0;
+    // The stored weight is read inside the handler, while the batch's slots
+    // are still held: once they are released the sender may reuse them.
+    let consumed = ring.consume_up_to_owned(max, |item| {
+        weight += weights.take_recorded(id);
+        handler(item);
+    });
+    weights.release(id, weight);
+    consumed
+}
+
+/// Drains up to `max` items from every ring in producer order, like
+/// `Channel::consume_all_up_to_owned`, releasing their weight.
+pub(crate) fn consume_up_to<T>(
+    channel: &Channel<T>,
+    weights: Option<&WeightGauge<T>>,
+    max: usize,
+    mut handler: impl FnMut(T),
+) -> usize {
+    let Some(weights) = weights else {
+        return channel.consume_all_up_to_owned(max, handler);
+    };
+    let mut total = 0;
+    for id in 0..channel.producer_count() {
+        if total >= max {
+            break;
+        }
+        let ring = channel.get_ring(id).expect("id is below producer_count");
+        total += consume_ring(ring, id, Some(weights), max - total, &mut handler);
+    }
+    total
+}
@@ -0,0 +1,183 @@
+//! Runtime-agnostic tests: `futures::executor` and plain threads, no Tokio.
+//!
+//! Timed features use `ThreadTimer`, so these pass with or without the
+//! `tokio` feature.
+
+use futures::executor::block_on;
+use futures::{SinkExt, StreamExt};
+use ringmpsc_rs::Config;
+use ringmpsc_stream::{
+    channel, channel_with_stream_config, SendError, SendPolicy, StreamConfig, ThreadTimer,
+};
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn thread_timer_config() -> StreamConfig {
+    StreamConfig::default().with_timer(ThreadTimer)
+}
+
+#[test]
+fn test_send_receive_on_futures_executor() {
+    let (factory, rx) = channel::<u64>(Config::default());
+    let tx = factory.register().expect("registration failed");
+
+    block_on(async {
+        for i in 0..10 {
+            tx.send(i).await.expect("send failed");
+        }
+    });
+    factory.close();
+
+    let items: Vec<u64> = block_on(rx.collect());
+    assert_eq!(items, (0..10).collect::<Vec<_>>());
+}
+
+/// INV-STREAM-02 / INV-STREAM-03 without a runtime: a producer thread blocked
+/// on a 4-slot ring and an idle consumer thread wake each other.
+#[test]
+fn test_backpressure_across_threads() {
+    const ITEMS: u64 = 1_000;
+    let (factory, mut rx) = channel::<u64>(Config::new(2, 1, false));
+    let tx = factory.register().expect("registration failed");
+
+    let producer = thread::spawn(move || {
+        block_on(async {
+            for i in 0..ITEMS {
+                tx.send(i).await.expect("send failed");
+            }
+        });
+        factory.close();
+    });
+
+    let mut next = 0;
+    block_on(async {
+        while let Some(item) = rx.next().await {
+            assert_eq!(item, next);
+            next += 1;
+        }
+    });
+    producer.join().unwrap();
+    assert_eq!(next, ITEMS);
+}
+
+#[test]
+fn test_sink_on_futures_executor() {
+    let (factory, rx) = channel::<u64>(Config::new(2, 1, false));
+    let mut tx = factory.register().expect("registration failed");
+
+    let consumer = thread::spawn(move || block_on(rx.collect::<Vec<_>>()));
+    block_on(async {
+        let mut items = futures::stream::iter((0..100).map(Ok));
+        tx.send_all(&mut items).await.expect("send_all failed");
+        SinkExt::close(&mut tx).await.expect("close failed");
+    });
+    factory.close();
+
+    assert_eq!(consumer.join().unwrap(), (0..100).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_batch_linger_with_thread_timer() {
+    let stream_config = thread_timer_config().with_batch_linger(Duration::from_secs(5));
+    let (factory, mut rx) = channel_with_stream_config::<u64>(Config::default(), stream_config);
+    let tx = factory.register().expect("registration failed");
+
+    let producer = thread::spawn(move || {
+        for i in 0..3 {
+            tx.try_send(i).expect("ring should have space");
+            thread::sleep(Duration::from_millis(10));
+        }
+    });
+
+    let started = Instant::now();
+    let mut out = Vec::new();
+    assert_eq!(block_on(rx.recv_batch(&mut out, 3)), 3);
+    assert_eq!(out, vec![0, 1, 2]);
+    assert!(started.elapsed() < Duration::from_secs(5));
+    producer.join().unwrap();
+
+    // With nothing more coming, a short linger returns the partial batch.
+    let stream_config = thread_timer_config().with_batch_linger(Duration::from_millis(20));
+    let (factory, mut rx) = channel_with_stream_config::<u64>(Config::default(), stream_config);
+    factory.register().unwrap().try_send(9).unwrap();
+    let started = Instant::now();
+    out.clear();
+    assert_eq!(block_on(rx.recv_batch(&mut out, 8)), 1);
+    assert!(started.elapsed() >= Duration::from_millis(20));
+}
+
+#[test]
+fn test_send_timeout_with_thread_timer() {
+    let (factory, _rx) =
+        channel_with_stream_config::<u64>(Config::new(2, 1, false), thread_timer_config());
+    let tx = factory
+        .register_with(SendPolicy::BlockWithTimeout(Duration::from_millis(20)))
+        .expect("registration failed");
+
+    block_on(async {
+        for i in 0..4 {
+            tx.send(i).await.unwrap();
+        }
+        assert_eq!(tx.send(4).await, Err(SendError::Timeout(4)));
+    });
+    assert_eq!(tx.stats().timed_out, 1);
+}
+
+/// INV-STREAM-04 / INV-SHUT-01: shutdown from another thread wakes the idle
+/// receiver and `ShutdownSignal::wait`, and the receiver drains first.
+#[test]
+fn test_shutdown_signal_across_threads() {
+    let (factory, mut rx) = channel::<u64>(Config::default());
+    let tx = factory.register().expect("registration failed");
+    for i in 0..5 {
+        tx.try_send(i).unwrap();
+    }
+    let signal = rx.shutdown_signal();
+    let waiter = {
+        let wait = signal.wait();
+        thread::spawn(move || block_on(wait))
+    };
+
+    let mut received = Vec::new();
+    block_on(async {
+        for _ in 0..5 {
+            received.push(rx.next().await.unwrap());
+        }
+    });
+    let trigger = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(20));
+        tx.try_send(5).unwrap();
+        signal.shutdown();
+    });
+
+    block_on(async {
+        while let Some(item) = rx.next().await {
+            received.push(item);
+        }
+    });
+    trigger.join().unwrap();
+    waiter.join().unwrap();
+    assert_eq!(received, (0..6).collect::<Vec<_>>());
+    assert!(rx.is_shutdown());
+}
+
+#[test]
+fn test_take_until_shutdown_wait() {
+    let (factory, rx) = channel::<u64>(Config::default());
+    let tx = factory.register().expect("registration failed");
+    let signal = rx.shutdown_signal();
+    let stop = signal.wait();
+
+    let consumer = thread::spawn(move || block_on(rx.take_until(stop).collect::<Vec<_>>()));
+    block_on(async {
+        for i in 0..3 {
+            tx.send(i).await.unwrap();
+        }
+    });
+    thread::sleep(Duration::from_millis(20));
+    signal.shutdown();
+
+    let received = consumer.join().unwrap();
+    assert!(received.len() <= 3);
+    assert_eq!(received, (0..received.len() as u64).collect::<Vec<_>>());
+}
@@ -1,8 +1,16 @@
-//! Integration tests for ringmpsc-stream.
+//! Integration tests for ringmpsc-stream on Tokio.
+//!
+//! See `executor.rs` for the same guarantees under `futures::executor`.
+
+#![cfg(feature = "tokio")]
 
 use futures::SinkExt;
 use ringmpsc_rs::Config;
-use ringmpsc_stream::{channel, channel_with_stream_config, StreamConfig, StreamExt};
+use ringmpsc_stream::{
+    channel, channel_with_stream_config, SendError, SendPolicy, StreamConfig, StreamError,
+    StreamExt,
+};
+use std::pin::Pin;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -119,6 +127,7 @@ async fn test_graceful_shutdown() {
 }
 
 #[tokio::test]
+#[allow(deprecated)]
 async fn test_stream_config() {
     let config = StreamConfig::low_latency();
     assert_eq!(config.poll_interval, Duration::from_millis(1));
@@ -184,38 +193,29 @@ async fn test_fifo_ordering_single_producer() {
 // drain and the waker registration (the in-flight race window). These tests
 // verify that the fix works correctly.
 //
-// Architecture note: tokio::sync::Notify's `Notified` future is stack-local
-// in poll_next. When poll_next returns Pending, Notified is dropped, which
-// de-registers the waker. Any subsequent notify_one() stores a permit but
-// cannot wake the task — only the timer can do that. Therefore:
+// Architecture note: the receiver parks in each ring's consumer waker slot.
+// The slots stay registered after poll_next returns Pending, so a commit at
+// any later point wakes the task; there is no poll timer (INV-STREAM-02).
 //
-//   - The re-drain fixes items arriving DURING poll_next (in-flight race)
-//   - The timer catches items arriving AFTER poll_next returns (inter-call)
+//   - The re-drain covers items arriving DURING poll_next (in-flight race)
+//   - The ring waker slots cover items arriving AFTER poll_next returns
 //
 // Test 1 (current_thread, deterministic): Pre-fills the ring with try_send
-//   before the receiver runs. With a 60s timer, the only way items can be
-//   received is via the permit + re-drain path. This WILL fail without the
-//   re-drain fix, proving it works.
+//   before the receiver runs and exhausts batch_hint across several polls.
 //
-// Tests 2-3 (multi_thread, practical): Concurrent senders with a realistic
-//   timer (100ms / 50ms). Verify correctness (all items, FIFO order) under
-//   load. The timer handles inter-call gaps; the re-drain reduces latency
-//   for in-flight items.
+// Tests 2-3 (multi_thread, practical): Concurrent senders, and bursts with
+//   idle gaps in between. Verify correctness (all items, FIFO order) under
+//   load with no timer to fall back on.
 // =============================================================================
 
-/// Deterministic proof that the re-drain catches items the timer can't reach.
+/// Deterministic check that a pre-filled ring is fully drained across polls.
 ///
 /// On `current_thread`, `try_send` pre-fills the ring synchronously. The receiver
-/// then runs. With `batch_hint=64` and 100 items, the first drain gets 64 items.
-/// The next `poll_next` finds buffer empty, `data_pending=false`, and creates
-/// `data_notified` → Pending (Notify is EMPTY, no stored permit). Without the
-/// re-drain, the remaining 36 items would be stuck until the 60s timer fires.
-/// WITH the re-drain, they're caught immediately.
+/// then runs. With `batch_hint=64` and 100 items, the first drain gets 64 items
+/// and the next `poll_next` picks up the remaining 36 without ever parking.
 #[tokio::test(flavor = "current_thread")]
 async fn test_recheck_catches_prefilled_ring() {
-    let stream_config = StreamConfig::default()
-        .with_poll_interval(Duration::from_secs(60))
-        .with_batch_hint(64);
+    let stream_config = StreamConfig::default().with_batch_hint(64);
 
     let config = ringmpsc_rs::Config::new(14, 4, false);
     let (factory, mut rx) =
@@ -227,8 +227,7 @@ async fn test_recheck_catches_prefilled_ring() {
         tx.try_send(i).expect("ring should not be full with 16384 slots");
     }
 
-    // Receive all 100 items. With 60s timer disabled, this MUST use
-    // permit consumption + re-drain to find all items.
+    // Receive all 100 items.
     let mut received = Vec::new();
     let deadline = tokio::time::timeout(Duration::from_secs(5), async {
         while received.len() < 100 {
@@ -240,8 +239,7 @@ async fn test_recheck_catches_prefilled_ring() {
     });
 
     deadline.await.expect(
-        "Timed out — re-drain failed to catch items after batch_hint exhausted \
-         (remaining items stuck in ring, timer disabled at 60s)",
+        "Timed out — remaining items stuck in ring after batch_hint exhausted",
     );
 
     assert_eq!(received.len(), 100, "expected all 100 items");
@@ -250,17 +248,15 @@ async fn test_recheck_catches_prefilled_ring() {
     }
 }
 
-/// Multi-producer correctness test with realistic timer.
+/// Multi-producer correctness test.
 ///
-/// 4 producers send concurrently on a `multi_thread` runtime. The 100ms timer
-/// catches items arriving between `poll_next` calls (when Notified is dropped).
-/// The re-drain catches items arriving during `poll_next` (in-flight race).
-/// Together they ensure all items are received with per-producer FIFO order.
+/// 4 producers send concurrently on a `multi_thread` runtime. The ring waker
+/// slots catch items arriving between `poll_next` calls; the re-drain catches
+/// items arriving during `poll_next` (in-flight race). Together they ensure all
+/// items are received with per-producer FIFO order.
 #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
 async fn test_no_lost_wakeup_multi_producer() {
-    let stream_config = StreamConfig::default()
-        .with_poll_interval(Duration::from_millis(100))
-        .with_batch_hint(64);
+    let stream_config = StreamConfig::default().with_batch_hint(64);
 
     let (factory, mut rx) =
         channel_with_stream_config::<u64>(Config::default(), stream_config);
@@ -295,7 +291,7 @@ async fn test_no_lost_wakeup_multi_producer() {
     });
 
     deadline.await.expect(
-        "Timed out waiting for items (100ms timer should catch inter-call gaps)",
+        "Timed out waiting for items (lost wakeup between poll_next calls)",
     );
 
     for h in handles {
@@ -330,13 +326,12 @@ async fn test_no_lost_wakeup_multi_producer() {
 /// Burst-pattern test: rapid sends with pauses between bursts.
 ///
 /// The 5ms pauses create windows where the receiver returns Pending with no
-/// active senders. The 50ms timer catches these inter-burst gaps. Within
-/// each burst, the re-drain catches items pushed during `poll_next` execution.
+/// active senders. The first commit of the next burst must wake it through the
+/// ring waker slot. Within each burst, the re-drain catches items pushed during
+/// `poll_next` execution.
 #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
 async fn test_no_lost_wakeup_burst_pattern() {
-    let stream_config = StreamConfig::default()
-        .with_poll_interval(Duration::from_millis(50))
-        .with_batch_hint(16);
+    let stream_config = StreamConfig::default().with_batch_hint(16);
 
     let (factory, mut rx) =
         channel_with_stream_config::<u64>(Config::default(), stream_config);
@@ -352,8 +347,8 @@ async fn test_no_lost_wakeup_burst_pattern() {
                     .await
                     .expect("send failed");
             }
-            // Pause between bursts — receiver returns Pending, Notified dropped,
-            // timer must re-wake the task for the next burst's items
+            // Pause between bursts — receiver parks, and the next burst's
+            // first commit must wake it
             tokio::time::sleep(Duration::from_millis(5)).await;
         }
     });
@@ -369,9 +364,737 @@ async fn test_no_lost_wakeup_burst_pattern() {
     });
 
     deadline.await.expect(
-        "Timed out — burst pattern with 50ms timer should complete well within 10s",
+        "Timed out — burst pattern should complete well within 10s",
     );
 
     sender.await.expect("sender panicked");
     assert_eq!(received.len(), total as usize);
 }
+
+// =============================================================================
+// Event-driven wakeups (INV-STREAM-02, INV-STREAM-07, INV-SHUT-02)
+//
+// There is no poll timer to fall back on: a lost wakeup leaves the waiter
+// parked, and the bounding timeout fails the test instead of hanging it.
+// =============================================================================
+
+/// A receiver parked on an empty channel is woken by the next send.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_idle_receiver_woken_by_send() {
+    let stream_config = StreamConfig::default();
+    let (factory, mut rx) = channel_with_stream_config::<u64>(Config::default(), stream_config);
+    let tx = factory.register().expect("registration failed");
+
+    let receiver = tokio::spawn(async move { rx.next().await });
+
+    // Let the receiver park before sending.
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    tx.send(7).await.expect("send failed");
+
+    let item = tokio::time::timeout(Duration::from_secs(1), receiver)
+        .await
+        .expect("receiver was not woken by the send")
+        .expect("receiver panicked");
+    assert_eq!(item, Some(7));
+}
+
+/// A sender parked on a full ring is woken when the receiver drains it.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_blocked_sender_woken_by_drain() {
+    let stream_config = StreamConfig::default();
+    let config = Config::new(2, 1, false); // 4 slots
+    let (factory, mut rx) = channel_with_stream_config::<u64>(config, stream_config);
+    let tx = factory.register().expect("registration failed");
+
+    for i in 0..4 {
+        tx.try_send(i).expect("ring should have space");
+    }
+
+    let sender = tokio::spawn(async move {
+        tx.send(4).await.expect("send failed");
+    });
+
+    // Let the sender park on the full ring before draining.
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert_eq!(rx.next().await, Some(0));
+
+    tokio::time::timeout(Duration::from_secs(1), sender)
+        .await
+        .expect("sender was not woken by the drain")
+        .expect("sender panicked");
+}
+
+/// A shutdown signal from another task wakes a parked receiver, which then ends.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_shutdown_signal_wakes_idle_receiver() {
+    let stream_config = StreamConfig::default();
+    let (_factory, mut rx) = channel_with_stream_config::<u64>(Config::default(), stream_config);
+    let signal = rx.shutdown_signal();
+
+    let receiver = tokio::spawn(async move { rx.next().await });
+
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    signal.shutdown();
+
+    let item = tokio::time::timeout(Duration::from_secs(1), receiver)
+        .await
+        .expect("receiver was not woken by shutdown")
+        .expect("receiver panicked");
+    assert_eq!(item, None);
+}
+
+/// Shutdown wakes a sender parked on a full ring with `Closed`.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_shutdown_wakes_blocked_sender() {
+    let stream_config = StreamConfig::default();
+    let config = Config::new(2, 1, false); // 4 slots
+    let (factory, rx) = channel_with_stream_config::<u64>(config, stream_config);
+    let tx = factory.register().expect("registration failed");
+    let signal = rx.shutdown_signal();
+
+    for i in 0..4 {
+        tx.try_send(i).expect("ring should have space");
+    }
+
+    let sender = tokio::spawn(async move { tx.send(4).await });
+
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    signal.shutdown();
+
+    let result = tokio::time::timeout(Duration::from_secs(1), sender)
+        .await
+        .expect("sender was not woken by shutdown")
+        .expect("sender panicked");
+    assert!(matches!(result, Err(ringmpsc_stream::SendError::Closed(4))));
+}
+
+#[tokio::test]
+async fn test_shutdown_with_deadline_returns_leftovers() {
+    let config = Config::new(4, 2, false);
+    let (factory, mut rx) = channel::<u64>(config);
+    let tx0 = factory.register().expect("registration failed");
+    let tx1 = factory.register().expect("registration failed");
+
+    for i in 0..5 {
+        tx0.send(i).await.expect("send failed");
+        tx1.send(100 + i).await.expect("send failed");
+    }
+    drop(tx0);
+    drop(tx1);
+
+    // One poll moves a batch into the buffer and yields its first item.
+    let first = rx.next().await.expect("item expected");
+
+    let report = rx.shutdown_with_deadline(Duration::from_secs(1)).await;
+    assert!(!report.timed_out);
+    assert_eq!(report.senders_outstanding, 0);
+    assert_eq!(report.len(), 9);
+    assert_eq!(report.counts().len(), 2);
+    assert!(factory.register().is_err(), "registration after shutdown");
+
+    let mut items = vec![first];
+    items.extend(report.into_items());
+    let from_0: Vec<u64> = items.iter().copied().filter(|&v| v < 100).collect();
+    let from_1: Vec<u64> = items.iter().copied().filter(|&v| v >= 100).collect();
+    assert_eq!(from_0, (0..5).collect::<Vec<_>>());
+    assert_eq!(from_1, (100..105).collect::<Vec<_>>());
+}
+
+#[tokio::test]
+async fn test_shutdown_with_deadline_fails_blocked_sender() {
+    let config = Config::new(2, 1, false); // 4 slots
+    let (factory, rx) = channel::<u64>(config);
+    let tx = factory.register().expect("registration failed");
+
+    for i in 0..4 {
+        tx.try_send(i).expect("ring should have space");
+    }
+    // Blocks on the full ring; dropping `tx` afterwards ends the wait.
+    let sender = tokio::spawn(async move { tx.send(4).await });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    let report = tokio::time::timeout(
+        Duration::from_secs(5),
+        rx.shutdown_with_deadline(Duration::from_secs(2)),
+    )
+    .await
+    .expect("blocked sender held up the shutdown");
+    assert!(!report.timed_out);
+    assert_eq!(report.per_producer, vec![vec![0, 1, 2, 3]]);
+
+    let result = sender.await.expect("sender panicked");
+    assert!(matches!(result, Err(SendError::Closed(4))));
+}
+
+#[tokio::test]
+async fn test_shutdown_with_deadline_reports_idle_sender() {
+    let (factory, rx) = channel::<u64>(Config::default());
+    let tx = factory.register().expect("registration failed");
+    tx.send(7).await.expect("send failed");
+
+    // `tx` stays alive, but it cannot commit after the close: the shutdown
+    // returns once the rings are drained instead of burning the deadline.
+    let report = tokio::time::timeout(
+        Duration::from_secs(5),
+        rx.shutdown_with_deadline(Duration::from_mins(1)),
+    )
+    .await
+    .expect("shutdown waited on an idle sender");
+    assert!(!report.timed_out);
+    assert_eq!(report.senders_outstanding, 1);
+    assert_eq!(report.counts(), vec![1]);
+    assert!(matches!(tx.send(8).await, Err(SendError::Closed(8))));
+}
+
+/// Draining one ring wakes only that ring's sender (INV-STREAM-03): senders
+/// parked on other full rings — via `send()` or `Sink::poll_ready` — stay
+/// asleep instead of waking to find their ring still full.
+#[test]
+fn test_drain_wakes_only_drained_senders() {
+    use futures::Sink;
+    use std::future::Future;
+    use std::pin::pin;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::task::{Context, Poll, Wake, Waker};
+
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    const SENDERS: usize = 8;
+    // 4-slot rings; a batch hint of 4 drains exactly one full ring per poll.
+    let config = Config::new(2, SENDERS, false);
+    let (factory, mut rx) =
+        channel_with_stream_config::<u64>(config, StreamConfig::default().with_batch_hint(4));
+    let mut senders: Vec<_> = (0..SENDERS)
+        .map(|_| factory.register().expect("registration failed"))
+        .collect();
+    for tx in &senders {
+        for i in 0..4 {
+            tx.try_send(i).expect("ring should have space");
+        }
+    }
+
+    let counters: Vec<_> = (0..SENDERS)
+        .map(|_| Arc::new(CountingWaker(AtomicUsize::new(0))))
+        .collect();
+    let wakers: Vec<_> = counters.iter().map(|c| Waker::from(Arc::clone(c))).collect();
+
+    // Even senders park in `send()`, odd senders in `Sink::poll_ready`.
+    let (even, odd): (Vec<_>, Vec<_>) = senders.iter_mut().enumerate().partition(|(i, _)| i % 2 == 0);
+    let mut sends: Vec<_> = even
+        .into_iter()
+        .map(|(i, tx)| (i, Box::pin(tx.send(99))))
+        .collect();
+    for (i, fut) in &mut sends {
+        let mut cx = Context::from_waker(&wakers[*i]);
+        assert!(fut.as_mut().poll(&mut cx).is_pending());
+    }
+    let mut sinks: Vec<_> = odd.into_iter().collect();
+    for (i, tx) in &mut sinks {
+        let mut cx = Context::from_waker(&wakers[*i]);
+        let mut tx = Pin::new(&mut **tx);
+        assert!(tx.as_mut().start_send(99).is_ok());
+        assert!(tx.as_mut().poll_ready(&mut cx).is_pending());
+    }
+
+    // One poll drains ring 0 only.
+    let noop = Waker::noop();
+    let mut cx = Context::from_waker(noop);
+    assert_eq!(pin!(rx.next()).poll(&mut cx), Poll::Ready(Some(0)));
+
+    let woken: Vec<_> = counters.iter().map(|c| c.0.load(Ordering::SeqCst)).collect();
+    assert_eq!(woken[0], 1, "drained sender was not woken");
+    assert!(
+        woken[1..].iter().all(|&n| n == 0),
+        "senders on undrained rings were woken: {woken:?}"
+    );
+
+    // The next drain moves on to ring 1 and wakes exactly that sender.
+    for expected in 1..4 {
+        assert_eq!(pin!(rx.next()).poll(&mut cx), Poll::Ready(Some(expected)));
+    }
+    assert_eq!(pin!(rx.next()).poll(&mut cx), Poll::Ready(Some(0)));
+    let woken: Vec<_> = counters.iter().map(|c| c.0.load(Ordering::SeqCst)).collect();
+    assert_eq!(&woken[..2], &[1, 1]);
+    assert!(woken[2..].iter().all(|&n| n == 0), "{woken:?}");
+}
+
+#[tokio::test]
+async fn test_recv_batch_respects_max() {
+    let (factory, mut rx) = channel::<u64>(Config::default());
+    let tx = factory.register().expect("registration failed");
+    for i in 0..10 {
+        tx.try_send(i).expect("ring should have space");
+    }
+
+    let mut out = Vec::new();
+    assert_eq!(rx.recv_batch(&mut out, 4).await, 4);
+    assert_eq!(rx.recv_batch(&mut out, 4).await, 4);
+    assert_eq!(rx.recv_batch(&mut out, 4).await, 2);
+    assert_eq!(out, (0..10).collect::<Vec<_>>());
+}
+
+/// Items buffered by `poll_next` are returned by `recv_batch` first, in order.
+#[tokio::test]
+async fn test_recv_batch_after_next() {
+    let (factory, mut rx) = channel_with_stream_config::<u64>(
+        Config::default(),
+        StreamConfig::default().with_batch_hint(8),
+    );
+    let tx = factory.register().expect("registration failed");
+    for i in 0..6 {
+        tx.try_send(i).expect("ring should have space");
+    }
+
+    assert_eq!(rx.next().await, Some(0));
+    let mut out = vec![99];
+    assert_eq!(rx.recv_batch(&mut out, 16).await, 5);
+    assert_eq!(out, vec![99, 1, 2, 3, 4, 5]);
+}
+
+/// With a linger window, a partial batch keeps filling until it is full.
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn test_recv_batch_linger_fills_batch() {
+    let stream_config = StreamConfig::default().with_batch_linger(Duration::from_secs(1));
+    let (factory, mut rx) = channel_with_stream_config::<u64>(Config::default(), stream_config);
+    let tx = factory.register().expect("registration failed");
+
+    tokio::spawn(async move {
+        for i in 0..3 {
+            tx.send(i).await.expect("send failed");
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    });
+
+    let started = tokio::time::Instant::now();
+    let mut out = Vec::new();
+    assert_eq!(rx.recv_batch(&mut out, 3).await, 3);
+    assert_eq!(out, vec![0, 1, 2]);
+    assert!(started.elapsed() < Duration::from_secs(1));
+}
+
+/// The linger window bounds how long a partial batch waits.
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn test_recv_batch_linger_expires() {
+    let linger = Duration::from_millis(50);
+    let stream_config = StreamConfig::default().with_batch_linger(linger);
+    let (factory, mut rx) = channel_with_stream_config::<u64>(Config::default(), stream_config);
+    let tx = factory.register().expect("registration failed");
+    tx.try_send(7).expect("ring should have space");
+
+    let started = tokio::time::Instant::now();
+    let mut out = Vec::new();
+    assert_eq!(rx.recv_batch(&mut out, 8).await, 1);
+    assert_eq!(out, vec![7]);
+    assert!(started.elapsed() >= linger);
+}
+
+/// Closing the channel ends the linger window early and then ends the stream.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_recv_batch_close_cuts_linger() {
+    let stream_config = StreamConfig::default().with_batch_linger(Duration::from_hours(1));
+    let (factory, mut rx) = channel_with_stream_config::<u64>(Config::default(), stream_config);
+    let tx = factory.register().expect("registration failed");
+    tx.try_send(1).expect("ring should have space");
+
+    let closer = tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        tx.try_send(2).expect("ring should have space");
+        factory.close();
+    });
+
+    let mut out = Vec::new();
+    let n = tokio::time::timeout(Duration::from_secs(1), rx.recv_batch(&mut out, 8))
+        .await
+        .expect("close did not end the linger window");
+    assert_eq!(n, 2);
+    assert_eq!(out, vec![1, 2]);
+    assert_eq!(rx.recv_batch(&mut out, 8).await, 0);
+    closer.await.unwrap();
+}
+
+/// Shutdown drains every committed item through the batch API before it
+/// reports the end of the stream.
+#[tokio::test]
+async fn test_recv_batch_shutdown_drain() {
+    let (factory, mut rx) = channel::<u64>(Config::default());
+    let tx = factory.register().expect("registration failed");
+    for i in 0..10 {
+        tx.try_send(i).expect("ring should have space");
+    }
+    rx.shutdown();
+
+    let mut out = Vec::new();
+    assert_eq!(rx.recv_batch(&mut out, 4).await, 4);
+    assert_eq!(rx.recv_batch(&mut out, 4).await, 4);
+    assert_eq!(rx.recv_batch(&mut out, 4).await, 2);
+    assert_eq!(rx.recv_batch(&mut out, 4).await, 0);
+    assert_eq!(out, (0..10).collect::<Vec<_>>());
+    assert_eq!(rx.next().await, None);
+}
+
+#[tokio::test]
+async fn test_into_batch_stream() {
+    let (factory, rx) = channel::<u64>(Config::default());
+    let senders: Vec<_> = (0..3)
+        .map(|_| factory.register().expect("registration failed"))
+        .collect();
+    for (p, tx) in senders.iter().enumerate() {
+        for i in 0..20 {
+            tx.try_send(p as u64 * 100 + i).expect("ring should have space");
+        }
+    }
+    factory.close();
+
+    let batches: Vec<Vec<u64>> = rx.into_batch_stream(16).collect().await;
+    assert!(batches.iter().all(|b| !b.is_empty() && b.len() <= 16));
+
+    // INV-STREAM-01: per-producer order survives batching
+    let items: Vec<u64> = batches.into_iter().flatten().collect();
+    assert_eq!(items.len(), 60);
+    for p in 0..3u64 {
+        let from_p: Vec<_> = items.iter().copied().filter(|x| x / 100 == p).collect();
+        assert_eq!(from_p, (0..20).map(|i| p * 100 + i).collect::<Vec<_>>());
+    }
+}
+
+fn drain_now(rx: &mut ringmpsc_stream::RingReceiver<u64>) -> Vec<u64> {
+    let mut out = Vec::new();
+    futures::executor::block_on(rx.recv_batch(&mut out, 64));
+    out
+}
+
+#[tokio::test]
+async fn test_send_policy_drop_newest() {
+    let (factory, mut rx) = channel::<u64>(Config::new(2, 1, false));
+    let tx = factory
+        .register_with(SendPolicy::DropNewest)
+        .expect("registration failed");
+
+    for i in 0..10 {
+        tx.send(i).await.expect("drop policy never fails while open");
+    }
+    assert_eq!(tx.stats().dropped, 6);
+    assert_eq!(drain_now(&mut rx), vec![0, 1, 2, 3]);
+}
+
+/// `KeepLatest` keeps the newest item in the overflow slot and delivers it
+/// ahead of later sends.
+#[tokio::test]
+async fn test_send_policy_keep_latest() {
+    let (factory, mut rx) = channel::<u64>(Config::new(2, 1, false));
+    let tx = factory
+        .register_with(SendPolicy::KeepLatest)
+        .expect("registration failed");
+
+    for i in 0..10 {
+        tx.send(i).await.expect("drop policy never fails while open");
+    }
+    // 0..4 fill the ring, 9 is held in the slot, 4..9 were displaced.
+    assert_eq!(tx.stats().dropped, 5);
+    assert_eq!(drain_now(&mut rx), vec![0, 1, 2, 3]);
+
+    tx.flush_pending().await.expect("flush failed");
+    tx.send(10).await.unwrap();
+    assert_eq!(drain_now(&mut rx), vec![9, 10]);
+    assert_eq!(tx.stats().dropped, 5);
+}
+
+/// A dropped `KeepLatest` sender commits its slot if the ring has room, and
+/// counts it as abandoned if not.
+#[tokio::test]
+async fn test_send_policy_keep_latest_slot_on_drop() {
+    let (factory, mut rx) = channel::<u64>(Config::new(2, 2, false));
+    let full = factory
+        .register_with(SendPolicy::KeepLatest)
+        .expect("registration failed");
+    let drained = factory
+        .register_with(SendPolicy::KeepLatest)
+        .expect("registration failed");
+
+    for i in 0..5 {
+        full.send(i).await.unwrap();
+        drained.send(100 + i).await.unwrap();
+    }
+    // Both rings are full and both slots hold an item
+    drop(full);
+    assert_eq!(rx.abandoned(), 1);
+    assert_eq!(drain_now(&mut rx).len(), 8);
+
+    drop(drained);
+    assert_eq!(drain_now(&mut rx), vec![104]);
+    assert_eq!(rx.abandoned(), 1);
+}
+
+#[tokio::test]
+async fn test_send_policy_fail() {
+    let (factory, mut rx) = channel::<u64>(Config::new(2, 1, false));
+    let tx = factory
+        .register_with(SendPolicy::Fail)
+        .expect("registration failed");
+
+    for i in 0..4 {
+        tx.send(i).await.unwrap();
+    }
+    assert_eq!(tx.send(4).await, Err(SendError::Full(4)));
+    assert_eq!(tx.stats(), ringmpsc_stream::SendStats::default());
+    assert_eq!(drain_now(&mut rx), vec![0, 1, 2, 3]);
+    tx.send(4).await.unwrap();
+}
+
+/// `BlockWithTimeout` hands the item back on timeout and counts it; a drain
+/// within the limit lets the send complete.
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn test_send_policy_block_with_timeout() {
+    let limit = Duration::from_millis(100);
+    let (factory, mut rx) = channel::<u64>(Config::new(2, 1, false));
+    let tx = factory
+        .register_with(SendPolicy::BlockWithTimeout(limit))
+        .expect("registration failed");
+    for i in 0..4 {
+        tx.send(i).await.unwrap();
+    }
+
+    let started = tokio::time::Instant::now();
+    assert_eq!(tx.send(4).await, Err(SendError::Timeout(4)));
+    assert!(started.elapsed() >= limit);
+    assert_eq!(tx.stats().timed_out, 1);
+
+    let drainer = tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let mut out = Vec::new();
+        rx.recv_batch(&mut out, 2).await;
+        out
+    });
+    tx.send(4).await.expect("drain within the limit");
+    assert_eq!(drainer.await.unwrap(), vec![0, 1]);
+    assert_eq!(tx.stats().timed_out, 1);
+}
+
+/// The `Sink` impl applies the same policies: `Fail` surfaces from
+/// `poll_ready`, and a `BlockWithTimeout` item stays retrievable.
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn test_send_policy_sink() {
+    let (factory, mut rx) = channel::<u64>(Config::new(2, 2, false));
+
+    let mut failing = factory
+        .register_with(SendPolicy::Fail)
+        .expect("registration failed");
+    for i in 0..4 {
+        failing.feed(i).await.unwrap();
+    }
+    assert_eq!(failing.feed(4).await, Err(StreamError::Full));
+
+    let mut timed = factory
+        .register_with(SendPolicy::BlockWithTimeout(Duration::from_millis(50)))
+        .expect("registration failed");
+    for i in 10..14 {
+        SinkExt::send(&mut timed, i).await.unwrap();
+    }
+    assert_eq!(SinkExt::send(&mut timed, 14).await, Err(StreamError::Timeout));
+    assert_eq!(timed.stats().timed_out, 1);
+    assert_eq!(timed.take_pending(), Some(14));
+
+    let mut drained = drain_now(&mut rx);
+    drained.sort_unstable();
+    assert_eq!(drained, vec![0, 1, 2, 3, 10, 11, 12, 13]);
+}
+
+#[tokio::test]
+async fn test_send_policy_closed_returns_item() {
+    let (factory, _rx) = channel::<u64>(Config::default());
+    let tx = factory
+        .register_with(SendPolicy::DropNewest)
+        .expect("registration failed");
+    factory.close();
+
+    let err = tx.send(5).await.unwrap_err();
+    assert_eq!(err.kind(), StreamError::Closed);
+    assert_eq!(err.into_inner(), 5);
+    assert_eq!(tx.stats().dropped, 0);
+}
+
+/// A `send_ref` that loses a `select!` leaves the item in the caller's slot.
+#[tokio::test]
+async fn test_send_ref_cancel_safe() {
+    let (factory, mut rx) = channel::<u64>(Config::new(2, 1, false));
+    let tx = factory.register().expect("registration failed");
+    for i in 0..4 {
+        tx.send(i).await.unwrap();
+    }
+
+    let mut slot = Some(4);
+    tokio::select! {
+        _ = tx.send_ref(&mut slot) => panic!("ring is full"),
+        () = tokio::time::sleep(Duration::from_millis(20)) => {}
+    }
+    assert_eq!(slot, Some(4));
+
+    assert_eq!(drain_now(&mut rx), vec![0, 1, 2, 3]);
+    tx.send_ref(&mut slot).await.expect("send failed");
+    assert_eq!(slot, None);
+    assert_eq!(drain_now(&mut rx), vec![4]);
+}
+
+#[tokio::test]
+async fn test_send_ref_keeps_item_on_error() {
+    let (factory, _rx) = channel::<u64>(Config::new(2, 1, false));
+    let tx = factory
+        .register_with(SendPolicy::Fail)
+        .expect("registration failed");
+    for i in 0..4 {
+        tx.send(i).await.unwrap();
+    }
+
+    let mut slot = Some(4);
+    assert_eq!(tx.send_ref(&mut slot).await, Err(StreamError::Full));
+    assert_eq!(slot, Some(4));
+    factory.close();
+    assert_eq!(tx.send_ref(&mut slot).await, Err(StreamError::Closed));
+    assert_eq!(slot, Some(4));
+}
+
+/// `reserve` waits without an item; a cancelled reserve loses nothing and a
+/// later permit commits synchronously.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_reserve_permit() {
+    let (factory, mut rx) = channel::<u64>(Config::new(2, 1, false));
+    let mut tx = factory.register().expect("registration failed");
+    for i in 0..4 {
+        tx.reserve().await.unwrap().send(i).unwrap();
+    }
+
+    tokio::select! {
+        _ = tx.reserve() => panic!("ring is full"),
+        () = tokio::time::sleep(Duration::from_millis(20)) => {}
+    }
+
+    let drainer = tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        drain_now(&mut rx)
+    });
+    let permit = tx.reserve().await.expect("drain frees space");
+    permit.send(4).unwrap();
+    assert_eq!(drainer.await.unwrap(), vec![0, 1, 2, 3]);
+
+    factory.close();
+    assert_eq!(tx.reserve().await.unwrap_err(), StreamError::Closed);
+}
+
+/// Cancelling a `Sink` flush keeps the accepted item in the sender.
+#[tokio::test]
+async fn test_sink_pending_survives_cancel() {
+    let (factory, mut rx) = channel::<u64>(Config::new(2, 1, false));
+    let mut tx = factory.register().expect("registration failed");
+    for i in 0..4 {
+        tx.feed(i).await.unwrap();
+    }
+
+    tokio::select! {
+        _ = SinkExt::send(&mut tx, 4) => panic!("ring is full"),
+        () = tokio::time::sleep(Duration::from_millis(20)) => {}
+    }
+
+    assert_eq!(drain_now(&mut rx), vec![0, 1, 2, 3]);
+    SinkExt::flush(&mut tx).await.expect("flush failed");
+    assert_eq!(drain_now(&mut rx), vec![4]);
+    assert_eq!(tx.take_pending(), None);
+}
+
+/// INV-STREAM-08: one substream per sender, each in FIFO order, including a
+/// sender registered after the split; the split ends when the channel closes.
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_into_per_producer() {
+    const PER_SENDER: u64 = 500;
+    let (factory, rx) = channel::<u64>(Config::new(4, 4, false));
+    let early: Vec<_> = (0..2)
+        .map(|_| factory.register().expect("registration failed"))
+        .collect();
+
+    let mut producers = rx.into_per_producer();
+    let consumers = tokio::spawn(async move {
+        let mut tasks = Vec::new();
+        while let Some((id, substream)) = producers.next().await {
+            assert_eq!(substream.producer_id(), id);
+            tasks.push(tokio::spawn(async move {
+                (id, substream.collect::<Vec<u64>>().await)
+            }));
+        }
+        let mut results = Vec::new();
+        for task in tasks {
+            results.push(task.await.unwrap());
+        }
+        results
+    });
+
+    let late = factory.register().expect("registration failed");
+    let mut senders = early;
+    senders.push(late);
+    let sending: Vec<_> = senders
+        .into_iter()
+        .map(|tx| {
+            tokio::spawn(async move {
+                let id = tx.id() as u64;
+                for i in 0..PER_SENDER {
+                    tx.send(id * 1_000 + i).await.expect("send failed");
+                }
+                // dropping the sender ends its substream
+            })
+        })
+        .collect();
+    for task in sending {
+        task.await.unwrap();
+    }
+    factory.close();
+
+    let mut results = tokio::time::timeout(Duration::from_secs(5), consumers)
+        .await
+        .expect("per-producer split did not end")
+        .unwrap();
+    results.sort_by_key(|(id, _)| *id);
+    assert_eq!(results.len(), 3);
+    for (id, items) in results {
+        let expected: Vec<u64> = (0..PER_SENDER).map(|i| id as u64 * 1_000 + i).collect();
+        assert_eq!(items, expected, "producer {id}");
+    }
+}
+
+/// Each substream applies its own backpressure: draining one sender's ring
+/// leaves the other sender's ring full.
+#[tokio::test]
+async fn test_per_producer_independent_backpressure() {
+    let (factory, rx) = channel::<u64>(Config::new(2, 2, false));
+    let fast = factory.register().expect("registration failed");
+    let slow = factory.register().expect("registration failed");
+    let mut producers = rx.into_per_producer();
+    let (_, mut fast_stream) = producers.next().await.unwrap();
+    let (_, _slow_stream) = producers.next().await.unwrap();
+
+    for i in 0..4 {
+        slow.try_send(i).unwrap();
+    }
+    for i in 0..100 {
+        fast.try_send(i).unwrap();
+        assert_eq!(fast_stream.next().await, Some(i));
+    }
+    assert_eq!(slow.try_send(4), Err(4));
+
+    fast.close();
+    assert_eq!(fast_stream.next().await, None);
+}
+
+#[tokio::test]
+#[should_panic(expected = "merged items still buffered")]
+async fn test_into_per_producer_rejects_buffered_items() {
+    let (factory, mut rx) = channel::<u64>(Config::default());
+    let tx = factory.register().expect("registration failed");
+    tx.try_send(1).unwrap();
+    tx.try_send(2).unwrap();
+    assert_eq!(rx.next().await, Some(1));
+    let _ = rx.into_per_producer();
+}
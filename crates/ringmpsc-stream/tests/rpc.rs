@@ -0,0 +1,232 @@
+//! Request/reply tests for the `rpc` module on Tokio.
+
+#![cfg(feature = "tokio")]
+
+use ringmpsc_rs::Config;
+use ringmpsc_stream::{rpc, rpc_with_config, RpcConfig, StreamConfig, StreamError, StreamExt};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_rpc_call_reply() {
+    let (clients, mut server) = rpc::<u64, u64>(Config::default());
+
+    let serve = tokio::spawn(async move {
+        while let Some((req, responder)) = server.next().await {
+            let _ = responder.respond(req * 2);
+        }
+    });
+
+    let client = clients.register().expect("registration failed");
+    for i in 0..100 {
+        assert_eq!(client.call(i).await, Ok(i * 2));
+    }
+    assert_eq!(client.in_flight(), 0);
+
+    drop(client);
+    clients.close();
+    serve.await.expect("server panicked");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_rpc_concurrent_clients() {
+    const CLIENTS: usize = 4;
+    const CALLS: u64 = 200;
+
+    let (clients, mut server) = rpc::<(usize, u64), (usize, u64)>(Config::default());
+    tokio::spawn(async move {
+        while let Some((req, responder)) = server.next().await {
+            let _ = responder.respond(req);
+        }
+    });
+
+    let mut tasks = Vec::new();
+    for c in 0..CLIENTS {
+        let client = Arc::new(clients.register().expect("registration failed"));
+        // Two tasks share each client.
+        for half in 0..2 {
+            let client = Arc::clone(&client);
+            tasks.push(tokio::spawn(async move {
+                for i in 0..CALLS {
+                    let req = (c, half * CALLS + i);
+                    assert_eq!(client.call(req).await, Ok(req));
+                }
+            }));
+        }
+    }
+    for task in tasks {
+        task.await.expect("client task panicked");
+    }
+}
+
+#[tokio::test]
+async fn test_rpc_call_timeout() {
+    let rpc_config = RpcConfig::default().with_call_timeout(Duration::from_millis(30));
+    let (clients, mut server) =
+        rpc_with_config::<u64, u64>(Config::default(), StreamConfig::default(), rpc_config);
+    let client = clients.register().expect("registration failed");
+
+    // The server takes the request but never answers in time.
+    let (result, held) = tokio::join!(client.call(1), server.next());
+    assert_eq!(result, Err(StreamError::Timeout));
+    assert_eq!(client.in_flight(), 0);
+
+    // The timed-out call is visible as canceled on the server side.
+    let (req, responder) = held.expect("request expected");
+    assert_eq!(req, 1);
+    assert!(responder.is_canceled());
+    assert_eq!(responder.respond(2), Err(2));
+
+    // A per-call override still succeeds.
+    let serve = async {
+        let (req, responder) = server.next().await.expect("request expected");
+        responder.respond(req + 1).expect("caller still waiting");
+    };
+    let (result, ()) = tokio::join!(client.call_with_timeout(5, Duration::from_secs(5)), serve);
+    assert_eq!(result, Ok(6));
+}
+
+#[tokio::test]
+async fn test_rpc_cancellation_propagates() {
+    let (clients, mut server) = rpc::<u64, u64>(Config::default());
+    let client = clients.register().expect("registration failed");
+
+    // Dropped after enqueueing, before the server reads it: skipped.
+    let mut call = Box::pin(client.call(1));
+    assert!(futures::poll!(call.as_mut()).is_pending());
+    drop(call);
+
+    // Dropped after the server took it: the responder observes it.
+    let ((), (req, mut responder)) = tokio::join!(
+        async {
+            let mut call = Box::pin(client.call(2));
+            assert!(futures::poll!(call.as_mut()).is_pending());
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        },
+        async { server.next().await.expect("request expected") },
+    );
+    assert_eq!(req, 2);
+    assert_eq!(server.canceled(), 1);
+    tokio::time::timeout(Duration::from_secs(1), responder.cancelled())
+        .await
+        .expect("cancellation not observed");
+}
+
+#[tokio::test]
+async fn test_rpc_max_in_flight() {
+    let rpc_config = RpcConfig::default().with_max_in_flight(2);
+    let (clients, mut server) =
+        rpc_with_config::<u64, u64>(Config::default(), StreamConfig::default(), rpc_config);
+    let client = Arc::new(clients.register().expect("registration failed"));
+
+    let calls: Vec<_> = (0..3)
+        .map(|i| {
+            let client = Arc::clone(&client);
+            tokio::spawn(async move { client.call(i).await })
+        })
+        .collect();
+
+    // Only two requests reach the server while none is answered.
+    let mut held = Vec::new();
+    for _ in 0..2 {
+        held.push(server.next().await.expect("request expected"));
+    }
+    tokio::time::sleep(Duration::from_millis(30)).await;
+    assert_eq!(client.in_flight(), 2);
+    assert!(
+        tokio::time::timeout(Duration::from_millis(30), server.next())
+            .await
+            .is_err(),
+        "third call exceeded max_in_flight"
+    );
+
+    // Answering one admits the third.
+    let (req, responder) = held.remove(0);
+    responder.respond(req).expect("caller waiting");
+    let (req, responder) = server.next().await.expect("third request expected");
+    responder.respond(req).expect("caller waiting");
+    let (req, responder) = held.remove(0);
+    responder.respond(req).expect("caller waiting");
+
+    let mut results = Vec::new();
+    for call in calls {
+        results.push(call.await.expect("call panicked").expect("call failed"));
+    }
+    results.sort_unstable();
+    assert_eq!(results, vec![0, 1, 2]);
+}
+
+/// A weight budget holds back requests the server has not read yet;
+/// reading them releases the budget.
+#[tokio::test]
+async fn test_rpc_weight_budget() {
+    let stream_config = StreamConfig::default()
+        .with_weigher(|req: &Vec<u8>| req.len())
+        .with_max_weight(100);
+    let (clients, mut server) =
+        rpc_with_config::<Vec<u8>, usize>(Config::default(), stream_config, RpcConfig::default());
+    let client = Arc::new(clients.register().expect("registration failed"));
+
+    let calls: Vec<_> = (0..2)
+        .map(|_| {
+            let client = Arc::clone(&client);
+            tokio::spawn(async move { client.call(vec![0; 60]).await })
+        })
+        .collect();
+    tokio::time::sleep(Duration::from_millis(30)).await;
+    // 60 + 60 exceeds the budget: the second request waits at the sender.
+    let stats = server.weight_stats().expect("weighted channel");
+    assert_eq!(stats.total, 60);
+    assert_eq!(stats.max_weight, Some(100));
+
+    for _ in 0..2 {
+        let (req, responder) = server.next().await.expect("request expected");
+        responder.respond(req.len()).expect("caller waiting");
+    }
+    for call in calls {
+        assert_eq!(call.await.expect("call panicked"), Ok(60));
+    }
+    assert_eq!(server.weight_stats().unwrap().total, 0);
+
+    // Without a weigher for the request type, each call weighs one unit.
+    let stream_config = StreamConfig::default().with_max_weight(1);
+    let (clients, mut server) =
+        rpc_with_config::<u64, u64>(Config::default(), stream_config, RpcConfig::default());
+    let client = Arc::new(clients.register().expect("registration failed"));
+    let calls: Vec<_> = (0..2)
+        .map(|i| {
+            let client = Arc::clone(&client);
+            tokio::spawn(async move { client.call(i).await })
+        })
+        .collect();
+    tokio::time::sleep(Duration::from_millis(30)).await;
+    assert_eq!(server.weight_stats().unwrap().total, 1);
+    for _ in 0..2 {
+        let (req, responder) = server.next().await.expect("request expected");
+        responder.respond(req).expect("caller waiting");
+    }
+    let mut results = Vec::new();
+    for call in calls {
+        results.push(call.await.expect("call panicked").expect("call failed"));
+    }
+    results.sort_unstable();
+    assert_eq!(results, vec![0, 1]);
+}
+
+#[tokio::test]
+async fn test_rpc_unanswered_and_closed() {
+    let (clients, mut server) = rpc::<u64, u64>(Config::default());
+    let client = clients.register().expect("registration failed");
+
+    // A dropped responder fails the call.
+    let (result, ()) = tokio::join!(client.call(1), async {
+        drop(server.next().await);
+    });
+    assert_eq!(result, Err(StreamError::Closed));
+
+    // After shutdown neither clients nor calls get in.
+    server.shutdown();
+    assert_eq!(client.call(2).await, Err(StreamError::Closed));
+    assert!(clients.register().is_err());
+    assert!(server.next().await.is_none());
+}
@@ -0,0 +1,117 @@
+//! `StackChannel` adapters on `futures::executor` and plain threads.
+//!
+//! Run with `cargo test -p ringmpsc-stream --features stack-ring`.
+
+#![cfg(feature = "stack-ring")]
+
+use futures::executor::block_on;
+use futures::{SinkExt, StreamExt};
+use ringmpsc_rs::StackChannel;
+use ringmpsc_stream::{stack_channel, SendError, StreamError};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn test_stack_static_channel() {
+    static CHANNEL: StackChannel<u64, 64, 4> = StackChannel::with_wakers();
+
+    let (factory, rx) = stack_channel(&CHANNEL);
+    let producers: Vec<_> = (0..3u64)
+        .map(|p| {
+            let tx = factory.register().expect("registration failed");
+            thread::spawn(move || {
+                block_on(async {
+                    for i in 0..500 {
+                        tx.send(p * 1_000 + i).await.expect("send failed");
+                    }
+                });
+            })
+        })
+        .collect();
+    // 500 items overflow a 64-slot ring: close only once the producers finish.
+    let closer = thread::spawn(move || {
+        for producer in producers {
+            producer.join().unwrap();
+        }
+        factory.close();
+    });
+
+    let items: Vec<u64> = block_on(rx.collect());
+    closer.join().unwrap();
+    assert_eq!(items.len(), 1_500);
+    // INV-STREAM-01: per-producer FIFO
+    for p in 0..3 {
+        let own: Vec<u64> = items.iter().copied().filter(|v| v / 1_000 == p).collect();
+        assert_eq!(own, (0..500).map(|i| p * 1_000 + i).collect::<Vec<_>>());
+    }
+}
+
+/// A sender blocked on a 4-slot ring and an idle receiver wake each other
+/// through the stack ring's waker slots.
+#[test]
+fn test_stack_backpressure_across_threads() {
+    const ITEMS: u64 = 1_000;
+    let channel = Arc::new(StackChannel::<u64, 4, 1>::with_wakers());
+    let (factory, mut rx) = stack_channel(Arc::clone(&channel));
+    let mut tx = factory.register().expect("registration failed");
+
+    let producer = thread::spawn(move || {
+        block_on(async {
+            for i in 0..ITEMS {
+                tx.feed(i).await.expect("feed failed");
+            }
+            SinkExt::close(&mut tx).await.expect("close failed");
+        });
+        factory.close();
+    });
+
+    let mut next = 0;
+    block_on(async {
+        while let Some(item) = rx.next().await {
+            assert_eq!(item, next);
+            next += 1;
+        }
+    });
+    producer.join().unwrap();
+    assert_eq!(next, ITEMS);
+}
+
+#[test]
+fn test_stack_shutdown_wakes_blocked_sender() {
+    let channel = Arc::new(StackChannel::<u64, 4, 1>::with_wakers());
+    let (factory, mut rx) = stack_channel(channel);
+    let tx = factory.register().expect("registration failed");
+    for i in 0..4 {
+        tx.try_send(i).expect("ring should have space");
+    }
+    assert_eq!(tx.try_send(4), Err(4));
+
+    let sender = thread::spawn(move || block_on(tx.send(4)));
+    thread::sleep(Duration::from_millis(20));
+    rx.shutdown();
+
+    assert!(matches!(sender.join().unwrap(), Err(SendError::Closed(4))));
+    let drained: Vec<u64> = block_on(rx.collect());
+    assert_eq!(drained, vec![0, 1, 2, 3]);
+    assert_eq!(factory.register().unwrap_err(), StreamError::Closed);
+}
+
+#[test]
+fn test_stack_registration_limit() {
+    let channel = Arc::new(StackChannel::<u64, 4, 2>::with_wakers());
+    let (factory, _rx) = stack_channel(channel);
+    let _a = factory.register().expect("registration failed");
+    let _b = factory.register().expect("registration failed");
+    assert!(matches!(
+        factory.register(),
+        Err(StreamError::RegistrationFailed(_))
+    ));
+}
+
+#[test]
+#[should_panic(expected = "with_wakers")]
+fn test_stack_channel_requires_wakers() {
+    let channel = Arc::new(StackChannel::<u64, 4, 1>::new());
+    let _ = stack_channel(channel);
+}
@@ -0,0 +1,240 @@
+//! Weight budgets of weighted channels on Tokio.
+
+#![cfg(feature = "tokio")]
+
+use ringmpsc_rs::Config;
+use ringmpsc_stream::{
+    channel_with_weigher, RingReceiver, SendError, SendPolicy, SenderFactory, StreamConfig,
+    StreamError, StreamExt,
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+fn weighted(stream_config: StreamConfig) -> (SenderFactory<Vec<u8>>, RingReceiver<Vec<u8>>) {
+    channel_with_weigher(Config::default(), stream_config, |buf: &Vec<u8>| buf.len())
+}
+
+#[tokio::test]
+async fn test_weight_budget_applies_with_free_slots() {
+    let (factory, mut rx) = weighted(StreamConfig::default().with_max_weight(100));
+    let tx = factory.register().expect("registration failed");
+
+    tx.try_send(vec![0; 60]).expect("budget has room");
+    // Plenty of slots, but 60 + 60 exceeds the budget.
+    assert_eq!(tx.try_send(vec![1; 60]), Err(vec![1; 60]));
+    tx.try_send(vec![2; 40]).expect("exactly fills the budget");
+
+    let stats = factory.weight_stats().expect("weighted channel");
+    assert_eq!(stats.total, 100);
+    assert_eq!(stats.per_sender, vec![100]);
+    assert_eq!(stats.max_weight, Some(100));
+    assert_eq!(tx.stats().weight, 100);
+
+    // Draining releases the weight.
+    assert_eq!(rx.next().await.map(|buf| buf.len()), Some(60));
+    assert_eq!(rx.weight_stats().unwrap().total, 0);
+    tx.try_send(vec![1; 60]).expect("budget released");
+}
+
+#[tokio::test]
+async fn test_weight_oversized_item_admitted_alone() {
+    let (factory, mut rx) = weighted(StreamConfig::default().with_max_weight(10));
+    let tx = factory.register().expect("registration failed");
+
+    tx.try_send(vec![0; 1_000]).expect("an empty budget admits any item");
+    assert_eq!(tx.try_send(vec![0; 1]), Err(vec![0; 1]));
+    assert_eq!(rx.next().await.map(|buf| buf.len()), Some(1_000));
+    tx.try_send(vec![0; 1]).expect("budget released");
+}
+
+/// Weight drained from one sender's ring admits a sender blocked on the
+/// shared budget.
+#[tokio::test]
+async fn test_weight_blocked_sender_woken_by_other_drain() {
+    let (factory, mut rx) = weighted(StreamConfig::default().with_max_weight(100));
+    let heavy = factory.register().expect("registration failed");
+    let blocked = factory.register().expect("registration failed");
+    heavy.try_send(vec![0; 100]).unwrap();
+
+    let send = tokio::spawn(async move { blocked.send(vec![1; 50]).await });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert!(!send.is_finished(), "send ignored the weight budget");
+
+    assert_eq!(rx.next().await, Some(vec![0; 100]));
+    tokio::time::timeout(Duration::from_secs(1), send)
+        .await
+        .expect("blocked sender not woken")
+        .unwrap()
+        .expect("send failed");
+    assert_eq!(rx.next().await, Some(vec![1; 50]));
+}
+
+#[tokio::test]
+async fn test_weight_per_sender_budget() {
+    let stream_config = StreamConfig::default()
+        .with_max_weight(1_000)
+        .with_max_sender_weight(100);
+    let (factory, mut rx) = weighted(stream_config);
+    let a = factory.register().expect("registration failed");
+    let b = factory
+        .register_with(SendPolicy::Fail)
+        .expect("registration failed");
+
+    a.try_send(vec![0; 100]).unwrap();
+    assert!(a.try_send(vec![0; 1]).is_err());
+    // Another sender still has its own budget.
+    b.send(vec![1; 100]).await.expect("b has room");
+    assert!(matches!(b.send(vec![1; 1]).await, Err(SendError::Full(_))));
+    assert_eq!(factory.weight_stats().unwrap().per_sender, vec![100, 100]);
+
+    assert_eq!(rx.next().await, Some(vec![0; 100]));
+    assert_eq!(rx.next().await, Some(vec![1; 100]));
+    assert_eq!(factory.weight_stats().unwrap().total, 0);
+}
+
+#[tokio::test]
+async fn test_weight_drop_newest_counts_over_budget() {
+    let (factory, _rx) = weighted(StreamConfig::default().with_max_weight(10));
+    let tx = factory
+        .register_with(SendPolicy::DropNewest)
+        .expect("registration failed");
+
+    tx.send(vec![0; 10]).await.unwrap();
+    tx.send(vec![0; 5]).await.unwrap();
+    assert_eq!(tx.stats().dropped, 1);
+    assert_eq!(tx.stats().weight, 10);
+}
+
+/// Shutdown wakes a sender parked on the budget, not just on its ring.
+#[tokio::test]
+async fn test_weight_blocked_sender_woken_by_shutdown() {
+    let (factory, mut rx) = weighted(StreamConfig::default().with_max_weight(100));
+    let tx = factory.register().expect("registration failed");
+    tx.try_send(vec![0; 100]).unwrap();
+
+    let send = tokio::spawn(async move { tx.send(vec![1; 50]).await });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    rx.shutdown();
+    let result = tokio::time::timeout(Duration::from_secs(1), send)
+        .await
+        .expect("sender not woken by shutdown")
+        .expect("send panicked");
+    assert_eq!(result, Err(SendError::Closed(vec![1; 50])));
+}
+
+#[tokio::test]
+async fn test_weight_released_by_shutdown_drains() {
+    let (factory, rx) = weighted(StreamConfig::default().with_max_weight(1_000));
+    let tx = factory.register().expect("registration failed");
+    tx.try_send(vec![0; 300]).unwrap();
+    tx.try_send(vec![0; 200]).unwrap();
+    drop(tx);
+
+    let report = rx.shutdown_with_deadline(Duration::from_secs(1)).await;
+    assert_eq!(report.counts(), vec![2]);
+    let stats = factory.weight_stats().unwrap();
+    assert_eq!((stats.total, stats.per_sender), (0, vec![0]));
+
+    // Per-producer substreams release as they drain, too.
+    let (factory, rx) = weighted(StreamConfig::default().with_max_weight(1_000));
+    let tx = factory.register().expect("registration failed");
+    tx.try_send(vec![0; 300]).unwrap();
+    tx.close();
+    let mut producers = rx.into_per_producer();
+    let (_, substream) = producers.next().await.unwrap();
+    assert_eq!(substream.collect::<Vec<_>>().await, vec![vec![0; 300]]);
+    assert_eq!(factory.weight_stats().unwrap().total, 0);
+}
+
+#[tokio::test]
+async fn test_weight_permit_charges_past_budget() {
+    let (factory, mut rx) = weighted(StreamConfig::default().with_max_weight(10));
+    let mut tx = factory.register().expect("registration failed");
+
+    tx.reserve().await.unwrap().send(vec![0; 8]).unwrap();
+    // The budget still has room, so a permit is issued; its item overshoots.
+    tx.reserve().await.unwrap().send(vec![0; 8]).unwrap();
+    assert_eq!(factory.weight_stats().unwrap().total, 16);
+
+    // A full budget holds back the next permit until a drain.
+    let waiting = tokio::time::timeout(Duration::from_millis(20), tx.reserve()).await;
+    assert!(waiting.is_err(), "permit issued past an exhausted budget");
+    assert_eq!(rx.next().await.map(|buf| buf.len()), Some(8));
+    assert_eq!(rx.next().await.map(|buf| buf.len()), Some(8));
+    tx.reserve().await.unwrap().send(vec![0; 1]).unwrap();
+
+    drop(tx);
+    factory.close();
+    assert_eq!(rx.next().await, Some(vec![0; 1]));
+    assert_eq!(rx.next().await, None);
+    assert!(matches!(factory.register(), Err(StreamError::Closed)));
+}
+
+#[tokio::test]
+async fn test_unweighted_channel_has_no_weight_stats() {
+    let (factory, rx) = ringmpsc_stream::channel::<u64>(Config::default());
+    let tx = factory.register().expect("registration failed");
+    tx.try_send(1).unwrap();
+    assert_eq!(factory.weight_stats(), None);
+    assert_eq!(rx.weight_stats(), None);
+    assert_eq!(tx.stats().weight, 0);
+}
+
+/// Each item is weighed once, at commit; the drain releases the stored weight.
+#[tokio::test]
+async fn test_weigher_called_once_per_item() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&calls);
+    let (factory, mut rx) = channel_with_weigher(
+        Config::default(),
+        StreamConfig::default().with_max_weight(1_000),
+        move |buf: &Vec<u8>| {
+            counter.fetch_add(1, Ordering::Relaxed);
+            buf.len()
+        },
+    );
+    let tx = factory.register().expect("registration failed");
+    for len in 1..=3 {
+        tx.send(vec![0; len]).await.unwrap();
+    }
+    assert_eq!(factory.weight_stats().unwrap().total, 6);
+    for _ in 0..3 {
+        rx.next().await.unwrap();
+    }
+    assert_eq!(factory.weight_stats().unwrap().total, 0);
+    assert_eq!(calls.load(Ordering::Relaxed), 3);
+}
+
+/// `StreamConfig::with_weigher` weighs like `channel_with_weigher`.
+#[tokio::test]
+async fn test_stream_config_weigher() {
+    let stream_config = StreamConfig::default()
+        .with_weigher(|buf: &Vec<u8>| buf.len())
+        .with_max_weight(100);
+    let (factory, _rx) = ringmpsc_stream::channel_with_stream_config::<Vec<u8>>(
+        Config::default(),
+        stream_config,
+    );
+    let tx = factory.register().expect("registration failed");
+    tx.try_send(vec![0; 60]).expect("budget has room");
+    assert_eq!(tx.try_send(vec![1; 60]), Err(vec![1; 60]));
+    assert_eq!(factory.weight_stats().unwrap().total, 60);
+}
+
+/// A budget without a weigher for the item type counts items.
+#[tokio::test]
+async fn test_budget_without_weigher_counts_items() {
+    let stream_config = StreamConfig::default()
+        .with_weigher(|buf: &Vec<u8>| buf.len())
+        .with_max_sender_weight(2);
+    let (factory, mut rx) =
+        ringmpsc_stream::channel_with_stream_config::<u64>(Config::default(), stream_config);
+    let tx = factory.register().expect("registration failed");
+    tx.try_send(1).unwrap();
+    tx.try_send(2).unwrap();
+    assert_eq!(tx.try_send(3), Err(3));
+    assert_eq!(tx.stats().weight, 2);
+    assert_eq!(rx.next().await, Some(1));
+    tx.try_send(3).expect("budget released");
+}
@@ -103,6 +103,31 @@ impl<T, A: BufferAllocator + Clone> Channel<T, A> {
 }
 
 impl<T, A: BufferAllocator> Channel<T, A> {
+    /// Returns this channel with waker slots enabled on every ring
+    /// (see [`Ring::with_wakers`]).
+    ///
+    /// Chain it onto the constructor, e.g. `Channel::new(config).with_wakers()`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the channel was already cloned or has registered producers.
+    #[must_use]
+    pub fn with_wakers(mut self) -> Self {
+        let inner = Arc::get_mut(&mut self.inner)
+            .expect("Channel::with_wakers must be called before the channel is shared");
+        for ring in &mut inner.rings {
+            ring.enable_wakers();
+        }
+        self
+    }
+
+    /// Returns true if the channel was built with [`with_wakers`](Self::with_wakers).
+    #[inline]
+    #[must_use]
+    pub fn wakers_enabled(&self) -> bool {
+        self.inner.rings[0].wakers_enabled()
+    }
+
     /// Register a new producer. Returns an error if too many producers or closed.
     pub fn register(&self) -> Result<Producer<T, A>, ChannelError> {
         if self.inner.closed.load(Ordering::Acquire) {
@@ -312,6 +337,19 @@ impl<T, A: BufferAllocator> Channel<T, A> {
         self.inner.closed.load(Ordering::Acquire)
     }
 
+    /// Register the consumer's waker on every ring, including rings whose
+    /// producers have not registered yet.
+    ///
+    /// The next commit on any ring wakes `waker`. The caller must re-check
+    /// the channel (e.g. with [`consume_all_owned`](Self::consume_all_owned))
+    /// after registering (INV-WAKE-01). Has no effect unless the channel was
+    /// built with [`with_wakers`](Self::with_wakers).
+    pub fn register_consumer_waker(&self, waker: &std::task::Waker) {
+        for ring in &self.inner.rings {
+            ring.register_consumer_waker(waker);
+        }
+    }
+
     /// Returns the number of registered producers.
     #[must_use] 
     pub fn producer_count(&self) -> usize {
@@ -425,6 +463,17 @@ impl<T, A: BufferAllocator> Producer<T, A> {
         self.channel.rings[self.id].send(items)
     }
 
+    /// Register a waker to be woken when the consumer frees space in this
+    /// producer's ring.
+    ///
+    /// The caller must retry [`reserve`](Self::reserve) after registering
+    /// (INV-WAKE-01). Has no effect unless the channel was built with
+    /// [`Channel::with_wakers`].
+    #[inline]
+    pub fn register_waker(&self, waker: &std::task::Waker) {
+        self.channel.rings[self.id].register_producer_waker(waker);
+    }
+
     /// Close the producer's ring.
     #[inline]
     pub fn close(&self) {
@@ -0,0 +1,447 @@
+//! Delayed (scheduled) delivery on top of [`Channel`].
+//!
+//! Producers keep the lock-free `reserve`/`push` fast path of the underlying
+//! [`Channel`]; each item simply carries a deadline. The consumer side drains
+//! every producer ring into a min-heap keyed by deadline and releases an item
+//! only once its deadline has passed.
+//!
+//! ```text
+//! Producer 1 ──→ [Ring 1] ──┐
+//! Producer 2 ──→ [Ring 2] ──┼──→ drain ──→ [min-heap by deadline] ──→ consume_due(now)
+//! Producer 3 ──→ [Ring 3] ──┘
+//! ```
+//!
+//! Draining moves items out of the rings as soon as the consumer runs, so a
+//! long delay does not pin ring capacity while the heap has room. The heap is
+//! bounded (see [`DelayChannel::with_max_pending`]): once it is full the
+//! consumer stops draining, the rings fill up and producers see backpressure,
+//! exactly as with a plain [`Channel`] whose consumer falls behind.
+//!
+//! # Ordering
+//!
+//! Items are released in non-decreasing deadline order (INV-DLY-02). Items with
+//! equal deadlines are released in drain order, which preserves per-producer
+//! FIFO for ties.
+//!
+//! # Example
+//!
+//! ```
+//! use ringmpsc_rs::{Config, DelayChannel};
+//! use std::time::{Duration, Instant};
+//!
+//! let mut channel = DelayChannel::<u64>::new(Config::default());
+//! let producer = channel.register().unwrap();
+//!
+//! let now = Instant::now();
+//! producer.push(2, now + Duration::from_secs(60));
+//! producer.push(1, now);
+//!
+//! let mut released = Vec::new();
+//! channel.consume_due(now, |item| released.push(item));
+//! assert_eq!(released, vec![1]);
+//! assert_eq!(channel.next_deadline(), Some(now + Duration::from_secs(60)));
+//! ```
+
+use crate::allocator::{BufferAllocator, HeapAllocator};
+use crate::invariants::{debug_assert_deadline_order, debug_assert_not_early};
+use crate::{Channel, ChannelError, Config, Producer, Reservation};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::mem::MaybeUninit;
+use std::time::Instant;
+
+/// An item paired with the earliest instant at which it may be released.
+///
+/// This is the element type of the underlying rings. Producers using the
+/// zero-copy [`DelayProducer::reserve`] path write `Delayed` values directly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Delayed<T> {
+    /// Earliest instant at which the consumer may release `item`.
+    pub deadline: Instant,
+    /// The payload.
+    pub item: T,
+}
+
+impl<T> Delayed<T> {
+    /// Creates a new delayed item.
+    #[inline]
+    pub const fn new(item: T, deadline: Instant) -> Self {
+        Self { deadline, item }
+    }
+}
+
+/// Heap entry: deadline plus a drain sequence number for stable tie-breaking.
+struct Pending<T> {
+    deadline: Instant,
+    seq: u64,
+    item: T,
+}
+
+impl<T> PartialEq for Pending<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.deadline == other.deadline && self.seq == other.seq
+    }
+}
+
+impl<T> Eq for Pending<T> {}
+
+impl<T> PartialOrd for Pending<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Pending<T> {
+    // Reversed so that `BinaryHeap` (a max-heap) pops the earliest deadline,
+    // and the lowest sequence number among equal deadlines.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .deadline
+            .cmp(&self.deadline)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+/// Multi-Producer Single-Consumer channel with deadline-based delivery.
+///
+/// Wraps a [`Channel<Delayed<T>, A>`](Channel). Producers are registered
+/// through `&self` and may be moved to other threads; the consumer-side
+/// methods take `&mut self` because they own the pending heap.
+pub struct DelayChannel<T, A: BufferAllocator = HeapAllocator> {
+    channel: Channel<Delayed<T>, A>,
+    pending: BinaryHeap<Pending<T>>,
+    max_pending: usize,
+    next_seq: u64,
+}
+
+impl<T> DelayChannel<T, HeapAllocator> {
+    /// Creates a new delay channel with the given configuration.
+    #[must_use]
+    pub fn new(config: Config) -> Self {
+        Self::new_in(config, HeapAllocator)
+    }
+}
+
+impl<T, A: BufferAllocator + Clone> DelayChannel<T, A> {
+    /// Creates a new delay channel with the given configuration and allocator.
+    ///
+    /// The pending heap holds at most as many items as all rings together
+    /// (`capacity() * max_producers`); see [`with_max_pending`](Self::with_max_pending).
+    pub fn new_in(config: Config, alloc: A) -> Self {
+        Self {
+            channel: Channel::new_in(config, alloc),
+            pending: BinaryHeap::new(),
+            max_pending: config.capacity() * config.max_producers,
+            next_seq: 0,
+        }
+    }
+}
+
+impl<T, A: BufferAllocator> DelayChannel<T, A> {
+    /// Returns this channel with the pending heap bounded to `max_pending`
+    /// items (at least 1).
+    ///
+    /// While the heap is full the consumer leaves items in the rings, so ring
+    /// capacity is the backpressure on producers. An item still in a ring is
+    /// not seen until due items make room, even if its deadline is earlier
+    /// than everything pending: size the bound for the number of items that
+    /// may be waiting at once.
+    #[must_use]
+    pub fn with_max_pending(mut self, max_pending: usize) -> Self {
+        self.max_pending = max_pending.max(1);
+        self
+    }
+
+    /// Register a new producer. Returns an error if too many producers or closed.
+    pub fn register(&self) -> Result<DelayProducer<T, A>, ChannelError> {
+        self.channel
+            .register()
+            .map(|inner| DelayProducer { inner })
+    }
+
+    /// Release every pending item whose deadline is at or before `now`.
+    ///
+    /// Newly committed items are first drained from the producer rings into
+    /// the pending heap, up to its bound, then due items are handed to
+    /// `handler` in deadline order. Returns the number of items released; if
+    /// the heap was full, more due items may still be waiting in the rings,
+    /// so call again while this returns non-zero.
+    pub fn consume_due<F>(&mut self, now: Instant, mut handler: F) -> usize
+    where
+        F: FnMut(T),
+    {
+        self.drain_rings();
+
+        let mut released = 0;
+        let mut last: Option<(Instant, u64)> = None;
+        while self.pending.peek().is_some_and(|p| p.deadline <= now) {
+            let Some(entry) = self.pending.pop() else {
+                break;
+            };
+            // INV-DLY-01: No early release
+            debug_assert_not_early!(entry.deadline, now);
+            // INV-DLY-02: Deadline order
+            debug_assert_deadline_order!(last, (entry.deadline, entry.seq));
+            last = Some((entry.deadline, entry.seq));
+            handler(entry.item);
+            released += 1;
+        }
+
+        released
+    }
+
+    /// Returns the earliest deadline among all pending items, if any.
+    ///
+    /// Drains newly committed items from the producer rings first, so the
+    /// answer reflects everything published so far unless the heap is full.
+    /// Useful for computing how
+    /// long the consumer may sleep before the next [`consume_due`](Self::consume_due).
+    pub fn next_deadline(&mut self) -> Option<Instant> {
+        self.drain_rings();
+        self.pending.peek().map(|p| p.deadline)
+    }
+
+    /// Returns the number of drained items waiting for their deadline (at
+    /// most the [`max_pending`](Self::with_max_pending) bound).
+    ///
+    /// Items still sitting in producer rings are not counted.
+    #[must_use]
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Remove every pending item regardless of deadline.
+    ///
+    /// Intended for shutdown: items are handed to `handler` in deadline order
+    /// within each heap's worth, since the rings are drained into the bounded
+    /// heap in rounds. Returns the number of items removed.
+    pub fn drain_all<F>(&mut self, mut handler: F) -> usize
+    where
+        F: FnMut(Delayed<T>),
+    {
+        let mut drained = 0;
+        loop {
+            self.drain_rings();
+            if self.pending.is_empty() {
+                return drained;
+            }
+            while let Some(entry) = self.pending.pop() {
+                handler(Delayed::new(entry.item, entry.deadline));
+                drained += 1;
+            }
+        }
+    }
+
+    /// Close the channel, preventing further registrations.
+    ///
+    /// Already pending items remain available to the consumer.
+    pub fn close(&self) {
+        self.channel.close();
+    }
+
+    /// Returns true if the channel is closed.
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.channel.is_closed()
+    }
+
+    /// Returns the number of registered producers.
+    #[must_use]
+    pub fn producer_count(&self) -> usize {
+        self.channel.producer_count()
+    }
+
+    /// Get aggregated metrics snapshot from all rings if enabled.
+    #[must_use]
+    pub fn metrics(&self) -> crate::MetricsSnapshot {
+        self.channel.metrics()
+    }
+
+    /// Move committed items from the producer rings into the pending heap
+    /// until it is full.
+    fn drain_rings(&mut self) {
+        let room = self.max_pending.saturating_sub(self.pending.len());
+        if room == 0 {
+            return;
+        }
+        let pending = &mut self.pending;
+        let next_seq = &mut self.next_seq;
+        self.channel.consume_all_up_to_owned(room, |delayed| {
+            pending.push(Pending {
+                deadline: delayed.deadline,
+                seq: *next_seq,
+                item: delayed.item,
+            });
+            *next_seq += 1;
+        });
+    }
+}
+
+// Safety: The only `&self` methods (`register`, `close`, `is_closed`,
+// `producer_count`, `metrics`, `pending_len`) touch the underlying `Channel`
+// (itself `Sync` for `T: Send`) or the heap's length — never a `&T`. All access
+// to pending items goes through `&mut self`.
+unsafe impl<T: Send, A: BufferAllocator> Sync for DelayChannel<T, A> {}
+
+/// Producer handle for a [`DelayChannel`].
+///
+/// Identical to [`Producer`] except that every item carries a deadline.
+pub struct DelayProducer<T, A: BufferAllocator = HeapAllocator> {
+    inner: Producer<Delayed<T>, A>,
+}
+
+impl<T, A: BufferAllocator> DelayProducer<T, A> {
+    /// Get the producer's ID.
+    #[inline]
+    #[must_use]
+    pub fn id(&self) -> usize {
+        self.inner.id()
+    }
+
+    /// Reserve n slots for zero-copy writing of [`Delayed`] items.
+    ///
+    /// Same semantics as [`Producer::reserve`], including partial reservations
+    /// on wrap-around (INV-RES-01).
+    #[inline]
+    #[must_use]
+    pub fn reserve(&self, n: usize) -> Option<Reservation<'_, Delayed<T>, A>> {
+        self.inner.reserve(n)
+    }
+
+    /// Send a single item that becomes deliverable at `deadline`.
+    ///
+    /// Returns `true` if the item was enqueued, `false` if the ring is full
+    /// or closed.
+    #[inline]
+    pub fn push(&self, item: T, deadline: Instant) -> bool {
+        self.inner.reserve(1).is_some_and(|mut r| {
+            r.as_mut_slice()[0] = MaybeUninit::new(Delayed::new(item, deadline));
+            r.commit();
+            true
+        })
+    }
+
+    /// Close the producer's ring.
+    #[inline]
+    pub fn close(&self) {
+        self.inner.close();
+    }
+
+    /// Returns true if the producer's ring is closed.
+    #[inline]
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.inner.is_closed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_delay_releases_only_due_items() {
+        let mut ch = DelayChannel::<u64>::new(Config::default());
+        let p = ch.register().unwrap();
+
+        let t0 = Instant::now();
+        assert!(p.push(3, t0 + Duration::from_millis(30)));
+        assert!(p.push(1, t0 + Duration::from_millis(10)));
+        assert!(p.push(2, t0 + Duration::from_millis(20)));
+
+        let mut out = Vec::new();
+        assert_eq!(ch.consume_due(t0, |v| out.push(v)), 0);
+        assert_eq!(ch.pending_len(), 3);
+
+        assert_eq!(ch.consume_due(t0 + Duration::from_millis(20), |v| out.push(v)), 2);
+        assert_eq!(out, vec![1, 2]);
+        assert_eq!(ch.next_deadline(), Some(t0 + Duration::from_millis(30)));
+
+        assert_eq!(ch.consume_due(t0 + Duration::from_millis(30), |v| out.push(v)), 1);
+        assert_eq!(out, vec![1, 2, 3]);
+        assert_eq!(ch.next_deadline(), None);
+    }
+
+    // INV-DLY-02: equal deadlines leave in drain order, so per producer in FIFO order
+    #[test]
+    fn test_delay_equal_deadlines_keep_fifo() {
+        let mut ch = DelayChannel::<(usize, u64)>::new(Config::default());
+        let p1 = ch.register().unwrap();
+        let p2 = ch.register().unwrap();
+
+        let t0 = Instant::now();
+        for i in 0..100 {
+            assert!(p1.push((0, i), t0));
+            assert!(p2.push((1, i), t0));
+        }
+
+        let mut last = [None::<u64>; 2];
+        let released = ch.consume_due(t0, |(id, v)| {
+            assert!(last[id].is_none_or(|prev| prev < v), "FIFO violation");
+            last[id] = Some(v);
+        });
+        assert_eq!(released, 200);
+    }
+
+    #[test]
+    fn test_delay_frees_ring_capacity() {
+        let config = Config::new(4, 1, false); // 16 slots
+        let mut ch = DelayChannel::<u64>::new(config);
+        let p = ch.register().unwrap();
+
+        let far = Instant::now() + Duration::from_hours(1);
+        for i in 0..16 {
+            assert!(p.push(i, far));
+        }
+        assert!(!p.push(16, far), "ring should be full");
+
+        // Draining into the heap frees the ring even though nothing is due.
+        assert_eq!(ch.next_deadline(), Some(far));
+        assert!(p.push(16, far));
+    }
+
+    #[test]
+    fn test_delay_full_heap_keeps_ring_backpressure() {
+        let config = Config::new(4, 1, false); // 16 slots
+        let mut ch = DelayChannel::<u64>::new(config).with_max_pending(8);
+        let p = ch.register().unwrap();
+
+        let t0 = Instant::now();
+        let far = t0 + Duration::from_hours(1);
+        for i in 0..16 {
+            assert!(p.push(i, far));
+        }
+        assert_eq!(ch.next_deadline(), Some(far));
+        assert_eq!(ch.pending_len(), 8);
+        for i in 16..24 {
+            assert!(p.push(i, far));
+        }
+        assert!(!p.push(24, far), "ring should be full while the heap is");
+        assert_eq!(ch.consume_due(t0, |_| {}), 0);
+        assert_eq!(ch.pending_len(), 8);
+
+        // Each call releases at most a heap's worth, making room for more
+        let mut out = Vec::new();
+        while ch.consume_due(far, |v| out.push(v)) > 0 {}
+        assert_eq!(out, (0..24).collect::<Vec<_>>());
+        assert!(p.push(24, far));
+    }
+
+    #[test]
+    fn test_delay_reserve_and_drain_all() {
+        let mut ch = DelayChannel::<u64>::new(Config::default());
+        let p = ch.register().unwrap();
+
+        let t0 = Instant::now();
+        let mut r = p.reserve(2).unwrap();
+        r.as_mut_slice()[0].write(Delayed::new(7, t0 + Duration::from_secs(2)));
+        r.as_mut_slice()[1].write(Delayed::new(8, t0 + Duration::from_secs(1)));
+        r.commit();
+
+        let mut out = Vec::new();
+        assert_eq!(ch.drain_all(|d| out.push(d.item)), 2);
+        assert_eq!(out, vec![8, 7]);
+        assert_eq!(ch.pending_len(), 0);
+    }
+}
@@ -0,0 +1,634 @@
+//! Request/response channel with a private reply ring per client.
+//!
+//! Many client threads send requests to one server thread and wait for the
+//! reply. Each registered client owns two SPSC rings:
+//!
+//! ```text
+//! Client 1 ──(seq, Req)──→ [Request Ring 1] ──┐
+//!          ←─(seq, Resp)── [Reply Ring 1]  ←──┤
+//! Client 2 ──(seq, Req)──→ [Request Ring 2] ──┼──→ Server (single thread)
+//!          ←─(seq, Resp)── [Reply Ring 2]  ←──┘
+//! ```
+//!
+//! The server consumes requests together with a [`ReplyHandle`] that writes
+//! straight into the originating client's reply ring, so no per-message
+//! oneshot allocation is needed. Replies are tagged with the request's
+//! sequence number; a client discards stale replies left behind by an
+//! abandoned (dropped) async call.
+//!
+//! # Waiting
+//!
+//! [`DuplexClient::call`] blocks the calling thread (spin, yield, then park)
+//! and [`DuplexClient::call_async`] returns a `Future` that registers the
+//! task's waker. Both wait in the rings' own waker slots (INV-WAKE-01): the
+//! producer slot of the request ring while it is full, and the consumer slot
+//! of the reply ring while no reply has arrived. Consuming a request or
+//! committing a reply wakes the client without any lock on the server's path.
+//!
+//! # Example
+//!
+//! ```
+//! use ringmpsc_rs::{Config, DuplexChannel};
+//! use std::thread;
+//!
+//! let channel = DuplexChannel::<u64, u64>::new(Config::default());
+//! let mut client = channel.register().unwrap();
+//!
+//! let server = {
+//!     let channel = channel.clone();
+//!     thread::spawn(move || {
+//!         let mut served = 0;
+//!         while served < 1 {
+//!             served += channel.consume_requests(|req, reply| {
+//!                 reply.reply(req * 2).unwrap();
+//!             });
+//!         }
+//!     })
+//! };
+//!
+//! assert_eq!(client.call(21), Ok(42));
+//! server.join().unwrap();
+//! ```
+
+use crate::invariants::{debug_assert_loss_recorded, debug_assert_reply_correlated};
+use crate::{Backoff, Channel, ChannelError, Config, Producer, Ring};
+use std::future::Future;
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+use thiserror::Error;
+
+/// Error returned by [`DuplexClient::call`] and [`DuplexClient::call_async`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum DuplexError {
+    /// The channel was closed before the call completed.
+    #[error("duplex channel is closed")]
+    Closed,
+    /// The server dropped the reply handle without replying.
+    #[error("server dropped the request without replying")]
+    NoReply,
+    /// The server's reply did not fit in the client's reply ring.
+    #[error("reply ring was full; the reply was lost")]
+    ReplyLost,
+}
+
+/// Error returned by [`ReplyHandle::reply`] when the client's reply ring is
+/// full. Holds the response that could not be delivered; the client's call
+/// fails with [`DuplexError::ReplyLost`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("client's reply ring is full")]
+pub struct ReplyFull<Resp>(pub Resp);
+
+/// Per-client reply ring plus the record of replies that did not fit.
+struct ReplySlot<Resp> {
+    ring: Ring<(u64, Option<Resp>)>,
+    /// One past the highest sequence number whose reply was dropped because
+    /// the ring was full (0 if none).
+    lost: AtomicU64,
+}
+
+struct DuplexInner<Req, Resp> {
+    requests: Channel<(u64, Req)>,
+    /// One reply slot per client, indexed by producer id. Shared separately
+    /// so that `ReplyHandle` does not need to know the request type.
+    replies: Arc<[ReplySlot<Resp>]>,
+}
+
+/// Many-client, single-server request/response channel.
+///
+/// Handles are cheap to clone. [`consume_requests`](Self::consume_requests)
+/// must only be called from the single server thread (INV-CH-02); the reply
+/// handles it produces are `!Send` for the same reason (INV-DPX-01).
+pub struct DuplexChannel<Req, Resp> {
+    inner: Arc<DuplexInner<Req, Resp>>,
+}
+
+impl<Req, Resp> DuplexChannel<Req, Resp> {
+    /// Creates a new duplex channel.
+    ///
+    /// Request and reply rings both use `config`'s capacity; `max_producers`
+    /// bounds the number of clients. Waker slots are always enabled, since
+    /// clients wait in them.
+    #[must_use]
+    pub fn new(config: Config) -> Self {
+        let replies = (0..config.max_producers)
+            .map(|_| ReplySlot {
+                ring: Ring::new(config).with_wakers(),
+                lost: AtomicU64::new(0),
+            })
+            .collect();
+
+        Self {
+            inner: Arc::new(DuplexInner {
+                requests: Channel::new(config).with_wakers(),
+                replies,
+            }),
+        }
+    }
+
+    /// Register a new client. Returns an error if too many clients or closed.
+    pub fn register(&self) -> Result<DuplexClient<Req, Resp>, ChannelError> {
+        let requests = self.inner.requests.register()?;
+        self.inner.replies[requests.id()].ring.set_active(true);
+
+        Ok(DuplexClient {
+            requests,
+            replies: Arc::clone(&self.inner.replies),
+            next_seq: 0,
+        })
+    }
+
+    /// Consume all pending requests from all clients.
+    ///
+    /// The handler receives each request with a [`ReplyHandle`] bound to the
+    /// originating client. The handle may be answered immediately or kept and
+    /// answered later on this thread. Returns the number of requests consumed.
+    pub fn consume_requests<F>(&self, mut handler: F) -> usize
+    where
+        F: FnMut(Req, ReplyHandle<Resp>),
+    {
+        let inner = &self.inner;
+        let mut total = 0;
+
+        for client_id in 0..inner.requests.producer_count() {
+            let Some(ring) = inner.requests.get_ring(client_id) else {
+                continue;
+            };
+            // Consuming wakes a client parked on a full request ring.
+            total += ring.consume_batch_owned(|(seq, req)| {
+                handler(
+                    req,
+                    ReplyHandle {
+                        replies: Arc::clone(&inner.replies),
+                        client_id,
+                        seq,
+                        replied: false,
+                        _not_send: PhantomData,
+                    },
+                );
+            });
+        }
+
+        total
+    }
+
+    /// Close the channel and wake every waiting client.
+    ///
+    /// Clients observe [`DuplexError::Closed`] once any reply already queued
+    /// for them has been read.
+    pub fn close(&self) {
+        self.inner.requests.close();
+        let count = self.inner.requests.producer_count();
+        for slot in &self.inner.replies[..count] {
+            slot.ring.close();
+        }
+    }
+
+    /// Returns true if the channel is closed.
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.inner.requests.is_closed()
+    }
+
+    /// Returns the number of registered clients.
+    #[must_use]
+    pub fn client_count(&self) -> usize {
+        self.inner.requests.producer_count()
+    }
+}
+
+impl<Req, Resp> Clone for DuplexChannel<Req, Resp> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+/// Server-side handle for answering one request.
+///
+/// Dropping the handle without calling [`reply`](Self::reply) completes the
+/// client's call with [`DuplexError::NoReply`]. The handle is `!Send`: the
+/// server thread is the single writer of every reply ring (INV-DPX-01).
+pub struct ReplyHandle<Resp> {
+    replies: Arc<[ReplySlot<Resp>]>,
+    client_id: usize,
+    seq: u64,
+    replied: bool,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl<Resp> ReplyHandle<Resp> {
+    /// Returns the id of the client that sent the request.
+    #[inline]
+    #[must_use]
+    pub fn client_id(&self) -> usize {
+        self.client_id
+    }
+
+    /// Write `resp` into the client's reply ring, waking the client.
+    ///
+    /// Fails with [`ReplyFull`], handing `resp` back, if the reply ring is
+    /// full; the client's call then fails with [`DuplexError::ReplyLost`]
+    /// rather than waiting forever. A reply ring only fills up if the client
+    /// abandoned more than `capacity` calls without issuing a new one.
+    pub fn reply(mut self, resp: Resp) -> Result<(), ReplyFull<Resp>> {
+        self.replied = true;
+        self.send(Some(resp)).map_err(|resp| ReplyFull(resp.unwrap()))
+    }
+
+    fn send(&self, resp: Option<Resp>) -> Result<(), Option<Resp>> {
+        let slot = &self.replies[self.client_id];
+        match slot.ring.reserve(1) {
+            Some(mut r) => {
+                r.as_mut_slice()[0] = MaybeUninit::new((self.seq, resp));
+                r.commit();
+                Ok(())
+            }
+            None => {
+                // Record the loss before waking, so the client's re-check
+                // after registering sees it (INV-WAKE-01).
+                slot.lost.fetch_max(self.seq + 1, Ordering::Release);
+                debug_assert_loss_recorded!(slot.lost.load(Ordering::Relaxed), self.seq);
+                slot.ring.wake_consumer();
+                Err(resp)
+            }
+        }
+    }
+}
+
+impl<Resp> Drop for ReplyHandle<Resp> {
+    fn drop(&mut self) {
+        if !self.replied {
+            let _ = self.send(None);
+        }
+    }
+}
+
+/// Client handle for a [`DuplexChannel`].
+///
+/// Owns the write side of its request ring and the read side of its reply
+/// ring. Calls take `&mut self`, so a client has at most one call in flight.
+pub struct DuplexClient<Req, Resp> {
+    requests: Producer<(u64, Req)>,
+    replies: Arc<[ReplySlot<Resp>]>,
+    next_seq: u64,
+}
+
+impl<Req, Resp> DuplexClient<Req, Resp> {
+    /// Get the client's ID (equal to its request ring's producer ID).
+    #[inline]
+    #[must_use]
+    pub fn id(&self) -> usize {
+        self.requests.id()
+    }
+
+    /// Send `req` and block the current thread until the server replies.
+    ///
+    /// Waits with adaptive backoff (spin → yield) before parking; the rings'
+    /// waker slots unpark the thread when the server frees request-ring space
+    /// or writes the reply.
+    pub fn call(&mut self, req: Req) -> Result<Resp, DuplexError> {
+        let seq = self.next_seq();
+        let mut pending = Some(req);
+
+        let requests = &self.requests;
+        block_until(|w| requests.register_waker(w), || {
+            let req = pending.take()?;
+            match self.try_send(seq, req) {
+                Ok(()) => Some(Ok(())),
+                Err(SendFailure::Closed) => Some(Err(DuplexError::Closed)),
+                Err(SendFailure::Full(req)) => {
+                    pending = Some(req);
+                    None
+                }
+            }
+        })?;
+
+        block_until(|w| self.slot().ring.register_consumer_waker(w), || self.poll_reply(seq))
+    }
+
+    /// Send `req` and return a future resolving to the server's reply.
+    ///
+    /// Dropping the future before it completes abandons the call; a reply
+    /// that arrives later is discarded by the next call.
+    pub fn call_async(&mut self, req: Req) -> Call<'_, Req, Resp> {
+        let seq = self.next_seq();
+        Call {
+            client: self,
+            seq,
+            request: Some(req),
+        }
+    }
+
+    /// Returns true if the channel has been closed.
+    #[inline]
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.requests.is_closed()
+    }
+
+    fn next_seq(&mut self) -> u64 {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        seq
+    }
+
+    fn slot(&self) -> &ReplySlot<Resp> {
+        &self.replies[self.requests.id()]
+    }
+
+    fn try_send(&self, seq: u64, req: Req) -> Result<(), SendFailure<Req>> {
+        if self.is_closed() {
+            return Err(SendFailure::Closed);
+        }
+        match self.requests.reserve(1) {
+            Some(mut r) => {
+                r.as_mut_slice()[0] = MaybeUninit::new((seq, req));
+                r.commit();
+                Ok(())
+            }
+            None => Err(SendFailure::Full(req)),
+        }
+    }
+
+    /// Take the reply for `seq`, discarding stale replies from abandoned calls.
+    fn poll_reply(&self, seq: u64) -> Option<Result<Resp, DuplexError>> {
+        // Read `closed` and `lost` first: a reply written before either is
+        // still returned.
+        let closed = self.is_closed();
+        let lost = self.slot().lost.load(Ordering::Acquire) > seq;
+        loop {
+            let mut next = None;
+            self.slot().ring.consume_up_to_owned(1, |r| next = Some(r));
+            match next {
+                Some((s, _)) if s < seq => {}
+                Some((s, reply)) => {
+                    debug_assert_reply_correlated!(s, seq);
+                    return Some(reply.ok_or(DuplexError::NoReply));
+                }
+                None if lost => return Some(Err(DuplexError::ReplyLost)),
+                None if closed => return Some(Err(DuplexError::Closed)),
+                None => return None,
+            }
+        }
+    }
+}
+
+/// Polls until `poll` returns a value, parking the thread in the waker
+/// slot that `register` registers with.
+fn block_until<R>(
+    register: impl Fn(&Waker),
+    mut poll: impl FnMut() -> Option<R>,
+) -> R {
+    let mut backoff = Backoff::new();
+    let mut waker = None;
+    loop {
+        if let Some(r) = poll() {
+            return r;
+        }
+        if backoff.is_completed() {
+            let waker = waker
+                .get_or_insert_with(|| Waker::from(Arc::new(ThreadWaker(thread::current()))));
+            register(waker);
+            // Re-check after registering so a wake between the first poll
+            // and registration is not lost (INV-WAKE-01).
+            if let Some(r) = poll() {
+                return r;
+            }
+            thread::park();
+        } else {
+            backoff.snooze();
+        }
+    }
+}
+
+/// Unparks a thread blocked in [`DuplexClient::call`].
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+enum SendFailure<Req> {
+    Closed,
+    Full(Req),
+}
+
+/// Future returned by [`DuplexClient::call_async`].
+pub struct Call<'a, Req, Resp> {
+    client: &'a DuplexClient<Req, Resp>,
+    seq: u64,
+    request: Option<Req>,
+}
+
+// `Call` never hands out a pinned reference to `request`.
+impl<Req, Resp> Unpin for Call<'_, Req, Resp> {}
+
+impl<Req, Resp> Future for Call<'_, Req, Resp> {
+    type Output = Result<Resp, DuplexError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let client = this.client;
+
+        if let Some(req) = this.request.take() {
+            let req = match client.try_send(this.seq, req) {
+                Ok(()) => None,
+                Err(SendFailure::Closed) => return Poll::Ready(Err(DuplexError::Closed)),
+                Err(SendFailure::Full(req)) => Some(req),
+            };
+            if let Some(req) = req {
+                client.requests.register_waker(cx.waker());
+                match client.try_send(this.seq, req) {
+                    Ok(()) => {}
+                    Err(SendFailure::Closed) => return Poll::Ready(Err(DuplexError::Closed)),
+                    Err(SendFailure::Full(req)) => {
+                        this.request = Some(req);
+                        return Poll::Pending;
+                    }
+                }
+            }
+        }
+
+        if let Some(r) = client.poll_reply(this.seq) {
+            return Poll::Ready(r);
+        }
+        client.slot().ring.register_consumer_waker(cx.waker());
+        client.poll_reply(this.seq).map_or(Poll::Pending, Poll::Ready)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+
+    fn block_on<F: Future>(fut: F) -> F::Output {
+        let mut fut = std::pin::pin!(fut);
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(out) = fut.as_mut().poll(&mut cx) {
+                return out;
+            }
+            thread::park();
+        }
+    }
+
+    fn spawn_echo_server(
+        channel: DuplexChannel<u64, u64>,
+        stop: Arc<AtomicBool>,
+    ) -> thread::JoinHandle<()> {
+        thread::spawn(move || {
+            while !stop.load(Ordering::Acquire) {
+                let n = channel.consume_requests(|req, reply| {
+                    reply.reply(req + 1).unwrap();
+                });
+                if n == 0 {
+                    thread::yield_now();
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn test_duplex_sync_calls_from_many_clients() {
+        let channel = DuplexChannel::<u64, u64>::new(Config::new(4, 4, false));
+        let stop = Arc::new(AtomicBool::new(false));
+        let server = spawn_echo_server(channel.clone(), Arc::clone(&stop));
+
+        let clients: Vec<_> = (0..4)
+            .map(|_| {
+                let mut client = channel.register().unwrap();
+                thread::spawn(move || {
+                    for i in 0..1_000 {
+                        assert_eq!(client.call(i), Ok(i + 1));
+                    }
+                })
+            })
+            .collect();
+        for c in clients {
+            c.join().unwrap();
+        }
+
+        stop.store(true, Ordering::Release);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_duplex_async_call() {
+        let channel = DuplexChannel::<u64, u64>::new(Config::default());
+        let stop = Arc::new(AtomicBool::new(false));
+        let server = spawn_echo_server(channel.clone(), Arc::clone(&stop));
+
+        let mut client = channel.register().unwrap();
+        for i in 0..100 {
+            assert_eq!(block_on(client.call_async(i)), Ok(i + 1));
+        }
+
+        stop.store(true, Ordering::Release);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_duplex_dropped_handle_reports_no_reply() {
+        let channel = DuplexChannel::<u64, u64>::new(Config::default());
+        let mut client = channel.register().unwrap();
+
+        let mut fut = client.call_async(1);
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        assert!(Pin::new(&mut fut).poll(&mut cx).is_pending());
+
+        assert_eq!(channel.consume_requests(|_, reply| drop(reply)), 1);
+        assert_eq!(
+            Pin::new(&mut fut).poll(&mut cx),
+            Poll::Ready(Err(DuplexError::NoReply))
+        );
+    }
+
+    // INV-DPX-02: replies to abandoned calls never answer a later one
+    #[test]
+    fn test_duplex_stale_reply_is_discarded() {
+        let channel = DuplexChannel::<u64, u64>::new(Config::default());
+        let mut client = channel.register().unwrap();
+
+        // Abandon a call after its request was sent.
+        {
+            let mut fut = client.call_async(10);
+            let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+            let mut cx = Context::from_waker(&waker);
+            assert!(Pin::new(&mut fut).poll(&mut cx).is_pending());
+        }
+
+        let mut held = Vec::new();
+        channel.consume_requests(|req, reply| held.push((req, reply)));
+        for (req, reply) in held.drain(..) {
+            reply.reply(req * 100).unwrap();
+        }
+
+        let server = {
+            let channel = channel.clone();
+            thread::spawn(move || {
+                while channel.consume_requests(|req, reply| {
+                    reply.reply(req * 100).unwrap();
+                }) == 0
+                {
+                    thread::yield_now();
+                }
+            })
+        };
+        assert_eq!(client.call(2), Ok(200));
+        server.join().unwrap();
+    }
+
+    // INV-DPX-03: a reply that does not fit wakes the client with ReplyLost
+    #[test]
+    fn test_duplex_full_reply_ring_fails_the_call() {
+        let channel = DuplexChannel::<u64, u64>::new(Config::new(1, 1, false)); // 2 slots
+        let mut client = channel.register().unwrap();
+
+        // Abandon two calls, then start a third; the stale replies to the
+        // first two fill the reply ring.
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut held = Vec::new();
+        for i in 0..2 {
+            assert!(Pin::new(&mut client.call_async(i)).poll(&mut cx).is_pending());
+        }
+        channel.consume_requests(|req, reply| held.push((req, reply)));
+        let mut fut = client.call_async(2);
+        assert!(Pin::new(&mut fut).poll(&mut cx).is_pending());
+        channel.consume_requests(|req, reply| held.push((req, reply)));
+
+        let replies: Vec<_> = held.into_iter().map(|(req, reply)| reply.reply(req)).collect();
+        assert_eq!(replies, vec![Ok(()), Ok(()), Err(ReplyFull(2))]);
+        assert_eq!(
+            Pin::new(&mut fut).poll(&mut cx),
+            Poll::Ready(Err(DuplexError::ReplyLost))
+        );
+    }
+
+    #[test]
+    fn test_duplex_close_wakes_blocked_client() {
+        let channel = DuplexChannel::<u64, u64>::new(Config::default());
+        let mut client = channel.register().unwrap();
+
+        let handle = thread::spawn(move || client.call(1));
+        thread::sleep(std::time::Duration::from_millis(20));
+        channel.close();
+
+        assert_eq!(handle.join().unwrap(), Err(DuplexError::Closed));
+    }
+}
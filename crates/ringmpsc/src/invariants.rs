@@ -30,8 +30,8 @@ macro_rules! debug_assert_bounded_count {
 ///
 /// **Invariant**: `head ≤ tail` (after advance)
 ///
-/// This check enforces INV-ORD-03 (HappensBefore: `head ≤ tail`) which is also
-/// implied by INV-SEQ-01 (BoundedCount: `0 ≤ (tail - head) ≤ capacity`).
+/// This check enforces INV-ORD-03 (`HappensBefore`: `head ≤ tail`) which is also
+/// implied by INV-SEQ-01 (`BoundedCount`: `0 ≤ (tail - head) ≤ capacity`).
 ///
 /// Used in: `advance()` before updating head
 macro_rules! debug_assert_head_not_past_tail {
@@ -156,6 +156,93 @@ macro_rules! debug_assert_fifo_count {
     };
 }
 
+// =============================================================================
+// INV-DLY-01: No Early Release
+// =============================================================================
+
+/// Assert that a delayed item is not released before its deadline.
+///
+/// **Invariant**: every item handed out by `DelayChannel::consume_due(now, _)`
+/// satisfies `deadline ≤ now`.
+///
+/// Used in: `DelayChannel::consume_due()` before invoking the handler
+macro_rules! debug_assert_not_early {
+    ($deadline:expr, $now:expr) => {
+        debug_assert!(
+            $deadline <= $now,
+            "INV-DLY-01 violated: item with deadline {:?} released at {:?}",
+            $deadline,
+            $now
+        )
+    };
+}
+
+// =============================================================================
+// INV-DLY-02: Deadline Order
+// =============================================================================
+
+/// Assert that delayed items are released in deadline order, ties in drain
+/// order.
+///
+/// **Invariant**: within one `DelayChannel::consume_due` call, each released
+/// item's `(deadline, seq)` is greater than the previous item's.
+///
+/// Used in: `DelayChannel::consume_due()` before invoking the handler
+macro_rules! debug_assert_deadline_order {
+    ($prev:expr, $next:expr) => {
+        debug_assert!(
+            $prev.is_none_or(|prev| prev < $next),
+            "INV-DLY-02 violated: released (deadline, seq) {:?} after {:?}",
+            $next,
+            $prev
+        )
+    };
+}
+
+// =============================================================================
+// INV-DPX-02: Reply Correlation
+// =============================================================================
+
+/// Assert that a reply returned to a duplex call carries the call's sequence
+/// number.
+///
+/// **Invariant**: a call waiting for sequence `s` discards stale replies
+/// (`< s`) and returns only the reply tagged `s`.
+///
+/// Used in: `DuplexClient::poll_reply()` before returning a reply
+macro_rules! debug_assert_reply_correlated {
+    ($reply_seq:expr, $call_seq:expr) => {
+        debug_assert!(
+            $reply_seq == $call_seq,
+            "INV-DPX-02 violated: call {} returned the reply to call {}",
+            $call_seq,
+            $reply_seq
+        )
+    };
+}
+
+// =============================================================================
+// INV-DPX-03: No Lost Wakeup
+// =============================================================================
+
+/// Assert that a reply that did not fit was recorded as lost before the
+/// client is woken.
+///
+/// **Invariant**: `lost > seq` is visible to the client's re-check, so the
+/// call fails with `ReplyLost` instead of waiting forever.
+///
+/// Used in: `ReplyHandle::send()` before waking the reply ring's consumer
+macro_rules! debug_assert_loss_recorded {
+    ($lost:expr, $seq:expr) => {
+        debug_assert!(
+            $lost > $seq,
+            "INV-DPX-03 violated: reply {} dropped but lost mark is {}",
+            $seq,
+            $lost
+        )
+    };
+}
+
 // =============================================================================
 // INV-ALLOC-01: Alignment Guarantee
 // =============================================================================
@@ -209,6 +296,10 @@ pub(crate) use debug_assert_head_not_past_tail;
 pub(crate) use debug_assert_initialized_read;
 pub(crate) use debug_assert_monotonic;
 pub(crate) use debug_assert_no_wrap;
+pub(crate) use debug_assert_not_early;
+pub(crate) use debug_assert_deadline_order;
+pub(crate) use debug_assert_loss_recorded;
+pub(crate) use debug_assert_reply_correlated;
 pub(crate) use debug_assert_valid_ring_ptr;
 #[allow(unused_imports)]
 pub(crate) use debug_assert_aligned;
@@ -13,6 +13,10 @@
 //! - Batch consumption API (single head update for N items)
 //! - Adaptive backoff (spin → yield → park)
 //! - Zero-copy reserve/commit API
+//! - Deadline-ordered delivery via [`DelayChannel`]
+//! - Allocation-free buffer reuse via [`RecyclingChannel`]
+//! - Request/response with per-client reply rings via [`DuplexChannel`]
+//! - Optional consumer/producer waker slots for async adapters ([`Channel::with_wakers`])
 //!
 //! Achieves 50+ billion messages/second on AMD Ryzen 7 5700.
 //!
@@ -44,10 +48,14 @@ pub mod allocator;
 mod backoff;
 mod channel;
 mod config;
+mod delay_channel;
+mod duplex_channel;
 mod invariants;
 mod metrics;
+mod recycling_channel;
 mod reservation;
 mod ring;
+mod waker;
 
 #[cfg(feature = "stack-ring")]
 mod stack_ring;
@@ -66,7 +74,10 @@ pub use allocator::StdAllocator;
 pub use backoff::Backoff;
 pub use channel::{Channel, ChannelError, Producer};
 pub use config::{Config, HIGH_THROUGHPUT_CONFIG, LOW_LATENCY_CONFIG};
+pub use delay_channel::{DelayChannel, DelayProducer, Delayed};
+pub use duplex_channel::{Call, DuplexChannel, DuplexClient, DuplexError, ReplyFull, ReplyHandle};
 pub use metrics::{Metrics, MetricsSnapshot};
+pub use recycling_channel::{RecyclingChannel, RecyclingProducer};
 pub use reservation::{CommitError, Reservation};
 pub use ring::Ring;
 
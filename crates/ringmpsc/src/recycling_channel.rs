@@ -0,0 +1,357 @@
+//! Buffer recycling via a per-producer return-path ring.
+//!
+//! When `T` owns a heap allocation (`Vec<u8>`, `Box<[u8; N]>`), a plain
+//! [`Channel`] allocates on every producer and frees on the consumer, which
+//! generates cross-thread allocator traffic that dwarfs the ring cost.
+//! [`RecyclingChannel`] pairs each producer's forward ring with a reverse SPSC
+//! ring that carries emptied buffers back to the same producer:
+//!
+//! ```text
+//!              forward (Producer → Consumer)
+//! Producer 1 ──→ [Ring 1] ──┐
+//!           ←── [Return 1] ←┤
+//! Producer 2 ──→ [Ring 2] ──┼──→ Consumer
+//!           ←── [Return 2] ←┘
+//!              return  (Consumer → Producer)
+//! ```
+//!
+//! Both directions are ordinary [`Ring`]s built with the channel's
+//! [`BufferAllocator`], so once the pool is warm the steady state performs no
+//! allocation at all.
+//!
+//! # Roles
+//!
+//! Each return ring is an SPSC ring with the roles reversed (INV-RCY-01): the
+//! channel's single consumer is its only writer (via [`RecyclingChannel::release`])
+//! and the owning producer is its only reader (via
+//! [`RecyclingProducer::acquire_buffer`]).
+//!
+//! # Example
+//!
+//! ```
+//! use ringmpsc_rs::{Config, RecyclingChannel};
+//!
+//! let channel = RecyclingChannel::<Vec<u8>>::new(Config::default());
+//! let producer = channel.register().unwrap();
+//!
+//! let mut buf = producer.acquire_buffer_or_else(|| Vec::with_capacity(1024));
+//! buf.extend_from_slice(b"hello");
+//! producer.push(buf);
+//!
+//! channel.consume_all_owned(|producer_id, mut buf| {
+//!     assert_eq!(buf, b"hello");
+//!     buf.clear();
+//!     channel.release(producer_id, buf);
+//! });
+//!
+//! // The same allocation comes back to the producer.
+//! let buf = producer.acquire_buffer().unwrap();
+//! assert!(buf.capacity() >= 1024);
+//! ```
+
+use crate::allocator::{BufferAllocator, HeapAllocator};
+use crate::{Channel, ChannelError, Config, Producer, Reservation, Ring};
+use std::sync::Arc;
+
+/// Multi-Producer Single-Consumer channel with a per-producer buffer return path.
+///
+/// Like [`Channel`], handles are cheap to clone and the consumer-side methods
+/// ([`consume_all_owned`](Self::consume_all_owned), [`release`](Self::release))
+/// must only be called from the single consumer thread (INV-CH-02).
+pub struct RecyclingChannel<T, A: BufferAllocator = HeapAllocator> {
+    inner: Arc<RecyclingInner<T, A>>,
+}
+
+struct RecyclingInner<T, A: BufferAllocator> {
+    forward: Channel<T, A>,
+    /// One return ring per producer slot, indexed by producer id.
+    returns: Vec<Ring<T, A>>,
+}
+
+impl<T> RecyclingChannel<T, HeapAllocator> {
+    /// Creates a new recycling channel with the given configuration.
+    ///
+    /// Return rings use the same capacity as forward rings.
+    #[must_use]
+    pub fn new(config: Config) -> Self {
+        Self::new_in(config, HeapAllocator)
+    }
+}
+
+impl<T, A: BufferAllocator + Clone> RecyclingChannel<T, A> {
+    /// Creates a new recycling channel with the given configuration and allocator.
+    ///
+    /// The allocator is cloned for every forward and every return ring.
+    pub fn new_in(config: Config, alloc: A) -> Self {
+        let returns = (0..config.max_producers)
+            .map(|_| Ring::new_in(config, alloc.clone()))
+            .collect();
+
+        Self {
+            inner: Arc::new(RecyclingInner {
+                forward: Channel::new_in(config, alloc),
+                returns,
+            }),
+        }
+    }
+}
+
+impl<T, A: BufferAllocator> RecyclingChannel<T, A> {
+    /// Register a new producer. Returns an error if too many producers or closed.
+    pub fn register(&self) -> Result<RecyclingProducer<T, A>, ChannelError> {
+        let producer = self.inner.forward.register()?;
+        self.inner.returns[producer.id()].set_active(true);
+
+        Ok(RecyclingProducer {
+            producer,
+            inner: Arc::clone(&self.inner),
+        })
+    }
+
+    /// Batch consume from all producers, transferring ownership.
+    ///
+    /// The handler receives the producer id alongside each item so the buffer
+    /// can later be handed back with [`release`](Self::release).
+    pub fn consume_all_owned<F>(&self, mut handler: F) -> usize
+    where
+        F: FnMut(usize, T),
+    {
+        let mut total = 0;
+        for producer_id in 0..self.inner.forward.producer_count() {
+            if let Some(ring) = self.inner.forward.get_ring(producer_id) {
+                total += ring.consume_batch_owned(|item| handler(producer_id, item));
+            }
+        }
+        total
+    }
+
+    /// Consume up to `max_total` items from all producers, transferring ownership.
+    ///
+    /// Prefers earlier rings (producer 0, then 1, etc.).
+    pub fn consume_all_up_to_owned<F>(&self, max_total: usize, mut handler: F) -> usize
+    where
+        F: FnMut(usize, T),
+    {
+        let mut total = 0;
+        for producer_id in 0..self.inner.forward.producer_count() {
+            if total >= max_total {
+                break;
+            }
+            if let Some(ring) = self.inner.forward.get_ring(producer_id) {
+                total += ring.consume_up_to_owned(max_total - total, |item| {
+                    handler(producer_id, item);
+                });
+            }
+        }
+        total
+    }
+
+    /// Return an emptied buffer to the producer it came from.
+    ///
+    /// Returns `true` if the buffer was queued on the producer's return ring.
+    /// Returns `false` (and drops the buffer) if `producer_id` is not
+    /// registered or its return ring is full — the pool simply shrinks and the
+    /// producer falls back to allocating.
+    ///
+    /// Must only be called from the consumer thread (INV-RCY-01).
+    pub fn release(&self, producer_id: usize, buffer: T) -> bool {
+        if producer_id >= self.inner.forward.producer_count() {
+            return false;
+        }
+        self.inner.returns[producer_id].push(buffer)
+    }
+
+    /// Close the channel, preventing further registrations.
+    pub fn close(&self) {
+        self.inner.forward.close();
+    }
+
+    /// Returns true if the channel is closed.
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.inner.forward.is_closed()
+    }
+
+    /// Returns the number of registered producers.
+    #[must_use]
+    pub fn producer_count(&self) -> usize {
+        self.inner.forward.producer_count()
+    }
+
+    /// Get aggregated metrics snapshot from the forward rings if enabled.
+    #[must_use]
+    pub fn metrics(&self) -> crate::MetricsSnapshot {
+        self.inner.forward.metrics()
+    }
+}
+
+impl<T, A: BufferAllocator> Clone for RecyclingChannel<T, A> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+// Safety: Same reasoning as `Channel` — all shared state is in `Ring`s, which
+// are `Send + Sync` for `T: Send`. BufferAllocator requires Send + Sync.
+unsafe impl<T: Send, A: BufferAllocator> Send for RecyclingChannel<T, A> {}
+unsafe impl<T: Send, A: BufferAllocator> Sync for RecyclingChannel<T, A> {}
+
+/// Producer handle for a [`RecyclingChannel`].
+///
+/// Owns the write side of its forward ring and the read side of its return
+/// ring. Like [`Producer`], it intentionally does not implement `Clone`.
+pub struct RecyclingProducer<T, A: BufferAllocator = HeapAllocator> {
+    producer: Producer<T, A>,
+    inner: Arc<RecyclingInner<T, A>>,
+}
+
+impl<T, A: BufferAllocator> RecyclingProducer<T, A> {
+    /// Get the producer's ID.
+    #[inline]
+    #[must_use]
+    pub fn id(&self) -> usize {
+        self.producer.id()
+    }
+
+    /// Take a recycled buffer from this producer's return ring, if any.
+    ///
+    /// Buffers come back exactly as the consumer released them; clearing or
+    /// resetting them is the caller's responsibility.
+    #[inline]
+    pub fn acquire_buffer(&self) -> Option<T> {
+        let mut buffer = None;
+        self.return_ring().consume_up_to_owned(1, |b| buffer = Some(b));
+        buffer
+    }
+
+    /// Take a recycled buffer, or create a fresh one with `make` if the pool is empty.
+    #[inline]
+    pub fn acquire_buffer_or_else<F>(&self, make: F) -> T
+    where
+        F: FnOnce() -> T,
+    {
+        self.acquire_buffer().unwrap_or_else(make)
+    }
+
+    /// Returns the number of recycled buffers waiting on the return ring.
+    #[inline]
+    #[must_use]
+    pub fn pooled(&self) -> usize {
+        self.return_ring().len()
+    }
+
+    /// Reserve n slots for zero-copy writing. Returns None if full/closed.
+    ///
+    /// See [`Producer::reserve`] for partial-reservation semantics.
+    #[inline]
+    #[must_use]
+    pub fn reserve(&self, n: usize) -> Option<Reservation<'_, T, A>> {
+        self.producer.reserve(n)
+    }
+
+    /// Send a single item (convenience).
+    ///
+    /// Returns `true` if the item was successfully enqueued, `false` if the
+    /// ring is full or closed.
+    #[inline]
+    pub fn push(&self, item: T) -> bool {
+        self.producer.push(item)
+    }
+
+    /// Close the producer's forward ring.
+    #[inline]
+    pub fn close(&self) {
+        self.producer.close();
+    }
+
+    /// Returns true if the producer's forward ring is closed.
+    #[inline]
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.producer.is_closed()
+    }
+
+    #[inline]
+    fn return_ring(&self) -> &Ring<T, A> {
+        &self.inner.returns[self.producer.id()]
+    }
+}
+
+// Safety: Same reasoning as `Producer` — this handle is the sole writer of its
+// forward ring and the sole reader of its return ring (INV-CH-01, INV-RCY-01).
+unsafe impl<T: Send, A: BufferAllocator> Send for RecyclingProducer<T, A> {}
+unsafe impl<T: Send, A: BufferAllocator> Sync for RecyclingProducer<T, A> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recycling_round_trip_reuses_allocation() {
+        let ch = RecyclingChannel::<Vec<u8>>::new(Config::default());
+        let p = ch.register().unwrap();
+
+        assert!(p.acquire_buffer().is_none());
+
+        let mut buf = p.acquire_buffer_or_else(|| Vec::with_capacity(64));
+        let original_ptr = buf.as_ptr();
+        buf.extend_from_slice(&[1, 2, 3]);
+        assert!(p.push(buf));
+
+        let consumed = ch.consume_all_owned(|id, mut buf| {
+            assert_eq!(id, 0);
+            assert_eq!(buf, vec![1, 2, 3]);
+            buf.clear();
+            assert!(ch.release(id, buf));
+        });
+        assert_eq!(consumed, 1);
+        assert_eq!(p.pooled(), 1);
+
+        let buf = p.acquire_buffer().unwrap();
+        assert_eq!(buf.as_ptr(), original_ptr);
+        assert!(buf.is_empty());
+    }
+
+    // INV-RCY-02: a released buffer goes back to the producer that sent it
+    #[test]
+    fn test_recycling_returns_to_originating_producer() {
+        let ch = RecyclingChannel::<Vec<u8>>::new(Config::default());
+        let p0 = ch.register().unwrap();
+        let p1 = ch.register().unwrap();
+
+        assert!(p0.push(vec![0]));
+        assert!(p1.push(vec![1]));
+
+        ch.consume_all_owned(|id, buf| {
+            assert_eq!(buf[0] as usize, id);
+            ch.release(id, buf);
+        });
+
+        assert_eq!(p0.acquire_buffer(), Some(vec![0]));
+        assert_eq!(p1.acquire_buffer(), Some(vec![1]));
+    }
+
+    // INV-RCY-02: a release to an unregistered id drops the buffer
+    #[test]
+    fn test_recycling_release_unknown_producer_drops() {
+        let ch = RecyclingChannel::<Vec<u8>>::new(Config::default());
+        let _p = ch.register().unwrap();
+        assert!(!ch.release(5, Vec::new()));
+    }
+
+    // INV-RCY-02: a full return ring drops the buffer instead of blocking
+    #[test]
+    fn test_recycling_full_return_ring_drops() {
+        let config = Config::new(2, 1, false); // 4 slots
+        let ch = RecyclingChannel::<Box<[u8; 16]>>::new(config);
+        let p = ch.register().unwrap();
+
+        for _ in 0..4 {
+            assert!(ch.release(p.id(), Box::new([0; 16])));
+        }
+        assert!(!ch.release(p.id(), Box::new([0; 16])));
+        assert_eq!(p.pooled(), 4);
+    }
+}
@@ -3,10 +3,12 @@ use crate::invariants::{
     debug_assert_bounded_count, debug_assert_head_not_past_tail, debug_assert_initialized_read,
     debug_assert_monotonic, debug_assert_no_wrap,
 };
+use crate::waker::WakerSlot;
 use crate::{Backoff, Config, Metrics, Reservation};
 use std::cell::UnsafeCell;
 use std::ptr;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::task::Waker;
 
 // =============================================================================
 // MEMORY ORDERING & SYNCHRONIZATION STRATEGY
@@ -51,6 +53,13 @@ use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 // These invariants are enforced by the SPSC design: one Producer handle,
 // one consumer (Channel polls rings sequentially on single thread).
 //
+// ## Waker Slots (Ring::with_wakers)
+//
+// When enabled, commit notifies `consumer_waker` after the tail store and
+// advance notifies `producer_waker` after the head store. A waiter registers
+// and then re-checks; both sides fence with SeqCst between their store and
+// their load, so no wakeup is lost (INV-WAKE-01, see waker.rs).
+//
 // =============================================================================
 
 /// SPSC ring buffer - the core building block.
@@ -85,9 +94,15 @@ pub struct Ring<T, A: BufferAllocator = HeapAllocator> {
     closed: AtomicBool,
     /// Thread-safe metrics (uses atomics internally)
     metrics: Metrics,
+    /// Consumer parked on an empty ring (notified by commit)
+    consumer_waker: CacheAligned<WakerSlot>,
+    /// Producer parked on a full ring (notified by advance)
+    producer_waker: CacheAligned<WakerSlot>,
 
     // === CONFIG ===
     config: Config,
+    /// Waker slots enabled ([`with_wakers`](Self::with_wakers))
+    wakers: bool,
 
     // === DATA BUFFER === (64-byte aligned)
     /// The actual ring buffer storage, allocated via [`BufferAllocator`].
@@ -132,11 +147,30 @@ impl<T, A: BufferAllocator> Ring<T, A> {
             active: CacheAligned::new(AtomicBool::new(false)),
             closed: AtomicBool::new(false),
             metrics: Metrics::new(),
+            consumer_waker: CacheAligned::new(WakerSlot::new()),
+            producer_waker: CacheAligned::new(WakerSlot::new()),
             config,
+            wakers: false,
             buffer: UnsafeCell::new(buffer),
         }
     }
 
+    /// Returns this ring with its consumer and producer waker slots enabled.
+    ///
+    /// Async adapters (such as `ringmpsc-stream`) enable wakers so that a
+    /// commit wakes a parked consumer and an advance wakes a parked producer,
+    /// at the cost of one fence per commit and per advance.
+    #[must_use]
+    pub fn with_wakers(mut self) -> Self {
+        self.enable_wakers();
+        self
+    }
+
+    /// Internal: enable the waker slots of a ring that is not yet shared.
+    pub(crate) fn enable_wakers(&mut self) {
+        self.wakers = true;
+    }
+
     // ---------------------------------------------------------------------
     // CONSTANTS & STATUS
     // ---------------------------------------------------------------------
@@ -184,6 +218,63 @@ impl<T, A: BufferAllocator> Ring<T, A> {
         self.active.store(active, Ordering::Release);
     }
 
+    // ---------------------------------------------------------------------
+    // ASYNC WAKERS (with_wakers)
+    // ---------------------------------------------------------------------
+
+    /// Returns true if the ring was built with [`with_wakers`](Self::with_wakers).
+    #[inline]
+    #[must_use]
+    pub fn wakers_enabled(&self) -> bool {
+        self.wakers
+    }
+
+    /// Register the consumer's waker to be woken by the next commit or close.
+    ///
+    /// The caller must re-check the ring (e.g. via [`consume_batch_owned`])
+    /// after registering; a commit that raced with registration is then
+    /// either observed by the re-check or wakes the waker (INV-WAKE-01).
+    ///
+    /// Has no effect unless the ring was built with
+    /// [`with_wakers`](Self::with_wakers).
+    ///
+    /// [`consume_batch_owned`]: Self::consume_batch_owned
+    #[inline]
+    pub fn register_consumer_waker(&self, waker: &Waker) {
+        if self.wakers {
+            self.consumer_waker.register(waker);
+        }
+    }
+
+    /// Register the producer's waker to be woken by the next advance or close.
+    ///
+    /// The caller must retry [`reserve`](Self::reserve) after registering.
+    /// Has no effect unless the ring was built with
+    /// [`with_wakers`](Self::with_wakers).
+    #[inline]
+    pub fn register_producer_waker(&self, waker: &Waker) {
+        if self.wakers {
+            self.producer_waker.register(waker);
+        }
+    }
+
+    /// Internal: wake a consumer parked on an empty ring. Call after the tail
+    /// store, or after publishing any other state the consumer re-checks.
+    #[inline]
+    pub(crate) fn wake_consumer(&self) {
+        if self.wakers {
+            self.consumer_waker.notify();
+        }
+    }
+
+    /// Internal: wake a producer parked on a full ring. Call after the head store.
+    #[inline]
+    fn wake_producer(&self) {
+        if self.wakers {
+            self.producer_waker.notify();
+        }
+    }
+
     // ---------------------------------------------------------------------
     // PRODUCER API
     // ---------------------------------------------------------------------
@@ -277,7 +368,7 @@ impl<T, A: BufferAllocator> Ring<T, A> {
         };
 
         // Create reservation with commit callback
-        let ring_ptr = self as *const Self;
+        let ring_ptr = std::ptr::from_ref::<Self>(self);
         Reservation::new(slice, ring_ptr)
     }
 
@@ -301,6 +392,7 @@ impl<T, A: BufferAllocator> Ring<T, A> {
         debug_assert_no_wrap!("tail", tail, new_tail);
 
         self.tail.store(new_tail, Ordering::Release);
+        self.wake_consumer();
 
         if self.config.enable_metrics {
             self.metrics.add_messages_sent(n as u64);
@@ -371,6 +463,7 @@ impl<T, A: BufferAllocator> Ring<T, A> {
         debug_assert_monotonic!("head", head, new_head);
 
         self.head.store(new_head, Ordering::Release);
+        self.wake_producer();
 
         if self.config.enable_metrics {
             self.metrics.add_messages_received(n as u64);
@@ -465,6 +558,7 @@ impl<T, A: BufferAllocator> Ring<T, A> {
 
         // Single atomic update for entire batch
         self.head.store(tail, Ordering::Release);
+        self.wake_producer();
 
         if self.config.enable_metrics {
             self.metrics.add_messages_received(count as u64);
@@ -538,6 +632,7 @@ impl<T, A: BufferAllocator> Ring<T, A> {
 
         // Single atomic update for entire batch
         self.head.store(tail, Ordering::Release);
+        self.wake_producer();
 
         if self.config.enable_metrics {
             self.metrics.add_messages_received(count as u64);
@@ -597,6 +692,7 @@ impl<T, A: BufferAllocator> Ring<T, A> {
 
         // Single atomic update for the batch
         self.head.store(head.wrapping_add(count as u64), Ordering::Release);
+        self.wake_producer();
 
         if self.config.enable_metrics {
             self.metrics.add_messages_received(count as u64);
@@ -656,6 +752,7 @@ impl<T, A: BufferAllocator> Ring<T, A> {
 
         // Single atomic update for the batch
         self.head.store(head.wrapping_add(count as u64), Ordering::Release);
+        self.wake_producer();
 
         if self.config.enable_metrics {
             self.metrics.add_messages_received(count as u64);
@@ -723,8 +820,13 @@ impl<T, A: BufferAllocator> Ring<T, A> {
     // ---------------------------------------------------------------------
 
     /// Close the ring, preventing further operations.
+    ///
+    /// Wakes both a parked consumer and a parked producer so they can
+    /// observe the closed state.
     pub fn close(&self) {
         self.closed.store(true, Ordering::Release);
+        self.wake_consumer();
+        self.wake_producer();
     }
 
     /// Get a snapshot of metrics if enabled.
@@ -962,4 +1064,94 @@ mod tests {
         assert_eq!(consumed, 5);
         assert_eq!(DROP_COUNT.load(Ordering::SeqCst), 10);
     }
+
+    struct CountingWaker(std::sync::atomic::AtomicUsize);
+
+    impl std::task::Wake for CountingWaker {
+        fn wake(self: std::sync::Arc<Self>) {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        }
+    }
+
+    fn counting_waker() -> (std::sync::Arc<CountingWaker>, Waker) {
+        let counter = std::sync::Arc::new(CountingWaker(std::sync::atomic::AtomicUsize::new(0)));
+        let waker = Waker::from(std::sync::Arc::clone(&counter));
+        (counter, waker)
+    }
+
+    #[test]
+    fn test_ring_wakers_disabled_by_default() {
+        let ring = Ring::<u64>::new(Config::default());
+        let (counter, waker) = counting_waker();
+
+        ring.register_consumer_waker(&waker);
+        assert!(ring.push(1));
+        ring.close();
+
+        assert_eq!(counter.0.load(std::sync::atomic::Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_ring_commit_wakes_registered_consumer() {
+        let ring = Ring::<u64>::new(Config::default()).with_wakers();
+        let (counter, waker) = counting_waker();
+
+        // No interest registered: commit does not wake.
+        assert!(ring.push(1));
+        assert_eq!(counter.0.load(std::sync::atomic::Ordering::SeqCst), 0);
+        ring.consume_batch(|_| {});
+
+        ring.register_consumer_waker(&waker);
+        assert!(ring.push(2));
+        assert_eq!(counter.0.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        // Interest is consumed: a second commit does not wake again.
+        assert!(ring.push(3));
+        assert_eq!(counter.0.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_ring_advance_wakes_registered_producer() {
+        let ring = Ring::<u64>::new(Config::new(2, 1, false)).with_wakers(); // 4 slots
+        let (counter, waker) = counting_waker();
+
+        for i in 0..4 {
+            assert!(ring.push(i));
+        }
+        assert!(ring.reserve(1).is_none());
+
+        ring.register_producer_waker(&waker);
+        assert_eq!(ring.consume_up_to(1, |_| {}), 1);
+        assert_eq!(counter.0.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert!(ring.reserve(1).is_some());
+    }
+
+    #[test]
+    fn test_ring_empty_consume_does_not_wake_producer() {
+        let ring = Ring::<u64>::new(Config::default()).with_wakers();
+        let (counter, waker) = counting_waker();
+
+        // A consumer sweeping every ring must not wake producers it made no
+        // room for.
+        ring.register_producer_waker(&waker);
+        assert_eq!(ring.consume_batch(|_| {}), 0);
+        assert_eq!(ring.consume_batch_owned(|_| {}), 0);
+        assert_eq!(ring.consume_up_to(8, |_| {}), 0);
+        assert_eq!(ring.consume_up_to_owned(8, |_| {}), 0);
+        assert_eq!(counter.0.load(std::sync::atomic::Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_ring_close_wakes_both_sides() {
+        let ring = Ring::<u64>::new(Config::default()).with_wakers();
+        let (consumer, consumer_waker) = counting_waker();
+        let (producer, producer_waker) = counting_waker();
+
+        ring.register_consumer_waker(&consumer_waker);
+        ring.register_producer_waker(&producer_waker);
+        ring.close();
+
+        assert_eq!(consumer.0.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert_eq!(producer.0.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
 }
@@ -62,6 +62,7 @@ use crate::invariants::debug_assert_fifo_count;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 #[cfg(debug_assertions)]
 use std::sync::atomic::AtomicU64;
+use std::task::Waker;
 use thiserror::Error;
 
 // =============================================================================
@@ -168,14 +169,29 @@ impl<T, const N: usize, const P: usize> StackChannel<T, N, P> {
     /// let channel: StackChannel<u64, 4096, 4> = StackChannel::new();
     /// ```
     pub const fn new() -> Self {
+        Self::build([const { StackRing::new() }; P])
+    }
+
+    /// Creates a channel whose rings have waker slots enabled
+    /// ([`StackRing::with_wakers`]), as required by async adapters.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// static CHANNEL: StackChannel<u64, 1024, 4> = StackChannel::with_wakers();
+    /// ```
+    pub const fn with_wakers() -> Self {
+        Self::build([const { StackRing::with_wakers() }; P])
+    }
+
+    const fn build(rings: [StackRing<T, N>; P]) -> Self {
         assert_power_of_two::<N>();
         assert_valid_producer_count::<P>();
 
         Self {
             producer_count: AtomicUsize::new(0),
             closed: AtomicBool::new(false),
-            // Initialize P rings using const evaluation
-            rings: [const { StackRing::new() }; P],
+            rings,
             // Initialize P consumption counters (debug only)
             #[cfg(debug_assertions)]
             consumed_counts: [const { AtomicU64::new(0) }; P],
@@ -210,6 +226,24 @@ impl<T, const N: usize, const P: usize> StackChannel<T, N, P> {
         self.closed.load(Ordering::Acquire)
     }
 
+    /// Returns true if the channel was built with [`with_wakers`](Self::with_wakers).
+    #[inline]
+    pub const fn wakers_enabled(&self) -> bool {
+        self.rings[0].wakers_enabled()
+    }
+
+    /// Register the consumer's waker on every ring, including rings whose
+    /// producers have not registered yet.
+    ///
+    /// The next commit on any ring wakes `waker`. The caller must re-check
+    /// the channel after registering (INV-WAKE-01). Has no effect unless the
+    /// channel was built with [`with_wakers`](Self::with_wakers).
+    pub fn register_consumer_waker(&self, waker: &Waker) {
+        for ring in &self.rings {
+            ring.register_consumer_waker(waker);
+        }
+    }
+
     // =========================================================================
     // PRODUCER REGISTRATION
     // =========================================================================
@@ -377,6 +411,39 @@ impl<T, const N: usize, const P: usize> StackChannel<T, N, P> {
         total
     }
 
+    /// Consume up to `max_total` items from all producers, transferring ownership.
+    ///
+    /// Similar to [`consume_all_up_to`](Self::consume_all_up_to), but the
+    /// handler receives ownership of each item.
+    pub fn consume_all_up_to_owned<F>(&self, max_total: usize, mut handler: F) -> usize
+    where
+        F: FnMut(T),
+    {
+        let count = self.producer_count.load(Ordering::Acquire);
+        let mut total = 0;
+
+        for (producer_id, ring) in self.rings[..count].iter().enumerate() {
+            if total >= max_total {
+                break;
+            }
+            let remaining = max_total - total;
+            let consumed = unsafe { ring.consume_up_to_owned(remaining, &mut handler) };
+
+            // INV-CH-03: Verify per-producer FIFO by tracking cumulative count
+            #[cfg(debug_assertions)]
+            {
+                let old_count = self.consumed_counts[producer_id].load(Ordering::Relaxed);
+                let new_count = old_count + consumed as u64;
+                debug_assert_fifo_count!(producer_id, old_count, new_count);
+                self.consumed_counts[producer_id].store(new_count, Ordering::Relaxed);
+            }
+
+            total += consumed;
+        }
+
+        total
+    }
+
     /// Get a reference to a specific producer's ring.
     ///
     /// Useful for dedicated consumer patterns where each consumer reads
@@ -439,7 +506,7 @@ pub struct StackProducer<'a, T, const N: usize, const P: usize> {
 // Only this producer writes to its ring; the consumer reads.
 unsafe impl<T: Send, const N: usize, const P: usize> Send for StackProducer<'_, T, N, P> {}
 
-impl<'a, T, const N: usize, const P: usize> StackProducer<'a, T, N, P> {
+impl<T, const N: usize, const P: usize> StackProducer<'_, T, N, P> {
     /// Returns this producer's ID (ring index).
     #[inline]
     pub fn id(&self) -> usize {
@@ -52,10 +52,12 @@ use crate::invariants::{
     debug_assert_bounded_count, debug_assert_head_not_past_tail, debug_assert_initialized_read,
     debug_assert_monotonic, debug_assert_no_wrap,
 };
+use crate::waker::WakerSlot;
 
 use std::cell::UnsafeCell;
 use std::mem::MaybeUninit;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::task::Waker;
 
 // =============================================================================
 // COMPILE-TIME ASSERTIONS
@@ -131,6 +133,7 @@ impl<T> std::ops::DerefMut for CacheAligned<T> {
 /// ├────────────────────────────────────────────────────────────────────┤
 /// │ Cold state                                                         │
 /// │   closed: AtomicBool                                               │
+/// │   wakers: bool, consumer_waker, producer_waker (async, opt-in)     │
 /// ├────────────────────────────────────────────────────────────────────┤
 /// │ Data buffer (inline, no pointer indirection)                       │
 /// │   [UnsafeCell<MaybeUninit<T>>; N]                                  │
@@ -153,6 +156,12 @@ pub struct StackRing<T, const N: usize> {
     // === COLD STATE ===
     /// Whether the ring is closed
     closed: AtomicBool,
+    /// Whether commit/advance notify the waker slots (INV-WAKE-02)
+    wakers: bool,
+    /// Consumer parked on an empty ring (woken by commit and close)
+    consumer_waker: WakerSlot,
+    /// Producer parked on a full ring (woken by advance/consume and close)
+    producer_waker: WakerSlot,
 
     // === DATA BUFFER === (inline, no heap allocation)
     /// The ring buffer storage, embedded directly in the struct.
@@ -186,6 +195,21 @@ impl<T, const N: usize> StackRing<T, N> {
     /// let ring: StackRing<u64, 4096> = StackRing::new();
     /// ```
     pub const fn new() -> Self {
+        Self::build(false)
+    }
+
+    /// Creates a ring whose waker slots are enabled, for async adapters.
+    ///
+    /// The stack counterpart of [`Ring::with_wakers`](crate::Ring::with_wakers):
+    /// commit wakes a consumer registered with
+    /// [`register_consumer_waker`](Self::register_consumer_waker), and
+    /// advancing the head wakes a producer registered with
+    /// [`register_producer_waker`](Self::register_producer_waker).
+    pub const fn with_wakers() -> Self {
+        Self::build(true)
+    }
+
+    const fn build(wakers: bool) -> Self {
         // Compile-time assertion
         assert_power_of_two::<N>();
 
@@ -195,6 +219,9 @@ impl<T, const N: usize> StackRing<T, N> {
             head: CacheAligned::new(AtomicU64::new(0)),
             cached_tail: UnsafeCell::new(0),
             closed: AtomicBool::new(false),
+            wakers,
+            consumer_waker: WakerSlot::new(),
+            producer_waker: WakerSlot::new(),
             // SAFETY: MaybeUninit<T> does not require initialization
             // This is the standard pattern for const-initializing arrays of MaybeUninit
             buffer: unsafe { MaybeUninit::uninit().assume_init() },
@@ -238,8 +265,61 @@ impl<T, const N: usize> StackRing<T, N> {
     }
 
     /// Closes the ring, preventing further writes.
+    ///
+    /// With wakers enabled, wakes a parked consumer and producer so they
+    /// observe the closed state.
     pub fn close(&self) {
         self.closed.store(true, Ordering::Release);
+        self.wake_consumer();
+        self.wake_producer();
+    }
+
+    // =========================================================================
+    // ASYNC WAKERS (with_wakers)
+    // =========================================================================
+
+    /// Returns true if the ring was built with [`with_wakers`](Self::with_wakers).
+    #[inline]
+    pub const fn wakers_enabled(&self) -> bool {
+        self.wakers
+    }
+
+    /// Register the consumer's waker to be woken by the next commit or close.
+    ///
+    /// The caller must re-check the ring after registering (INV-WAKE-01).
+    /// Has no effect unless the ring was built with [`with_wakers`](Self::with_wakers).
+    #[inline]
+    pub fn register_consumer_waker(&self, waker: &Waker) {
+        if self.wakers {
+            self.consumer_waker.register(waker);
+        }
+    }
+
+    /// Register the producer's waker to be woken by the next advance or close.
+    ///
+    /// The caller must re-check for space after registering (INV-WAKE-01).
+    /// Has no effect unless the ring was built with [`with_wakers`](Self::with_wakers).
+    #[inline]
+    pub fn register_producer_waker(&self, waker: &Waker) {
+        if self.wakers {
+            self.producer_waker.register(waker);
+        }
+    }
+
+    /// Internal: wake a consumer parked on an empty ring. Call after the tail store.
+    #[inline]
+    fn wake_consumer(&self) {
+        if self.wakers {
+            self.consumer_waker.notify();
+        }
+    }
+
+    /// Internal: wake a producer parked on a full ring. Call after the head store.
+    #[inline]
+    fn wake_producer(&self) {
+        if self.wakers {
+            self.producer_waker.notify();
+        }
     }
 
     // =========================================================================
@@ -315,7 +395,7 @@ impl<T, const N: usize> StackRing<T, N> {
         // Contiguous slots available before wrap-around
         let contiguous = n.min(N - idx);
 
-        let ptr = (*self.buffer.as_ptr().add(idx)).get() as *mut T;
+        let ptr = (*self.buffer.as_ptr().add(idx)).get().cast::<T>();
         (ptr, contiguous)
     }
 
@@ -341,6 +421,7 @@ impl<T, const N: usize> StackRing<T, N> {
         debug_assert_no_wrap!("tail", tail, new_tail);
 
         self.tail.store(new_tail, Ordering::Release);
+        self.wake_consumer();
     }
 
     // =========================================================================
@@ -406,6 +487,7 @@ impl<T, const N: usize> StackRing<T, N> {
         debug_assert_monotonic!("head", head, new_head);
 
         self.head.store(new_head, Ordering::Release);
+        self.wake_producer();
     }
 
     /// Process ALL available items with a single head update.
@@ -460,6 +542,7 @@ impl<T, const N: usize> StackRing<T, N> {
 
         // Single atomic update for entire batch
         self.head.store(tail, Ordering::Release);
+        self.wake_producer();
 
         avail
     }
@@ -498,6 +581,7 @@ impl<T, const N: usize> StackRing<T, N> {
         }
 
         self.head.store(tail, Ordering::Release);
+        self.wake_producer();
 
         avail
     }
@@ -537,6 +621,49 @@ impl<T, const N: usize> StackRing<T, N> {
         }
 
         self.head.store(end, Ordering::Release);
+        self.wake_producer();
+
+        to_consume
+    }
+
+    /// Process up to `max` items with a single head update, transferring
+    /// ownership to the handler.
+    ///
+    /// Similar to [`consume_up_to`](Self::consume_up_to), but the handler
+    /// receives ownership of each item.
+    ///
+    /// # Safety
+    ///
+    /// Must be called from a single consumer thread only.
+    #[inline]
+    pub unsafe fn consume_up_to_owned<F>(&self, max: usize, mut handler: F) -> usize
+    where
+        F: FnMut(T),
+    {
+        let head = self.head.load(Ordering::Relaxed);
+        let tail = self.tail.load(Ordering::Acquire);
+
+        let avail = tail.wrapping_sub(head) as usize;
+        if avail == 0 || max == 0 {
+            return 0;
+        }
+
+        let to_consume = avail.min(max);
+        let end = head.wrapping_add(to_consume as u64);
+        let mut pos = head;
+
+        while pos != end {
+            // INV-INIT-01: Verify we're reading from initialized range
+            debug_assert_initialized_read!(pos, head, tail);
+
+            let idx = (pos as usize) & Self::MASK;
+            let item = (*self.buffer.as_ptr().add(idx)).get().cast::<T>().read();
+            handler(item);
+            pos = pos.wrapping_add(1);
+        }
+
+        self.head.store(end, Ordering::Release);
+        self.wake_producer();
 
         to_consume
     }
@@ -839,4 +966,70 @@ mod tests {
         assert_eq!(collected, vec!["item_0", "item_1", "item_2"]);
         assert!(ring.is_empty());
     }
+
+    #[test]
+    fn test_consume_up_to_owned() {
+        let ring: StackRing<String, 8> = StackRing::new();
+        for i in 0..5 {
+            unsafe {
+                let (ptr, _) = ring.reserve(1).unwrap();
+                std::ptr::write(ptr, format!("item_{}", i));
+                ring.commit(1);
+            }
+        }
+
+        let mut collected = Vec::new();
+        let consumed = unsafe { ring.consume_up_to_owned(3, |s| collected.push(s)) };
+
+        assert_eq!(consumed, 3);
+        assert_eq!(collected, vec!["item_0", "item_1", "item_2"]);
+        assert_eq!(ring.len(), 2);
+    }
+
+    struct CountingWaker(std::sync::atomic::AtomicUsize);
+
+    impl std::task::Wake for CountingWaker {
+        fn wake(self: std::sync::Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_stack_ring_wakers() {
+        let counter = std::sync::Arc::new(CountingWaker(std::sync::atomic::AtomicUsize::new(0)));
+        let waker = Waker::from(std::sync::Arc::clone(&counter));
+
+        // Disabled by default: registration is a no-op.
+        let plain: StackRing<u64, 4> = StackRing::new();
+        plain.register_consumer_waker(&waker);
+        unsafe {
+            let (ptr, _) = plain.reserve(1).unwrap();
+            ptr.write(1);
+        }
+        plain.commit(1);
+        assert_eq!(counter.0.load(Ordering::SeqCst), 0);
+
+        let ring: StackRing<u64, 4> = StackRing::with_wakers();
+        assert!(ring.wakers_enabled());
+
+        // Commit wakes the consumer.
+        ring.register_consumer_waker(&waker);
+        unsafe {
+            let (ptr, _) = ring.reserve(1).unwrap();
+            ptr.write(1);
+        }
+        ring.commit(1);
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+
+        // Consuming wakes the producer.
+        ring.register_producer_waker(&waker);
+        unsafe { ring.consume_batch(|_| {}) };
+        assert_eq!(counter.0.load(Ordering::SeqCst), 2);
+
+        // Close wakes both sides.
+        ring.register_consumer_waker(&waker);
+        ring.register_producer_waker(&waker);
+        ring.close();
+        assert_eq!(counter.0.load(Ordering::SeqCst), 4);
+    }
 }
@@ -0,0 +1,404 @@
+//! Waker slots for async integration of [`Ring`](crate::Ring).
+//!
+//! A ring carries two [`WakerSlot`]s: one the consumer parks in while the ring
+//! is empty, and one the producer parks in while the ring is full. The slot is
+//! only consulted when the ring was built with
+//! [`Ring::with_wakers`](crate::Ring::with_wakers), so rings used purely from spinning threads pay nothing.
+//!
+//! # Protocol (INV-WAKE-01)
+//!
+//! The waiting side registers, then re-checks the ring:
+//!
+//! ```text
+//! Waiter                               Notifier
+//! ------                               --------
+//! register waker                       store tail/head (Release)
+//! interest = true (Release)            fence(SeqCst)
+//! fence(SeqCst)                        if interest.swap(false) { wake() }
+//! re-check tail/head
+//! ```
+//!
+//! The paired `SeqCst` fences form a Dekker handshake: either the waiter's
+//! re-check observes the notifier's store, or the notifier observes
+//! `interest == true` and wakes the registered waker. A wakeup can therefore
+//! not be lost between the waiter's last check and its registration.
+//!
+//! # Model checking
+//!
+//! Built with `--cfg ringmpsc_loom`, this module runs on loom's primitives
+//! and its `loom_tests` explore the protocol exhaustively. (A crate-specific
+//! cfg, since a global `--cfg loom` also switches tokio into loom mode.)
+//!
+//! ```text
+//! RUSTFLAGS="--cfg ringmpsc_loom" cargo test -p ringmpsc-rs --features loom --lib --release waker::loom_tests
+//! ```
+
+use std::task::Waker;
+use sync::{fence, spin_loop, AtomicBool, AtomicUsize, Ordering, UnsafeCell};
+
+#[cfg(not(ringmpsc_loom))]
+mod sync {
+    pub(super) use std::hint::spin_loop;
+    pub(super) use std::sync::atomic::{fence, AtomicBool, AtomicUsize, Ordering};
+
+    /// `std` cell with loom's closure-based access, so the code below is the
+    /// same under both.
+    pub(super) struct UnsafeCell<T>(std::cell::UnsafeCell<T>);
+
+    impl<T> UnsafeCell<T> {
+        pub(super) const fn new(value: T) -> Self {
+            Self(std::cell::UnsafeCell::new(value))
+        }
+
+        #[inline]
+        pub(super) fn with_mut<R>(&self, f: impl FnOnce(*mut T) -> R) -> R {
+            f(self.0.get())
+        }
+    }
+}
+
+#[cfg(ringmpsc_loom)]
+mod sync {
+    pub(super) use loom::cell::UnsafeCell;
+    pub(super) use loom::sync::atomic::{fence, AtomicBool, AtomicUsize, Ordering};
+    // Loom must be told when a thread waits on another.
+    pub(super) use loom::thread::yield_now as spin_loop;
+}
+
+// AtomicWaker states. The algorithm follows the one popularised by
+// `futures::task::AtomicWaker`: registration and wake each take a "lock" bit,
+// and whichever side observes the other's bit is responsible for waking.
+const WAITING: usize = 0;
+const REGISTERING: usize = 0b01;
+const WAKING: usize = 0b10;
+
+/// A lock-free cell holding at most one [`Waker`].
+///
+/// `register` may race with `wake`; a `wake` that overlaps or follows a
+/// `register` always wakes the most recently registered waker.
+pub(crate) struct AtomicWaker {
+    state: AtomicUsize,
+    waker: UnsafeCell<Option<Waker>>,
+}
+
+// Safety: access to `waker` is serialized by the REGISTERING / WAKING bits in
+// `state` — only the thread that moved `state` out of WAITING touches the cell.
+unsafe impl Send for AtomicWaker {}
+unsafe impl Sync for AtomicWaker {}
+
+impl AtomicWaker {
+    #[cfg(not(ringmpsc_loom))]
+    pub(crate) const fn new() -> Self {
+        Self {
+            state: AtomicUsize::new(WAITING),
+            waker: UnsafeCell::new(None),
+        }
+    }
+
+    // Loom's primitives have no `const` constructors.
+    #[cfg(ringmpsc_loom)]
+    pub(crate) fn new() -> Self {
+        Self {
+            state: AtomicUsize::new(WAITING),
+            waker: UnsafeCell::new(None),
+        }
+    }
+
+    /// Store `waker`, replacing any previously registered waker.
+    pub(crate) fn register(&self, waker: &Waker) {
+        match self
+            .state
+            .compare_exchange(WAITING, REGISTERING, Ordering::Acquire, Ordering::Acquire)
+            .unwrap_or_else(|actual| actual)
+        {
+            WAITING => {
+                // Safety: we hold the REGISTERING bit, so no other thread
+                // reads or writes the cell until we release it below.
+                self.waker.with_mut(|slot| unsafe {
+                    if !(*slot).as_ref().is_some_and(|old| old.will_wake(waker)) {
+                        *slot = Some(waker.clone());
+                    }
+                });
+
+                if let Err(actual) = self.state.compare_exchange(
+                    REGISTERING,
+                    WAITING,
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                ) {
+                    // A concurrent wake() set WAKING while we held the lock and
+                    // left the wake to us.
+                    debug_assert_eq!(actual, REGISTERING | WAKING);
+                    // Safety: still exclusive — the waker saw REGISTERING and
+                    // backed off without touching the cell.
+                    let pending = self.waker.with_mut(|slot| unsafe { (*slot).take() });
+                    self.state.swap(WAITING, Ordering::AcqRel);
+                    if let Some(pending) = pending {
+                        pending.wake();
+                    }
+                }
+            }
+            WAKING => {
+                // A wake is in progress on the old waker; make sure this task
+                // is polled again rather than waiting for a wake that already
+                // happened.
+                waker.wake_by_ref();
+                spin_loop();
+            }
+            state => {
+                // Another thread is registering concurrently, which violates
+                // the single-waiter contract of the slot.
+                debug_assert!(state == REGISTERING || state == REGISTERING | WAKING);
+            }
+        }
+    }
+
+    /// Take the registered waker, if any, and wake it.
+    pub(crate) fn wake(&self) {
+        if let Some(waker) = self.take() {
+            waker.wake();
+        }
+    }
+
+    fn take(&self) -> Option<Waker> {
+        match self.state.fetch_or(WAKING, Ordering::AcqRel) {
+            WAITING => {
+                // Safety: we moved the state from WAITING to WAKING, which
+                // grants exclusive access to the cell.
+                let waker = self.waker.with_mut(|slot| unsafe { (*slot).take() });
+                self.state.fetch_and(!WAKING, Ordering::Release);
+                waker
+            }
+            _ => {
+                // Either a registration is in progress (it will observe WAKING
+                // and wake itself) or another wake is already running.
+                None
+            }
+        }
+    }
+}
+
+/// An [`AtomicWaker`] paired with an interest flag for a cheap notify path.
+///
+/// The notifier only touches the `AtomicWaker` when the waiter has declared
+/// interest since the last notification, so an uncontended ring pays one
+/// fence and one relaxed load per notify.
+pub(crate) struct WakerSlot {
+    interest: AtomicBool,
+    waker: AtomicWaker,
+}
+
+impl WakerSlot {
+    #[cfg(not(ringmpsc_loom))]
+    pub(crate) const fn new() -> Self {
+        Self {
+            interest: AtomicBool::new(false),
+            waker: AtomicWaker::new(),
+        }
+    }
+
+    #[cfg(ringmpsc_loom)]
+    pub(crate) fn new() -> Self {
+        Self {
+            interest: AtomicBool::new(false),
+            waker: AtomicWaker::new(),
+        }
+    }
+
+    /// Waiter side: register `waker` and declare interest.
+    ///
+    /// The caller must re-check the ring condition after this returns.
+    #[inline]
+    pub(crate) fn register(&self, waker: &Waker) {
+        self.waker.register(waker);
+        // Release pairs with the notifier's swap so the swap also observes the
+        // waker registered above.
+        self.interest.store(true, Ordering::Release);
+        // INV-WAKE-01: order the interest store before the caller's re-check.
+        fence(Ordering::SeqCst);
+    }
+
+    /// Notifier side: wake the waiter if it declared interest.
+    ///
+    /// The caller must have published its state change (tail or head store)
+    /// before calling this.
+    #[inline]
+    pub(crate) fn notify(&self) {
+        // INV-WAKE-01: order the caller's tail/head store before the interest load.
+        fence(Ordering::SeqCst);
+        if self.interest.load(Ordering::Relaxed) && self.interest.swap(false, Ordering::AcqRel) {
+            self.waker.wake();
+        }
+    }
+}
+
+#[cfg(all(test, not(ringmpsc_loom)))]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+    use std::task::Wake;
+
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_waker_slot_wakes_only_with_interest() {
+        let counter = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let waker = Waker::from(Arc::clone(&counter));
+        let slot = WakerSlot::new();
+
+        slot.notify();
+        assert_eq!(counter.0.load(Ordering::SeqCst), 0);
+
+        slot.register(&waker);
+        slot.notify();
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+
+        // Interest is consumed by the first notify.
+        slot.notify();
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_atomic_waker_replaces_registration() {
+        let first = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let second = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let waker = AtomicWaker::new();
+
+        waker.register(&Waker::from(Arc::clone(&first)));
+        waker.register(&Waker::from(Arc::clone(&second)));
+        waker.wake();
+
+        assert_eq!(first.0.load(Ordering::SeqCst), 0);
+        assert_eq!(second.0.load(Ordering::SeqCst), 1);
+    }
+}
+
+/// Exhaustive interleavings of the slot protocol (INV-WAKE-01) on a ring
+/// reduced to its two indices.
+#[cfg(all(test, ringmpsc_loom))]
+mod loom_tests {
+    use super::*;
+    use loom::sync::atomic::AtomicU64;
+    use loom::sync::Arc;
+    use loom::thread;
+    use std::future::poll_fn;
+    use std::task::Poll;
+
+    struct LoomRing {
+        tail: AtomicU64,
+        head: AtomicU64,
+        capacity: u64,
+        consumer_waker: WakerSlot,
+        producer_waker: WakerSlot,
+    }
+
+    impl LoomRing {
+        fn new(capacity: u64) -> Self {
+            Self {
+                tail: AtomicU64::new(0),
+                head: AtomicU64::new(0),
+                capacity,
+                consumer_waker: WakerSlot::new(),
+                producer_waker: WakerSlot::new(),
+            }
+        }
+
+        /// Reserve + commit of one slot, then notify the consumer.
+        fn push(&self) -> bool {
+            let tail = self.tail.load(Ordering::Relaxed);
+            let head = self.head.load(Ordering::Acquire);
+            if tail - head == self.capacity {
+                return false;
+            }
+            self.tail.store(tail + 1, Ordering::Release);
+            self.consumer_waker.notify();
+            true
+        }
+
+        /// Consume one slot, then notify the producer.
+        fn pop(&self) -> bool {
+            let head = self.head.load(Ordering::Relaxed);
+            let tail = self.tail.load(Ordering::Acquire);
+            if head == tail {
+                return false;
+            }
+            self.head.store(head + 1, Ordering::Release);
+            self.producer_waker.notify();
+            true
+        }
+    }
+
+    /// Poll `op` until it succeeds, parking in `slot` in between. Deadlocks
+    /// (and fails the model) if a wakeup is lost.
+    fn park_until(slot: &WakerSlot, op: impl Fn() -> bool) {
+        loom::future::block_on(poll_fn(|cx| {
+            if op() {
+                return Poll::Ready(());
+            }
+            slot.register(cx.waker());
+            if op() {
+                return Poll::Ready(());
+            }
+            Poll::Pending
+        }));
+    }
+
+    /// A consumer parked on an empty ring is always woken by a racing commit.
+    #[test]
+    fn loom_consumer_waker_no_lost_wakeup() {
+        loom::model(|| {
+            let ring = Arc::new(LoomRing::new(4));
+            let producer_ring = Arc::clone(&ring);
+
+            let producer = thread::spawn(move || {
+                assert!(producer_ring.push());
+            });
+
+            park_until(&ring.consumer_waker, || ring.pop());
+            producer.join().unwrap();
+        });
+    }
+
+    /// A consumer that parks twice receives both commits (interest re-arms).
+    #[test]
+    fn loom_consumer_waker_rearms() {
+        loom::model(|| {
+            let ring = Arc::new(LoomRing::new(4));
+            let producer_ring = Arc::clone(&ring);
+
+            let producer = thread::spawn(move || {
+                assert!(producer_ring.push());
+                assert!(producer_ring.push());
+            });
+
+            for _ in 0..2 {
+                park_until(&ring.consumer_waker, || ring.pop());
+            }
+            producer.join().unwrap();
+        });
+    }
+
+    /// A producer parked on a full ring is always woken by a racing advance.
+    #[test]
+    fn loom_producer_waker_no_lost_wakeup() {
+        loom::model(|| {
+            let ring = Arc::new(LoomRing::new(1));
+            assert!(ring.push());
+            let consumer_ring = Arc::clone(&ring);
+
+            let consumer = thread::spawn(move || {
+                assert!(consumer_ring.pop());
+            });
+
+            park_until(&ring.producer_waker, || ring.push());
+            consumer.join().unwrap();
+        });
+    }
+}
@@ -9,6 +9,8 @@
 //! `run_workload(steps)` generates random operations: register writers,
 //! create transactions, insert entries, commit or abort, advance clock.
 //! Acknowledged commits are recorded in the oracle for later verification.
+//! A failed flush poisons the WAL (fail-stop); the driver then shuts it down,
+//! recovers and reopens it, as an application would.
 
 use std::path::PathBuf;
 
@@ -17,7 +19,7 @@ use rand::{Rng, SeedableRng};
 
 use ringwal::{
     IoEngine, RecoveredTransaction, RecoveryStats, Transaction, Wal,
-    WalConfig, WalError, WalWriter,
+    WalConfig, WalError, WalWriter, WalWriterFactory,
 };
 
 use crate::clock::SimClock;
@@ -25,6 +27,9 @@ use crate::fault::FaultConfig;
 use crate::oracle::{self, CommitOracle, VerificationResult};
 use crate::sim_io::SimIo;
 
+/// Size of the key space workload commits draw from.
+const WORKLOAD_KEYS: u64 = 16;
+
 /// Deterministic simulation harness for the ring-buffer WAL.
 ///
 /// Owns the entire simulated environment: in-memory I/O, clock, oracle,
@@ -64,6 +69,8 @@ pub struct WorkloadStats {
     pub writers_registered: usize,
     /// Transaction IDs that were successfully aborted.
     pub aborted_tx_ids: Vec<u64>,
+    /// Times the WAL was poisoned by a failed flush and reopened.
+    pub reopens: usize,
 }
 
 impl RingwalSimulator {
@@ -193,7 +200,9 @@ impl RingwalSimulator {
     /// - Advance clock occasionally
     ///
     /// Every acknowledged commit (where `tx.commit()` returns `Ok`) is
-    /// recorded in the oracle for later verification.
+    /// recorded in the oracle for later verification. When a commit or abort
+    /// finds the WAL poisoned, the WAL is shut down, recovered and reopened
+    /// with fresh writers.
     ///
     /// Returns statistics about the workload, or a fatal WAL error.
     /// Non-fatal I/O errors (from fault injection) are counted but tolerated.
@@ -201,7 +210,7 @@ impl RingwalSimulator {
         let io = self.sim_io.clone();
         let config = self.wal_config.clone();
 
-        let (mut wal, factory) = Wal::open::<String, Vec<u8>>(config, io)?;
+        let (mut wal, mut factory) = Wal::open::<String, Vec<u8>>(config, io)?;
 
         let mut writers: Vec<WalWriter<String, Vec<u8>>> = Vec::new();
         let mut stats = WorkloadStats::default();
@@ -253,8 +262,10 @@ impl RingwalSimulator {
                     let tx_id = tx.id;
                     let mut entries = Vec::new();
 
+                    // Keys come from a small shared space so that commits
+                    // overwrite each other and replay order is observable.
                     for e in 0..num_entries {
-                        let key = format!("k-{tx_id}-{e}");
+                        let key = format!("k-{}", self.rng.gen_range(0..WORKLOAD_KEYS));
                         let value = format!("v-{tx_id}-{e}").into_bytes();
                         entries.push((key.clone(), value.clone()));
                         tx.insert(key, value);
@@ -272,6 +283,20 @@ impl RingwalSimulator {
                             // returned Err, we do NOT record it in the oracle.
                             stats.fault_errors += 1;
                         }
+                        Err(WalError::FlushFailed(_) | WalError::Poisoned(_)) => {
+                            // Fail-stop: not acknowledged, so not recorded.
+                            // Reopen to keep the workload going.
+                            stats.fault_errors += 1;
+                            writers.clear();
+                            match self.reopen(&mut wal).await {
+                                Ok(reopened) => (wal, factory) = reopened,
+                                Err(_) => {
+                                    stats.steps_executed = _step + 1;
+                                    return Ok(stats);
+                                }
+                            }
+                            stats.reopens += 1;
+                        }
                         Err(_) => {
                             // Fatal error (Closed, etc.) — abort the workload
                             // but don't propagate. The WAL might have been
@@ -311,6 +336,18 @@ impl RingwalSimulator {
                         Err(WalError::Io(_)) => {
                             stats.fault_errors += 1;
                         }
+                        Err(WalError::Poisoned(_)) => {
+                            stats.fault_errors += 1;
+                            writers.clear();
+                            match self.reopen(&mut wal).await {
+                                Ok(reopened) => (wal, factory) = reopened,
+                                Err(_) => {
+                                    stats.steps_executed = _step + 1;
+                                    return Ok(stats);
+                                }
+                            }
+                            stats.reopens += 1;
+                        }
                         Err(_) => {
                             stats.fault_errors += 1;
                             let _ = wal.shutdown().await;
@@ -337,6 +374,18 @@ impl RingwalSimulator {
         Ok(stats)
     }
 
+    /// Shuts down a poisoned WAL, runs recovery and opens it again.
+    ///
+    /// Returns `Err` if recovery or the reopen fails under fault injection.
+    async fn reopen(
+        &self,
+        wal: &mut Wal<SimIo>,
+    ) -> Result<(Wal<SimIo>, WalWriterFactory<String, Vec<u8>>), WalError> {
+        let _ = wal.shutdown().await;
+        self.recover()?;
+        Wal::open::<String, Vec<u8>>(self.wal_config.clone(), self.sim_io.clone())
+    }
+
     // ── Verification ─────────────────────────────────────────────────────
 
     /// Verifies recovery output against the oracle (no lost commits, no phantoms).
@@ -3,8 +3,11 @@
 //! The `CommitOracle` records every acknowledged commit and its entries.
 //! After a crash-and-recover cycle, `verify_against()` checks that every
 //! committed transaction appears in the recovery output (no lost commits).
+//! `expected_state()` folds the acknowledged commits, in acknowledgement
+//! order, into the key-value state a replay of the recovered log must match.
 
 use std::collections::HashMap;
+use std::hash::Hash;
 
 use ringwal::{RecoveredTransaction, RecoveryAction};
 
@@ -24,6 +27,8 @@ pub struct CommittedTx<K, V> {
 pub struct CommitOracle<K, V> {
     /// `tx_id` → committed entries (ground truth).
     committed: HashMap<u64, CommittedTx<K, V>>,
+    /// `tx_id`s in the order their commits were acknowledged.
+    commit_order: Vec<u64>,
 }
 
 impl<K, V> CommitOracle<K, V> {
@@ -32,6 +37,7 @@ impl<K, V> CommitOracle<K, V> {
     pub fn new() -> Self {
         Self {
             committed: HashMap::new(),
+            commit_order: Vec::new(),
         }
     }
 
@@ -39,7 +45,13 @@ impl<K, V> CommitOracle<K, V> {
     ///
     /// Should be called only when `WalWriter::commit(tx_id).await` returns `Ok`.
     pub fn record_commit(&mut self, tx_id: u64, entries: Vec<(K, V)>) {
-        self.committed.insert(tx_id, CommittedTx { tx_id, entries });
+        if self
+            .committed
+            .insert(tx_id, CommittedTx { tx_id, entries })
+            .is_none()
+        {
+            self.commit_order.push(tx_id);
+        }
     }
 
     /// Returns the number of acknowledged commits.
@@ -56,6 +68,27 @@ impl<K, V> CommitOracle<K, V> {
     /// Resets the oracle (e.g., after a full WAL truncate).
     pub fn clear(&mut self) {
         self.committed.clear();
+        self.commit_order.clear();
+    }
+
+    /// Returns the key-value state produced by applying every acknowledged
+    /// commit in acknowledgement order, later writes to a key winning.
+    ///
+    /// Only meaningful when commits were acknowledged one at a time, so that
+    /// acknowledgement order is the WAL's durable commit order.
+    #[must_use]
+    pub fn expected_state(&self) -> HashMap<K, V>
+    where
+        K: Eq + Hash + Clone,
+        V: Clone,
+    {
+        let mut state = HashMap::new();
+        for tx_id in &self.commit_order {
+            for (key, value) in &self.committed[tx_id].entries {
+                state.insert(key.clone(), value.clone());
+            }
+        }
+        state
     }
 }
 
@@ -158,6 +191,8 @@ mod tests {
             RecoveredTransaction {
                 tx_id: 1,
                 action: RecoveryAction::Commit,
+                first_lsn: 0,
+                last_lsn: 0,
                 entries: vec![WalEntry::Insert {
                     tx_id: 1,
                     timestamp: 100,
@@ -168,6 +203,8 @@ mod tests {
             RecoveredTransaction {
                 tx_id: 2,
                 action: RecoveryAction::Commit,
+                first_lsn: 0,
+                last_lsn: 0,
                 entries: vec![WalEntry::Insert {
                     tx_id: 2,
                     timestamp: 101,
@@ -192,6 +229,8 @@ mod tests {
         let recovered = vec![RecoveredTransaction {
             tx_id: 1,
             action: RecoveryAction::Commit,
+            first_lsn: 0,
+            last_lsn: 0,
             entries: vec![],
         }];
 
@@ -208,6 +247,8 @@ mod tests {
         let recovered = vec![RecoveredTransaction {
             tx_id: 99,
             action: RecoveryAction::Commit,
+            first_lsn: 0,
+            last_lsn: 0,
             entries: vec![],
         }];
 
@@ -216,6 +257,21 @@ mod tests {
         assert_eq!(result.phantom, vec![99]);
     }
 
+    #[test]
+    fn expected_state_applies_commits_in_ack_order() {
+        let mut oracle: CommitOracle<String, Vec<u8>> = CommitOracle::new();
+        oracle.record_commit(7, vec![("k".into(), b"first".to_vec())]);
+        oracle.record_commit(3, vec![("k".into(), b"second".to_vec())]);
+        oracle.record_commit(5, vec![("j".into(), b"j".to_vec())]);
+
+        let state = oracle.expected_state();
+        assert_eq!(state.len(), 2);
+        assert_eq!(state["k"], b"second".to_vec());
+
+        oracle.clear();
+        assert!(oracle.expected_state().is_empty());
+    }
+
     #[test]
     fn incomplete_txns_ignored() {
         let mut oracle: CommitOracle<String, Vec<u8>> = CommitOracle::new();
@@ -226,11 +282,15 @@ mod tests {
             RecoveredTransaction {
                 tx_id: 1,
                 action: RecoveryAction::Commit,
+                first_lsn: 0,
+                last_lsn: 0,
                 entries: vec![],
             },
             RecoveredTransaction {
                 tx_id: 2,
                 action: RecoveryAction::Incomplete,
+                first_lsn: 0,
+                last_lsn: 0,
                 entries: vec![],
             },
         ];
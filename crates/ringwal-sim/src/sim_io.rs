@@ -11,14 +11,14 @@
 
 use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
-use std::io::{self, Read, Write};
+use std::io::{self, Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use rand::rngs::SmallRng;
 use rand::{Rng, SeedableRng};
 
-use ringwal::io::{DirEntry, FileHandle, IoEngine, ReadHandle};
+use ringwal::io::{DirEntry, FileHandle, IoEngine, ReadHandle, SyncHandle};
 
 use crate::clock::SimClock;
 use crate::fault::FaultConfig;
@@ -109,6 +109,10 @@ struct SimFs {
     clock: SimClock,
     /// Incremented on each crash for diagnostics.
     crash_count: u64,
+    /// One-shot failure for the next `write()`, set by `SimIo::fail_next_write`.
+    next_write_error: Option<io::ErrorKind>,
+    /// One-shot failure for the next sync, set by `SimIo::fail_next_fsync`.
+    next_fsync_error: Option<io::ErrorKind>,
 }
 
 impl SimFs {
@@ -120,6 +124,8 @@ impl SimFs {
             fault_config,
             clock,
             crash_count: 0,
+            next_write_error: None,
+            next_fsync_error: None,
         }
     }
 
@@ -242,6 +248,18 @@ impl SimIo {
         fs.files.get(&norm).map(|f| f.durable.clone())
     }
 
+    /// Makes the next `write()` on any file fail with `kind`, regardless of
+    /// the fault rates. Deterministic, unlike `FaultConfig::write_fail_rate`.
+    pub fn fail_next_write(&self, kind: io::ErrorKind) {
+        self.inner.0.borrow_mut().next_write_error = Some(kind);
+    }
+
+    /// Makes the next `sync_all()` / `sync_data()` on any file fail with
+    /// `kind`, regardless of the fault rates. Nothing is promoted to durable.
+    pub fn fail_next_fsync(&self, kind: io::ErrorKind) {
+        self.inner.0.borrow_mut().next_fsync_error = Some(kind);
+    }
+
     /// Returns the fault config (for diagnostic printing on failure).
     #[must_use] 
     pub fn fault_config(&self) -> FaultConfig {
@@ -396,6 +414,23 @@ impl IoEngine for SimIo {
         Ok(())
     }
 
+    fn truncate_file(&self, path: &Path, len: u64) -> io::Result<()> {
+        let mut fs = self.borrow_mut();
+        let norm = SimFs::normalize(path);
+        match fs.files.get_mut(&norm) {
+            // Like set_len + fsync: the kept prefix becomes durable.
+            Some(file) => {
+                file.sync();
+                file.durable.truncate(len as usize);
+                Ok(())
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("file not found: {}", norm.display()),
+            )),
+        }
+    }
+
     fn read_file_bytes(&self, path: &Path) -> io::Result<Vec<u8>> {
         let fs = self.borrow();
         let norm = SimFs::normalize(path);
@@ -441,6 +476,9 @@ impl Write for SimFileHandle {
         let crash_probability = fs.fault_config.crash_probability;
 
         // Check for write failure
+        if let Some(kind) = fs.next_write_error.take() {
+            return Err(io::Error::new(kind, "injected write failure"));
+        }
         if fs.should_fault(write_fail_rate) {
             return Err(io::Error::other(
                 "simulated write failure",
@@ -494,6 +532,9 @@ impl FileHandle for SimFileHandle {
         let crash_probability = fs.fault_config.crash_probability;
 
         // Check for fsync failure
+        if let Some(kind) = fs.next_fsync_error.take() {
+            return Err(io::Error::new(kind, "injected fsync failure"));
+        }
         if fs.should_fault(fsync_fail_rate) {
             return Err(io::Error::other(
                 "simulated fsync failure",
@@ -520,15 +561,33 @@ impl FileHandle for SimFileHandle {
     }
 
     fn try_clone_file(&self) -> io::Result<std::fs::File> {
-        // SimIo doesn't support real fd cloning — pipelined modes are
-        // out of scope for Phase 3. Return an error that prevents
-        // accidentally using pipelined modes in DST.
+        // SimIo has no real fd to clone; off-flusher syncs go through
+        // `try_clone_sync` instead.
         Err(io::Error::new(
             io::ErrorKind::Unsupported,
-            "SimIo does not support try_clone_file (pipelined modes not supported in DST)",
+            "SimIo does not support try_clone_file (use try_clone_sync)",
         ))
     }
 
+    /// Syncs now, on the flusher, and hands the outcome to the returned
+    /// handle.
+    ///
+    /// The filesystem state is not thread-safe, so it must not be touched
+    /// from the blocking thread that runs the sync. The caller has just
+    /// flushed the batch, so syncing here makes the same bytes durable, and
+    /// fault injection (`fail_next_fsync`, `fsync_fail_rate`) surfaces
+    /// through the off-flusher sync path exactly as a real fsync error would.
+    fn try_clone_sync(&self) -> io::Result<Box<dyn SyncHandle>> {
+        let mut handle = Self {
+            io: self.io.clone(),
+            path: self.path.clone(),
+            size: self.size,
+        };
+        Ok(Box::new(SimSyncHandle {
+            result: Some(handle.sync_all()),
+        }))
+    }
+
     fn metadata_len(&self) -> io::Result<u64> {
         let fs = self.io.borrow();
         match fs.files.get(&self.path) {
@@ -538,6 +597,24 @@ impl FileHandle for SimFileHandle {
     }
 }
 
+// ── SimSyncHandle ────────────────────────────────────────────────────────────
+
+/// Off-flusher sync handle from [`SimFileHandle::try_clone_sync`], carrying
+/// the outcome of a sync already performed.
+struct SimSyncHandle {
+    result: Option<io::Result<()>>,
+}
+
+impl SyncHandle for SimSyncHandle {
+    fn sync_all(&mut self) -> io::Result<()> {
+        self.result.take().unwrap_or(Ok(()))
+    }
+
+    fn sync_data(&mut self) -> io::Result<()> {
+        self.sync_all()
+    }
+}
+
 // ── SimReadHandle ────────────────────────────────────────────────────────────
 
 /// Readable file handle backed by an in-memory snapshot.
@@ -562,6 +639,25 @@ impl Read for SimReadHandle {
     }
 }
 
+impl Seek for SimReadHandle {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => i128::from(offset),
+            SeekFrom::End(delta) => i128::from(self.len) + i128::from(delta),
+            SeekFrom::Current(delta) => self.pos as i128 + i128::from(delta),
+        };
+        if target < 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "seek before start of file",
+            ));
+        }
+        // Positions past the end clamp to it, where reads return 0 bytes.
+        self.pos = usize::try_from(target).unwrap_or(usize::MAX).min(self.data.len());
+        Ok(self.pos as u64)
+    }
+}
+
 impl ReadHandle for SimReadHandle {
     fn metadata_len(&self) -> io::Result<u64> {
         Ok(self.len)
@@ -770,4 +866,26 @@ mod tests {
         // Data should still be in write_buffer/kernel_buffer but NOT durable
         assert_eq!(sim.durable_bytes(Path::new("/wal/test.log")), Some(vec![]));
     }
+
+    #[test]
+    fn injected_fsync_failure_fires_once() {
+        let sim = SimIo::new(42, FaultConfig::none());
+        sim.create_dir_all(Path::new("/wal")).unwrap();
+
+        let path = Path::new("/wal/test.log");
+        let mut fh = sim.open_append(path, false).unwrap();
+        fh.write_all(b"data").unwrap();
+        sim.fail_next_fsync(io::ErrorKind::StorageFull);
+        let err = fh.sync_all().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::StorageFull);
+        assert_eq!(sim.durable_bytes(path), Some(vec![]));
+
+        fh.sync_all().unwrap();
+        assert_eq!(sim.durable_bytes(path), Some(b"data".to_vec()));
+
+        sim.fail_next_write(io::ErrorKind::PermissionDenied);
+        let err = fh.write(b"more").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        fh.write_all(b"more").unwrap();
+    }
 }
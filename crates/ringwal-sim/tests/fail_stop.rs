@@ -0,0 +1,195 @@
+//! Fail-stop flush handling (INV-WAL-08) under deterministic fault injection.
+//!
+//! A failed write or fsync must reach the committers of the batch as
+//! `WalError::FlushFailed`, poison the WAL until it is reopened, and never
+//! leave an acknowledged commit unrecoverable.
+
+use std::io::ErrorKind;
+
+use ringwal::{
+    FlushStage, IoEngine, RecoveryAction, SyncMode, Transaction, Wal, WalConfig, WalError,
+    WalHealth,
+};
+use ringwal_sim::{FaultConfig, RingwalSimulator, SimIo};
+
+fn config(sync_mode: SyncMode) -> WalConfig {
+    WalConfig::new("/wal")
+        .with_ring_bits(10)
+        .with_max_writers(4)
+        .with_max_segment_size(1024 * 1024)
+        .with_flush_interval(std::time::Duration::from_millis(5))
+        .with_batch_hint(64)
+        .with_sync_mode(sync_mode)
+}
+
+fn tx(tag: &str) -> Transaction<String, Vec<u8>> {
+    let mut tx = Transaction::new();
+    tx.insert(format!("{tag}-key"), tag.as_bytes().to_vec());
+    tx
+}
+
+fn committed(io: &SimIo) -> Vec<u64> {
+    let (recovered, _) = ringwal::recover::<String, Vec<u8>, SimIo>("/wal".as_ref(), io).unwrap();
+    recovered
+        .into_iter()
+        .filter(|tx| tx.action == RecoveryAction::Commit)
+        .map(|tx| tx.tx_id)
+        .collect()
+}
+
+/// Drives one WAL through a flush failure: the failing commit gets the real
+/// error, the WAL is poisoned, and a reopened WAL accepts commits again.
+///
+/// `inject` arms the fault after a first durable commit.
+async fn check_fail_stop(sync_mode: SyncMode, inject: fn(&SimIo), kind: ErrorKind) {
+    let io = SimIo::new(7, FaultConfig::none());
+    io.create_dir_all("/wal".as_ref()).unwrap();
+    let (mut wal, factory) = Wal::open::<String, Vec<u8>>(config(sync_mode), io.clone()).unwrap();
+    let writer = factory.register().unwrap();
+
+    let mut durable = Vec::new();
+    let first = tx("first");
+    durable.push(first.id);
+    first.commit(&writer).await.unwrap();
+    inject(&io);
+    let failed_from = wal.current_lsn();
+
+    let failure = match tx("failing").commit(&writer).await {
+        Err(WalError::FlushFailed(failure)) => failure,
+        other => panic!("{sync_mode:?}: expected FlushFailed, got {other:?}"),
+    };
+    assert_eq!(failure.kind, kind, "{sync_mode:?}: {failure}");
+    assert_eq!(failure.segment_id, 1);
+    assert!(failure.first_lsn >= failed_from, "{failure}");
+    assert!(failure.first_lsn <= failure.last_lsn, "{failure}");
+    assert!(failure.last_lsn < wal.current_lsn(), "{failure}");
+    assert_eq!(wal.health(), WalHealth::Poisoned(failure.clone()));
+
+    // Everything after the failure is rejected with the same failure.
+    let rejected = tx("rejected").commit(&writer).await;
+    assert!(
+        matches!(&rejected, Err(WalError::Poisoned(f)) if *f == failure),
+        "{sync_mode:?}: {rejected:?}"
+    );
+
+    wal.shutdown().await.unwrap();
+
+    // Reopened after recovery, the WAL is healthy and commits again.
+    let (mut wal, factory) = Wal::open::<String, Vec<u8>>(config(sync_mode), io.clone()).unwrap();
+    assert!(wal.health().is_healthy());
+    let writer = factory.register().unwrap();
+    let after = tx("after");
+    durable.push(after.id);
+    after.commit(&writer).await.unwrap();
+    wal.shutdown().await.unwrap();
+
+    let recovered = committed(&io);
+    for id in durable {
+        assert!(recovered.contains(&id), "{sync_mode:?}: lost commit {id}");
+    }
+}
+
+fn fail_fsync(io: &SimIo) {
+    io.fail_next_fsync(ErrorKind::StorageFull);
+}
+
+fn fail_write(io: &SimIo) {
+    io.fail_next_write(ErrorKind::PermissionDenied);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn fail_stop_full() {
+    check_fail_stop(SyncMode::Full, fail_fsync, ErrorKind::StorageFull).await;
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn fail_stop_data_only() {
+    check_fail_stop(SyncMode::DataOnly, fail_fsync, ErrorKind::StorageFull).await;
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn fail_stop_write_failure() {
+    check_fail_stop(
+        SyncMode::Full,
+        fail_write,
+        ErrorKind::PermissionDenied,
+    )
+    .await;
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn fail_stop_no_sync() {
+    check_fail_stop(
+        SyncMode::None,
+        fail_write,
+        ErrorKind::PermissionDenied,
+    )
+    .await;
+}
+
+/// The fsync error of an off-flusher sync (`SyncJob::run` on a blocking or
+/// dedicated thread, or the `Background` blocking task) fails its batch.
+#[tokio::test(flavor = "current_thread")]
+async fn fail_stop_off_flusher_syncs() {
+    for sync_mode in [
+        SyncMode::Background,
+        SyncMode::Pipelined,
+        SyncMode::PipelinedDataOnly,
+        SyncMode::PipelinedDedicated,
+    ] {
+        check_fail_stop(sync_mode, fail_fsync, ErrorKind::StorageFull).await;
+    }
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn fail_stop_reports_stage_and_rejects_appends() {
+    let io = SimIo::new(7, FaultConfig::none());
+    io.create_dir_all("/wal".as_ref()).unwrap();
+    let (mut wal, factory) =
+        Wal::open::<String, Vec<u8>>(config(SyncMode::Full), io.clone()).unwrap();
+    let writer = factory.register().unwrap();
+
+    io.fail_next_fsync(ErrorKind::Other);
+    let Err(WalError::FlushFailed(failure)) = tx("a").commit(&writer).await else {
+        panic!("fsync failure not reported");
+    };
+    assert_eq!(failure.stage, FlushStage::Sync);
+    assert!(
+        failure.message.contains("injected fsync failure"),
+        "{failure}"
+    );
+
+    // Appends are rejected up front, before reaching the ring.
+    let entry = ringwal::WalEntry::Insert {
+        tx_id: 1,
+        key: "k".to_string(),
+        value: b"v".to_vec(),
+        timestamp: 0,
+    };
+    assert!(matches!(
+        writer.append(entry).await,
+        Err(WalError::Poisoned(_))
+    ));
+    wal.shutdown().await.unwrap();
+}
+
+/// Random fsync failures: every poisoning is followed by a reopen, and no
+/// acknowledged commit is lost or invented.
+#[tokio::test(flavor = "current_thread")]
+async fn fail_stop_workload_reopens() {
+    let faults = FaultConfig::builder().fsync_fail_rate(0.1).build();
+    let mut reopens = 0;
+    for seed in 0..50 {
+        let mut sim = RingwalSimulator::new(seed, faults.clone(), config(SyncMode::Full));
+        let Ok(stats) = sim.run_workload(60).await else {
+            continue;
+        };
+        reopens += stats.reopens;
+        let Ok((recovered, _)) = sim.crash_and_recover() else {
+            continue;
+        };
+        sim.assert_no_lost_commits(&recovered);
+        sim.assert_no_phantom_commits(&recovered);
+    }
+    assert!(reopens > 0, "fsync faults never poisoned the WAL");
+}
@@ -0,0 +1,153 @@
+//! Primary → follower replication under deterministic fault injection.
+//!
+//! Primary and follower each run on their own `SimIo` and talk over an
+//! in-memory duplex stream. A follower whose fsync fails must not
+//! acknowledge, must stop until reopened, and after a crash must catch up
+//! from its last durable LSN without losing or duplicating commits.
+
+use std::io::ErrorKind;
+use std::sync::Arc;
+use std::time::Duration;
+
+use ringwal::{
+    FlushStage, Follower, IoEngine, RecoveryAction, Transaction, Wal, WalConfig, WalError,
+    WalWriter,
+};
+use ringwal_sim::{FaultConfig, SimIo};
+use tokio::task::JoinHandle;
+
+fn config() -> WalConfig {
+    WalConfig::new("/wal")
+        .with_ring_bits(10)
+        .with_max_writers(4)
+        .with_max_segment_size(1024 * 1024)
+        .with_flush_interval(Duration::from_millis(5))
+        .with_batch_hint(64)
+}
+
+fn sim_io(seed: u64) -> SimIo {
+    let io = SimIo::new(seed, FaultConfig::none());
+    io.create_dir_all("/wal".as_ref()).unwrap();
+    io
+}
+
+fn committed(io: &SimIo) -> Vec<u64> {
+    let (recovered, _) = ringwal::recover::<String, Vec<u8>, SimIo>("/wal".as_ref(), io).unwrap();
+    recovered
+        .into_iter()
+        .filter(|tx| tx.action == RecoveryAction::Commit)
+        .map(|tx| tx.tx_id)
+        .collect()
+}
+
+fn commit(writer: &Arc<WalWriter<String, Vec<u8>>>, tag: &str) -> (u64, JoinHandle<Result<(), WalError>>) {
+    let mut tx = Transaction::new();
+    tx.insert(format!("{tag}-key"), tag.as_bytes().to_vec());
+    let id = tx.id;
+    let writer = Arc::clone(writer);
+    (id, tokio::spawn(async move { tx.commit(&writer).await }))
+}
+
+type Following = JoinHandle<(Follower<SimIo>, Result<(), WalError>)>;
+
+/// Connects `follower` to the primary over a fresh in-memory stream.
+fn connect(wal: &Wal<SimIo>, mut follower: Follower<SimIo>) -> (JoinHandle<Result<(), WalError>>, Following) {
+    let (primary_end, follower_end) = tokio::io::duplex(64 * 1024);
+    let serving = tokio::spawn(wal.replication_source().serve(primary_end));
+    let following = tokio::spawn(async move {
+        let result = follower.run(follower_end).await;
+        (follower, result)
+    });
+    (serving, following)
+}
+
+async fn within<T>(task: JoinHandle<T>) -> T {
+    tokio::time::timeout(Duration::from_secs(5), task)
+        .await
+        .expect("task stalled")
+        .unwrap()
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn follower_fsync_failure_and_crash_then_catch_up() {
+    let primary_io = sim_io(1);
+    let follower_io = sim_io(2);
+    let (mut wal, factory) =
+        Wal::open::<String, Vec<u8>>(config().with_replication_acks(1), primary_io.clone())
+            .unwrap();
+    let writer = Arc::new(factory.register().unwrap());
+    let mut expected = Vec::new();
+
+    let follower = Follower::open(config(), follower_io.clone()).unwrap();
+    let (serving, following) = connect(&wal, follower);
+    let (id, first) = commit(&writer, "first");
+    expected.push(id);
+    within(first).await.unwrap();
+    let acked_lsn = wal.current_lsn() - 1;
+
+    // The follower's fsync fails: it reports the failure instead of acking,
+    // and the commit stays pending on the primary.
+    follower_io.fail_next_fsync(ErrorKind::StorageFull);
+    let (id, mut second) = commit(&writer, "second");
+    expected.push(id);
+    let (mut follower, result) = within(following).await;
+    match result {
+        Err(WalError::FlushFailed(failure)) => {
+            assert_eq!(failure.stage, FlushStage::Sync);
+            assert_eq!(failure.kind, ErrorKind::StorageFull);
+        }
+        other => panic!("expected FlushFailed, got {other:?}"),
+    }
+    assert!(matches!(within(serving).await, Err(WalError::Replication(_))));
+    assert!(
+        tokio::time::timeout(Duration::from_millis(100), &mut second)
+            .await
+            .is_err(),
+        "commit acknowledged without a durable follower copy"
+    );
+    let (_, end) = tokio::io::duplex(64);
+    assert!(matches!(follower.run(end).await, Err(WalError::Poisoned(_))));
+
+    // After a crash the follower holds what it had synced and catches up
+    // from there.
+    follower_io.crash();
+    drop(follower);
+    let follower = Follower::open(config(), follower_io.clone()).unwrap();
+    assert_eq!(follower.last_lsn(), acked_lsn);
+    let (serving, following) = connect(&wal, follower);
+    within(second).await.unwrap();
+
+    let (id, third) = commit(&writer, "third");
+    expected.push(id);
+    within(third).await.unwrap();
+    let last_lsn = wal.current_lsn() - 1;
+
+    wal.shutdown().await.unwrap();
+    within(serving).await.unwrap();
+    let (follower, result) = within(following).await;
+    result.unwrap();
+    assert_eq!(follower.last_lsn(), last_lsn);
+    drop(follower);
+
+    assert_eq!(committed(&primary_io), expected);
+    assert_eq!(committed(&follower_io), expected);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn commits_waiting_for_followers_fail_at_shutdown() {
+    let primary_io = sim_io(3);
+    let (mut wal, factory) =
+        Wal::open::<String, Vec<u8>>(config().with_replication_acks(1), primary_io.clone())
+            .unwrap();
+    let writer = Arc::new(factory.register().unwrap());
+
+    let (id, pending) = commit(&writer, "unreplicated");
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    wal.shutdown().await.unwrap();
+    assert!(matches!(
+        within(pending).await,
+        Err(WalError::NotReplicated { acked: 0, required: 1, .. })
+    ));
+    // Durable locally all the same
+    assert_eq!(committed(&primary_io), [id]);
+}
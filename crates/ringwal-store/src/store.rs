@@ -243,6 +243,8 @@ mod tests {
             RecoveredTransaction {
                 tx_id: 1,
                 action: RecoveryAction::Commit,
+                first_lsn: 0,
+                last_lsn: 0,
                 entries: vec![WalEntry::Insert {
                     tx_id: 1,
                     timestamp: 0,
@@ -253,6 +255,8 @@ mod tests {
             RecoveredTransaction {
                 tx_id: 2,
                 action: RecoveryAction::Rollback,
+                first_lsn: 0,
+                last_lsn: 0,
                 entries: vec![WalEntry::Insert {
                     tx_id: 2,
                     timestamp: 0,
@@ -263,6 +267,8 @@ mod tests {
             RecoveredTransaction {
                 tx_id: 3,
                 action: RecoveryAction::Incomplete,
+                first_lsn: 0,
+                last_lsn: 0,
                 entries: vec![WalEntry::Insert {
                     tx_id: 3,
                     timestamp: 0,
@@ -0,0 +1,722 @@
+//! Entry codecs — how a [`WalEntry`] becomes the bytes after its header.
+//!
+//! Writers encode each entry with the codec chosen in
+//! [`WalConfig::codec`](crate::WalConfig::codec); its [`Codec`] ID is
+//! recorded in the entry header, so recovery decodes every entry with the
+//! codec that wrote it, whatever the WAL is configured with today.
+//!
+//! Applications can add codecs of their own: a [`CustomCodec`] registered
+//! in [`WalConfig::custom_codecs`](crate::WalConfig::custom_codecs) under
+//! one of [`Codec::CUSTOM_IDS`], recorded as [`Codec::Custom`]. That is the
+//! only extension point; the built-in codecs are selected by [`Codec`]
+//! variant alone.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use serde::de::value::SeqDeserializer;
+use serde::de::{DeserializeOwned, Visitor};
+use serde::{ser, Serialize};
+
+use crate::entry::WalEntry;
+use crate::error::WalError;
+
+/// Encodes WAL entries to bytes and back; implemented by the built-in
+/// codecs and dispatched to through [`Codec`].
+pub(crate) trait WalCodec {
+    /// Serializes `entry`.
+    fn encode<K: Serialize, V: Serialize>(&self, entry: &WalEntry<K, V>) -> Result<Vec<u8>, WalError>;
+
+    /// Deserializes an entry written by [`encode`](Self::encode).
+    fn decode<K: DeserializeOwned, V: DeserializeOwned>(
+        &self,
+        data: &[u8],
+    ) -> Result<WalEntry<K, V>, WalError>;
+}
+
+/// The entry codecs a WAL can write, by the ID recorded in each entry header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Codec {
+    /// `bincode` 1.x with its default options — the format of every WAL
+    /// written before codecs were recorded.
+    #[default]
+    Bincode,
+    /// `postcard` — compact varint encoding, usually smaller than bincode.
+    Postcard,
+    /// JSON via `serde_json` — human-readable, for debugging.
+    Json,
+    /// Keys and values stored verbatim as the caller's bytes; they must
+    /// serialize as bytes or strings (`Vec<u8>`, `[u8; N]`, `String`, ...),
+    /// and other types fail to encode.
+    Raw,
+    /// A [`CustomCodec`] registered under this ID, one of
+    /// [`CUSTOM_IDS`](Self::CUSTOM_IDS).
+    Custom(u8),
+}
+
+impl Codec {
+    /// IDs reserved for [`Codec::Custom`]; 4–7 are kept for built-in codecs.
+    pub const CUSTOM_IDS: RangeInclusive<u8> = 8..=15;
+
+    /// Returns the ID stored in entry headers (0–15).
+    #[must_use]
+    pub fn id(self) -> u8 {
+        match self {
+            Self::Bincode => 0,
+            Self::Postcard => 1,
+            Self::Json => 2,
+            Self::Raw => 3,
+            Self::Custom(id) => id,
+        }
+    }
+
+    /// Returns the codec with ID `id`, if there is one.
+    #[must_use]
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Self::Bincode),
+            1 => Some(Self::Postcard),
+            2 => Some(Self::Json),
+            3 => Some(Self::Raw),
+            id if Self::CUSTOM_IDS.contains(&id) => Some(Self::Custom(id)),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn error(self, message: impl fmt::Display) -> WalError {
+        WalError::Codec {
+            codec: self,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl WalCodec for Codec {
+    fn encode<K: Serialize, V: Serialize>(&self, entry: &WalEntry<K, V>) -> Result<Vec<u8>, WalError> {
+        match self {
+            Self::Bincode => BincodeCodec.encode(entry),
+            Self::Postcard => PostcardCodec.encode(entry),
+            Self::Json => JsonCodec.encode(entry),
+            Self::Raw => RawCodec.encode(entry),
+            Self::Custom(_) => Err(self.error(UNREGISTERED)),
+        }
+    }
+
+    fn decode<K: DeserializeOwned, V: DeserializeOwned>(
+        &self,
+        data: &[u8],
+    ) -> Result<WalEntry<K, V>, WalError> {
+        match self {
+            Self::Bincode => BincodeCodec.decode(data),
+            Self::Postcard => PostcardCodec.decode(data),
+            Self::Json => JsonCodec.decode(data),
+            Self::Raw => RawCodec.decode(data),
+            Self::Custom(_) => Err(self.error(UNREGISTERED)),
+        }
+    }
+}
+
+const UNREGISTERED: &str = "no codec registered under this ID";
+
+/// Error a [`CustomCodec`] fails with.
+pub type CodecError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Reads an entry from the deserializer a [`CustomCodec`] hands it.
+pub type EntryVisitor<'a> =
+    dyn for<'de> FnMut(&mut dyn erased_serde::Deserializer<'de>) -> Result<(), erased_serde::Error> + 'a;
+
+/// An entry codec of the application's own.
+///
+/// This is how applications plug in an encoding of their own. It is
+/// object-safe, so it can be registered at run time: entries pass through
+/// [`erased_serde`], which any serde format can back.
+///
+/// ```ignore
+/// struct Cbor;
+///
+/// impl CustomCodec for Cbor {
+///     fn encode(&self, entry: &dyn erased_serde::Serialize) -> Result<Vec<u8>, CodecError> {
+///         Ok(serde_cbor::to_vec(entry)?)
+///     }
+///
+///     fn decode(&self, data: &[u8], visit: &mut EntryVisitor<'_>) -> Result<(), CodecError> {
+///         let mut deserializer = serde_cbor::Deserializer::from_slice(data);
+///         Ok(visit(&mut <dyn erased_serde::Deserializer>::erase(&mut deserializer))?)
+///     }
+/// }
+///
+/// let config = WalConfig::new("/tmp/wal")
+///     .with_custom_codec(8, Arc::new(Cbor))
+///     .with_codec(Codec::Custom(8));
+/// ```
+pub trait CustomCodec: Send + Sync {
+    /// Serializes `entry`.
+    fn encode(&self, entry: &dyn erased_serde::Serialize) -> Result<Vec<u8>, CodecError>;
+
+    /// Hands `visit` a deserializer over `data`, written by
+    /// [`encode`](Self::encode); it reads the entry.
+    fn decode(&self, data: &[u8], visit: &mut EntryVisitor<'_>) -> Result<(), CodecError>;
+}
+
+/// The [`CustomCodec`]s entries can be written and read with, by ID.
+#[derive(Clone, Default)]
+pub struct CustomCodecs {
+    codecs: BTreeMap<u8, Arc<dyn CustomCodec>>,
+}
+
+impl CustomCodecs {
+    /// Registers `codec` under `id`, replacing the codec registered there.
+    ///
+    /// # Panics
+    ///
+    /// If `id` is not one of [`Codec::CUSTOM_IDS`].
+    pub fn insert(&mut self, id: u8, codec: Arc<dyn CustomCodec>) {
+        assert!(Codec::CUSTOM_IDS.contains(&id), "custom codec IDs are 8..=15");
+        self.codecs.insert(id, codec);
+    }
+
+    /// Whether entries written with `codec` can be read: it is built in or
+    /// registered here.
+    pub fn contains(&self, codec: Codec) -> bool {
+        match codec {
+            Codec::Custom(id) => self.codecs.contains_key(&id),
+            _ => true,
+        }
+    }
+
+    /// Encodes `entry` with `codec`, built in or registered here.
+    pub(crate) fn encode<K, V>(&self, codec: Codec, entry: &WalEntry<K, V>) -> Result<Vec<u8>, WalError>
+    where
+        K: Serialize,
+        V: Serialize,
+    {
+        let Codec::Custom(id) = codec else {
+            return codec.encode(entry);
+        };
+        let custom = self.codecs.get(&id).ok_or_else(|| codec.error(UNREGISTERED))?;
+        custom.encode(entry).map_err(|e| codec.error(e))
+    }
+
+    /// Decodes an entry written with `codec`, built in or registered here.
+    pub(crate) fn decode<K, V>(&self, codec: Codec, data: &[u8]) -> Result<WalEntry<K, V>, WalError>
+    where
+        K: DeserializeOwned,
+        V: DeserializeOwned,
+    {
+        let Codec::Custom(id) = codec else {
+            return codec.decode(data);
+        };
+        let custom = self.codecs.get(&id).ok_or_else(|| codec.error(UNREGISTERED))?;
+        let mut entry = None;
+        custom
+            .decode(data, &mut |deserializer| {
+                entry = Some(erased_serde::deserialize(deserializer)?);
+                Ok(())
+            })
+            .map_err(|e| codec.error(e))?;
+        entry.ok_or_else(|| codec.error("no entry read"))
+    }
+}
+
+impl fmt::Debug for CustomCodecs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.codecs.keys()).finish()
+    }
+}
+
+/// `bincode` 1.x with its default options (the default codec).
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct BincodeCodec;
+
+impl WalCodec for BincodeCodec {
+    fn encode<K: Serialize, V: Serialize>(&self, entry: &WalEntry<K, V>) -> Result<Vec<u8>, WalError> {
+        bincode::serialize(entry).map_err(|e| Codec::Bincode.error(e))
+    }
+
+    fn decode<K: DeserializeOwned, V: DeserializeOwned>(
+        &self,
+        data: &[u8],
+    ) -> Result<WalEntry<K, V>, WalError> {
+        bincode::deserialize(data).map_err(|e| Codec::Bincode.error(e))
+    }
+}
+
+/// `postcard` — varint-encoded, usually smaller than bincode.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct PostcardCodec;
+
+impl WalCodec for PostcardCodec {
+    fn encode<K: Serialize, V: Serialize>(&self, entry: &WalEntry<K, V>) -> Result<Vec<u8>, WalError> {
+        postcard::to_allocvec(entry).map_err(|e| Codec::Postcard.error(e))
+    }
+
+    fn decode<K: DeserializeOwned, V: DeserializeOwned>(
+        &self,
+        data: &[u8],
+    ) -> Result<WalEntry<K, V>, WalError> {
+        postcard::from_bytes(data).map_err(|e| Codec::Postcard.error(e))
+    }
+}
+
+/// JSON via `serde_json`, so segment files can be read by eye.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct JsonCodec;
+
+impl WalCodec for JsonCodec {
+    fn encode<K: Serialize, V: Serialize>(&self, entry: &WalEntry<K, V>) -> Result<Vec<u8>, WalError> {
+        serde_json::to_vec(entry).map_err(|e| Codec::Json.error(e))
+    }
+
+    fn decode<K: DeserializeOwned, V: DeserializeOwned>(
+        &self,
+        data: &[u8],
+    ) -> Result<WalEntry<K, V>, WalError> {
+        serde_json::from_slice(data).map_err(|e| Codec::Json.error(e))
+    }
+}
+
+/// Passthrough for callers who serialize keys and values themselves.
+///
+/// Keys and values must serialize as bytes or strings (`Vec<u8>`,
+/// `[u8; N]`, `String`, ...); they are stored verbatim, with no encoding of
+/// their own. Other types fail to encode.
+///
+/// ```text
+/// [kind: u8][tx_id: u64 LE][timestamp: u64 LE] then per key/value: [len: u32 LE][bytes]
+/// ```
+/// `kind` is 0 Insert (key, value), 1 Update (key, old, new), 2 Delete
+/// (key, old), 3 Commit, 4 Abort.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct RawCodec;
+
+impl WalCodec for RawCodec {
+    fn encode<K: Serialize, V: Serialize>(&self, entry: &WalEntry<K, V>) -> Result<Vec<u8>, WalError> {
+        let (kind, fields): (u8, Vec<&dyn RawField>) = match entry {
+            WalEntry::Insert { key, value, .. } => (0, vec![key, value]),
+            WalEntry::Update { key, old_value, new_value, .. } => (1, vec![key, old_value, new_value]),
+            WalEntry::Delete { key, old_value, .. } => (2, vec![key, old_value]),
+            WalEntry::Commit { .. } => (3, Vec::new()),
+            WalEntry::Abort { .. } => (4, Vec::new()),
+        };
+        let mut out = Vec::with_capacity(17);
+        out.push(kind);
+        out.extend_from_slice(&entry.tx_id().to_le_bytes());
+        out.extend_from_slice(&entry.timestamp().to_le_bytes());
+        for field in fields {
+            let bytes = field.raw_bytes().map_err(|e| Codec::Raw.error(e))?;
+            let len = u32::try_from(bytes.len()).map_err(|_| Codec::Raw.error("field over 4 GiB"))?;
+            out.extend_from_slice(&len.to_le_bytes());
+            out.extend_from_slice(&bytes);
+        }
+        Ok(out)
+    }
+
+    fn decode<K: DeserializeOwned, V: DeserializeOwned>(
+        &self,
+        data: &[u8],
+    ) -> Result<WalEntry<K, V>, WalError> {
+        let mut reader = RawReader { data };
+        let kind = reader.take(1)?[0];
+        let tx_id = u64::from_le_bytes(reader.take(8)?.try_into().unwrap());
+        let timestamp = u64::from_le_bytes(reader.take(8)?.try_into().unwrap());
+        let entry = match kind {
+            0 => WalEntry::Insert {
+                tx_id,
+                timestamp,
+                key: reader.field()?,
+                value: reader.field()?,
+            },
+            1 => WalEntry::Update {
+                tx_id,
+                timestamp,
+                key: reader.field()?,
+                old_value: reader.field()?,
+                new_value: reader.field()?,
+            },
+            2 => WalEntry::Delete {
+                tx_id,
+                timestamp,
+                key: reader.field()?,
+                old_value: reader.field()?,
+            },
+            3 => WalEntry::Commit { tx_id, timestamp },
+            4 => WalEntry::Abort { tx_id, timestamp },
+            kind => return Err(Codec::Raw.error(format!("unknown entry kind {kind}"))),
+        };
+        if !reader.data.is_empty() {
+            return Err(Codec::Raw.error("trailing bytes"));
+        }
+        Ok(entry)
+    }
+}
+
+/// Cursor over a [`RawCodec`] entry.
+struct RawReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> RawReader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], WalError> {
+        if self.data.len() < n {
+            return Err(Codec::Raw.error("truncated entry"));
+        }
+        let (head, tail) = self.data.split_at(n);
+        self.data = tail;
+        Ok(head)
+    }
+
+    fn field<T: DeserializeOwned>(&mut self) -> Result<T, WalError> {
+        let len = u32::from_le_bytes(self.take(4)?.try_into().unwrap()) as usize;
+        let bytes = self.take(len)?.to_vec();
+        T::deserialize(RawBytes(bytes)).map_err(|e| Codec::Raw.error(e))
+    }
+}
+
+/// Error raised while converting a key or value to or from raw bytes.
+#[derive(Debug)]
+struct RawError(String);
+
+impl fmt::Display for RawError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for RawError {}
+
+impl ser::Error for RawError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self(msg.to_string())
+    }
+}
+
+impl serde::de::Error for RawError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self(msg.to_string())
+    }
+}
+
+/// A key or value whose raw bytes can be taken; object-safe, so the fields
+/// of an entry fit in one list.
+trait RawField {
+    fn raw_bytes(&self) -> Result<Vec<u8>, RawError>;
+}
+
+impl<T: Serialize> RawField for T {
+    fn raw_bytes(&self) -> Result<Vec<u8>, RawError> {
+        let mut sink = ByteSink(Vec::new());
+        self.serialize(&mut sink)?;
+        Ok(sink.0)
+    }
+}
+
+/// Collects a value that serializes as bytes, a string or a sequence of
+/// `u8`; rejects anything else.
+struct ByteSink(Vec<u8>);
+
+fn not_bytes<T>() -> Result<T, RawError> {
+    Err(RawError(
+        "raw codec keys and values must serialize as bytes or strings".into(),
+    ))
+}
+
+macro_rules! reject {
+    ($($method:ident($($arg:ty),*) -> $ret:ty;)*) => {
+        $(fn $method(self, $(_: $arg),*) -> Result<$ret, RawError> {
+            not_bytes()
+        })*
+    };
+}
+
+impl ser::Serializer for &mut ByteSink {
+    type Ok = ();
+    type Error = RawError;
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = ser::Impossible<(), RawError>;
+    type SerializeTupleVariant = ser::Impossible<(), RawError>;
+    type SerializeMap = ser::Impossible<(), RawError>;
+    type SerializeStruct = ser::Impossible<(), RawError>;
+    type SerializeStructVariant = ser::Impossible<(), RawError>;
+
+    fn serialize_u8(self, v: u8) -> Result<(), RawError> {
+        self.0.push(v);
+        Ok(())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), RawError> {
+        self.0.extend_from_slice(v.as_bytes());
+        Ok(())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), RawError> {
+        self.0.extend_from_slice(v);
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), RawError> {
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self, RawError> {
+        Ok(self)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self, RawError> {
+        Ok(self)
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, _value: &T) -> Result<(), RawError> {
+        not_bytes()
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<(), RawError> {
+        not_bytes()
+    }
+
+    reject! {
+        serialize_bool(bool) -> ();
+        serialize_i8(i8) -> ();
+        serialize_i16(i16) -> ();
+        serialize_i32(i32) -> ();
+        serialize_i64(i64) -> ();
+        serialize_u16(u16) -> ();
+        serialize_u32(u32) -> ();
+        serialize_u64(u64) -> ();
+        serialize_f32(f32) -> ();
+        serialize_f64(f64) -> ();
+        serialize_char(char) -> ();
+        serialize_none() -> ();
+        serialize_unit() -> ();
+        serialize_unit_struct(&'static str) -> ();
+        serialize_unit_variant(&'static str, u32, &'static str) -> ();
+        serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct;
+        serialize_tuple_variant(&'static str, u32, &'static str, usize) -> Self::SerializeTupleVariant;
+        serialize_map(Option<usize>) -> Self::SerializeMap;
+        serialize_struct(&'static str, usize) -> Self::SerializeStruct;
+        serialize_struct_variant(&'static str, u32, &'static str, usize) -> Self::SerializeStructVariant;
+    }
+}
+
+impl ser::SerializeSeq for &mut ByteSink {
+    type Ok = ();
+    type Error = RawError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), RawError> {
+        value.serialize(ByteElement(self))
+    }
+
+    fn end(self) -> Result<(), RawError> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTuple for &mut ByteSink {
+    type Ok = ();
+    type Error = RawError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), RawError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), RawError> {
+        Ok(())
+    }
+}
+
+/// One element of a byte sequence: only a `u8` is accepted.
+struct ByteElement<'a>(&'a mut ByteSink);
+
+impl ser::Serializer for ByteElement<'_> {
+    type Ok = ();
+    type Error = RawError;
+    type SerializeSeq = ser::Impossible<(), RawError>;
+    type SerializeTuple = ser::Impossible<(), RawError>;
+    type SerializeTupleStruct = ser::Impossible<(), RawError>;
+    type SerializeTupleVariant = ser::Impossible<(), RawError>;
+    type SerializeMap = ser::Impossible<(), RawError>;
+    type SerializeStruct = ser::Impossible<(), RawError>;
+    type SerializeStructVariant = ser::Impossible<(), RawError>;
+
+    fn serialize_u8(self, v: u8) -> Result<(), RawError> {
+        self.0 .0.push(v);
+        Ok(())
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, _value: &T) -> Result<(), RawError> {
+        not_bytes()
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _value: &T,
+    ) -> Result<(), RawError> {
+        not_bytes()
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<(), RawError> {
+        not_bytes()
+    }
+
+    reject! {
+        serialize_bool(bool) -> ();
+        serialize_i8(i8) -> ();
+        serialize_i16(i16) -> ();
+        serialize_i32(i32) -> ();
+        serialize_i64(i64) -> ();
+        serialize_u16(u16) -> ();
+        serialize_u32(u32) -> ();
+        serialize_u64(u64) -> ();
+        serialize_f32(f32) -> ();
+        serialize_f64(f64) -> ();
+        serialize_char(char) -> ();
+        serialize_str(&str) -> ();
+        serialize_bytes(&[u8]) -> ();
+        serialize_none() -> ();
+        serialize_unit() -> ();
+        serialize_unit_struct(&'static str) -> ();
+        serialize_unit_variant(&'static str, u32, &'static str) -> ();
+        serialize_seq(Option<usize>) -> Self::SerializeSeq;
+        serialize_tuple(usize) -> Self::SerializeTuple;
+        serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct;
+        serialize_tuple_variant(&'static str, u32, &'static str, usize) -> Self::SerializeTupleVariant;
+        serialize_map(Option<usize>) -> Self::SerializeMap;
+        serialize_struct(&'static str, usize) -> Self::SerializeStruct;
+        serialize_struct_variant(&'static str, u32, &'static str, usize) -> Self::SerializeStructVariant;
+    }
+}
+
+/// Hands stored raw bytes to a key or value type as bytes, a string or a
+/// sequence of `u8`, whichever it asks for.
+struct RawBytes(Vec<u8>);
+
+impl<'de> serde::Deserializer<'de> for RawBytes {
+    type Error = RawError;
+
+    fn deserialize_any<W: Visitor<'de>>(self, visitor: W) -> Result<W::Value, RawError> {
+        visitor.visit_byte_buf(self.0)
+    }
+
+    fn deserialize_str<W: Visitor<'de>>(self, visitor: W) -> Result<W::Value, RawError> {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_string<W: Visitor<'de>>(self, visitor: W) -> Result<W::Value, RawError> {
+        let s = String::from_utf8(self.0).map_err(|e| RawError(e.to_string()))?;
+        visitor.visit_string(s)
+    }
+
+    fn deserialize_seq<W: Visitor<'de>>(self, visitor: W) -> Result<W::Value, RawError> {
+        visitor.visit_seq(SeqDeserializer::new(self.0.into_iter()))
+    }
+
+    fn deserialize_tuple<W: Visitor<'de>>(self, _len: usize, visitor: W) -> Result<W::Value, RawError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_newtype_struct<W: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: W,
+    ) -> Result<W::Value, RawError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char bytes byte_buf
+        option unit unit_struct tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entry::ByteWalEntry;
+
+    fn entries() -> Vec<ByteWalEntry> {
+        vec![
+            WalEntry::Insert {
+                tx_id: 1,
+                timestamp: 10,
+                key: "k".into(),
+                value: vec![0, 1, 255],
+            },
+            WalEntry::Update {
+                tx_id: 2,
+                timestamp: 11,
+                key: "k".into(),
+                old_value: vec![1],
+                new_value: Vec::new(),
+            },
+            WalEntry::Delete {
+                tx_id: 3,
+                timestamp: 12,
+                key: String::new(),
+                old_value: vec![7; 300],
+            },
+            WalEntry::Commit { tx_id: 4, timestamp: 13 },
+            WalEntry::Abort { tx_id: 5, timestamp: 14 },
+        ]
+    }
+
+    #[test]
+    fn every_codec_round_trips_and_has_a_distinct_id() {
+        let codecs = [Codec::Bincode, Codec::Postcard, Codec::Json, Codec::Raw];
+        for codec in codecs {
+            assert_eq!(Codec::from_id(codec.id()), Some(codec));
+            for entry in entries() {
+                let data = codec.encode(&entry).unwrap();
+                assert_eq!(codec.decode::<String, Vec<u8>>(&data).unwrap(), entry, "{codec:?}");
+            }
+        }
+        assert_eq!(Codec::from_id(4), None);
+        assert_eq!(Codec::from_id(8), Some(Codec::Custom(8)));
+        assert!(matches!(
+            Codec::Custom(8).encode(&entries()[0]),
+            Err(WalError::Codec { codec: Codec::Custom(8), .. })
+        ));
+    }
+
+    #[test]
+    fn raw_codec_stores_bytes_verbatim_and_rejects_other_types() {
+        let entry: ByteWalEntry = WalEntry::Insert {
+            tx_id: 1,
+            timestamp: 2,
+            key: "ab".into(),
+            value: vec![9, 8],
+        };
+        let data = RawCodec.encode(&entry).unwrap();
+        assert_eq!(&data[17..], [2, 0, 0, 0, b'a', b'b', 2, 0, 0, 0, 9, 8]);
+
+        let numeric: WalEntry<u64, Vec<u8>> = WalEntry::Insert {
+            tx_id: 1,
+            timestamp: 2,
+            key: 7,
+            value: Vec::new(),
+        };
+        assert!(matches!(
+            RawCodec.encode(&numeric),
+            Err(WalError::Codec { codec: Codec::Raw, .. })
+        ));
+        assert!(RawCodec.decode::<String, Vec<u8>>(&data[..20]).is_err());
+    }
+}
@@ -0,0 +1,296 @@
+//! Per-batch compression of segment data.
+//!
+//! With [`WalConfig::compression`](crate::WalConfig::compression) set, the
+//! segment manager writes each group-commit batch as one compressed frame:
+//! a [`CompressedFrameHeader`] followed by the batch's entries — their
+//! version-2 headers and data, exactly as they would be written
+//! uncompressed — compressed as a single block. Readers decompress frames
+//! as they meet them, so uncompressed entries and frames mix freely in a
+//! segment and compression can be changed between opens.
+
+use std::io::Read;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::error::WalError;
+
+/// Compression applied to each batch the flusher writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Compression {
+    /// Entries are written as they are (default).
+    #[default]
+    None,
+    /// LZ4 block compression — fast, moderate ratio.
+    Lz4,
+    /// Zstandard at the given level (1–22; 3 is zstd's default). The level
+    /// only affects writing and is not recorded.
+    Zstd { level: i32 },
+}
+
+impl Compression {
+    /// Zstandard level used for frames read back with [`from_id`](Self::from_id).
+    pub const ZSTD_DEFAULT_LEVEL: i32 = 3;
+
+    /// Returns the ID stored in frame headers (0–15).
+    #[must_use]
+    pub fn id(self) -> u8 {
+        match self {
+            Self::None => 0,
+            Self::Lz4 => 1,
+            Self::Zstd { .. } => 2,
+        }
+    }
+
+    /// Returns the compression with ID `id`, if there is one.
+    #[must_use]
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Self::None),
+            1 => Some(Self::Lz4),
+            2 => Some(Self::Zstd {
+                level: Self::ZSTD_DEFAULT_LEVEL,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Compresses a batch of encoded entries.
+    pub(crate) fn compress(self, data: &[u8]) -> Result<Vec<u8>, WalError> {
+        match self {
+            Self::None => Ok(data.to_vec()),
+            Self::Lz4 => Ok(lz4_flex::block::compress(data)),
+            Self::Zstd { level } => Ok(zstd::bulk::compress(data, level)?),
+        }
+    }
+
+    /// Decompresses a frame payload of `uncompressed_length` bytes.
+    pub(crate) fn decompress(self, data: &[u8], uncompressed_length: usize) -> Result<Vec<u8>, WalError> {
+        let decompressed = match self {
+            Self::None => Ok(data.to_vec()),
+            Self::Lz4 => lz4_flex::block::decompress(data, uncompressed_length)
+                .map_err(|e| WalError::InvalidSegment(format!("corrupt LZ4 frame: {e}"))),
+            Self::Zstd { .. } => zstd::bulk::decompress(data, uncompressed_length)
+                .map_err(|e| WalError::InvalidSegment(format!("corrupt zstd frame: {e}"))),
+        }?;
+        if decompressed.len() != uncompressed_length {
+            return Err(WalError::InvalidSegment(format!(
+                "frame decompressed to {} bytes, header says {uncompressed_length}",
+                decompressed.len()
+            )));
+        }
+        Ok(decompressed)
+    }
+}
+
+/// On-disk header of a compressed batch frame (21 bytes):
+/// ```text
+/// [length: u64 LE][checksum: u32 LE][compression << 4 | version: u8 = 3][uncompressed_length: u64 LE]
+/// ```
+/// It shares its first 13 bytes with [`WalEntryHeader`](crate::WalEntryHeader),
+/// so a reader tells frames from entries by the low nibble of byte 12.
+/// `length` is the size of the compressed payload; the checksum covers
+/// byte 12, `uncompressed_length` and the payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompressedFrameHeader {
+    /// Length of the compressed payload (not including header).
+    pub length: u64,
+    /// CRC32 of the compression ID, uncompressed length and payload.
+    pub checksum: u32,
+    pub compression: Compression,
+    /// Length of the entries once decompressed.
+    pub uncompressed_length: u64,
+}
+
+impl CompressedFrameHeader {
+    /// Format version in the low nibble of byte 12, after entry versions 1
+    /// and 2.
+    pub const VERSION: u8 = 3;
+
+    /// Size of the header in bytes (8 + 4 + 1 + 8 = 21).
+    pub const SIZE: usize = 21;
+
+    /// Size of the prefix shared with entry headers.
+    pub const PREFIX_SIZE: usize = 13;
+
+    /// Creates the header for a payload compressed from
+    /// `uncompressed_length` bytes.
+    #[must_use]
+    pub fn new(compression: Compression, uncompressed_length: u64, payload: &[u8]) -> Self {
+        Self {
+            length: payload.len() as u64,
+            checksum: frame_checksum(compression, uncompressed_length, payload),
+            compression,
+            uncompressed_length,
+        }
+    }
+
+    /// Returns `true` if byte 12 of a header (`version_byte`) marks a
+    /// compressed frame rather than an entry.
+    #[must_use]
+    pub fn is_frame(version_byte: u8) -> bool {
+        version_byte & 0x0f == Self::VERSION
+    }
+
+    /// Serializes the header to bytes.
+    #[must_use]
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0u8; Self::SIZE];
+        bytes[0..8].copy_from_slice(&self.length.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.checksum.to_le_bytes());
+        bytes[12] = self.compression.id() << 4 | Self::VERSION;
+        bytes[13..21].copy_from_slice(&self.uncompressed_length.to_le_bytes());
+        bytes
+    }
+
+    /// Reads the rest of a frame header whose shared prefix has been read.
+    pub fn read_with_prefix(
+        prefix: &[u8; Self::PREFIX_SIZE],
+        reader: &mut impl Read,
+    ) -> Result<Self, WalError> {
+        let compression = Compression::from_id(prefix[12] >> 4)
+            .filter(|_| Self::is_frame(prefix[12]))
+            .ok_or_else(|| {
+                WalError::InvalidSegment(format!("unknown frame header version {}", prefix[12]))
+            })?;
+        let mut extension = [0u8; Self::SIZE - Self::PREFIX_SIZE];
+        reader.read_exact(&mut extension)?;
+        Ok(Self {
+            length: u64::from_le_bytes(prefix[0..8].try_into().unwrap()),
+            checksum: u32::from_le_bytes(prefix[8..12].try_into().unwrap()),
+            compression,
+            uncompressed_length: u64::from_le_bytes(extension),
+        })
+    }
+
+    /// Validates that the payload matches this header's checksum.
+    pub fn validate(&self, payload: &[u8]) -> Result<(), WalError> {
+        let actual = frame_checksum(self.compression, self.uncompressed_length, payload);
+        if actual != self.checksum {
+            return Err(WalError::ChecksumMismatch {
+                expected: self.checksum,
+                actual,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// CRC32 of a frame: version byte, uncompressed length, then payload.
+fn frame_checksum(compression: Compression, uncompressed_length: u64, payload: &[u8]) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&[compression.id() << 4 | CompressedFrameHeader::VERSION]);
+    hasher.update(&uncompressed_length.to_le_bytes());
+    hasher.update(payload);
+    hasher.finalize()
+}
+
+/// Running totals of the batches the flusher compressed, shared between
+/// the segment manager and [`Wal::compression_stats`](crate::Wal::compression_stats).
+///
+/// Counters use `Ordering::Relaxed`: they are statistics only.
+#[derive(Debug, Default)]
+pub(crate) struct CompressionMetrics {
+    batches: AtomicU64,
+    uncompressed_bytes: AtomicU64,
+    written_bytes: AtomicU64,
+    compress_nanos: AtomicU64,
+}
+
+impl CompressionMetrics {
+    /// Records a batch of `uncompressed` bytes that took `elapsed` to
+    /// compress and `written` bytes on disk.
+    pub(crate) fn record(&self, uncompressed: usize, written: usize, elapsed: Duration) {
+        self.batches.fetch_add(1, Ordering::Relaxed);
+        self.uncompressed_bytes
+            .fetch_add(uncompressed as u64, Ordering::Relaxed);
+        self.written_bytes.fetch_add(written as u64, Ordering::Relaxed);
+        let nanos = u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX);
+        self.compress_nanos.fetch_add(nanos, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> CompressionStats {
+        CompressionStats {
+            batches: self.batches.load(Ordering::Relaxed),
+            uncompressed_bytes: self.uncompressed_bytes.load(Ordering::Relaxed),
+            written_bytes: self.written_bytes.load(Ordering::Relaxed),
+            compress_time: Duration::from_nanos(self.compress_nanos.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// Compression totals since the WAL was opened, from
+/// [`Wal::compression_stats`](crate::Wal::compression_stats).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CompressionStats {
+    /// Batches compressed.
+    pub batches: u64,
+    /// Size of those batches' entries, headers included, before compression.
+    pub uncompressed_bytes: u64,
+    /// Bytes written for them: frames, or the plain entries of batches
+    /// that did not shrink.
+    pub written_bytes: u64,
+    /// CPU time spent compressing.
+    pub compress_time: Duration,
+}
+
+impl CompressionStats {
+    /// Uncompressed over written bytes (1.0 before any batch).
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn ratio(&self) -> f64 {
+        if self.written_bytes == 0 {
+            1.0
+        } else {
+            self.uncompressed_bytes as f64 / self.written_bytes as f64
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_round_trip_with_each_compression() {
+        let data = b"{\"name\":\"ringwal\",\"tags\":[\"wal\",\"wal\",\"wal\"]}".repeat(20);
+        for compression in [Compression::Lz4, Compression::Zstd { level: 3 }] {
+            let payload = compression.compress(&data).unwrap();
+            assert!(payload.len() < data.len() / 4, "{compression:?}");
+            let header = CompressedFrameHeader::new(compression, data.len() as u64, &payload);
+            let bytes = header.to_bytes();
+            assert!(CompressedFrameHeader::is_frame(bytes[12]));
+
+            let prefix = bytes[..CompressedFrameHeader::PREFIX_SIZE].try_into().unwrap();
+            let mut rest = &bytes[CompressedFrameHeader::PREFIX_SIZE..];
+            let restored = CompressedFrameHeader::read_with_prefix(prefix, &mut rest).unwrap();
+            assert_eq!(restored.compression.id(), compression.id());
+            restored.validate(&payload).unwrap();
+            let length = usize::try_from(restored.uncompressed_length).unwrap();
+            assert_eq!(restored.compression.decompress(&payload, length).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn corrupt_frames_are_rejected() {
+        let data = vec![7u8; 1000];
+        let payload = Compression::Lz4.compress(&data).unwrap();
+        let header = CompressedFrameHeader::new(Compression::Lz4, 1000, &payload);
+        let mut corrupt = payload.clone();
+        corrupt[0] ^= 0xff;
+        assert!(matches!(
+            header.validate(&corrupt),
+            Err(WalError::ChecksumMismatch { .. })
+        ));
+        assert!(Compression::Lz4.decompress(&payload, 999).is_err());
+
+        // An entry header is not a frame, nor is an unknown compression
+        assert!(!CompressedFrameHeader::is_frame(2));
+        let mut bytes = header.to_bytes();
+        bytes[12] = 0xf0 | CompressedFrameHeader::VERSION;
+        let prefix = bytes[..CompressedFrameHeader::PREFIX_SIZE].try_into().unwrap();
+        assert!(matches!(
+            CompressedFrameHeader::read_with_prefix(prefix, &mut &bytes[13..]),
+            Err(WalError::InvalidSegment(_))
+        ));
+    }
+}
@@ -1,8 +1,12 @@
 //! WAL configuration.
 
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Duration;
 
+use crate::codec::{Codec, CustomCodec, CustomCodecs};
+use crate::compression::Compression;
+
 /// Controls when and how the flusher syncs data to disk after writing a batch.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SyncMode {
@@ -55,6 +59,8 @@ pub struct WalConfig {
     /// Default: 64 MB.
     pub max_segment_size: u64,
     /// Background flusher poll interval.
+    /// Unused: the flusher is woken by commits (ring waker slots), not by a
+    /// poll timer. Retained so existing configurations keep compiling.
     /// Default: 10ms.
     pub flush_interval: Duration,
     /// Hint for batch drain size per flush cycle.
@@ -78,6 +84,33 @@ pub struct WalConfig {
     ///
     /// Default: `false`.
     pub direct_io: bool,
+    /// Bytes of recently written entries kept in memory for
+    /// [`Wal::subscribe`](crate::Wal::subscribe) subscribers. A subscriber
+    /// that falls further behind reads from the segment files instead.
+    /// Default: 8 MB.
+    pub subscriber_buffer_bytes: usize,
+    /// Number of followers that must acknowledge a batch (see
+    /// [`ReplicationSource`](crate::ReplicationSource)) before its commits
+    /// return. 0 waits for local durability only.
+    /// Default: 0.
+    pub replication_acks: usize,
+    /// How long a commit waits for its [`replication_acks`](Self::replication_acks)
+    /// before failing with [`WalError::NotReplicated`](crate::WalError::NotReplicated);
+    /// it stays durable locally.
+    /// Default: `None` (waits until the WAL shuts down).
+    pub replication_timeout: Option<Duration>,
+    /// Codec writers encode entries with. Recorded per entry, so it can be
+    /// changed between opens.
+    /// Default: `Codec::Bincode`.
+    pub codec: Codec,
+    /// Application codecs by ID: the one [`codec`](Self::codec) names, and
+    /// any that wrote entries still on disk.
+    /// Default: none.
+    pub custom_codecs: CustomCodecs,
+    /// Compression of each batch the flusher writes. Recorded per frame, so
+    /// it can be changed between opens.
+    /// Default: `Compression::None`.
+    pub compression: Compression,
 }
 
 impl WalConfig {
@@ -93,6 +126,12 @@ impl WalConfig {
             enable_metrics: false,
             sync_mode: SyncMode::Full,
             direct_io: false,
+            subscriber_buffer_bytes: 8 * 1024 * 1024,
+            replication_acks: 0,
+            replication_timeout: None,
+            codec: Codec::Bincode,
+            custom_codecs: CustomCodecs::default(),
+            compression: Compression::None,
         }
     }
 
@@ -147,6 +186,48 @@ impl WalConfig {
         self
     }
 
+    #[must_use] 
+    pub fn with_subscriber_buffer_bytes(mut self, bytes: usize) -> Self {
+        self.subscriber_buffer_bytes = bytes;
+        self
+    }
+
+    #[must_use] 
+    pub fn with_replication_acks(mut self, followers: usize) -> Self {
+        self.replication_acks = followers;
+        self
+    }
+
+    #[must_use] 
+    pub fn with_replication_timeout(mut self, timeout: Duration) -> Self {
+        self.replication_timeout = Some(timeout);
+        self
+    }
+
+    #[must_use] 
+    pub fn with_codec(mut self, codec: Codec) -> Self {
+        self.codec = codec;
+        self
+    }
+
+    /// Registers `codec` under `id`, one of [`Codec::CUSTOM_IDS`]. Select it
+    /// with `with_codec(Codec::Custom(id))`.
+    ///
+    /// # Panics
+    ///
+    /// If `id` is outside [`Codec::CUSTOM_IDS`].
+    #[must_use] 
+    pub fn with_custom_codec(mut self, id: u8, codec: Arc<dyn CustomCodec>) -> Self {
+        self.custom_codecs.insert(id, codec);
+        self
+    }
+
+    #[must_use] 
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
     /// Returns the ring capacity per writer.
     #[must_use] 
     pub fn ring_capacity(&self) -> usize {
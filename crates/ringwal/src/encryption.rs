@@ -0,0 +1,457 @@
+//! Encryption at rest.
+//!
+//! Wrapping the I/O engine in an [`EncryptedIo`](crate::EncryptedIo) makes
+//! the WAL seal what it writes with an AEAD cipher: the segment manager
+//! writes each batch as one [`EncryptedFrameHeader`] frame — its entries,
+//! or their compressed frame, encrypted as a whole — and the checkpoint
+//! file is sealed too. Segment headers and footers stay in the clear; they
+//! hold IDs, LSN bounds and checksums, not entry data.
+//!
+//! A frame is encrypted with a key derived from the provider's key and the
+//! WAL ID, under a nonce made of its offset and segment ID, so no two
+//! frames share a key and nonce. Its header is authenticated as associated
+//! data. The key ID recorded in each frame selects the key to read it
+//! with, so segments written before a key rotation stay readable while the
+//! [`KeyProvider`] still holds their key.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Read;
+use std::sync::{Arc, RwLock};
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::Aes256Gcm;
+use chacha20poly1305::ChaCha20Poly1305;
+use sha2::{Digest, Sha256};
+
+use crate::error::WalError;
+
+/// A 256-bit key.
+pub type EncryptionKey = [u8; 32];
+
+/// Supplies the keys a WAL is encrypted with.
+///
+/// New data is written with the [`current_key`](Self::current_key); data
+/// written earlier is read with the key its ID names, so a provider must
+/// keep retired keys for as long as segments or checkpoints written with
+/// them exist.
+pub trait KeyProvider: Send + Sync + 'static {
+    /// Returns the ID and key to encrypt new data with.
+    fn current_key(&self) -> (u32, EncryptionKey);
+
+    /// Returns the key with ID `key_id`, if the provider has it.
+    fn key(&self, key_id: u32) -> Option<EncryptionKey>;
+}
+
+/// In-memory [`KeyProvider`]: a set of keys by ID, one of them current.
+pub struct KeyRing {
+    state: RwLock<KeyRingState>,
+}
+
+struct KeyRingState {
+    keys: HashMap<u32, EncryptionKey>,
+    current: u32,
+}
+
+impl KeyRing {
+    /// Creates a key ring whose current key is `key`.
+    #[must_use]
+    pub fn new(key_id: u32, key: EncryptionKey) -> Self {
+        Self {
+            state: RwLock::new(KeyRingState {
+                keys: HashMap::from([(key_id, key)]),
+                current: key_id,
+            }),
+        }
+    }
+
+    /// Adds a key for reading only (e.g. one retired before a restart).
+    pub fn insert(&self, key_id: u32, key: EncryptionKey) {
+        self.state.write().unwrap().keys.insert(key_id, key);
+    }
+
+    /// Adds `key` and makes it the current key. Data written with earlier
+    /// keys stays readable.
+    pub fn rotate(&self, key_id: u32, key: EncryptionKey) {
+        let mut state = self.state.write().unwrap();
+        state.keys.insert(key_id, key);
+        state.current = key_id;
+    }
+
+    /// Returns the ID of the current key.
+    #[must_use]
+    pub fn current_key_id(&self) -> u32 {
+        self.state.read().unwrap().current
+    }
+}
+
+impl KeyProvider for KeyRing {
+    fn current_key(&self) -> (u32, EncryptionKey) {
+        let state = self.state.read().unwrap();
+        (state.current, state.keys[&state.current])
+    }
+
+    fn key(&self, key_id: u32) -> Option<EncryptionKey> {
+        self.state.read().unwrap().keys.get(&key_id).copied()
+    }
+}
+
+/// AEAD ciphers, by the ID recorded with encrypted data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cipher {
+    /// AES-256 in Galois/Counter Mode — fastest with AES-NI.
+    Aes256Gcm,
+    /// ChaCha20-Poly1305 — fast without AES hardware.
+    ChaCha20Poly1305,
+}
+
+impl Cipher {
+    /// Returns the ID stored with encrypted data (1–15).
+    #[must_use]
+    pub fn id(self) -> u8 {
+        match self {
+            Self::Aes256Gcm => 1,
+            Self::ChaCha20Poly1305 => 2,
+        }
+    }
+
+    /// Returns the cipher with ID `id`, if there is one.
+    #[must_use]
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            1 => Some(Self::Aes256Gcm),
+            2 => Some(Self::ChaCha20Poly1305),
+            _ => None,
+        }
+    }
+
+    fn seal(
+        self,
+        key: &EncryptionKey,
+        nonce: &[u8; NONCE_SIZE],
+        aad: &[u8],
+        msg: &[u8],
+    ) -> Result<Vec<u8>, WalError> {
+        let payload = Payload { msg, aad };
+        match self {
+            Self::Aes256Gcm => Aes256Gcm::new(key.into()).encrypt(nonce.into(), payload),
+            Self::ChaCha20Poly1305 => ChaCha20Poly1305::new(key.into()).encrypt(nonce.into(), payload),
+        }
+        .map_err(|_| WalError::Encryption(format!("{self:?} encryption failed")))
+    }
+
+    /// Decrypts and authenticates; `None` if authentication fails.
+    fn open(
+        self,
+        key: &EncryptionKey,
+        nonce: &[u8; NONCE_SIZE],
+        aad: &[u8],
+        msg: &[u8],
+    ) -> Option<Vec<u8>> {
+        let payload = Payload { msg, aad };
+        match self {
+            Self::Aes256Gcm => Aes256Gcm::new(key.into()).decrypt(nonce.into(), payload),
+            Self::ChaCha20Poly1305 => ChaCha20Poly1305::new(key.into()).decrypt(nonce.into(), payload),
+        }
+        .ok()
+    }
+}
+
+const NONCE_SIZE: usize = 12;
+const TAG_SIZE: usize = 16;
+
+/// Encryption settings carried by an [`EncryptedIo`](crate::EncryptedIo):
+/// the cipher for new data and where keys come from.
+#[derive(Clone)]
+pub struct Encryption {
+    cipher: Cipher,
+    keys: Arc<dyn KeyProvider>,
+}
+
+impl fmt::Debug for Encryption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Encryption")
+            .field("cipher", &self.cipher)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Encryption {
+    /// Encrypts new data with `cipher` and the provider's current key.
+    pub fn new(cipher: Cipher, keys: Arc<dyn KeyProvider>) -> Self {
+        Self { cipher, keys }
+    }
+
+    /// Returns the cipher new data is encrypted with.
+    #[must_use]
+    pub fn cipher(&self) -> Cipher {
+        self.cipher
+    }
+
+    fn key(&self, key_id: u32) -> Result<EncryptionKey, WalError> {
+        self.keys
+            .key(key_id)
+            .ok_or_else(|| WalError::Encryption(format!("no key with ID {key_id}")))
+    }
+
+    /// Encrypts the records of a frame at `offset` of segment `segment_id`
+    /// of WAL `wal_id`, returning its header and ciphertext.
+    pub(crate) fn seal_frame(
+        &self,
+        wal_id: u128,
+        segment_id: u64,
+        offset: u64,
+        plaintext: &[u8],
+    ) -> Result<(EncryptedFrameHeader, Vec<u8>), WalError> {
+        let (key_id, key) = self.keys.current_key();
+        let mut header = EncryptedFrameHeader {
+            length: (plaintext.len() + TAG_SIZE) as u64,
+            checksum: 0,
+            cipher: self.cipher,
+            key_id,
+        };
+        let nonce = frame_nonce(segment_id, offset)?;
+        let key = derive_key(&key, &wal_id.to_le_bytes());
+        let ciphertext = self.cipher.seal(&key, &nonce, &header.aad(), plaintext)?;
+        header.checksum = header.compute_checksum(&ciphertext);
+        Ok((header, ciphertext))
+    }
+
+    /// Decrypts a frame written by [`seal_frame`](Self::seal_frame). Fails
+    /// with [`WalError::Tampered`] if the ciphertext or header does not
+    /// authenticate, or [`WalError::Encryption`] without its key.
+    pub(crate) fn open_frame(
+        &self,
+        header: &EncryptedFrameHeader,
+        wal_id: u128,
+        segment_id: u64,
+        offset: u64,
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, WalError> {
+        let nonce = frame_nonce(segment_id, offset)?;
+        let key = derive_key(&self.key(header.key_id)?, &wal_id.to_le_bytes());
+        header
+            .cipher
+            .open(&key, &nonce, &header.aad(), ciphertext)
+            .ok_or_else(|| {
+                WalError::Tampered(format!(
+                    "frame at offset {offset} of segment {segment_id} failed authentication"
+                ))
+            })
+    }
+
+    /// Encrypts a small standalone value (e.g. the checkpoint) under a
+    /// random nonce, authenticating `aad` with it:
+    /// `[cipher: u8][key_id: u32 LE][nonce: 12 bytes][ciphertext + tag]`.
+    pub(crate) fn seal(&self, aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, WalError> {
+        let (key_id, key) = self.keys.current_key();
+        let nonce: [u8; NONCE_SIZE] = rand::random();
+        let mut sealed = vec![self.cipher.id()];
+        sealed.extend_from_slice(&key_id.to_le_bytes());
+        sealed.extend_from_slice(&nonce);
+        let aad = [aad, &sealed[..5]].concat();
+        let key = derive_key(&key, b"standalone");
+        sealed.extend(self.cipher.seal(&key, &nonce, &aad, plaintext)?);
+        Ok(sealed)
+    }
+
+    /// Decrypts a value written by [`seal`](Self::seal) with the same `aad`.
+    pub(crate) fn open(&self, aad: &[u8], sealed: &[u8]) -> Result<Vec<u8>, WalError> {
+        if sealed.len() < 5 + NONCE_SIZE + TAG_SIZE {
+            return Err(WalError::Tampered("sealed value is truncated".into()));
+        }
+        let cipher = Cipher::from_id(sealed[0])
+            .ok_or_else(|| WalError::Tampered(format!("unknown cipher {}", sealed[0])))?;
+        let key_id = u32::from_le_bytes(sealed[1..5].try_into().unwrap());
+        let nonce: [u8; NONCE_SIZE] = sealed[5..5 + NONCE_SIZE].try_into().unwrap();
+        let aad = [aad, &sealed[..5]].concat();
+        let key = derive_key(&self.key(key_id)?, b"standalone");
+        cipher
+            .open(&key, &nonce, &aad, &sealed[5 + NONCE_SIZE..])
+            .ok_or_else(|| WalError::Tampered("sealed value failed authentication".into()))
+    }
+}
+
+/// Derives the key for one use (`context`) of a provider key.
+fn derive_key(key: &EncryptionKey, context: &[u8]) -> EncryptionKey {
+    Sha256::new()
+        .chain_update(b"ringwal-encryption-v1")
+        .chain_update(key)
+        .chain_update(context)
+        .finalize()
+        .into()
+}
+
+/// Nonce of the frame at `offset` of segment `segment_id`:
+/// `[offset: u64 LE][segment_id: u32 LE]`.
+fn frame_nonce(segment_id: u64, offset: u64) -> Result<[u8; NONCE_SIZE], WalError> {
+    let segment_id = u32::try_from(segment_id)
+        .map_err(|_| WalError::Encryption(format!("segment ID {segment_id} too large to encrypt")))?;
+    let mut nonce = [0u8; NONCE_SIZE];
+    nonce[..8].copy_from_slice(&offset.to_le_bytes());
+    nonce[8..].copy_from_slice(&segment_id.to_le_bytes());
+    Ok(nonce)
+}
+
+/// On-disk header of an encrypted frame (17 bytes):
+/// ```text
+/// [length: u64 LE][checksum: u32 LE][cipher << 4 | version: u8 = 4][key_id: u32 LE]
+/// ```
+/// It shares its first 13 bytes with [`WalEntryHeader`](crate::WalEntryHeader).
+/// `length` is the size of the ciphertext, tag included. The CRC32 checksum
+/// covers byte 12, `key_id` and the ciphertext: a mismatch is a torn or
+/// corrupted write, while a frame that matches it but fails authentication
+/// was tampered with. Every field but the checksum is authenticated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncryptedFrameHeader {
+    /// Length of the ciphertext (not including header).
+    pub length: u64,
+    /// CRC32 of the cipher ID, key ID and ciphertext.
+    pub checksum: u32,
+    pub cipher: Cipher,
+    /// ID of the [`KeyProvider`] key the frame was encrypted with.
+    pub key_id: u32,
+}
+
+impl EncryptedFrameHeader {
+    /// Format version in the low nibble of byte 12, after compressed frames.
+    pub const VERSION: u8 = 4;
+
+    /// Size of the header in bytes (8 + 4 + 1 + 4 = 17).
+    pub const SIZE: usize = 17;
+
+    /// Returns `true` if byte 12 of a header (`version_byte`) marks an
+    /// encrypted frame.
+    #[must_use]
+    pub fn is_frame(version_byte: u8) -> bool {
+        version_byte & 0x0f == Self::VERSION
+    }
+
+    /// Serializes the header to bytes.
+    #[must_use]
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0u8; Self::SIZE];
+        bytes[0..8].copy_from_slice(&self.length.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.checksum.to_le_bytes());
+        bytes[12] = self.cipher.id() << 4 | Self::VERSION;
+        bytes[13..17].copy_from_slice(&self.key_id.to_le_bytes());
+        bytes
+    }
+
+    /// Reads the rest of a frame header whose shared prefix has been read.
+    pub fn read_with_prefix(prefix: &[u8; 13], reader: &mut impl Read) -> Result<Self, WalError> {
+        let cipher = Cipher::from_id(prefix[12] >> 4)
+            .filter(|_| Self::is_frame(prefix[12]))
+            .ok_or_else(|| {
+                WalError::InvalidSegment(format!("unknown frame header version {}", prefix[12]))
+            })?;
+        let mut key_id = [0u8; 4];
+        reader.read_exact(&mut key_id)?;
+        Ok(Self {
+            length: u64::from_le_bytes(prefix[0..8].try_into().unwrap()),
+            checksum: u32::from_le_bytes(prefix[8..12].try_into().unwrap()),
+            cipher,
+            key_id: u32::from_le_bytes(key_id),
+        })
+    }
+
+    /// Validates that the ciphertext matches this header's checksum. This
+    /// detects corruption only; authenticity is checked on decryption.
+    pub fn validate(&self, ciphertext: &[u8]) -> Result<(), WalError> {
+        let actual = self.compute_checksum(ciphertext);
+        if actual != self.checksum {
+            return Err(WalError::ChecksumMismatch {
+                expected: self.checksum,
+                actual,
+            });
+        }
+        Ok(())
+    }
+
+    fn compute_checksum(&self, ciphertext: &[u8]) -> u32 {
+        let bytes = self.to_bytes();
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(&bytes[12..]);
+        hasher.update(ciphertext);
+        hasher.finalize()
+    }
+
+    /// Associated data: every header field except the checksum.
+    fn aad(&self) -> [u8; 13] {
+        let bytes = self.to_bytes();
+        let mut aad = [0u8; 13];
+        aad[..8].copy_from_slice(&bytes[..8]);
+        aad[8..].copy_from_slice(&bytes[12..]);
+        aad
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encryption(cipher: Cipher) -> (Encryption, Arc<KeyRing>) {
+        let keys = Arc::new(KeyRing::new(1, [7; 32]));
+        (Encryption::new(cipher, keys.clone()), keys)
+    }
+
+    #[test]
+    fn frames_round_trip_across_key_rotation() {
+        for cipher in [Cipher::Aes256Gcm, Cipher::ChaCha20Poly1305] {
+            let (encryption, keys) = encryption(cipher);
+            let (old_header, old) = encryption.seal_frame(9, 3, 53, b"entries").unwrap();
+            keys.rotate(2, [8; 32]);
+            let (header, ciphertext) = encryption.seal_frame(9, 3, 100, b"more entries").unwrap();
+            assert_eq!((old_header.key_id, header.key_id), (1, 2));
+
+            let bytes = header.to_bytes();
+            let restored =
+                EncryptedFrameHeader::read_with_prefix(bytes[..13].try_into().unwrap(), &mut &bytes[13..])
+                    .unwrap();
+            assert_eq!(restored, header);
+            restored.validate(&ciphertext).unwrap();
+            assert_eq!(encryption.open_frame(&restored, 9, 3, 100, &ciphertext).unwrap(), b"more entries");
+            assert_eq!(encryption.open_frame(&old_header, 9, 3, 53, &old).unwrap(), b"entries");
+        }
+    }
+
+    #[test]
+    fn moved_or_modified_frames_fail_authentication() {
+        let (encryption, _) = encryption(Cipher::Aes256Gcm);
+        let (header, ciphertext) = encryption.seal_frame(9, 3, 53, b"entries").unwrap();
+        let tampered = |result: Result<Vec<u8>, WalError>| matches!(result, Err(WalError::Tampered(_)));
+
+        // Another position, segment or WAL
+        assert!(tampered(encryption.open_frame(&header, 9, 3, 54, &ciphertext)));
+        assert!(tampered(encryption.open_frame(&header, 9, 4, 53, &ciphertext)));
+        assert!(tampered(encryption.open_frame(&header, 10, 3, 53, &ciphertext)));
+
+        // Modified ciphertext or header, checksum recomputed
+        let mut modified = ciphertext.clone();
+        modified[0] ^= 1;
+        let mut forged = header;
+        forged.checksum = forged.compute_checksum(&modified);
+        forged.validate(&modified).unwrap();
+        assert!(tampered(encryption.open_frame(&forged, 9, 3, 53, &modified)));
+        let mut forged = header;
+        forged.length -= 1;
+        assert!(tampered(encryption.open_frame(&forged, 9, 3, 53, &ciphertext)));
+
+        // An unknown key is not tampering
+        let mut unknown = header;
+        unknown.key_id = 5;
+        assert!(matches!(
+            encryption.open_frame(&unknown, 9, 3, 53, &ciphertext),
+            Err(WalError::Encryption(_))
+        ));
+    }
+
+    #[test]
+    fn sealed_values_authenticate_their_context() {
+        let (encryption, _) = encryption(Cipher::ChaCha20Poly1305);
+        let sealed = encryption.seal(&[3], &42u64.to_le_bytes()).unwrap();
+        assert_eq!(encryption.open(&[3], &sealed).unwrap(), 42u64.to_le_bytes());
+        assert!(matches!(encryption.open(&[2], &sealed), Err(WalError::Tampered(_))));
+        assert!(matches!(encryption.open(&[3], &sealed[..20]), Err(WalError::Tampered(_))));
+    }
+}
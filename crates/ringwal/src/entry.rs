@@ -2,6 +2,7 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::codec::Codec;
 use crate::error::WalError;
 
 /// A write-ahead log entry.
@@ -86,64 +87,168 @@ pub type ByteWalEntry = WalEntry<String, Vec<u8>>;
 
 /// On-disk header prepended to each serialized WAL entry.
 ///
-/// Format (13 bytes):
+/// Version 2 format (25 bytes), written by the flusher:
 /// ```text
-/// [length: u64 LE][checksum: u32 LE][version: u8]
+/// [length: u64 LE][checksum: u32 LE][codec << 4 | version: u8 = 2][lsn: u64 LE][writer_id: u32 LE]
 /// ```
+/// The checksum covers `lsn`, `writer_id`, the [`Codec`] ID unless it is 0
+/// (bincode), and the entry data. The codec ID sits in the high nibble of
+/// the version byte, so bincode entries read as plain version 2.
+///
+/// Version 1 format (13 bytes), still accepted by recovery:
+/// ```text
+/// [length: u64 LE][checksum: u32 LE][version: u8 = 1]
+/// ```
+/// Its checksum covers the entry data only, and it carries no LSN
+/// (`lsn` and `writer_id` read as 0).
 #[derive(Debug, Clone, Copy)]
 pub struct WalEntryHeader {
     /// Length of the serialized entry data (not including header).
     pub length: u64,
-    /// CRC32 checksum of the serialized entry data.
+    /// CRC32 checksum of the serialized entry data (and, in version 2, the
+    /// LSN and writer ID).
     pub checksum: u32,
-    /// Format version (currently always 1).
+    /// Format version (1 or 2).
     pub version: u8,
+    /// Log sequence number assigned by the flusher; 0 in version 1.
+    pub lsn: u64,
+    /// ID of the writer whose ring the entry came through; 0 in version 1.
+    pub writer_id: u32,
+    /// Codec the entry data was encoded with; bincode in version 1.
+    pub codec: Codec,
 }
 
 impl WalEntryHeader {
     /// Current WAL format version.
-    pub const VERSION: u8 = 1;
+    pub const VERSION: u8 = 2;
 
-    /// Size of header in bytes (8 + 4 + 1 = 13).
-    pub const SIZE: usize = 13;
+    /// Size of a version-2 header in bytes (8 + 4 + 1 + 8 + 4 = 25).
+    pub const SIZE: usize = 25;
 
-    /// Creates a new header for the given serialized entry data.
+    /// Size of a version-1 header in bytes (8 + 4 + 1 = 13), which is also
+    /// the prefix shared by both versions.
+    pub const V1_SIZE: usize = 13;
+
+    /// Creates a version-2 header for the given bincode-encoded entry data.
     #[must_use] 
-    pub fn new(data: &[u8]) -> Self {
-        let checksum = crc32fast::hash(data);
+    pub fn new(lsn: u64, writer_id: u32, data: &[u8]) -> Self {
+        Self::new_with_codec(lsn, writer_id, Codec::Bincode, data)
+    }
+
+    /// Creates a version-2 header for entry data encoded with `codec`.
+    #[must_use]
+    pub fn new_with_codec(lsn: u64, writer_id: u32, codec: Codec, data: &[u8]) -> Self {
         Self {
             length: data.len() as u64,
-            checksum,
+            checksum: checksum_v2(lsn, writer_id, codec, data),
             version: Self::VERSION,
+            lsn,
+            writer_id,
+            codec,
         }
     }
 
-    /// Serializes the header to bytes.
+    /// Creates a version-1 header, as written before LSNs were persisted.
+    #[must_use] 
+    pub fn new_v1(data: &[u8]) -> Self {
+        Self {
+            length: data.len() as u64,
+            checksum: crc32fast::hash(data),
+            version: 1,
+            lsn: 0,
+            writer_id: 0,
+            codec: Codec::Bincode,
+        }
+    }
+
+    /// Size of this header on disk.
+    #[must_use] 
+    pub fn encoded_len(&self) -> usize {
+        if self.version == 1 {
+            Self::V1_SIZE
+        } else {
+            Self::SIZE
+        }
+    }
+
+    /// Serializes the header to bytes. Only the first
+    /// [`encoded_len`](Self::encoded_len) bytes are meaningful.
     #[must_use] 
     pub fn to_bytes(&self) -> [u8; Self::SIZE] {
         let mut bytes = [0u8; Self::SIZE];
         bytes[0..8].copy_from_slice(&self.length.to_le_bytes());
         bytes[8..12].copy_from_slice(&self.checksum.to_le_bytes());
-        bytes[12] = self.version;
+        bytes[12] = self.codec.id() << 4 | self.version;
+        bytes[13..21].copy_from_slice(&self.lsn.to_le_bytes());
+        bytes[21..25].copy_from_slice(&self.writer_id.to_le_bytes());
         bytes
     }
 
-    /// Deserializes a header from bytes.
+    /// Deserializes the prefix shared by both versions. For a version-2
+    /// header, follow with [`read_extension`](Self::read_extension).
     #[must_use] 
-    pub fn from_bytes(bytes: &[u8; Self::SIZE]) -> Self {
+    pub fn from_bytes(bytes: &[u8; Self::V1_SIZE]) -> Self {
         let length = u64::from_le_bytes(bytes[0..8].try_into().unwrap());
         let checksum = u32::from_le_bytes(bytes[8..12].try_into().unwrap());
-        let version = bytes[12];
+        // An unknown codec ID leaves the whole byte as the version, which
+        // `read_from` then rejects
+        let (version, codec) = match Codec::from_id(bytes[12] >> 4) {
+            Some(codec) => (bytes[12] & 0x0f, codec),
+            None => (bytes[12], Codec::Bincode),
+        };
         Self {
             length,
             checksum,
             version,
+            lsn: 0,
+            writer_id: 0,
+            codec,
+        }
+    }
+
+    /// Reads a header of either version.
+    pub fn read_from(reader: &mut impl std::io::Read) -> Result<Self, WalError> {
+        let mut prefix = [0u8; Self::V1_SIZE];
+        reader.read_exact(&mut prefix)?;
+        Self::read_with_prefix(&prefix, reader)
+    }
+
+    /// Reads the rest of a header whose shared prefix has been read.
+    pub fn read_with_prefix(
+        prefix: &[u8; Self::V1_SIZE],
+        reader: &mut impl std::io::Read,
+    ) -> Result<Self, WalError> {
+        let mut header = Self::from_bytes(prefix);
+        match header.version {
+            1 if header.codec == Codec::Bincode => {}
+            2 => {
+                let mut extension = [0u8; Self::SIZE - Self::V1_SIZE];
+                reader.read_exact(&mut extension)?;
+                header.read_extension(&extension);
+            }
+            _ => {
+                return Err(WalError::InvalidSegment(format!(
+                    "unknown entry header version {}",
+                    prefix[12]
+                )))
+            }
         }
+        Ok(header)
+    }
+
+    /// Fills in the version-2 fields that follow the shared prefix.
+    pub fn read_extension(&mut self, bytes: &[u8; Self::SIZE - Self::V1_SIZE]) {
+        self.lsn = u64::from_le_bytes(bytes[0..8].try_into().unwrap());
+        self.writer_id = u32::from_le_bytes(bytes[8..12].try_into().unwrap());
     }
 
     /// Validates that the data matches this header's checksum.
     pub fn validate(&self, data: &[u8]) -> Result<(), WalError> {
-        let actual = crc32fast::hash(data);
+        let actual = if self.version == 1 {
+            crc32fast::hash(data)
+        } else {
+            checksum_v2(self.lsn, self.writer_id, self.codec, data)
+        };
         if actual != self.checksum {
             return Err(WalError::ChecksumMismatch {
                 expected: self.checksum,
@@ -154,6 +259,19 @@ impl WalEntryHeader {
     }
 }
 
+/// CRC32 of a version-2 entry: LSN, writer ID, codec ID (if not bincode),
+/// then data.
+fn checksum_v2(lsn: u64, writer_id: u32, codec: Codec, data: &[u8]) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&lsn.to_le_bytes());
+    hasher.update(&writer_id.to_le_bytes());
+    if codec != Codec::Bincode {
+        hasher.update(&[codec.id()]);
+    }
+    hasher.update(data);
+    hasher.finalize()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -161,21 +279,67 @@ mod tests {
     #[test]
     fn header_roundtrip() {
         let data = b"hello world";
-        let header = WalEntryHeader::new(data);
+        let header = WalEntryHeader::new(7, 3, data);
         let bytes = header.to_bytes();
-        let restored = WalEntryHeader::from_bytes(&bytes);
+        let restored = WalEntryHeader::read_from(&mut &bytes[..]).unwrap();
         assert_eq!(header.length, restored.length);
         assert_eq!(header.checksum, restored.checksum);
         assert_eq!(header.version, restored.version);
+        assert_eq!((restored.lsn, restored.writer_id), (7, 3));
+        assert_eq!(restored.encoded_len(), WalEntryHeader::SIZE);
+        restored.validate(data).unwrap();
+    }
+
+    #[test]
+    fn header_v1_still_readable() {
+        let data = b"hello world";
+        let header = WalEntryHeader::new_v1(data);
+        let bytes = header.to_bytes();
+        let restored = WalEntryHeader::read_from(&mut &bytes[..WalEntryHeader::V1_SIZE]).unwrap();
+        assert_eq!(restored.version, 1);
+        assert_eq!((restored.lsn, restored.writer_id), (0, 0));
+        assert_eq!(restored.encoded_len(), WalEntryHeader::V1_SIZE);
         restored.validate(data).unwrap();
     }
 
+    #[test]
+    fn header_records_codec() {
+        let data = b"{}";
+        let header = WalEntryHeader::new_with_codec(5, 1, Codec::Json, data);
+        let bytes = header.to_bytes();
+        assert_eq!(bytes[12], 0x22);
+        let restored = WalEntryHeader::read_from(&mut &bytes[..]).unwrap();
+        assert_eq!((restored.version, restored.codec), (2, Codec::Json));
+        restored.validate(data).unwrap();
+
+        // The codec ID is covered by the checksum
+        let mut bincode = restored;
+        bincode.codec = Codec::Bincode;
+        assert!(bincode.validate(data).is_err());
+        assert_eq!(WalEntryHeader::new(5, 1, data).to_bytes()[12], 2);
+    }
+
     #[test]
     fn header_detects_corruption() {
         let data = b"hello world";
-        let header = WalEntryHeader::new(data);
+        let header = WalEntryHeader::new(1, 0, data);
         let corrupt = b"hello worle";
         assert!(header.validate(corrupt).is_err());
+
+        // The LSN is covered by the checksum, too.
+        let mut moved = header;
+        moved.lsn = 2;
+        assert!(moved.validate(data).is_err());
+    }
+
+    #[test]
+    fn header_rejects_unknown_version() {
+        let mut bytes = WalEntryHeader::new(1, 0, b"x").to_bytes();
+        bytes[12] = 9;
+        assert!(matches!(
+            WalEntryHeader::read_from(&mut &bytes[..]),
+            Err(WalError::InvalidSegment(_))
+        ));
     }
 
     #[test]
@@ -2,6 +2,8 @@
 
 use thiserror::Error;
 
+use crate::codec::Codec;
+
 /// Errors that can occur during WAL operations.
 #[derive(Error, Debug)]
 pub enum WalError {
@@ -11,6 +13,10 @@ pub enum WalError {
     #[error("Serialization error: {0}")]
     Serialization(#[from] bincode::Error),
 
+    /// An entry could not be encoded or decoded with its codec.
+    #[error("{codec:?} codec error: {message}")]
+    Codec { codec: Codec, message: String },
+
     #[error("Checksum mismatch: expected {expected:#x}, got {actual:#x}")]
     ChecksumMismatch { expected: u32, actual: u32 },
 
@@ -32,6 +38,92 @@ pub enum WalError {
     #[error("Invalid segment file: {0}")]
     InvalidSegment(String),
 
+    /// Encrypted data cannot be read or written: no key provider, or no
+    /// key with the recorded key ID.
+    #[error("Encryption error: {0}")]
+    Encryption(String),
+
+    /// Encrypted data failed authentication although its checksum matched:
+    /// it was modified deliberately, not torn or corrupted.
+    #[error("Tampering detected: {0}")]
+    Tampered(String),
+
     #[error("No new checkpoints available")]
     NoNewCheckpoints,
+
+    /// A subscription or follower asked for entries from segments that were
+    /// already truncated.
+    #[error("LSN {requested} was truncated; the log now starts at LSN {oldest}")]
+    Truncated { requested: u64, oldest: u64 },
+
+    /// A replication peer broke the protocol, sent a corrupt frame or
+    /// reported an error.
+    #[error("Replication error: {0}")]
+    Replication(String),
+
+    /// The commit is durable locally, but the WAL shut down, or
+    /// [`WalConfig::replication_timeout`](crate::WalConfig::replication_timeout)
+    /// passed, before enough followers acknowledged it.
+    #[error("LSN {lsn} acknowledged by {acked} of {required} required followers")]
+    NotReplicated {
+        lsn: u64,
+        acked: usize,
+        required: usize,
+    },
+
+    /// The batch holding this commit could not be written or synced.
+    #[error("{0}")]
+    FlushFailed(FlushFailure),
+
+    /// An earlier flush failed; the WAL rejects appends until it is reopened
+    /// and recovered.
+    #[error("WAL poisoned by an earlier failure: {0}")]
+    Poisoned(FlushFailure),
+}
+
+/// Which step of a flush failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlushStage {
+    /// Writing the batch to the segment (including rotation).
+    Write,
+    /// Flushing or fsyncing the segment.
+    Sync,
+}
+
+/// A failed flush, as reported to every commit waiting on the batch.
+///
+/// Cloneable, unlike the underlying `io::Error`, so one failure can be
+/// handed to each waiter and kept as the WAL's poison state.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{stage:?} failed on segment {segment_id} for LSNs {first_lsn}..={last_lsn}: {message} ({kind:?})")]
+pub struct FlushFailure {
+    pub stage: FlushStage,
+    pub kind: std::io::ErrorKind,
+    pub message: String,
+    pub segment_id: u64,
+    pub first_lsn: u64,
+    pub last_lsn: u64,
+}
+
+impl FlushFailure {
+    /// Describes `error`, hit while flushing LSNs `lsns` to segment `segment_id`.
+    pub(crate) fn new(
+        stage: FlushStage,
+        error: &WalError,
+        segment_id: u64,
+        lsns: (u64, u64),
+    ) -> Self {
+        let kind = match error {
+            WalError::Io(e) => e.kind(),
+            _ => std::io::ErrorKind::Other,
+        };
+        Self {
+            stage,
+            kind,
+            message: error.to_string(),
+            segment_id,
+            first_lsn: lsns.0,
+            last_lsn: lsns.1,
+        }
+    }
 }
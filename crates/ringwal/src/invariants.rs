@@ -69,11 +69,86 @@ macro_rules! debug_assert_commit_durable {
     };
 }
 
+/// INV-WAL-08: No batch is acknowledged after an earlier batch failed
+/// (fail-stop).
+macro_rules! debug_assert_fail_stop {
+    ($acked:expr, $seq:expr, $failed_seq:expr) => {
+        #[cfg(debug_assertions)]
+        debug_assert!(
+            !$acked || $failed_seq.is_none_or(|failed: u64| failed > $seq),
+            "INV-WAL-08 violated: batch {} acknowledged after batch {:?} failed",
+            $seq,
+            $failed_seq
+        );
+    };
+}
+
+/// INV-WAL-09: Recovery returns committed transactions in durable commit
+/// order. Version-1 entries carry no LSN (0) and are not checked.
+macro_rules! debug_assert_commit_order {
+    ($prev_lsn:expr, $lsn:expr) => {
+        #[cfg(debug_assertions)]
+        debug_assert!(
+            $lsn == 0 || $lsn > $prev_lsn,
+            "INV-WAL-09 violated: commit at LSN {} recovered after commit at LSN {}",
+            $lsn,
+            $prev_lsn
+        );
+    };
+}
+
+/// INV-WAL-10: A subscription reads only entries at or below the durable
+/// LSN.
+macro_rules! debug_assert_tail_durable {
+    ($lsn:expr, $durable_lsn:expr) => {
+        #[cfg(debug_assertions)]
+        debug_assert!(
+            $lsn <= $durable_lsn,
+            "INV-WAL-10 violated: entry at LSN {} handed to a subscriber before durable LSN {}",
+            $lsn,
+            $durable_lsn
+        );
+    };
+}
+
+/// INV-WAL-11: In replicated commit mode, a commit is acknowledged only once
+/// the required number of followers have durably written its LSN.
+macro_rules! debug_assert_replicated {
+    ($lsn:expr, $quorum_lsn:expr) => {
+        #[cfg(debug_assertions)]
+        debug_assert!(
+            $lsn <= $quorum_lsn,
+            "INV-WAL-11 violated: commit at LSN {} acknowledged with followers at LSN {}",
+            $lsn,
+            $quorum_lsn
+        );
+    };
+}
+
+/// INV-WAL-12: A checkpoint's low-water mark is at most one past its LSN,
+/// so truncation never removes a segment past the checkpoint.
+macro_rules! debug_assert_low_water {
+    ($low_water:expr, $lsn:expr) => {
+        #[cfg(debug_assertions)]
+        debug_assert!(
+            $low_water <= $lsn + 1,
+            "INV-WAL-12 violated: low-water mark {} past checkpoint LSN {}",
+            $low_water,
+            $lsn
+        );
+    };
+}
+
 #[allow(unused_imports)]
 pub(crate) use debug_assert_commit_durable;
+pub(crate) use debug_assert_commit_order;
 #[allow(unused_imports)]
 pub(crate) use debug_assert_entry_checksum;
+pub(crate) use debug_assert_fail_stop;
 #[allow(unused_imports)]
 pub(crate) use debug_assert_lsn_monotonic;
+pub(crate) use debug_assert_low_water;
+pub(crate) use debug_assert_replicated;
 pub(crate) use debug_assert_segment_id_monotonic;
 pub(crate) use debug_assert_segment_size;
+pub(crate) use debug_assert_tail_durable;
@@ -0,0 +1,80 @@
+//! I/O wrapper that turns on encryption at rest.
+
+use std::io;
+use std::path::Path;
+
+use super::{DirEntry, IoEngine};
+use crate::encryption::Encryption;
+
+/// Wraps an [`IoEngine`] so the WAL encrypts what it writes through it.
+///
+/// File operations go straight to the inner engine; the segment manager,
+/// recovery and checkpoint code see [`IoEngine::encryption`] and seal or
+/// open their records. Opening a WAL with `EncryptedIo<RealIo>` instead of
+/// `RealIo` is the only change callers make.
+#[derive(Debug, Clone)]
+pub struct EncryptedIo<IO: IoEngine> {
+    inner: IO,
+    encryption: Encryption,
+}
+
+impl<IO: IoEngine> EncryptedIo<IO> {
+    /// Encrypts WAL data written through `inner` with `encryption`.
+    pub fn new(inner: IO, encryption: Encryption) -> Self {
+        Self { inner, encryption }
+    }
+
+    /// Returns the wrapped engine.
+    pub fn inner(&self) -> &IO {
+        &self.inner
+    }
+}
+
+impl<IO: IoEngine> IoEngine for EncryptedIo<IO> {
+    type FileHandle = IO::FileHandle;
+    type ReadHandle = IO::ReadHandle;
+
+    fn open_append(&self, path: &Path, direct_io: bool) -> io::Result<Self::FileHandle> {
+        self.inner.open_append(path, direct_io)
+    }
+
+    fn open_read(&self, path: &Path) -> io::Result<Self::ReadHandle> {
+        self.inner.open_read(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        self.inner.create_dir_all(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<DirEntry>> {
+        self.inner.read_dir(path)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        self.inner.remove_file(path)
+    }
+
+    fn write_file_bytes(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        self.inner.write_file_bytes(path, data)
+    }
+
+    fn truncate_file(&self, path: &Path, len: u64) -> io::Result<()> {
+        self.inner.truncate_file(path, len)
+    }
+
+    fn read_file_bytes(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.inner.read_file_bytes(path)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.inner.exists(path)
+    }
+
+    fn now_secs(&self) -> u64 {
+        self.inner.now_secs()
+    }
+
+    fn encryption(&self) -> Option<&Encryption> {
+        Some(&self.encryption)
+    }
+}
@@ -8,12 +8,19 @@
 //!
 //! Production code uses `IO = RealIo` via type aliases, so the generic parameter
 //! is invisible to callers unless they need a custom backend.
+//!
+//! [`EncryptedIo`] wraps either engine to encrypt segments and checkpoints at
+//! rest (see [`Encryption`]).
 
+mod encrypted;
 mod real;
 
+pub use encrypted::EncryptedIo;
 pub use real::RealIo;
 
-use std::io::{self, Read, Write};
+use crate::encryption::Encryption;
+
+use std::io::{self, Read, Seek, Write};
 use std::path::{Path, PathBuf};
 
 /// A directory entry returned by [`IoEngine::read_dir`].
@@ -63,6 +70,12 @@ pub trait IoEngine: Send + Sync + Clone + 'static {
     /// Used for checkpoint files where partial writes must not be visible.
     fn write_file_bytes(&self, path: &Path, data: &[u8]) -> io::Result<()>;
 
+    /// Cuts a file to its first `len` bytes and syncs it.
+    ///
+    /// The bytes before `len` are not rewritten, so a crash leaves the
+    /// file at its old length or at `len`, never with a damaged prefix.
+    fn truncate_file(&self, path: &Path, len: u64) -> io::Result<()>;
+
     /// Reads the entire contents of a file.
     fn read_file_bytes(&self, path: &Path) -> io::Result<Vec<u8>>;
 
@@ -73,6 +86,14 @@ pub trait IoEngine: Send + Sync + Clone + 'static {
 
     /// Returns the current time as seconds since the UNIX epoch.
     fn now_secs(&self) -> u64;
+
+    // ── Encryption ───────────────────────────────────────────────────────
+
+    /// Returns the encryption WAL data written through this engine gets,
+    /// if any. Only [`EncryptedIo`] overrides the default of `None`.
+    fn encryption(&self) -> Option<&Encryption> {
+        None
+    }
 }
 
 /// A writable file handle returned by [`IoEngine::open_append`].
@@ -93,14 +114,42 @@ pub trait FileHandle: Write + Send + 'static {
     /// the clone flushes the same inode. Used for pipelined fsync.
     fn try_clone_file(&self) -> io::Result<std::fs::File>;
 
+    /// Returns a handle that syncs this file from another thread.
+    ///
+    /// Used by the `Background` and pipelined sync modes, which fsync off
+    /// the flusher. Defaults to [`try_clone_file`](Self::try_clone_file);
+    /// engines without real file descriptors override it.
+    fn try_clone_sync(&self) -> io::Result<Box<dyn SyncHandle>> {
+        Ok(Box::new(self.try_clone_file()?))
+    }
+
     /// Returns the current file size in bytes.
     fn metadata_len(&self) -> io::Result<u64>;
 }
 
+/// Syncs a file off the flusher, from [`FileHandle::try_clone_sync`].
+pub trait SyncHandle: Send + 'static {
+    /// Syncs data + metadata of the file to durable storage.
+    fn sync_all(&mut self) -> io::Result<()>;
+
+    /// Syncs data only (no metadata) of the file to durable storage.
+    fn sync_data(&mut self) -> io::Result<()>;
+}
+
+impl SyncHandle for std::fs::File {
+    fn sync_all(&mut self) -> io::Result<()> {
+        std::fs::File::sync_all(self)
+    }
+
+    fn sync_data(&mut self) -> io::Result<()> {
+        std::fs::File::sync_data(self)
+    }
+}
+
 /// A readable file handle returned by [`IoEngine::open_read`].
 ///
-/// Extends `std::io::Read` with metadata access.
-pub trait ReadHandle: Read + Send + 'static {
+/// Extends `std::io::Read` and `std::io::Seek` with metadata access.
+pub trait ReadHandle: Read + Seek + Send + 'static {
     /// Returns the file size in bytes.
     fn metadata_len(&self) -> io::Result<u64>;
 }
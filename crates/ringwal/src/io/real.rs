@@ -2,7 +2,7 @@
 //!
 //! All operations map 1:1 to standard library calls with zero overhead.
 
-use std::io::{self, BufWriter, Read, Write};
+use std::io::{self, BufWriter, Read, Seek, SeekFrom, Write};
 use std::path::Path;
 
 use super::{DirEntry, FileHandle, IoEngine, ReadHandle};
@@ -22,6 +22,7 @@ fn set_direct_io(file: &std::fs::File) -> io::Result<()> {
 }
 
 #[cfg(target_os = "linux")]
+#[allow(clippy::unnecessary_wraps)] // signature shared with the macOS variant
 fn set_direct_io(_file: &std::fs::File) -> io::Result<()> {
     // O_DIRECT requires block-aligned writes — not yet implemented.
     // See docs/DIRECT_IO.md for the follow-up plan.
@@ -95,6 +96,12 @@ impl IoEngine for RealIo {
         Ok(())
     }
 
+    fn truncate_file(&self, path: &Path, len: u64) -> io::Result<()> {
+        let file = std::fs::OpenOptions::new().write(true).open(path)?;
+        file.set_len(len)?;
+        file.sync_all()
+    }
+
     fn read_file_bytes(&self, path: &Path) -> io::Result<Vec<u8>> {
         std::fs::read(path)
     }
@@ -165,6 +172,12 @@ impl Read for RealReadHandle {
     }
 }
 
+impl Seek for RealReadHandle {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.file.seek(pos)
+    }
+}
+
 impl ReadHandle for RealReadHandle {
     fn metadata_len(&self) -> io::Result<u64> {
         Ok(self.len)
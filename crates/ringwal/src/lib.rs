@@ -33,27 +33,46 @@
 //! }
 //! ```
 
+mod codec;
+mod compression;
 mod config;
+mod encryption;
 mod entry;
 mod error;
 pub mod io;
 mod invariants;
+mod reader;
 mod recovery;
+mod replication;
 mod segment;
+mod subscription;
 mod transaction;
 mod wal;
 mod writer;
 
+pub use codec::{Codec, CodecError, CustomCodec, CustomCodecs, EntryVisitor};
+pub use compression::{CompressedFrameHeader, Compression, CompressionStats};
 pub use config::WalConfig;
 pub use config::SyncMode;
+pub use encryption::{Cipher, EncryptedFrameHeader, Encryption, EncryptionKey, KeyProvider, KeyRing};
 pub use entry::{ByteWalEntry, WalEntry, WalEntryHeader};
-pub use error::WalError;
-pub use io::{DirEntry as IoDirEntry, FileHandle, IoEngine, ReadHandle, RealIo};
+pub use error::{FlushFailure, FlushStage, WalError};
+pub use io::{
+    DirEntry as IoDirEntry, FileHandle, EncryptedIo, IoEngine, ReadHandle, RealIo, SyncHandle,
+};
+pub use reader::RecoveryReader;
 pub use recovery::{
     checkpoint, read_checkpoint, recover, truncate_segments_before, write_checkpoint,
     RecoveredTransaction, RecoveryAction, RecoveryStats,
 };
-pub use segment::{SegmentMeta, SegmentManager};
+pub use replication::{Follower, ReplicationSource};
+pub use segment::{
+    read_segment_footer, read_segment_header, SegmentFooter, SegmentHeader, SegmentManager,
+    SegmentMeta,
+};
+pub use subscription::Subscription;
 pub use transaction::{Transaction, TxState};
-pub use wal::Wal;
+pub use wal::{Wal, WalHealth};
 pub use writer::{next_tx_id, WalWriter, WalWriterFactory};
+
+pub use erased_serde;
@@ -0,0 +1,536 @@
+//! Streaming, bounded-memory recovery.
+//!
+//! [`RecoveryReader`] scans segment files lazily, one entry at a time, and
+//! yields each transaction as soon as its outcome is known: committed and
+//! aborted transactions at their `Commit`/`Abort` record, incomplete ones
+//! once the end of the log is reached. Only the entries of transactions
+//! that are still open are held in memory, and with a spill threshold they
+//! move to temporary files in the WAL directory instead.
+
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::codec::CustomCodecs;
+use crate::entry::WalEntry;
+use crate::error::WalError;
+use crate::invariants::debug_assert_commit_order;
+use crate::io::IoEngine;
+use crate::recovery::{
+    read_checkpoint_marks, read_segment_first_lsn, RecoveredTransaction, RecoveryAction,
+    RecoveryStats, ScanStop, SegmentCursor,
+};
+use crate::segment::{discover_segment_ids, read_wal_id};
+use crate::writer::reset_tx_id;
+
+/// Iterator over the transactions recovered from a WAL directory, in the
+/// order they end in the log (INV-WAL-09).
+///
+/// Memory use is bounded by the entries of transactions open at the same
+/// point in the log rather than by the size of the log. Once the log is
+/// exhausted the global transaction ID counter is advanced past every ID
+/// seen, as [`recover`](crate::recover) does.
+///
+/// ```ignore
+/// let reader = RecoveryReader::<String, Vec<u8>, _>::new(dir, &RealIo)?
+///     .with_start_lsn(read_checkpoint(dir, &RealIo)?)
+///     .with_spill_threshold(64 * 1024 * 1024);
+/// for tx in reader {
+///     let tx = tx?;
+///     if tx.action == RecoveryAction::Commit {
+///         // apply tx.entries
+///     }
+/// }
+/// ```
+pub struct RecoveryReader<K, V, IO: IoEngine> {
+    dir: PathBuf,
+    io: IO,
+    /// The directory's WAL ID, checked against segment headers.
+    wal_id: Option<u128>,
+    /// Whether a segment with a header has been read; segments without
+    /// one may only precede it.
+    seen_header: bool,
+    /// Segments not yet opened, oldest first.
+    segment_ids: VecDeque<u64>,
+    cursor: Option<SegmentCursor<IO::ReadHandle>>,
+    start_lsn: u64,
+    /// First LSN that must be read: segments entirely below it are skipped.
+    low_water: Option<u64>,
+    custom_codecs: CustomCodecs,
+    started: bool,
+    /// Transactions seen without a `Commit`/`Abort` record so far.
+    open: HashMap<u64, OpenTransaction<K, V>>,
+    /// Open transactions left at the end of the log, by their last entry.
+    incomplete: Option<VecDeque<u64>>,
+    /// Index of the last entry read, across all segments.
+    position: u64,
+    /// Encoded size of the open transactions' entries held in memory.
+    buffered_bytes: usize,
+    spill: Option<SpillConfig<K, V>>,
+    max_tx_id: u64,
+    prev_commit_lsn: u64,
+    stats: RecoveryStats,
+    done: bool,
+}
+
+/// Entries of a transaction whose outcome is not yet known.
+struct OpenTransaction<K, V> {
+    entries: Vec<WalEntry<K, V>>,
+    /// Encoded size of `entries`.
+    bytes: usize,
+    /// Number of older entries already moved to the spill file.
+    spilled: usize,
+    first_lsn: u64,
+    last_lsn: u64,
+    last_position: u64,
+}
+
+struct SpillConfig<K, V> {
+    threshold: usize,
+    encode: fn(&WalEntry<K, V>) -> Result<Vec<u8>, WalError>,
+}
+
+fn encode_entry<K: Serialize, V: Serialize>(entry: &WalEntry<K, V>) -> Result<Vec<u8>, WalError> {
+    Ok(bincode::serialize(entry)?)
+}
+
+impl<K, V, IO> RecoveryReader<K, V, IO>
+where
+    K: DeserializeOwned + Send + 'static,
+    V: DeserializeOwned + Send + 'static,
+    IO: IoEngine,
+{
+    /// Creates a reader over all segment files in `dir`. Nothing is read
+    /// until the first call to `next()`.
+    pub fn new(dir: &Path, io: &IO) -> Result<Self, WalError> {
+        let mut segment_ids = discover_segment_ids(dir, io)?;
+        segment_ids.sort_unstable();
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            io: io.clone(),
+            wal_id: read_wal_id(dir, io)?,
+            seen_header: false,
+            segment_ids: segment_ids.into(),
+            cursor: None,
+            start_lsn: 0,
+            low_water: None,
+            custom_codecs: CustomCodecs::default(),
+            started: false,
+            open: HashMap::new(),
+            incomplete: None,
+            position: 0,
+            buffered_bytes: 0,
+            spill: None,
+            max_tx_id: 0,
+            prev_commit_lsn: 0,
+            stats: RecoveryStats::default(),
+            done: false,
+        })
+    }
+
+    /// Skips work already applied up to `lsn`, typically the value of
+    /// [`read_checkpoint`](crate::read_checkpoint).
+    ///
+    /// Transactions that committed or aborted at or before `lsn` are not
+    /// yielded. Leading segments are skipped only below the low-water mark
+    /// of the directory's checkpoint (INV-WAL-12) — the same segments
+    /// [`truncate_segments_before`](crate::truncate_segments_before) would
+    /// remove — so a transaction open at `lsn` is yielded with all its
+    /// entries. If `lsn` is below the checkpoint, or no checkpoint exists,
+    /// no segment is skipped. Segments without LSNs (version 1) are always
+    /// read.
+    #[must_use]
+    pub fn with_start_lsn(mut self, lsn: u64) -> Self {
+        self.start_lsn = lsn;
+        self
+    }
+
+    /// Reads from the segment holding `lsn` on, rather than from the one
+    /// holding the start LSN: a checkpoint's low-water mark, so transactions
+    /// open at the start LSN are read from their first entry.
+    #[must_use]
+    pub(crate) fn with_low_water(mut self, lsn: u64) -> Self {
+        self.low_water = Some(lsn);
+        self
+    }
+
+    /// Decodes entries written with [`Codec::Custom`](crate::Codec::Custom)
+    /// codecs, typically [`WalConfig::custom_codecs`](crate::WalConfig::custom_codecs).
+    /// Reading an entry of a codec not registered fails with
+    /// `WalError::Codec`.
+    #[must_use]
+    pub fn with_custom_codecs(mut self, codecs: CustomCodecs) -> Self {
+        self.custom_codecs = codecs;
+        self
+    }
+
+    /// Moves open transactions' entries to temporary files in the WAL
+    /// directory whenever more than `bytes` of them are held in memory.
+    /// The files are removed once their transaction is yielded, or when
+    /// the reader is dropped.
+    #[must_use]
+    pub fn with_spill_threshold(mut self, bytes: usize) -> Self
+    where
+        K: Serialize,
+        V: Serialize,
+    {
+        self.spill = Some(SpillConfig {
+            threshold: bytes,
+            encode: encode_entry::<K, V>,
+        });
+        self
+    }
+
+    /// Statistics for the transactions yielded so far.
+    pub fn stats(&self) -> &RecoveryStats {
+        &self.stats
+    }
+
+    /// Consumes the reader, returning its statistics.
+    pub fn into_stats(mut self) -> RecoveryStats {
+        std::mem::take(&mut self.stats)
+    }
+
+    fn advance(&mut self) -> Result<Option<RecoveredTransaction<K, V>>, WalError> {
+        if !self.started {
+            self.started = true;
+            self.skip_applied_segments()?;
+        }
+
+        while self.incomplete.is_none() {
+            let Some((lsn, entry, size)) = self.next_entry()? else {
+                // End of the log: what is still open never finished.
+                let mut remaining: Vec<_> = self
+                    .open
+                    .iter()
+                    .map(|(&tx_id, tx)| (tx.last_position, tx_id))
+                    .collect();
+                remaining.sort_unstable();
+                self.incomplete = Some(remaining.into_iter().map(|(_, tx_id)| tx_id).collect());
+                break;
+            };
+
+            let tx_id = entry.tx_id();
+            self.max_tx_id = self.max_tx_id.max(tx_id);
+            self.stats.max_lsn = self.stats.max_lsn.max(lsn);
+
+            let action = if entry.is_commit() {
+                RecoveryAction::Commit
+            } else if entry.is_abort() {
+                RecoveryAction::Rollback
+            } else {
+                self.buffer(tx_id, lsn, entry, size)?;
+                continue;
+            };
+
+            let tx = self.open.remove(&tx_id);
+            let first_lsn = tx.as_ref().map_or(lsn, |tx| tx.first_lsn);
+            let last_lsn = tx.as_ref().map_or(0, |tx| tx.last_lsn).max(lsn);
+            if last_lsn > 0 && last_lsn <= self.start_lsn {
+                // Already applied before the checkpoint
+                if let Some(tx) = tx {
+                    self.discard(tx_id, &tx);
+                }
+                continue;
+            }
+            let entries = match tx {
+                Some(tx) => self.take_entries(tx_id, tx)?,
+                None => Vec::new(),
+            };
+            return Ok(Some(self.emit(tx_id, action, entries, (first_lsn, last_lsn))));
+        }
+
+        let Some(tx_id) = self.incomplete.as_mut().and_then(VecDeque::pop_front) else {
+            return Ok(None);
+        };
+        let tx = self.open.remove(&tx_id).expect("incomplete transaction is open");
+        let lsns = (tx.first_lsn, tx.last_lsn);
+        let entries = self.take_entries(tx_id, tx)?;
+        Ok(Some(self.emit(tx_id, RecoveryAction::Incomplete, entries, lsns)))
+    }
+
+    /// Drops the leading segments that lie entirely below the low-water
+    /// mark: those followed by a segment whose first LSN is at or below it.
+    fn skip_applied_segments(&mut self) -> Result<(), WalError> {
+        let low_water = match self.low_water {
+            Some(low_water) => low_water,
+            None => {
+                // Every transaction open at the start LSN was open at the
+                // checkpoint or began after it: none starts below its mark.
+                let marks = read_checkpoint_marks(&self.dir, &self.io)?;
+                if self.start_lsn >= marks.lsn {
+                    marks.low_water
+                } else {
+                    0
+                }
+            }
+        };
+        let boundary = low_water.min(self.start_lsn + 1);
+        if boundary == 0 {
+            return Ok(());
+        }
+        let mut skip = 0;
+        for (i, &seg_id) in self.segment_ids.iter().enumerate() {
+            let first_lsn = read_segment_first_lsn(&self.dir, seg_id, self.wal_id, &self.io)?;
+            if first_lsn > boundary {
+                break;
+            }
+            if first_lsn > 0 {
+                skip = i;
+            }
+        }
+        self.segment_ids.drain(..skip);
+        self.stats.segments_skipped = skip;
+        Ok(())
+    }
+
+    /// Reads and decodes the next valid entry of the log with its LSN and
+    /// encoded size, moving on to the next segment when one ends.
+    fn next_entry(&mut self) -> Result<Option<(u64, WalEntry<K, V>, usize)>, WalError> {
+        loop {
+            let Some(cursor) = self.cursor.as_mut() else {
+                let Some(seg_id) = self.segment_ids.pop_front() else {
+                    return Ok(None);
+                };
+                let cursor = SegmentCursor::open(&self.dir, seg_id, self.wal_id, &self.io)?;
+                if let Some(cursor) = &cursor {
+                    if cursor.header().is_some() {
+                        self.seen_header = true;
+                    } else if self.seen_header && cursor.is_headerless() {
+                        return Err(WalError::InvalidSegment(format!(
+                            "segment {seg_id} has no header but follows segments that do"
+                        )));
+                    }
+                }
+                self.cursor = cursor;
+                continue;
+            };
+
+            if let Some((header, data)) = cursor.next_entry() {
+                match self.custom_codecs.decode::<K, V>(header.codec, &data) {
+                    Ok(entry) => {
+                        self.position += 1;
+                        return Ok(Some((header.lsn, entry, data.len())));
+                    }
+                    // Without its codec an entry cannot be told from a
+                    // corrupt one: stop rather than discard the log after it
+                    Err(e) if !self.custom_codecs.contains(header.codec) => return Err(e),
+                    Err(_) => cursor.reject(),
+                }
+            }
+
+            match cursor.stop() {
+                Some(ScanStop::PartialWrite) => self.stats.partial_writes += 1,
+                Some(ScanStop::Corrupt) => self.stats.checksum_failures += 1,
+                Some(ScanStop::Tampered) => {
+                    // Modified on purpose: the entries after it, in this
+                    // segment and later ones, cannot be trusted either
+                    self.stats.tampered += 1;
+                    let segment = cursor.header().map_or(0, |header| header.segment_id);
+                    return Err(WalError::Tampered(format!(
+                        "segment {segment} holds a frame that failed authentication"
+                    )));
+                }
+                Some(ScanStop::Unreadable) => cursor.check_readable()?,
+                None => {}
+            }
+            self.cursor = None;
+        }
+    }
+
+    fn buffer(
+        &mut self,
+        tx_id: u64,
+        lsn: u64,
+        entry: WalEntry<K, V>,
+        size: usize,
+    ) -> Result<(), WalError> {
+        let tx = self.open.entry(tx_id).or_insert_with(|| OpenTransaction {
+            entries: Vec::new(),
+            bytes: 0,
+            spilled: 0,
+            first_lsn: lsn,
+            last_lsn: 0,
+            last_position: 0,
+        });
+        tx.entries.push(entry);
+        tx.bytes += size;
+        tx.last_lsn = tx.last_lsn.max(lsn);
+        tx.last_position = self.position;
+        self.buffered_bytes += size;
+
+        if self
+            .spill
+            .as_ref()
+            .is_some_and(|spill| self.buffered_bytes > spill.threshold)
+        {
+            self.spill_open()?;
+        }
+        Ok(())
+    }
+
+    /// Appends every open transaction's in-memory entries to its spill file,
+    /// each sealed under the engine's encryption, if it has one.
+    fn spill_open(&mut self) -> Result<(), WalError> {
+        let Some(spill) = &self.spill else {
+            return Ok(());
+        };
+        let encryption = self.io.encryption();
+        for (&tx_id, tx) in &mut self.open {
+            if tx.entries.is_empty() {
+                continue;
+            }
+            let path = spill_path(&self.dir, tx_id);
+            if tx.spilled == 0 {
+                // Left behind by an earlier, interrupted recovery
+                match self.io.remove_file(&path) {
+                    Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                    _ => {}
+                }
+            }
+            let mut file = self.io.open_append(&path, false)?;
+            for (index, entry) in (tx.spilled..).zip(&tx.entries) {
+                let mut data = (spill.encode)(entry)?;
+                if let Some(encryption) = encryption {
+                    data = encryption.seal(&spill_aad(tx_id, index), &data)?;
+                }
+                file.write_all(&(data.len() as u32).to_le_bytes())?;
+                file.write_all(&data)?;
+            }
+            file.flush()?;
+            tx.spilled += tx.entries.len();
+            tx.entries.clear();
+            tx.bytes = 0;
+        }
+        self.buffered_bytes = 0;
+        Ok(())
+    }
+
+    /// Returns a transaction's entries in log order: spilled ones first.
+    fn take_entries(
+        &mut self,
+        tx_id: u64,
+        tx: OpenTransaction<K, V>,
+    ) -> Result<Vec<WalEntry<K, V>>, WalError> {
+        self.buffered_bytes -= tx.bytes;
+        if tx.spilled == 0 {
+            return Ok(tx.entries);
+        }
+
+        let path = spill_path(&self.dir, tx_id);
+        let data = self.io.read_file_bytes(&path)?;
+        let _ = self.io.remove_file(&path);
+
+        let mut entries = Vec::with_capacity(tx.spilled + tx.entries.len());
+        let mut rest = data.as_slice();
+        while entries.len() < tx.spilled {
+            let corrupt = || WalError::InvalidSegment(format!("truncated spill file {}", path.display()));
+            let (len, tail) = rest.split_first_chunk::<4>().ok_or_else(corrupt)?;
+            let len = u32::from_le_bytes(*len) as usize;
+            if tail.len() < len {
+                return Err(corrupt());
+            }
+            let data = match self.io.encryption() {
+                Some(encryption) => encryption.open(&spill_aad(tx_id, entries.len()), &tail[..len])?,
+                None => tail[..len].to_vec(),
+            };
+            entries.push(bincode::deserialize(&data)?);
+            rest = &tail[len..];
+        }
+        entries.extend(tx.entries);
+        Ok(entries)
+    }
+
+    fn discard(&mut self, tx_id: u64, tx: &OpenTransaction<K, V>) {
+        self.buffered_bytes -= tx.bytes;
+        if tx.spilled > 0 {
+            let _ = self.io.remove_file(&spill_path(&self.dir, tx_id));
+        }
+    }
+
+    fn emit(
+        &mut self,
+        tx_id: u64,
+        action: RecoveryAction,
+        entries: Vec<WalEntry<K, V>>,
+        (first_lsn, last_lsn): (u64, u64),
+    ) -> RecoveredTransaction<K, V> {
+        match action {
+            RecoveryAction::Commit => {
+                debug_assert_commit_order!(self.prev_commit_lsn, last_lsn);
+                self.prev_commit_lsn = self.prev_commit_lsn.max(last_lsn);
+                self.stats.committed += 1;
+            }
+            RecoveryAction::Rollback => self.stats.aborted += 1,
+            RecoveryAction::Incomplete => self.stats.incomplete += 1,
+        }
+        self.stats.total_transactions += 1;
+        RecoveredTransaction {
+            tx_id,
+            action,
+            entries,
+            first_lsn,
+            last_lsn,
+        }
+    }
+}
+
+impl<K, V, IO> Iterator for RecoveryReader<K, V, IO>
+where
+    K: DeserializeOwned + Send + 'static,
+    V: DeserializeOwned + Send + 'static,
+    IO: IoEngine,
+{
+    type Item = Result<RecoveredTransaction<K, V>, WalError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.advance() {
+            Ok(Some(tx)) => Some(Ok(tx)),
+            Ok(None) => {
+                self.done = true;
+                // Reset TX ID counter to avoid reuse
+                if self.max_tx_id > 0 {
+                    reset_tx_id(self.max_tx_id + 1);
+                }
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl<K, V, IO: IoEngine> Drop for RecoveryReader<K, V, IO> {
+    fn drop(&mut self) {
+        for (&tx_id, tx) in &self.open {
+            if tx.spilled > 0 {
+                let _ = self.io.remove_file(&spill_path(&self.dir, tx_id));
+            }
+        }
+    }
+}
+
+/// Temporary file holding a transaction's spilled entries as
+/// `[len: u32 LE][bincode entry]` records, each entry sealed under
+/// [`EncryptedIo`](crate::EncryptedIo).
+fn spill_path(dir: &Path, tx_id: u64) -> PathBuf {
+    dir.join(format!("recovery-spill-{tx_id}.tmp"))
+}
+
+/// Data authenticated with the `index`th sealed entry of a spill file, so
+/// entries cannot be moved between or within spill files.
+fn spill_aad(tx_id: u64, index: usize) -> [u8; 16] {
+    let mut aad = [0; 16];
+    aad[..8].copy_from_slice(&tx_id.to_le_bytes());
+    aad[8..].copy_from_slice(&(index as u64).to_le_bytes());
+    aad
+}
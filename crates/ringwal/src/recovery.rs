@@ -1,20 +1,28 @@
 //! WAL recovery and checkpoint support.
 //!
-//! Recovery reads all segment files in order, validates each entry's CRC32
-//! checksum, classifies transactions (committed / aborted / incomplete),
-//! and reports statistics. Partial writes at segment EOF are truncated.
-
-use std::collections::HashMap;
-use std::io::Read;
+//! Recovery reads all segment files in order, decrypting and decompressing
+//! batch frames, validates each entry's CRC32 checksum, classifies
+//! transactions (committed / aborted / incomplete), and reports statistics.
+//! Partial writes at segment EOF are truncated.
+//! [`recover`] collects the transactions streamed by a [`RecoveryReader`].
+
+use std::collections::VecDeque;
+use std::io::{Read, SeekFrom};
 use std::path::Path;
 
 use serde::de::DeserializeOwned;
 
+use crate::codec::CustomCodecs;
+use crate::compression::CompressedFrameHeader;
+use crate::encryption::{EncryptedFrameHeader, Encryption};
 use crate::entry::{WalEntry, WalEntryHeader};
 use crate::error::WalError;
+use crate::invariants::debug_assert_low_water;
 use crate::io::{IoEngine, ReadHandle};
-use crate::segment::{discover_segment_ids, segment_path};
-use crate::writer::reset_tx_id;
+use crate::reader::RecoveryReader;
+use crate::segment::{
+    discover_segment_ids, read_wal_id, segment_path, SegmentFooter, SegmentHeader,
+};
 
 /// Classification of a transaction's recovery outcome.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -36,6 +44,16 @@ pub struct RecoveryStats {
     pub incomplete: usize,
     pub partial_writes: usize,
     pub checksum_failures: usize,
+    /// Segments whose scan stopped at an encrypted frame that matched its
+    /// checksum but failed authentication — modified deliberately rather
+    /// than torn or corrupted. Recovery then fails with
+    /// [`WalError::Tampered`].
+    pub tampered: usize,
+    /// Highest LSN read from a version-2 entry header (0 if none).
+    pub max_lsn: u64,
+    /// Leading segments skipped as already applied (see
+    /// [`RecoveryReader::with_start_lsn`]).
+    pub segments_skipped: usize,
 }
 
 /// Result of recovering a single transaction.
@@ -44,11 +62,24 @@ pub struct RecoveredTransaction<K, V> {
     pub tx_id: u64,
     pub action: RecoveryAction,
     pub entries: Vec<WalEntry<K, V>>,
+    /// LSN of the transaction's first entry (its `Commit` or `Abort` marker
+    /// if it has no other). 0 if the entries carry version-1 headers.
+    pub first_lsn: u64,
+    /// LSN of the transaction's last entry — its `Commit` or `Abort` marker
+    /// when finalized. 0 if the entries carry version-1 headers.
+    pub last_lsn: u64,
 }
 
 /// Recovers WAL state from all segment files in `dir`.
 ///
-/// Returns the set of recovered transactions and aggregate statistics.
+/// Returns the recovered transactions and aggregate statistics.
+/// Transactions are ordered by where they end in the log — committed ones
+/// in durable commit order (the position of their `Commit` record) — and
+/// each transaction's entries are in log order, so replaying the committed
+/// ones front to back reproduces the pre-crash state (INV-WAL-09).
+///
+/// This collects a [`RecoveryReader`] into memory; use the reader directly
+/// to stream large logs, or to read entries of custom codecs.
 pub fn recover<K, V, IO>(
     dir: &Path,
     io: &IO,
@@ -58,206 +89,663 @@ where
     V: DeserializeOwned + Send + 'static,
     IO: IoEngine,
 {
-    let mut segment_ids = discover_segment_ids(dir, io)?;
-    segment_ids.sort_unstable();
+    let mut reader = RecoveryReader::<K, V, IO>::new(dir, io)?;
+    let recovered = reader.by_ref().collect::<Result<Vec<_>, _>>()?;
+    Ok((recovered, reader.into_stats()))
+}
 
-    let mut all_entries: Vec<WalEntry<K, V>> = Vec::new();
-    let mut stats = RecoveryStats::default();
+/// Why a segment scan stopped before the end of the file.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum ScanStop {
+    /// Torn tail: an incomplete header or entry.
+    PartialWrite,
+    /// A checksum mismatch, unknown header version or undecodable entry.
+    Corrupt,
+    /// An encrypted frame that matched its checksum but failed
+    /// authentication.
+    Tampered,
+    /// An encrypted frame that cannot be decrypted: no encryption is
+    /// configured, or its key is missing. Not a property of the data, so
+    /// readers fail rather than treat the log as ending there (see
+    /// [`SegmentCursor::check_readable`]).
+    Unreadable,
+}
 
-    for &seg_id in &segment_ids {
+/// Reads the valid entries of one segment file in order, one at a time,
+/// unpacking encrypted and compressed frames into their entries.
+///
+/// Checks the segment header, if the file has one, against the expected
+/// WAL and segment IDs, and limits the scan to the data before a sealed
+/// segment's footer, verifying the footer's whole-segment checksum before
+/// returning any of its entries. Stops at the first torn or corrupt entry or frame — the tail may be
+/// torn — and records why in [`stop`](Self::stop).
+pub(crate) struct SegmentCursor<R> {
+    file: R,
+    offset: u64,
+    /// Entries of the frame at `frame_offset` not yet returned.
+    frame: VecDeque<(WalEntryHeader, Vec<u8>)>,
+    frame_offset: u64,
+    /// End of the entries: the footer's start, or the file length.
+    data_end: u64,
+    header: Option<SegmentHeader>,
+    footer: Option<SegmentFooter>,
+    /// CRC32 of the segment header, until the rest of a sealed segment is
+    /// checked against the footer.
+    checksum: Option<crc32fast::Hasher>,
+    stop: Option<ScanStop>,
+    /// Decrypts encrypted frames, from the I/O engine.
+    encryption: Option<Encryption>,
+    /// Why an encrypted frame could not be decrypted.
+    unreadable: Option<String>,
+}
+
+impl<R: ReadHandle> SegmentCursor<R> {
+    /// Opens segment `seg_id` of `dir` for reading. A missing file has no
+    /// cursor.
+    ///
+    /// Fails with [`WalError::InvalidSegment`] if the segment header names
+    /// another segment, or another WAL than `wal_id` (when known).
+    pub(crate) fn open<IO>(
+        dir: &Path,
+        seg_id: u64,
+        wal_id: Option<u128>,
+        io: &IO,
+    ) -> Result<Option<Self>, WalError>
+    where
+        IO: IoEngine<ReadHandle = R>,
+    {
         let path = segment_path(dir, seg_id);
-        read_segment_entries(&path, &mut all_entries, &mut stats, io)?;
-    }
+        let file = match io.open_read(&path) {
+            Ok(f) => f,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let file_len = file.metadata_len()?;
+        let mut cursor = Self {
+            file,
+            offset: 0,
+            frame: VecDeque::new(),
+            frame_offset: 0,
+            data_end: file_len,
+            header: None,
+            footer: None,
+            checksum: None,
+            stop: None,
+            encryption: io.encryption().cloned(),
+            unreadable: None,
+        };
+
+        let mut prefix = Vec::with_capacity(SegmentHeader::SIZE);
+        (&mut cursor.file)
+            .take(SegmentHeader::SIZE as u64)
+            .read_to_end(&mut prefix)?;
+        let magic_len = prefix.len().min(SegmentHeader::MAGIC.len());
+        if prefix.is_empty() || prefix[..magic_len] != SegmentHeader::MAGIC[..magic_len] {
+            // Written before segment headers: entries start at offset 0
+            cursor.file.seek(SeekFrom::Start(0))?;
+            return Ok(Some(cursor));
+        }
 
-    // Classify transactions
-    let mut tx_entries: HashMap<u64, Vec<WalEntry<K, V>>> = HashMap::new();
-    let mut tx_status: HashMap<u64, RecoveryAction> = HashMap::new();
-    let mut max_tx_id: u64 = 0;
+        let Ok(bytes) = <[u8; SegmentHeader::SIZE]>::try_from(prefix.as_slice()) else {
+            cursor.stop = Some(ScanStop::PartialWrite);
+            return Ok(Some(cursor));
+        };
+        let header = match SegmentHeader::from_bytes(&bytes) {
+            Ok(header) => header,
+            Err(_) => {
+                cursor.stop = Some(ScanStop::Corrupt);
+                return Ok(Some(cursor));
+            }
+        };
+        if header.segment_id != seg_id {
+            return Err(WalError::InvalidSegment(format!(
+                "{} holds segment {}",
+                path.display(),
+                header.segment_id
+            )));
+        }
+        if let Some(wal_id) = wal_id
+            && header.wal_id != wal_id
+        {
+            return Err(WalError::InvalidSegment(format!(
+                "{} belongs to WAL {:032x}, not {wal_id:032x}",
+                path.display(),
+                header.wal_id
+            )));
+        }
 
-    for entry in all_entries {
-        let tx_id = entry.tx_id();
-        if tx_id > max_tx_id {
-            max_tx_id = tx_id;
+        if let Some(footer) = SegmentFooter::read_from(&mut cursor.file, file_len)? {
+            let mut checksum = crc32fast::Hasher::new();
+            checksum.update(&bytes);
+            cursor.checksum = Some(checksum);
+            cursor.data_end = footer.data_len;
+            cursor.footer = Some(footer);
         }
+        cursor.file.seek(SeekFrom::Start(SegmentHeader::SIZE as u64))?;
+        cursor.header = Some(header);
+        cursor.offset = SegmentHeader::SIZE as u64;
+        Ok(Some(cursor))
+    }
 
-        if entry.is_commit() {
-            tx_status.insert(tx_id, RecoveryAction::Commit);
-        } else if entry.is_abort() {
-            tx_status.insert(tx_id, RecoveryAction::Rollback);
+    /// The segment header, unless the file predates headers or is torn.
+    pub(crate) fn header(&self) -> Option<&SegmentHeader> {
+        self.header.as_ref()
+    }
+
+    /// The footer of a sealed segment.
+    pub(crate) fn footer(&self) -> Option<&SegmentFooter> {
+        self.footer.as_ref()
+    }
+
+    /// Whether the file holds entries without a segment header.
+    pub(crate) fn is_headerless(&self) -> bool {
+        self.header.is_none() && self.stop.is_none() && self.data_end > 0
+    }
+
+    /// Offset of the next entry to read, or of the frame holding it. Reading on from a frame returns its earlier entries again.
+    pub(crate) fn offset(&self) -> u64 {
+        if self.frame.is_empty() {
+            self.offset
         } else {
-            tx_entries.entry(tx_id).or_default().push(entry);
+            self.frame_offset
         }
     }
 
-    // Build recovered transactions
-    let mut recovered = Vec::new();
-    let all_tx_ids: Vec<u64> = tx_entries
-        .keys()
-        .copied()
-        .chain(tx_status.keys().copied())
-        .collect::<std::collections::HashSet<_>>()
-        .into_iter()
-        .collect();
-
-    for tx_id in all_tx_ids {
-        let action = tx_status
-            .get(&tx_id)
-            .copied()
-            .unwrap_or(RecoveryAction::Incomplete);
-        let entries = tx_entries.remove(&tx_id).unwrap_or_default();
-
-        match action {
-            RecoveryAction::Commit => stats.committed += 1,
-            RecoveryAction::Rollback => stats.aborted += 1,
-            RecoveryAction::Incomplete => stats.incomplete += 1,
+    /// Continues the scan from `offset`, which must be the start of an
+    /// entry or frame (e.g. from the footer index or an earlier
+    /// [`offset`](Self::offset)). The whole-segment checksum is no longer
+    /// verified.
+    pub(crate) fn seek_to(&mut self, offset: u64) -> Result<(), WalError> {
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.offset = offset;
+        self.frame.clear();
+        self.checksum = None;
+        Ok(())
+    }
+
+    /// Returns the next valid entry's header and data, or `None` at the end
+    /// of the segment or the first torn or corrupt entry.
+    pub(crate) fn next_entry(&mut self) -> Option<(WalEntryHeader, Vec<u8>)> {
+        if self.stop.is_some() {
+            return None;
+        }
+        if let Some(entry) = self.frame.pop_front() {
+            return Some(entry);
+        }
+        // A sealed segment must match its whole-segment checksum before any
+        // of its entries is used
+        if let Some(checksum) = self.checksum.take()
+            && !self.matches_footer(checksum)
+        {
+            return self.halt(ScanStop::Corrupt);
+        }
+        if self.offset >= self.data_end {
+            return None;
+        }
+
+        // Read header (either version, or a frame's); a short read is a
+        // torn tail
+        let mut prefix = [0u8; WalEntryHeader::V1_SIZE];
+        if self.file.read_exact(&mut prefix).is_err() {
+            return self.halt(ScanStop::PartialWrite);
+        }
+        if CompressedFrameHeader::is_frame(prefix[12])
+            || EncryptedFrameHeader::is_frame(prefix[12])
+        {
+            return self.next_frame(&prefix);
+        }
+        let header = match WalEntryHeader::read_with_prefix(&prefix, &mut self.file) {
+            Ok(header) => header,
+            Err(WalError::InvalidSegment(_)) => return self.halt(ScanStop::Corrupt),
+            Err(_) => return self.halt(ScanStop::PartialWrite),
+        };
+        self.offset += header.encoded_len() as u64;
+
+        // Sanity check: data length must not exceed remaining data
+        if self.offset > self.data_end || header.length > self.data_end - self.offset {
+            return self.halt(ScanStop::PartialWrite);
         }
 
-        recovered.push(RecoveredTransaction {
-            tx_id,
-            action,
-            entries,
-        });
+        // Read entry data
+        let mut data = vec![0u8; header.length as usize];
+        if self.file.read_exact(&mut data).is_err() {
+            return self.halt(ScanStop::PartialWrite);
+        }
+        self.offset += header.length;
+
+        if header.validate(&data).is_err() {
+            return self.halt(ScanStop::Corrupt);
+        }
+        Some((header, data))
     }
 
-    stats.total_transactions = recovered.len();
+    /// Reads the compressed or encrypted frame whose header starts with
+    /// `prefix`, and returns its first entry.
+    fn next_frame(
+        &mut self,
+        prefix: &[u8; CompressedFrameHeader::PREFIX_SIZE],
+    ) -> Option<(WalEntryHeader, Vec<u8>)> {
+        let header = if EncryptedFrameHeader::is_frame(prefix[12]) {
+            EncryptedFrameHeader::read_with_prefix(prefix, &mut self.file).map(FrameHeader::Encrypted)
+        } else {
+            CompressedFrameHeader::read_with_prefix(prefix, &mut self.file).map(FrameHeader::Compressed)
+        };
+        let header = match header {
+            Ok(header) => header,
+            Err(WalError::InvalidSegment(_)) => return self.halt(ScanStop::Corrupt),
+            Err(_) => return self.halt(ScanStop::PartialWrite),
+        };
+        let (size, length) = match &header {
+            FrameHeader::Compressed(h) => (CompressedFrameHeader::SIZE, h.length),
+            FrameHeader::Encrypted(h) => (EncryptedFrameHeader::SIZE, h.length),
+        };
+        let start = self.offset;
+        self.offset += size as u64;
+        if self.offset > self.data_end || length > self.data_end - self.offset {
+            return self.halt(ScanStop::PartialWrite);
+        }
 
-    // Reset TX ID counter to avoid reuse
-    if max_tx_id > 0 {
-        reset_tx_id(max_tx_id + 1);
+        let mut payload = vec![0u8; length as usize];
+        if self.file.read_exact(&mut payload).is_err() {
+            return self.halt(ScanStop::PartialWrite);
+        }
+        self.offset += length;
+
+        let entries = match header {
+            FrameHeader::Compressed(header) => unpack_frame(&header, &payload),
+            FrameHeader::Encrypted(header) => {
+                if header.validate(&payload).is_err() {
+                    return self.halt(ScanStop::Corrupt);
+                }
+                let (Some(encryption), Some(segment)) = (&self.encryption, &self.header) else {
+                    self.unreadable = Some(format!(
+                        "encrypted frame at offset {start} of a segment without {}",
+                        if self.header.is_some() { "encryption configured" } else { "a header" }
+                    ));
+                    return self.halt(ScanStop::Unreadable);
+                };
+                match encryption.open_frame(&header, segment.wal_id, segment.segment_id, start, &payload) {
+                    Ok(records) => unpack_records(&records),
+                    Err(WalError::Tampered(_)) => return self.halt(ScanStop::Tampered),
+                    Err(e) => {
+                        self.unreadable = Some(e.to_string());
+                        return self.halt(ScanStop::Unreadable);
+                    }
+                }
+            }
+        };
+        match entries {
+            Ok(entries) => {
+                self.frame = entries;
+                self.frame_offset = start;
+                self.frame.pop_front()
+            }
+            Err(_) => self.halt(ScanStop::Corrupt),
+        }
     }
 
-    Ok((recovered, stats))
-}
+    /// Rejects the entry just returned as corrupt (e.g. it did not decode),
+    /// ending the scan.
+    pub(crate) fn reject(&mut self) {
+        self.stop = Some(ScanStop::Corrupt);
+    }
 
-/// Reads all valid entries from a single segment file.
-fn read_segment_entries<K, V, IO>(
-    path: &Path,
-    entries: &mut Vec<WalEntry<K, V>>,
-    stats: &mut RecoveryStats,
-    io: &IO,
-) -> Result<(), WalError>
-where
-    K: DeserializeOwned,
-    V: DeserializeOwned,
-    IO: IoEngine,
-{
-    let mut file = match io.open_read(path) {
-        Ok(f) => f,
-        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
-        Err(e) => return Err(e.into()),
-    };
+    /// Why the scan ended early, if it did.
+    pub(crate) fn stop(&self) -> Option<ScanStop> {
+        self.stop
+    }
 
-    let file_len = file.metadata_len()?;
-    let mut offset: u64 = 0;
+    /// Fails with [`WalError::Encryption`] if the scan stopped at a frame
+    /// it could not decrypt: the entries from there on exist but cannot be
+    /// read, so the segment must not be taken to end there.
+    pub(crate) fn check_readable(&self) -> Result<(), WalError> {
+        match &self.unreadable {
+            Some(reason) => Err(WalError::Encryption(reason.clone())),
+            None => Ok(()),
+        }
+    }
 
-    loop {
-        if offset + WalEntryHeader::SIZE as u64 > file_len {
-            if offset < file_len {
-                // Trailing bytes that don't form a complete header
-                stats.partial_writes += 1;
+    /// Reads the entries of a sealed segment through once, from the current
+    /// offset, and checks them against the footer's checksum; `checksum`
+    /// already covers the bytes before the offset. Leaves the file at the
+    /// offset.
+    fn matches_footer(&mut self, mut checksum: crc32fast::Hasher) -> bool {
+        let Some(footer) = &self.footer else {
+            return true;
+        };
+        let mut buf = vec![0u8; 64 * 1024];
+        let mut remaining = self.data_end.saturating_sub(self.offset);
+        while remaining > 0 {
+            let n = buf.len().min(usize::try_from(remaining).unwrap_or(usize::MAX));
+            if self.file.read_exact(&mut buf[..n]).is_err() {
+                return false;
             }
-            break;
+            checksum.update(&buf[..n]);
+            remaining -= n as u64;
         }
+        checksum.finalize() == footer.checksum
+            && self.file.seek(SeekFrom::Start(self.offset)).is_ok()
+    }
 
-        // Read header
-        let mut header_bytes = [0u8; WalEntryHeader::SIZE];
-        if file.read_exact(&mut header_bytes).is_err() {
-            stats.partial_writes += 1;
-            break;
-        }
-        let header = WalEntryHeader::from_bytes(&header_bytes);
-        offset += WalEntryHeader::SIZE as u64;
+    fn halt(&mut self, stop: ScanStop) -> Option<(WalEntryHeader, Vec<u8>)> {
+        self.stop = Some(stop);
+        None
+    }
+}
 
-        // Sanity check: data length must not exceed remaining file
-        if offset + header.length > file_len {
-            stats.partial_writes += 1;
-            break;
-        }
+/// Header of a frame, told apart by the low nibble of byte 12.
+enum FrameHeader {
+    Compressed(CompressedFrameHeader),
+    Encrypted(EncryptedFrameHeader),
+}
 
-        // Read entry data
-        let mut data = vec![0u8; header.length as usize];
-        if file.read_exact(&mut data).is_err() {
-            stats.partial_writes += 1;
-            break;
+/// Verifies and decompresses a frame payload into its entries, checking
+/// each entry's checksum.
+fn unpack_frame(
+    header: &CompressedFrameHeader,
+    payload: &[u8],
+) -> Result<VecDeque<(WalEntryHeader, Vec<u8>)>, WalError> {
+    header.validate(payload)?;
+    let length = usize::try_from(header.uncompressed_length)
+        .map_err(|_| WalError::InvalidSegment("frame too large".into()))?;
+    let data = header.compression.decompress(payload, length)?;
+    unpack_entries(&data)
+}
+
+/// Splits the decrypted records of an encrypted frame — a compressed frame,
+/// or plain entries — into their entries.
+fn unpack_records(data: &[u8]) -> Result<VecDeque<(WalEntryHeader, Vec<u8>)>, WalError> {
+    if data.len() > CompressedFrameHeader::PREFIX_SIZE
+        && CompressedFrameHeader::is_frame(data[CompressedFrameHeader::PREFIX_SIZE - 1])
+    {
+        let (prefix, mut rest) = data.split_at(CompressedFrameHeader::PREFIX_SIZE);
+        let prefix = prefix.try_into().unwrap();
+        let header = CompressedFrameHeader::read_with_prefix(prefix, &mut rest)?;
+        if rest.len() as u64 != header.length {
+            return Err(WalError::InvalidSegment("malformed frame in encrypted frame".into()));
         }
-        offset += header.length;
+        return unpack_frame(&header, rest);
+    }
+    unpack_entries(data)
+}
 
-        // Validate checksum
-        if header.validate(&data).is_err() {
-            stats.checksum_failures += 1;
-            // Stop at first corruption — tail may be torn
-            break;
+/// Parses a run of version-2 entries, checking each entry's checksum.
+fn unpack_entries(mut rest: &[u8]) -> Result<VecDeque<(WalEntryHeader, Vec<u8>)>, WalError> {
+    let mut entries = VecDeque::new();
+    while !rest.is_empty() {
+        let entry = WalEntryHeader::read_from(&mut rest)?;
+        let length = usize::try_from(entry.length).unwrap_or(usize::MAX);
+        if entry.version != WalEntryHeader::VERSION || rest.len() < length {
+            return Err(WalError::InvalidSegment("malformed entry in frame".into()));
         }
+        let (data, tail) = rest.split_at(length);
+        entry.validate(data)?;
+        entries.push_back((entry, data.to_vec()));
+        rest = tail;
+    }
+    if entries.is_empty() {
+        return Err(WalError::InvalidSegment("empty frame".into()));
+    }
+    Ok(entries)
+}
 
-        // Deserialize
-        if let Ok(entry) = bincode::deserialize::<WalEntry<K, V>>(&data) { entries.push(entry) } else {
-            stats.checksum_failures += 1;
-            break;
+/// Returns the LSN of a segment's first entry, or 0 if it has none or the
+/// entry carries a version-1 header. Reads the segment header, or a single
+/// entry of a segment written before headers.
+pub(crate) fn read_segment_first_lsn<IO: IoEngine>(
+    dir: &Path,
+    seg_id: u64,
+    wal_id: Option<u128>,
+    io: &IO,
+) -> Result<u64, WalError> {
+    let Some(mut cursor) = SegmentCursor::open(dir, seg_id, wal_id, io)? else {
+        return Ok(0);
+    };
+    if let Some(header) = cursor.header() {
+        return Ok(header.first_lsn);
+    }
+    Ok(cursor.next_entry().map_or(0, |(header, _)| header.lsn))
+}
+
+/// Returns the highest LSN among the valid entries of a segment, or 0 if it
+/// has none with a version-2 header. A sealed segment's footer answers
+/// without reading the entries.
+fn read_segment_max_lsn<IO: IoEngine>(
+    dir: &Path,
+    seg_id: u64,
+    wal_id: Option<u128>,
+    io: &IO,
+) -> Result<u64, WalError> {
+    let Some(mut cursor) = SegmentCursor::open(dir, seg_id, wal_id, io)? else {
+        return Ok(0);
+    };
+    if let Some(footer) = cursor.footer() {
+        return Ok(footer.last_lsn);
+    }
+    let mut max_lsn = 0;
+    while let Some((header, _)) = cursor.next_entry() {
+        max_lsn = max_lsn.max(header.lsn);
+    }
+    cursor.check_readable()?;
+    Ok(max_lsn)
+}
+
+/// Returns the LSN of the last valid entry in the log, or 0 if no segment
+/// holds a version-2 entry. Scans segments newest first, so only the tail
+/// of the log is read.
+pub(crate) fn last_lsn<IO: IoEngine>(dir: &Path, io: &IO) -> Result<u64, WalError> {
+    let mut segment_ids = discover_segment_ids(dir, io)?;
+    segment_ids.sort_unstable();
+    let wal_id = read_wal_id(dir, io)?;
+    for &seg_id in segment_ids.iter().rev() {
+        let lsn = read_segment_max_lsn(dir, seg_id, wal_id, io)?;
+        if lsn > 0 {
+            return Ok(lsn);
         }
     }
+    Ok(0)
+}
 
-    Ok(())
+/// Format version of the checkpoint file before it held a low-water mark:
+/// `[version: u8][lsn: u64 LE]`.
+const LSN_CHECKPOINT_VERSION: u8 = 2;
+
+/// Encrypted form of [`LSN_CHECKPOINT_VERSION`]:
+/// `[version: u8][lsn: u64 LE, sealed]`, the version byte authenticated.
+const ENCRYPTED_LSN_CHECKPOINT_VERSION: u8 = 3;
+
+/// Format version of the checkpoint file:
+/// `[version: u8][lsn: u64 LE][low_water: u64 LE]`.
+const CHECKPOINT_VERSION: u8 = 4;
+
+/// Format version of an encrypted checkpoint file:
+/// `[version: u8][lsn: u64 LE, low_water: u64 LE, sealed]`, the version byte
+/// authenticated.
+const ENCRYPTED_CHECKPOINT_VERSION: u8 = 5;
+
+/// The two LSNs a checkpoint file records.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct CheckpointMarks {
+    /// Transactions that finished at or before this LSN are applied.
+    pub(crate) lsn: u64,
+    /// The lowest LSN reading past `lsn` still needs: the first entry of the
+    /// oldest transaction open at `lsn`, or `lsn + 1` if none was. Segments
+    /// entirely below it may be removed (or skipped) without cutting into a
+    /// transaction that finishes after `lsn`.
+    pub(crate) low_water: u64,
+}
+
+impl CheckpointMarks {
+    fn decode(data: &[u8]) -> Option<Self> {
+        let lsn = u64::from_le_bytes(data.get(..8)?.try_into().ok()?);
+        let low_water = match data.len() {
+            // Written before the low-water mark: truncation stopped at the
+            // LSN itself.
+            8 => lsn,
+            16 => u64::from_le_bytes(data[8..].try_into().ok()?),
+            _ => return None,
+        };
+        Some(Self { lsn, low_water })
+    }
 }
 
-/// Writes a checkpoint file recording the highest durable LSN.
+/// Writes a checkpoint file recording `lsn`, encrypted if the I/O engine
+/// [encrypts](IoEngine::encryption).
+///
+/// `lsn` is also recorded as the low-water mark, so truncation may remove
+/// every segment below it: call this only at an LSN no transaction is open
+/// across. [`checkpoint`] computes the mark from the log.
 pub fn write_checkpoint<IO: IoEngine>(dir: &Path, lsn: u64, io: &IO) -> Result<(), WalError> {
+    write_checkpoint_marks(dir, CheckpointMarks { lsn, low_water: lsn }, io)
+}
+
+fn write_checkpoint_marks<IO: IoEngine>(
+    dir: &Path,
+    marks: CheckpointMarks,
+    io: &IO,
+) -> Result<(), WalError> {
     let path = dir.join("checkpoint");
-    let data = lsn.to_le_bytes();
+    let lsns = [marks.lsn.to_le_bytes(), marks.low_water.to_le_bytes()].concat();
+    let data = match io.encryption() {
+        Some(encryption) => {
+            let version = [ENCRYPTED_CHECKPOINT_VERSION];
+            [&version[..], &encryption.seal(&version, &lsns)?].concat()
+        }
+        None => [&[CHECKPOINT_VERSION][..], &lsns].concat(),
+    };
     io.write_file_bytes(&path, &data)?;
     Ok(())
 }
 
 /// Reads the last checkpoint LSN, or 0 if no checkpoint exists.
+///
+/// A checkpoint file from before LSNs were persisted held a transaction ID
+/// rather than an LSN; it reads as 0. An unencrypted checkpoint is read
+/// even when the I/O engine encrypts; an encrypted one fails with
+/// [`WalError::Encryption`] if it does not (or lacks the key), and with
+/// [`WalError::Tampered`] if it fails authentication.
 pub fn read_checkpoint<IO: IoEngine>(dir: &Path, io: &IO) -> Result<u64, WalError> {
+    Ok(read_checkpoint_marks(dir, io)?.lsn)
+}
+
+/// Reads the last checkpoint's LSN and low-water mark; both are 0 if no
+/// checkpoint exists.
+pub(crate) fn read_checkpoint_marks<IO: IoEngine>(
+    dir: &Path,
+    io: &IO,
+) -> Result<CheckpointMarks, WalError> {
     let path = dir.join("checkpoint");
-    match io.read_file_bytes(&path) {
-        Ok(data) if data.len() == 8 => {
-            let bytes: [u8; 8] = data.try_into().unwrap();
-            Ok(u64::from_le_bytes(bytes))
+    let data = match io.read_file_bytes(&path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(CheckpointMarks::default()),
+        Err(e) => return Err(e.into()),
+    };
+    match data.first() {
+        Some(&(LSN_CHECKPOINT_VERSION | CHECKPOINT_VERSION)) => {
+            Ok(CheckpointMarks::decode(&data[1..]).unwrap_or_default())
+        }
+        Some(&(ENCRYPTED_LSN_CHECKPOINT_VERSION | ENCRYPTED_CHECKPOINT_VERSION)) => {
+            let encryption = io.encryption().ok_or_else(|| {
+                WalError::Encryption("checkpoint is encrypted but no encryption is configured".into())
+            })?;
+            let lsns = encryption.open(&data[..1], &data[1..])?;
+            CheckpointMarks::decode(&lsns)
+                .ok_or_else(|| WalError::Tampered("checkpoint holds no LSN".into()))
         }
-        Ok(_) => Ok(0),
-        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
-        Err(e) => Err(e.into()),
+        _ => Ok(CheckpointMarks::default()),
     }
 }
 
 /// Advances the checkpoint to the highest committed transaction's LSN.
 ///
-/// Scans all segment files, identifies committed transactions, and writes
-/// a checkpoint at the highest committed LSN. Returns `NoNewCheckpoints`
-/// if no committed transactions exist beyond the current checkpoint.
+/// Streams the segments past the current checkpoint, identifies committed
+/// transactions, and writes a checkpoint at the LSN of the last `Commit` marker. Returns
+/// `NoNewCheckpoints` if no committed transactions exist beyond the current
+/// checkpoint (including logs written only with version-1 headers).
+///
+/// The checkpoint also records a low-water mark: the first LSN of the
+/// oldest transaction still open at the new checkpoint. Truncation never
+/// removes a segment at or past it, so a transaction that commits later
+/// keeps its early entries. Transactions left open by a crash count as
+/// open too; [`Wal::checkpoint`](crate::Wal::checkpoint) knows which of
+/// them can no longer finish and lets them go.
 pub fn checkpoint<K, V, IO>(dir: &Path, io: &IO) -> Result<u64, WalError>
 where
     K: DeserializeOwned + Send + 'static,
     V: DeserializeOwned + Send + 'static,
     IO: IoEngine,
 {
-    let current_lsn = read_checkpoint(dir, io)?;
-    let (recovered, _stats) = recover::<K, V, IO>(dir, io)?;
-
-    // Find the highest tx_id among committed transactions
-    let max_committed_tx_id = recovered
-        .iter()
-        .filter(|t| t.action == RecoveryAction::Commit)
-        .map(|t| t.tx_id)
-        .max();
-
-    match max_committed_tx_id {
-        Some(tx_id) if tx_id > current_lsn => {
-            write_checkpoint(dir, tx_id, io)?;
-            Ok(tx_id)
+    checkpoint_live_from::<K, V, IO>(dir, io, 0, &CustomCodecs::default())
+}
+
+/// [`checkpoint`], treating an unfinished transaction whose last entry is
+/// below `live_from` as abandoned: it does not hold the low-water mark.
+///
+/// `Wal` passes the first LSN it wrote: a transaction its predecessor left
+/// open lost its writer and can never commit, and its custom codecs.
+pub(crate) fn checkpoint_live_from<K, V, IO>(
+    dir: &Path,
+    io: &IO,
+    live_from: u64,
+    custom_codecs: &CustomCodecs,
+) -> Result<u64, WalError>
+where
+    K: DeserializeOwned + Send + 'static,
+    V: DeserializeOwned + Send + 'static,
+    IO: IoEngine,
+{
+    let current = read_checkpoint_marks(dir, io)?;
+
+    // Find the highest commit-marker LSN among committed transactions and
+    // the first LSNs of those still open, reading from the current
+    // low-water mark so open transactions are seen from their first entry
+    let mut max_commit_lsn = None;
+    let mut open_first_lsns = Vec::new();
+    let reader = RecoveryReader::<K, V, IO>::new(dir, io)?
+        .with_start_lsn(current.lsn)
+        .with_low_water(current.low_water)
+        .with_custom_codecs(custom_codecs.clone());
+    for tx in reader {
+        let tx = tx?;
+        match tx.action {
+            RecoveryAction::Commit => max_commit_lsn = max_commit_lsn.max(Some(tx.last_lsn)),
+            RecoveryAction::Incomplete if tx.first_lsn > 0 && tx.last_lsn >= live_from => {
+                open_first_lsns.push(tx.first_lsn);
+            }
+            RecoveryAction::Incomplete | RecoveryAction::Rollback => {}
+        }
+    }
+
+    match max_commit_lsn {
+        Some(lsn) if lsn > current.lsn => {
+            let low_water = open_first_lsns
+                .into_iter()
+                .filter(|&first_lsn| first_lsn <= lsn)
+                .fold(lsn + 1, u64::min);
+            // INV-WAL-12: the mark never passes the checkpoint
+            debug_assert_low_water!(low_water, lsn);
+            write_checkpoint_marks(dir, CheckpointMarks { lsn, low_water }, io)?;
+            Ok(lsn)
         }
         Some(_) | None => Err(WalError::NoNewCheckpoints),
     }
 }
 
-/// Removes segment files whose names indicate an ID strictly less
-/// than the segment containing the given checkpoint LSN.
+/// Removes segment files whose entries all have LSNs strictly less than the
+/// given checkpoint LSN, and than the checkpoint's low-water mark.
+///
+/// The low-water mark (see [`checkpoint`]) caps truncation at the first
+/// entry of the oldest transaction open at the checkpoint, whatever
+/// `checkpoint_lsn` is; without a checkpoint file nothing is removed.
+/// Segments without version-2 entries (written before LSNs were persisted,
+/// or empty) are removed only together with a later segment that qualifies,
+/// since LSNs grow across segments. The last segment is always kept.
 ///
 /// This is a directory-level operation that does not require access to
 /// the live `SegmentManager`. It complements `SegmentManager::truncate_before()`
 /// for use outside the flusher task (e.g., from the checkpoint scheduler).
 pub fn truncate_segments_before<IO: IoEngine>(dir: &Path, checkpoint_lsn: u64, io: &IO) -> Result<usize, WalError> {
+    let checkpoint_lsn = checkpoint_lsn.min(read_checkpoint_marks(dir, io)?.low_water);
     let mut segment_ids = discover_segment_ids(dir, io)?;
     segment_ids.sort_unstable();
 
@@ -266,72 +754,26 @@ pub fn truncate_segments_before<IO: IoEngine>(dir: &Path, checkpoint_lsn: u64, i
         return Ok(0);
     }
 
-    let mut removed = 0;
     // Never remove the last segment — it might be the active one
     let candidates = &segment_ids[..segment_ids.len() - 1];
 
-    for &seg_id in candidates {
-        let path = segment_path(dir, seg_id);
-        // Read the segment to find its max tx_id.
-        // We read each segment cheaply with the raw header parser
-        // to find the highest tx_id. If it's below the checkpoint,
-        // every entry in that segment is already checkpointed.
-        let max_tx_id = read_segment_max_tx_id(&path, io)?;
-        if max_tx_id > 0 && max_tx_id < checkpoint_lsn {
-            let _ = io.remove_file(&path);
-            removed += 1;
-        }
-    }
-
-    Ok(removed)
-}
-
-/// Reads a segment file and returns the maximum `tx_id` found, or 0 if empty/unreadable.
-fn read_segment_max_tx_id<IO: IoEngine>(path: &Path, io: &IO) -> Result<u64, WalError> {
-    let mut file = match io.open_read(path) {
-        Ok(f) => f,
-        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
-        Err(e) => return Err(e.into()),
-    };
-
-    let file_len = file.metadata_len()?;
-    let mut offset: u64 = 0;
-    let mut max_tx_id: u64 = 0;
-
-    loop {
-        if offset + WalEntryHeader::SIZE as u64 > file_len {
-            break;
-        }
-
-        let mut header_bytes = [0u8; WalEntryHeader::SIZE];
-        if file.read_exact(&mut header_bytes).is_err() {
-            break;
+    // Count the leading segments that are fully below the checkpoint
+    let wal_id = read_wal_id(dir, io)?;
+    let mut removable = 0;
+    for (i, &seg_id) in candidates.iter().enumerate() {
+        let max_lsn = read_segment_max_lsn(dir, seg_id, wal_id, io)?;
+        if max_lsn == 0 {
+            continue;
         }
-        let header = WalEntryHeader::from_bytes(&header_bytes);
-        offset += WalEntryHeader::SIZE as u64;
-
-        if offset + header.length > file_len {
-            break;
-        }
-
-        let mut data = vec![0u8; header.length as usize];
-        if file.read_exact(&mut data).is_err() {
-            break;
-        }
-        offset += header.length;
-
-        if header.validate(&data).is_err() {
+        if max_lsn >= checkpoint_lsn {
             break;
         }
+        removable = i + 1;
+    }
 
-        // Deserialize as ByteWalEntry to extract tx_id
-        if let Ok(entry) = bincode::deserialize::<crate::entry::ByteWalEntry>(&data) {
-            let tx_id = entry.tx_id();
-            if tx_id > max_tx_id {
-                max_tx_id = tx_id;
-            }
-        }
+    for &seg_id in &candidates[..removable] {
+        let _ = io.remove_file(&segment_path(dir, seg_id));
     }
 
-    Ok(max_tx_id)
+    Ok(removable)
 }
@@ -0,0 +1,1394 @@
+//! Primary → follower replication over a framed byte stream (TCP).
+//!
+//! A [`ReplicationSource`] ships the primary's segment files to each
+//! connected [`Follower`] as they become durable: byte ranges of whole
+//! records, by segment and offset, from where the follower's copy ends.
+//! The follower appends them to segment files of the same IDs through its
+//! own [`IoEngine`], so it holds the primary's bytes — compressed and
+//! encrypted frames as they are — checks that they read back as the LSNs
+//! the frame names, fsyncs and acknowledges. With
+//! [`WalConfig::replication_acks`] set, the primary's commits return only
+//! once that many followers have acknowledged them (INV-WAL-11).
+//!
+//! # Protocol
+//!
+//! Every message is one frame:
+//!
+//! ```text
+//! [kind: u8][length: u32 LE][payload: length bytes][crc32: u32 LE]
+//! ```
+//!
+//! The CRC covers the kind, length and payload. Frames are:
+//!
+//! | Kind | Direction | Payload |
+//! |------|-----------|---------|
+//! | `HELLO` | follower → primary | `[follower_id: u128 LE][wal_id: u128 LE][last_lsn: u64 LE][segment_id: u64 LE][segment_len: u64 LE]` |
+//! | `SEGMENT` | primary → follower | `[segment_id: u64 LE][offset: u64 LE][first_lsn: u64 LE][last_lsn: u64 LE]` + segment bytes |
+//! | `ACK` | follower → primary | `[lsn: u64 LE]` |
+//! | `ERROR` | either | UTF-8 message; the connection then closes |
+//! | `PART` | primary → follower | as `SEGMENT`, both LSNs 0: the leading bytes of a record too large for one frame |
+//!
+//! `HELLO` names the primary WAL the follower copies (0 before its first
+//! segment) and where its copy ends: its last LSN, and the ID and length of
+//! its newest segment. The primary refuses a follower of another WAL, one
+//! ahead of its durable LSN, and one whose copy does not end with the
+//! record holding that LSN.
+//!
+//! A `SEGMENT` frame holds the whole records — entries, or compressed or
+//! encrypted frames — with LSNs `first_lsn..=last_lsn`, from the segment
+//! header on at offset 0. Once the next segment is in use, a sealed
+//! segment's footer follows in a frame of its own, with both LSNs 0. The
+//! follower acknowledges the `last_lsn` of each frame of records once it is
+//! durable.
+//!
+//! A record too large for one frame goes as `PART` frames followed by a
+//! `SEGMENT` frame with the rest of it and its LSNs. The follower writes
+//! the parts where its copy ends but reads them back, fsyncs and
+//! acknowledges only with that `SEGMENT` frame; it drops an incomplete
+//! record on reconnecting.
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+use crate::config::WalConfig;
+use crate::error::{FlushFailure, FlushStage, WalError};
+use crate::invariants::debug_assert_replicated;
+use crate::io::{FileHandle, IoEngine, ReadHandle};
+use crate::recovery::{ScanStop, SegmentCursor};
+use crate::segment::{
+    discover_segment_ids, read_segment_footer, read_segment_header, read_wal_id, segment_path,
+    write_wal_id, SegmentHeader,
+};
+use crate::subscription::TailCursor;
+use crate::wal::{CommitRegistry, CommitWaiter};
+
+const FRAME_HELLO: u8 = 1;
+const FRAME_SEGMENT: u8 = 2;
+const FRAME_ACK: u8 = 3;
+const FRAME_ERROR: u8 = 4;
+const FRAME_PART: u8 = 5;
+
+/// Largest frame payload accepted.
+const MAX_FRAME_LEN: usize = 64 * 1024 * 1024;
+
+/// Most segment bytes one `SEGMENT` or `PART` frame carries.
+const MAX_FRAME_BYTES: u64 = (MAX_FRAME_LEN - SegmentFrame::PREFIX_SIZE) as u64;
+
+/// A `SEGMENT` frame is sent once its records reach this size, or when no
+/// more durable records are ready.
+const SHIP_BATCH_BYTES: u64 = 1024 * 1024;
+
+/// Commits waiting for follower acknowledgements.
+pub(crate) struct Quorum {
+    /// Number of followers that must acknowledge an LSN.
+    required: usize,
+    /// How long a batch may wait for them.
+    timeout: Option<Duration>,
+    state: Mutex<QuorumState>,
+    /// Wakes [`expire`](Self::expire) for a newly parked batch or the close.
+    expiry: Notify,
+}
+
+struct QuorumState {
+    /// Highest LSN each follower has acknowledged, by follower ID.
+    acks: HashMap<u128, u64>,
+    /// Durable batches by last LSN, in LSN order (and so in deadline
+    /// order), with their deadline and waiters.
+    parked: VecDeque<(u64, Option<Instant>, Vec<CommitWaiter>)>,
+    closed: bool,
+}
+
+impl Quorum {
+    pub(crate) fn new(required: usize, timeout: Option<Duration>) -> Self {
+        Self {
+            required,
+            timeout,
+            expiry: Notify::new(),
+            state: Mutex::new(QuorumState {
+                acks: HashMap::new(),
+                parked: VecDeque::new(),
+                closed: false,
+            }),
+        }
+    }
+
+    /// Completes the waiters of a durable batch ending at `lsn` once enough
+    /// followers have acknowledged it — right away if none are required.
+    pub(crate) fn release_after(&self, lsn: u64, waiters: Vec<CommitWaiter>) {
+        if self.required > 0 {
+            let mut state = self.state.lock().unwrap();
+            if state.closed {
+                let acked = state.acked(lsn);
+                drop(state);
+                for waiter in waiters {
+                    let _ = waiter.send(Err(self.not_replicated(lsn, acked)));
+                }
+                return;
+            }
+            if state.quorum_lsn(self.required) < lsn {
+                let deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+                state.parked.push_back((lsn, deadline, waiters));
+                if deadline.is_some() {
+                    self.expiry.notify_one();
+                }
+                return;
+            }
+        }
+        for waiter in waiters {
+            let _ = waiter.send(Ok(()));
+        }
+    }
+
+    /// Records that `follower` has durably written every entry up to `lsn`,
+    /// completing the batches that now have enough acknowledgements.
+    pub(crate) fn ack(&self, follower: u128, lsn: u64) {
+        let released = {
+            let mut state = self.state.lock().unwrap();
+            let acked = state.acks.entry(follower).or_default();
+            *acked = (*acked).max(lsn);
+            let quorum_lsn = state.quorum_lsn(self.required);
+            let ready = state.parked.partition_point(|&(lsn, ..)| lsn <= quorum_lsn);
+            let released: Vec<_> = state.parked.drain(..ready).collect();
+            for (lsn, ..) in &released {
+                // INV-WAL-11: Verify commits wait for the required followers
+                debug_assert_replicated!(*lsn, quorum_lsn);
+            }
+            released
+        };
+        for (_, _, waiters) in released {
+            for waiter in waiters {
+                let _ = waiter.send(Ok(()));
+            }
+        }
+    }
+
+    /// Fails the batches whose [`WalConfig::replication_timeout`] has
+    /// passed with `NotReplicated`, until the quorum closes. Returns at
+    /// once without a timeout.
+    pub(crate) async fn expire(&self) {
+        if self.timeout.is_none() {
+            return;
+        }
+        loop {
+            let (expired, next) = {
+                let mut state = self.state.lock().unwrap();
+                if state.closed {
+                    return;
+                }
+                let now = Instant::now();
+                let due = state
+                    .parked
+                    .partition_point(|(_, deadline, _)| deadline.is_some_and(|at| at <= now));
+                let expired: Vec<_> = state.parked.drain(..due).collect();
+                let expired: Vec<_> = expired
+                    .into_iter()
+                    .map(|(lsn, _, waiters)| (lsn, state.acked(lsn), waiters))
+                    .collect();
+                (expired, state.parked.front().and_then(|(_, deadline, _)| *deadline))
+            };
+            for (lsn, acked, waiters) in expired {
+                for waiter in waiters {
+                    let _ = waiter.send(Err(self.not_replicated(lsn, acked)));
+                }
+            }
+            match next {
+                Some(deadline) => {
+                    tokio::select! {
+                        () = tokio::time::sleep_until(deadline) => {}
+                        () = self.expiry.notified() => {}
+                    }
+                }
+                None => self.expiry.notified().await,
+            }
+        }
+    }
+
+    /// Fails the batches still waiting with `NotReplicated`; the WAL has
+    /// shut down.
+    pub(crate) fn close(&self) {
+        let parked: Vec<_> = {
+            let mut state = self.state.lock().unwrap();
+            state.closed = true;
+            let parked = std::mem::take(&mut state.parked);
+            parked
+                .into_iter()
+                .map(|(lsn, _, waiters)| (lsn, state.acked(lsn), waiters))
+                .collect()
+        };
+        self.expiry.notify_one();
+        for (lsn, acked, waiters) in parked {
+            for waiter in waiters {
+                let _ = waiter.send(Err(self.not_replicated(lsn, acked)));
+            }
+        }
+    }
+
+    fn not_replicated(&self, lsn: u64, acked: usize) -> WalError {
+        WalError::NotReplicated {
+            lsn,
+            acked,
+            required: self.required,
+        }
+    }
+
+    /// Highest LSN acknowledged so far by each follower.
+    pub(crate) fn acks(&self) -> Vec<(u128, u64)> {
+        let state = self.state.lock().unwrap();
+        let mut acks: Vec<_> = state.acks.iter().map(|(&id, &lsn)| (id, lsn)).collect();
+        acks.sort_unstable();
+        acks
+    }
+}
+
+impl QuorumState {
+    /// Highest LSN acknowledged by at least `required` followers.
+    fn quorum_lsn(&self, required: usize) -> u64 {
+        let mut lsns: Vec<u64> = self.acks.values().copied().collect();
+        lsns.sort_unstable_by(|a, b| b.cmp(a));
+        lsns.get(required.saturating_sub(1)).copied().unwrap_or(0)
+    }
+
+    /// Number of followers that have acknowledged `lsn`.
+    fn acked(&self, lsn: u64) -> usize {
+        self.acks.values().filter(|&&acked| acked >= lsn).count()
+    }
+}
+
+/// A follower's `HELLO`: who it is and where its copy ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Hello {
+    follower_id: u128,
+    /// The primary WAL the follower copies, or 0 before its first segment.
+    wal_id: u128,
+    last_lsn: u64,
+    /// The follower's newest segment and its length, or 0 and 0.
+    segment_id: u64,
+    segment_len: u64,
+}
+
+impl Hello {
+    const SIZE: usize = 56;
+
+    fn to_bytes(self) -> [u8; Self::SIZE] {
+        let mut buf = [0u8; Self::SIZE];
+        buf[0..16].copy_from_slice(&self.follower_id.to_le_bytes());
+        buf[16..32].copy_from_slice(&self.wal_id.to_le_bytes());
+        buf[32..40].copy_from_slice(&self.last_lsn.to_le_bytes());
+        buf[40..48].copy_from_slice(&self.segment_id.to_le_bytes());
+        buf[48..56].copy_from_slice(&self.segment_len.to_le_bytes());
+        buf
+    }
+
+    fn from_bytes(payload: &[u8]) -> Result<Self, WalError> {
+        if payload.len() != Self::SIZE {
+            return Err(protocol_error("malformed HELLO"));
+        }
+        let u64_at = |at: usize| u64::from_le_bytes(payload[at..at + 8].try_into().unwrap());
+        Ok(Self {
+            follower_id: u128::from_le_bytes(payload[0..16].try_into().unwrap()),
+            wal_id: u128::from_le_bytes(payload[16..32].try_into().unwrap()),
+            last_lsn: u64_at(32),
+            segment_id: u64_at(40),
+            segment_len: u64_at(48),
+        })
+    }
+}
+
+/// A `SEGMENT` frame's payload: bytes of segment `segment_id` from
+/// `offset` on, holding LSNs `first_lsn..=last_lsn` (both 0 for a footer
+/// or a `PART` frame).
+#[derive(Debug, PartialEq, Eq)]
+struct SegmentFrame<'a> {
+    segment_id: u64,
+    offset: u64,
+    first_lsn: u64,
+    last_lsn: u64,
+    bytes: &'a [u8],
+}
+
+impl<'a> SegmentFrame<'a> {
+    const PREFIX_SIZE: usize = 32;
+
+    fn encode(&self) -> Vec<u8> {
+        let mut payload = Vec::with_capacity(Self::PREFIX_SIZE + self.bytes.len());
+        payload.extend_from_slice(&self.segment_id.to_le_bytes());
+        payload.extend_from_slice(&self.offset.to_le_bytes());
+        payload.extend_from_slice(&self.first_lsn.to_le_bytes());
+        payload.extend_from_slice(&self.last_lsn.to_le_bytes());
+        payload.extend_from_slice(self.bytes);
+        payload
+    }
+
+    fn decode(payload: &'a [u8]) -> Result<Self, WalError> {
+        if payload.len() <= Self::PREFIX_SIZE {
+            return Err(protocol_error("short SEGMENT frame"));
+        }
+        let u64_at = |at: usize| u64::from_le_bytes(payload[at..at + 8].try_into().unwrap());
+        let frame = Self {
+            segment_id: u64_at(0),
+            offset: u64_at(8),
+            first_lsn: u64_at(16),
+            last_lsn: u64_at(24),
+            bytes: &payload[Self::PREFIX_SIZE..],
+        };
+        if frame.first_lsn > frame.last_lsn || (frame.first_lsn == 0) != (frame.last_lsn == 0) {
+            return Err(protocol_error("SEGMENT frame with a malformed LSN range"));
+        }
+        Ok(frame)
+    }
+
+    fn is_footer(&self) -> bool {
+        self.last_lsn == 0
+    }
+
+    fn end(&self) -> u64 {
+        self.offset + self.bytes.len() as u64
+    }
+}
+
+/// A record of a segment file: an entry, or a compressed or encrypted
+/// frame of entries.
+struct Record {
+    first_lsn: u64,
+    last_lsn: u64,
+    /// Offset just past the record.
+    end: u64,
+}
+
+/// Reads the next whole record from `cursor`, or `None` at the end of the
+/// segment's valid records.
+fn next_record<R: ReadHandle>(cursor: &mut SegmentCursor<R>) -> Option<Record> {
+    let start = cursor.offset();
+    let (header, _) = cursor.next_entry()?;
+    let mut last_lsn = header.lsn;
+    // A frame's entries come one at a time; the offset moves past the frame
+    // with its last one
+    while cursor.offset() == start {
+        let Some((entry, _)) = cursor.next_entry() else {
+            break;
+        };
+        last_lsn = entry.lsn;
+    }
+    Some(Record {
+        first_lsn: header.lsn,
+        last_lsn,
+        end: cursor.offset(),
+    })
+}
+
+/// The primary side of replication, from
+/// [`Wal::replication_source`](crate::Wal::replication_source).
+///
+/// Cheap to clone; each connection is served independently. A connected
+/// follower holds back checkpoint truncation of the segments it has yet to
+/// receive, like a [`Subscription`](crate::Subscription).
+pub struct ReplicationSource<IO: IoEngine> {
+    registry: Arc<CommitRegistry>,
+    dir: PathBuf,
+    io: IO,
+}
+
+impl<IO: IoEngine> Clone for ReplicationSource<IO> {
+    fn clone(&self) -> Self {
+        Self {
+            registry: Arc::clone(&self.registry),
+            dir: self.dir.clone(),
+            io: self.io.clone(),
+        }
+    }
+}
+
+impl<IO: IoEngine> ReplicationSource<IO> {
+    pub(crate) fn new(registry: Arc<CommitRegistry>, dir: &Path, io: IO) -> Self {
+        Self {
+            registry,
+            dir: dir.to_path_buf(),
+            io,
+        }
+    }
+
+    /// Accepts followers on `listener`, serving each on its own task.
+    ///
+    /// Runs until accepting fails, returning that error; abort the task to
+    /// stop listening. An error on a connection ends that connection only
+    /// and is passed to `on_error`, from the connection's task.
+    ///
+    /// ```ignore
+    /// tokio::spawn(source.listen(listener, |e| tracing::warn!("replication: {e}")));
+    /// ```
+    pub async fn listen<F>(self, listener: TcpListener, on_error: F) -> Result<(), WalError>
+    where
+        F: Fn(WalError) + Send + Sync + 'static,
+    {
+        let on_error = Arc::new(on_error);
+        loop {
+            let (stream, _) = listener.accept().await?;
+            stream.set_nodelay(true)?;
+            let source = self.clone();
+            let on_error = Arc::clone(&on_error);
+            tokio::spawn(async move {
+                if let Err(e) = source.serve(stream).await {
+                    on_error(e);
+                }
+            });
+        }
+    }
+
+    /// Serves one follower connection: reads its `HELLO`, ships the durable
+    /// records after the end of the follower's copy and follows new ones,
+    /// recording its acknowledgements.
+    ///
+    /// Returns once the WAL has shut down and the follower has closed the
+    /// connection, or when the follower disconnects. Fails with
+    /// [`WalError::Truncated`] if the records the follower needs were
+    /// already truncated, and with [`WalError::Replication`] if its copy is
+    /// of another WAL, ahead of the durable LSN, or does not match the
+    /// primary's segments; the error is also sent to the follower.
+    pub async fn serve<S>(self, stream: S) -> Result<(), WalError>
+    where
+        S: AsyncRead + AsyncWrite,
+    {
+        let (mut reader, mut writer) = tokio::io::split(stream);
+        let hello = match read_frame(&mut reader).await? {
+            Some((FRAME_HELLO, payload)) => Hello::from_bytes(&payload)?,
+            Some((FRAME_ERROR, message)) => return Err(peer_error(&message)),
+            Some(_) => return Err(protocol_error("expected HELLO")),
+            None => return Ok(()),
+        };
+        let (mut cursor, mut shipper) = match self.start(&hello) {
+            Ok(started) => started,
+            Err(e) => {
+                let _ = write_frame(&mut writer, FRAME_ERROR, e.to_string().as_bytes()).await;
+                return Err(e);
+            }
+        };
+
+        let acks = receive_acks(&mut reader, &self.registry, hello.follower_id);
+        tokio::pin!(acks);
+        tokio::select! {
+            result = ship(&mut cursor, &mut shipper, &mut writer) => {
+                if let Err(e) = result {
+                    let _ = write_frame(&mut writer, FRAME_ERROR, e.to_string().as_bytes()).await;
+                    return Err(e);
+                }
+                // Wait for the acknowledgements of the last records
+                writer.shutdown().await?;
+                acks.await
+            }
+            result = &mut acks => result,
+        }
+    }
+
+    /// Checks the follower's `HELLO` against this WAL and finds where its
+    /// copy ends, holding back truncation from there.
+    fn start(&self, hello: &Hello) -> Result<(TailCursor<IO>, Shipper<IO>), WalError> {
+        let wal_id = read_wal_id(&self.dir, &self.io)?;
+        if hello.wal_id != 0 && Some(hello.wal_id) != wal_id {
+            return Err(WalError::Replication(format!(
+                "follower copies WAL {:032x}, not {:032x}",
+                hello.wal_id,
+                wal_id.unwrap_or(0)
+            )));
+        }
+        if hello.wal_id == 0 && hello.last_lsn > 0 {
+            return Err(WalError::Replication(format!(
+                "follower holds LSNs up to {} of an unknown WAL",
+                hello.last_lsn
+            )));
+        }
+        let mut position = None;
+        let cursor = TailCursor::open_with(
+            Arc::clone(&self.registry),
+            &self.dir,
+            self.io.clone(),
+            |io| {
+                let durable_lsn = self.registry.tail.durable_lsn();
+                if hello.last_lsn > durable_lsn {
+                    return Err(WalError::Replication(format!(
+                        "follower is ahead of the primary: LSN {} > durable LSN {durable_lsn}",
+                        hello.last_lsn
+                    )));
+                }
+                position = Some(resume_position(&self.dir, wal_id, io, hello)?);
+                Ok(hello.last_lsn + 1)
+            },
+        )?;
+        let (segment_id, offset) = position.expect("open_with calls start");
+        let shipper = Shipper {
+            dir: self.dir.clone(),
+            wal_id,
+            io: self.io.clone(),
+            segment_id,
+            offset,
+            split: None,
+        };
+        Ok((cursor, shipper))
+    }
+
+    /// Highest LSN acknowledged by each follower that has connected, by
+    /// follower ID.
+    pub fn follower_lsns(&self) -> Vec<(u128, u64)> {
+        self.registry.quorum.acks()
+    }
+}
+
+/// Finds the segment and offset where the follower's copy ends, checking
+/// that it ends with the record holding its last LSN.
+fn resume_position<IO: IoEngine>(
+    dir: &Path,
+    wal_id: Option<u128>,
+    io: &IO,
+    hello: &Hello,
+) -> Result<(u64, u64), WalError> {
+    let diverged = || {
+        WalError::Replication(format!(
+            "follower's copy ends at offset {} of segment {}, which is not the end of LSN {} on the primary",
+            hello.segment_len, hello.segment_id, hello.last_lsn
+        ))
+    };
+    if hello.last_lsn == 0 {
+        if hello.segment_id != 0 || hello.segment_len != 0 {
+            return Err(diverged());
+        }
+        return Ok((0, 0));
+    }
+    let Some(mut cursor) = SegmentCursor::open(dir, hello.segment_id, wal_id, io)? else {
+        // Truncated since: the follower has it whole if the next one starts
+        // after its last LSN
+        let next = next_segment_id(dir, io, hello.segment_id)?;
+        let first_lsn = match next {
+            Some(next) => read_segment_header(dir, next, io)?.map_or(0, |header| header.first_lsn),
+            None => 0,
+        };
+        if first_lsn <= hello.last_lsn {
+            return Err(diverged());
+        }
+        return Ok((hello.segment_id, hello.segment_len));
+    };
+    if let Some(footer) = cursor.footer() {
+        if hello.segment_len > footer.data_len {
+            // The follower has the footer too
+            let sealed_len = footer.data_len + footer.encoded_len() as u64;
+            if hello.segment_len != sealed_len || footer.last_lsn != hello.last_lsn {
+                return Err(diverged());
+            }
+            return Ok((hello.segment_id, hello.segment_len));
+        }
+        let from = footer.offset_for(hello.last_lsn);
+        if from > SegmentHeader::SIZE as u64 {
+            cursor.seek_to(from)?;
+        }
+    }
+    while let Some(record) = next_record(&mut cursor) {
+        if record.end >= hello.segment_len {
+            if record.end == hello.segment_len && record.last_lsn == hello.last_lsn {
+                return Ok((hello.segment_id, hello.segment_len));
+            }
+            break;
+        }
+    }
+    cursor.check_readable()?;
+    Err(diverged())
+}
+
+/// Returns the lowest segment ID above `after`.
+fn next_segment_id<IO: IoEngine>(dir: &Path, io: &IO, after: u64) -> Result<Option<u64>, WalError> {
+    Ok(discover_segment_ids(dir, io)?
+        .into_iter()
+        .filter(|&id| id > after)
+        .min())
+}
+
+/// Reads the primary's segment files for one follower, from the end of
+/// its copy on.
+struct Shipper<IO: IoEngine> {
+    dir: PathBuf,
+    wal_id: Option<u128>,
+    io: IO,
+    /// Segment being shipped, 0 before the first one.
+    segment_id: u64,
+    /// Bytes of the segment shipped so far.
+    offset: u64,
+    /// A record too large for one frame, while its parts are shipped.
+    split: Option<Record>,
+}
+
+impl<IO: IoEngine> Shipper<IO> {
+    /// Returns the next frame's kind, last LSN (0 for a footer or part)
+    /// and payload, or `None` once every record up to `durable_lsn` has
+    /// been shipped.
+    fn next_frame(&mut self, durable_lsn: u64) -> Result<Option<(u8, u64, Vec<u8>)>, WalError> {
+        loop {
+            if let Some(record) = &self.split {
+                let start = self.offset;
+                let end = record.end.min(start + MAX_FRAME_BYTES);
+                let (kind, first_lsn, last_lsn) = if end == record.end {
+                    (FRAME_SEGMENT, record.first_lsn, record.last_lsn)
+                } else {
+                    (FRAME_PART, 0, 0)
+                };
+                let bytes = self.read_range(start, Some(end))?;
+                self.offset = end;
+                if end == record.end {
+                    self.split = None;
+                }
+                let frame = SegmentFrame {
+                    segment_id: self.segment_id,
+                    offset: start,
+                    first_lsn,
+                    last_lsn,
+                    bytes: &bytes,
+                };
+                return Ok(Some((kind, last_lsn, frame.encode())));
+            }
+
+            let cursor = match self.segment_id {
+                0 => None,
+                id => SegmentCursor::open(&self.dir, id, self.wal_id, &self.io)?,
+            };
+            let Some(mut cursor) = cursor else {
+                // Not started yet, or truncated since: go on with the next one
+                match next_segment_id(&self.dir, &self.io, self.segment_id)? {
+                    Some(next) => {
+                        self.move_to(next);
+                        continue;
+                    }
+                    None => return Ok(None),
+                }
+            };
+            if cursor.is_headerless() {
+                return Err(WalError::Replication(format!(
+                    "segment {} predates segment headers",
+                    self.segment_id
+                )));
+            }
+
+            if let Some(data_len) = cursor.footer().map(|footer| footer.data_len)
+                && self.offset >= data_len
+            {
+                // Every record shipped: the footer goes once the next
+                // segment is in use, so after the seal's fsync
+                let Some(next) = self.next_in_use(durable_lsn)? else {
+                    return Ok(None);
+                };
+                if self.offset > data_len {
+                    self.move_to(next);
+                    continue;
+                }
+                let bytes = self.read_range(data_len, None)?;
+                self.offset = data_len + bytes.len() as u64;
+                let frame = SegmentFrame {
+                    segment_id: self.segment_id,
+                    offset: data_len,
+                    first_lsn: 0,
+                    last_lsn: 0,
+                    bytes: &bytes,
+                };
+                return Ok(Some((FRAME_SEGMENT, 0, frame.encode())));
+            }
+
+            if self.offset > 0 {
+                cursor.seek_to(self.offset)?;
+            }
+            let start = self.offset;
+            let mut lsns = None;
+            let mut end = start;
+            let mut held_back = false;
+            while let Some(record) = next_record(&mut cursor) {
+                if record.last_lsn > durable_lsn || (lsns.is_some() && record.end - start > SHIP_BATCH_BYTES) {
+                    held_back = true;
+                    break;
+                }
+                if lsns.is_none() && record.end - start > MAX_FRAME_BYTES {
+                    self.split = Some(record);
+                    break;
+                }
+                lsns = Some((lsns.map_or(record.first_lsn, |(first, _)| first), record.last_lsn));
+                end = record.end;
+            }
+            if self.split.is_some() {
+                continue;
+            }
+            if let Some((first_lsn, last_lsn)) = lsns {
+                let bytes = self.read_range(start, Some(end))?;
+                self.offset = end;
+                let frame = SegmentFrame {
+                    segment_id: self.segment_id,
+                    offset: start,
+                    first_lsn,
+                    last_lsn,
+                    bytes: &bytes,
+                };
+                return Ok(Some((FRAME_SEGMENT, last_lsn, frame.encode())));
+            }
+            if held_back {
+                return Ok(None);
+            }
+
+            // No more readable records. A sealed segment must end at its
+            // footer; an unsealed one may still be written to.
+            cursor.check_readable()?;
+            if let Some(stop) = cursor.stop()
+                && (cursor.footer().is_some() || matches!(stop, ScanStop::Tampered))
+            {
+                return Err(WalError::Replication(format!(
+                    "segment {} cannot be read at offset {}: {stop:?}",
+                    self.segment_id,
+                    cursor.offset()
+                )));
+            }
+            // It ends here once the next segment is in use: sealed since
+            // the cursor was opened, or left unsealed by a crash
+            let Some(next) = self.next_in_use(durable_lsn)? else {
+                return Ok(None);
+            };
+            if read_segment_footer(&self.dir, self.segment_id, &self.io)?.is_none() {
+                self.move_to(next);
+            }
+        }
+    }
+
+    fn move_to(&mut self, segment_id: u64) {
+        self.segment_id = segment_id;
+        self.offset = 0;
+    }
+
+    /// Returns the segment after the current one if it holds durable
+    /// records: the current one is then complete.
+    fn next_in_use(&self, durable_lsn: u64) -> Result<Option<u64>, WalError> {
+        let Some(next) = next_segment_id(&self.dir, &self.io, self.segment_id)? else {
+            return Ok(None);
+        };
+        let in_use = read_segment_header(&self.dir, next, &self.io)?
+            .is_some_and(|header| header.first_lsn <= durable_lsn);
+        Ok(in_use.then_some(next))
+    }
+
+    /// Reads the current segment's bytes from `start` to `end`, or to the
+    /// end of the file.
+    fn read_range(&self, start: u64, end: Option<u64>) -> Result<Vec<u8>, WalError> {
+        let mut file = self.io.open_read(&segment_path(&self.dir, self.segment_id))?;
+        let end = match end {
+            Some(end) => end,
+            None => file.metadata_len()?,
+        };
+        let len = usize::try_from(end.saturating_sub(start))
+            .map_err(|_| protocol_error("segment range too large"))?;
+        let mut bytes = vec![0u8; len];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+/// Sends the durable records of the segment files until the WAL shuts
+/// down, waking on `cursor` as the durable LSN advances.
+async fn ship<IO, W>(
+    cursor: &mut TailCursor<IO>,
+    shipper: &mut Shipper<IO>,
+    writer: &mut W,
+) -> Result<(), WalError>
+where
+    IO: IoEngine,
+    W: AsyncWrite + Unpin,
+{
+    loop {
+        let Some(durable_lsn) = std::future::poll_fn(|cx| cursor.poll_durable(cx)).await? else {
+            return Ok(());
+        };
+        while let Some((kind, last_lsn, payload)) = shipper.next_frame(durable_lsn)? {
+            write_frame(writer, kind, &payload).await?;
+            if last_lsn == durable_lsn {
+                break;
+            }
+        }
+        // Everything up to the durable LSN is shipped; truncation may go
+        // up to it
+        cursor.skip_to(durable_lsn + 1);
+    }
+}
+
+/// Records the follower's acknowledgements until it closes the connection.
+async fn receive_acks<R>(
+    reader: &mut R,
+    registry: &CommitRegistry,
+    follower: u128,
+) -> Result<(), WalError>
+where
+    R: AsyncRead + Unpin,
+{
+    loop {
+        match read_frame(reader).await? {
+            Some((FRAME_ACK, payload)) if payload.len() == 8 => {
+                registry
+                    .quorum
+                    .ack(follower, u64::from_le_bytes(payload[..].try_into().unwrap()));
+            }
+            Some((FRAME_ERROR, message)) => return Err(peer_error(&message)),
+            Some(_) => return Err(protocol_error("expected ACK")),
+            None => return Ok(()),
+        }
+    }
+}
+
+/// A WAL directory kept as a copy of a primary's log.
+///
+/// Receives the primary's segment bytes from a [`ReplicationSource`] and
+/// appends them to segment files of the same IDs through its own
+/// [`IoEngine`]. Each frame is read back — every record's checksum, and
+/// its LSNs against the frame's — and fsynced before it is acknowledged.
+/// The directory is the primary's, byte for byte up to the last
+/// acknowledged LSN, WAL ID included: [`recover`](crate::recover) reads
+/// it, and it can be opened with [`Wal::open`](crate::Wal::open) to
+/// promote the follower once it is no longer following. A follower of an
+/// encrypted primary needs the same keys to check what it receives.
+///
+/// A failed write or fsync, or a frame that does not read back as sent,
+/// stops the follower (fail-stop, as INV-WAL-08 for the primary): it must
+/// be reopened, which cuts its copy back to the last whole record.
+pub struct Follower<IO: IoEngine> {
+    dir: PathBuf,
+    io: IO,
+    /// Identifies the follower to the primary, from `follower.id`.
+    id: u128,
+    /// The primary WAL copied, once the first segment has arrived.
+    wal_id: Option<u128>,
+    /// The newest segment copied.
+    segment: Option<CopiedSegment<IO>>,
+    last_lsn: u64,
+    failure: Option<FlushFailure>,
+}
+
+/// The follower's copy of the segment being appended to.
+struct CopiedSegment<IO: IoEngine> {
+    id: u64,
+    /// Opened on the first append.
+    file: Option<IO::FileHandle>,
+    /// Bytes copied, read back and fsynced: whole records.
+    len: u64,
+    /// Bytes of a record arriving in `PART` frames, written after `len`
+    /// but not yet checked.
+    pending: u64,
+}
+
+impl<IO: IoEngine> Follower<IO> {
+    /// Opens or creates the follower's WAL directory `config.dir`.
+    ///
+    /// Only `config.dir` is used: segments are copied as the primary wrote
+    /// them. A torn tail of the newest segment is cut off, and a newest
+    /// segment without records removed. The directory must not be open as
+    /// a [`Wal`](crate::Wal) at the same time.
+    pub fn open(config: WalConfig, io: IO) -> Result<Self, WalError> {
+        let dir = config.dir;
+        io.create_dir_all(&dir)?;
+        let id = read_or_create_follower_id(&dir, &io)?;
+        let mut wal_id = read_wal_id(&dir, &io)?;
+        let mut segment_ids = discover_segment_ids(&dir, &io)?;
+        segment_ids.sort_unstable();
+
+        let mut segment = None;
+        let mut last_lsn = 0;
+        while let Some(segment_id) = segment_ids.pop() {
+            let path = segment_path(&dir, segment_id);
+            let (len, lsn) = copied_extent(&dir, segment_id, wal_id, &io)?;
+            if lsn == 0 {
+                io.remove_file(&path)?;
+                continue;
+            }
+            if io.open_read(&path)?.metadata_len()? > len {
+                io.truncate_file(&path, len)?;
+            }
+            if wal_id.is_none()
+                && let Some(header) = read_segment_header(&dir, segment_id, &io)?
+            {
+                write_wal_id(&dir, header.wal_id, &io)?;
+                wal_id = Some(header.wal_id);
+            }
+            segment = Some(CopiedSegment {
+                id: segment_id,
+                file: None,
+                len,
+                pending: 0,
+            });
+            last_lsn = lsn;
+            break;
+        }
+        Ok(Self {
+            dir,
+            io,
+            id,
+            wal_id,
+            segment,
+            last_lsn,
+            failure: None,
+        })
+    }
+
+    /// Returns the follower's ID, which identifies it to the primary.
+    pub fn id(&self) -> u128 {
+        self.id
+    }
+
+    /// Returns the LSN of the last entry the follower has made durable.
+    pub fn last_lsn(&self) -> u64 {
+        self.last_lsn
+    }
+
+    /// Connects to the primary at `addr` and follows it; see [`run`](Self::run).
+    pub async fn connect(&mut self, addr: impl ToSocketAddrs) -> Result<(), WalError> {
+        let stream = TcpStream::connect(addr).await?;
+        stream.set_nodelay(true)?;
+        self.run(stream).await
+    }
+
+    /// Follows the primary on `stream`, from the end of the follower's
+    /// copy, until the primary shuts down.
+    ///
+    /// Each `SEGMENT` frame must continue the copy where it ends; it is
+    /// written, read back, fsynced and then acknowledged with its last
+    /// LSN. Fails with `Replication` if the primary refuses the copy or
+    /// sends a frame out of place, with `FlushFailed` on a write or fsync
+    /// error or a frame that does not read back as sent, and with
+    /// `Poisoned` on every later call after that.
+    pub async fn run<S>(&mut self, mut stream: S) -> Result<(), WalError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        if let Some(failure) = &self.failure {
+            return Err(WalError::Poisoned(failure.clone()));
+        }
+        // The primary resends a record left incomplete by a disconnect
+        if let Some(segment) = &mut self.segment
+            && segment.pending > 0
+        {
+            segment.file = None;
+            self.io.truncate_file(&segment_path(&self.dir, segment.id), segment.len)?;
+            segment.pending = 0;
+        }
+        let hello = Hello {
+            follower_id: self.id,
+            wal_id: self.wal_id.unwrap_or(0),
+            last_lsn: self.last_lsn,
+            segment_id: self.segment.as_ref().map_or(0, |segment| segment.id),
+            segment_len: self.segment.as_ref().map_or(0, |segment| segment.len),
+        };
+        write_frame(&mut stream, FRAME_HELLO, &hello.to_bytes()).await?;
+
+        loop {
+            let applied = match read_frame(&mut stream).await? {
+                Some((FRAME_SEGMENT, payload)) => self.apply(&payload),
+                Some((FRAME_PART, payload)) => self.apply_part(&payload).map(|()| 0),
+                Some((FRAME_ERROR, message)) => return Err(peer_error(&message)),
+                Some(_) => return Err(protocol_error("expected SEGMENT")),
+                None => return Ok(()),
+            };
+            let lsn = match applied {
+                Ok(lsn) => lsn,
+                Err(e) => {
+                    let _ = write_frame(&mut stream, FRAME_ERROR, e.to_string().as_bytes()).await;
+                    return Err(e);
+                }
+            };
+            // Only LSNs this frame brought are acknowledged
+            if lsn > 0 {
+                write_frame(&mut stream, FRAME_ACK, &lsn.to_le_bytes()).await?;
+            }
+        }
+    }
+
+    /// Checks where a `SEGMENT` payload goes, then writes, verifies and
+    /// fsyncs it, with the parts of its first record if that came in
+    /// `PART` frames. Returns its last LSN, 0 for a footer.
+    fn apply(&mut self, payload: &[u8]) -> Result<u64, WalError> {
+        let frame = SegmentFrame::decode(payload)?;
+        let (len, pending) = self.place(&frame)?;
+        if frame.is_footer() {
+            if len == 0 || pending > 0 {
+                return Err(protocol_error("footer of a segment without records"));
+            }
+        } else if frame.first_lsn <= self.last_lsn {
+            return Err(protocol_error("SEGMENT frame LSNs out of order"));
+        }
+        if frame.offset == 0 {
+            self.start_segment(&frame)?;
+        }
+
+        let lsns = (frame.first_lsn, frame.last_lsn);
+        if let Err(e) = self.append(frame.bytes) {
+            return Err(self.fail(FlushStage::Write, &e, lsns));
+        }
+        if let Err(e) = self.verify(&frame, len) {
+            return Err(self.fail(FlushStage::Write, &e, lsns));
+        }
+        let segment = self.segment.as_mut().expect("append opened the segment");
+        if let Some(file) = &mut segment.file
+            && let Err(e) = file.sync_all()
+        {
+            return Err(self.fail(FlushStage::Sync, &e.into(), lsns));
+        }
+        segment.len = frame.end();
+        segment.pending = 0;
+        if !frame.is_footer() {
+            self.last_lsn = frame.last_lsn;
+        }
+        Ok(frame.last_lsn)
+    }
+
+    /// Writes a `PART` payload after the copy; it is checked and fsynced
+    /// with the `SEGMENT` frame that completes its record.
+    fn apply_part(&mut self, payload: &[u8]) -> Result<(), WalError> {
+        let frame = SegmentFrame::decode(payload)?;
+        if !frame.is_footer() {
+            return Err(protocol_error("PART frame with LSNs"));
+        }
+        self.place(&frame)?;
+        if frame.offset == 0 {
+            self.start_segment(&frame)?;
+        }
+        if let Err(e) = self.append(frame.bytes) {
+            return Err(self.fail(FlushStage::Write, &e, (0, 0)));
+        }
+        let segment = self.segment.as_mut().expect("append opened the segment");
+        segment.pending += frame.bytes.len() as u64;
+        Ok(())
+    }
+
+    /// Checks that `frame` continues the copy: the current segment where
+    /// its bytes end, or the start of a later one. Returns the checked
+    /// length of the segment and the length of the parts after it.
+    fn place(&self, frame: &SegmentFrame<'_>) -> Result<(u64, u64), WalError> {
+        let (len, pending) = match &self.segment {
+            Some(segment) if segment.id == frame.segment_id => (segment.len, segment.pending),
+            Some(segment) if segment.id > frame.segment_id => {
+                return Err(protocol_error("SEGMENT frame for an earlier segment"));
+            }
+            Some(segment) if segment.pending > 0 => {
+                return Err(protocol_error("SEGMENT frame before the rest of a record"));
+            }
+            _ => (0, 0),
+        };
+        if frame.offset != len + pending {
+            return Err(protocol_error(&format!(
+                "SEGMENT frame at offset {} of segment {}, which holds {} bytes",
+                frame.offset,
+                frame.segment_id,
+                len + pending
+            )));
+        }
+        Ok((len, pending))
+    }
+
+    /// Starts copying a new segment from a frame at offset 0, which must
+    /// begin with the segment's header. The first segment's header names
+    /// the primary WAL from then on.
+    fn start_segment(&mut self, frame: &SegmentFrame<'_>) -> Result<(), WalError> {
+        let header = frame
+            .bytes
+            .get(..SegmentHeader::SIZE)
+            .and_then(|bytes| SegmentHeader::from_bytes(bytes.try_into().unwrap()).ok())
+            .ok_or_else(|| protocol_error("segment without a header"))?;
+        if header.segment_id != frame.segment_id {
+            return Err(protocol_error("segment header of another segment"));
+        }
+        match self.wal_id {
+            Some(wal_id) if wal_id != header.wal_id => {
+                return Err(WalError::Replication(format!(
+                    "primary sent a segment of WAL {:032x}, not {wal_id:032x}",
+                    header.wal_id
+                )));
+            }
+            Some(_) => {}
+            None => {
+                write_wal_id(&self.dir, header.wal_id, &self.io)?;
+                self.wal_id = Some(header.wal_id);
+            }
+        }
+        self.segment = Some(CopiedSegment {
+            id: frame.segment_id,
+            file: None,
+            len: 0,
+            pending: 0,
+        });
+        Ok(())
+    }
+
+    /// Appends `bytes` to the current segment's copy.
+    fn append(&mut self, bytes: &[u8]) -> Result<(), WalError> {
+        let segment = self.segment.as_mut().expect("segment started");
+        let file = match &mut segment.file {
+            Some(file) => file,
+            None => segment
+                .file
+                .insert(self.io.open_append(&segment_path(&self.dir, segment.id), false)?),
+        };
+        file.write_all(bytes)?;
+        file.flush()?;
+        Ok(())
+    }
+
+    /// Reads the bytes from `start` (`frame`'s offset, or where the parts
+    /// of its first record began) back: records with increasing LSNs from
+    /// its first to its last, ending at the frame's end, or for a footer, a
+    /// sealed segment that matches its checksum.
+    fn verify(&self, frame: &SegmentFrame<'_>, start: u64) -> Result<(), WalError> {
+        let mismatch = || {
+            WalError::Replication(format!(
+                "bytes {start}..{} of segment {} do not read back as LSNs {}..={}",
+                frame.end(),
+                frame.segment_id,
+                frame.first_lsn,
+                frame.last_lsn
+            ))
+        };
+        let Some(mut cursor) = SegmentCursor::open(&self.dir, frame.segment_id, self.wal_id, &self.io)?
+        else {
+            return Err(mismatch());
+        };
+        if frame.is_footer() {
+            let sealed = cursor
+                .footer()
+                .is_some_and(|footer| footer.data_len == frame.offset && footer.last_lsn == self.last_lsn);
+            let mut records = 0;
+            while next_record(&mut cursor).is_some() {
+                records += 1;
+            }
+            cursor.check_readable()?;
+            if !sealed || records == 0 || cursor.stop().is_some() {
+                return Err(mismatch());
+            }
+            return Ok(());
+        }
+
+        if start > 0 {
+            cursor.seek_to(start)?;
+        }
+        let mut first_lsn = None;
+        let mut prev_lsn = self.last_lsn;
+        while cursor.offset() < frame.end() {
+            let Some(record) = next_record(&mut cursor) else {
+                break;
+            };
+            if record.first_lsn <= prev_lsn {
+                return Err(mismatch());
+            }
+            first_lsn.get_or_insert(record.first_lsn);
+            prev_lsn = record.last_lsn;
+        }
+        cursor.check_readable()?;
+        if cursor.offset() != frame.end()
+            || first_lsn != Some(frame.first_lsn)
+            || prev_lsn != frame.last_lsn
+        {
+            return Err(mismatch());
+        }
+        Ok(())
+    }
+
+    /// Stops the follower after a failed write, check or fsync.
+    fn fail(&mut self, stage: FlushStage, error: &WalError, lsns: (u64, u64)) -> WalError {
+        let segment_id = self.segment.as_ref().map_or(0, |segment| segment.id);
+        let failure = FlushFailure::new(stage, error, segment_id, lsns);
+        self.failure = Some(failure.clone());
+        WalError::FlushFailed(failure)
+    }
+}
+
+/// Returns the length of the whole records of a copied segment, and the
+/// LSN of the last one (0 if there are none). A sealed segment's footer
+/// counts once the segment matches its checksum.
+fn copied_extent<IO: IoEngine>(
+    dir: &Path,
+    segment_id: u64,
+    wal_id: Option<u128>,
+    io: &IO,
+) -> Result<(u64, u64), WalError> {
+    let Some(mut cursor) = SegmentCursor::open(dir, segment_id, wal_id, io)? else {
+        return Ok((0, 0));
+    };
+    if cursor.is_headerless() {
+        return Err(WalError::InvalidSegment(format!(
+            "{} predates segment headers",
+            segment_path(dir, segment_id).display()
+        )));
+    }
+    let mut extent = (0, 0);
+    while let Some(record) = next_record(&mut cursor) {
+        extent = (record.end, record.last_lsn);
+    }
+    cursor.check_readable()?;
+    if let Some(footer) = cursor.footer() {
+        if cursor.stop().is_some() {
+            return Err(WalError::InvalidSegment(format!(
+                "{} is sealed but does not match its footer",
+                segment_path(dir, segment_id).display()
+            )));
+        }
+        extent.0 = footer.data_len + footer.encoded_len() as u64;
+    }
+    Ok(extent)
+}
+
+/// Returns the follower ID kept in `dir`, creating it if missing.
+fn read_or_create_follower_id<IO: IoEngine>(dir: &Path, io: &IO) -> Result<u128, WalError> {
+    let path = dir.join("follower.id");
+    match io.read_file_bytes(&path) {
+        Ok(data) => {
+            let bytes: [u8; 16] = data.as_slice().try_into().map_err(|_| {
+                WalError::InvalidSegment(format!("malformed follower ID file {}", path.display()))
+            })?;
+            Ok(u128::from_le_bytes(bytes))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let id = rand::random();
+            io.write_file_bytes(&path, &u128::to_le_bytes(id))?;
+            Ok(id)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Writes one frame and flushes it.
+async fn write_frame<W>(writer: &mut W, kind: u8, payload: &[u8]) -> Result<(), WalError>
+where
+    W: AsyncWrite + Unpin,
+{
+    let length = u32::try_from(payload.len())
+        .ok()
+        .filter(|&length| length as usize <= MAX_FRAME_LEN)
+        .ok_or_else(|| protocol_error("frame too large"))?;
+    let mut frame = Vec::with_capacity(payload.len() + 9);
+    frame.push(kind);
+    frame.extend_from_slice(&length.to_le_bytes());
+    frame.extend_from_slice(payload);
+    let crc = crc32fast::hash(&frame);
+    frame.extend_from_slice(&crc.to_le_bytes());
+    writer.write_all(&frame).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Reads one frame, verifying its CRC. Returns `None` if the peer closed
+/// the connection between frames.
+async fn read_frame<R>(reader: &mut R) -> Result<Option<(u8, Vec<u8>)>, WalError>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut prefix = [0u8; 5];
+    if reader.read(&mut prefix[..1]).await? == 0 {
+        return Ok(None);
+    }
+    reader.read_exact(&mut prefix[1..]).await?;
+    let length = u32::from_le_bytes(prefix[1..5].try_into().unwrap()) as usize;
+    if length > MAX_FRAME_LEN {
+        return Err(protocol_error("frame too large"));
+    }
+    let mut payload = vec![0u8; length];
+    reader.read_exact(&mut payload).await?;
+    let mut crc = [0u8; 4];
+    reader.read_exact(&mut crc).await?;
+
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&prefix);
+    hasher.update(&payload);
+    let actual = hasher.finalize();
+    let expected = u32::from_le_bytes(crc);
+    if actual != expected {
+        return Err(WalError::ChecksumMismatch { expected, actual });
+    }
+    Ok(Some((prefix[0], payload)))
+}
+
+fn protocol_error(message: &str) -> WalError {
+    WalError::Replication(format!("protocol error: {message}"))
+}
+
+fn peer_error(message: &[u8]) -> WalError {
+    WalError::Replication(format!("peer: {}", String::from_utf8_lossy(message)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn waiter() -> (CommitWaiter, tokio::sync::oneshot::Receiver<Result<(), WalError>>) {
+        tokio::sync::oneshot::channel()
+    }
+
+    #[test]
+    fn quorum_releases_batches_once_enough_followers_ack() {
+        let quorum = Quorum::new(2, None);
+        let (tx5, mut rx5) = waiter();
+        let (tx9, mut rx9) = waiter();
+        quorum.release_after(5, vec![tx5]);
+        quorum.release_after(9, vec![tx9]);
+
+        quorum.ack(1, 9);
+        assert!(rx5.try_recv().is_err(), "one of two followers acked");
+        quorum.ack(2, 6);
+        assert!(matches!(rx5.try_recv(), Ok(Ok(()))));
+        assert!(rx9.try_recv().is_err());
+
+        quorum.close();
+        assert!(matches!(
+            rx9.try_recv(),
+            Ok(Err(WalError::NotReplicated { lsn: 9, acked: 1, required: 2 }))
+        ));
+        assert_eq!(quorum.acks(), [(1, 9), (2, 6)]);
+    }
+
+    #[tokio::test]
+    async fn frames_round_trip_and_reject_corruption() {
+        let mut buf = Vec::new();
+        write_frame(&mut buf, FRAME_ACK, &42u64.to_le_bytes()).await.unwrap();
+        let frame = read_frame(&mut &buf[..]).await.unwrap().unwrap();
+        assert_eq!(frame, (FRAME_ACK, 42u64.to_le_bytes().to_vec()));
+        assert!(read_frame(&mut &buf[..0]).await.unwrap().is_none());
+
+        buf[6] ^= 0xff;
+        assert!(matches!(
+            read_frame(&mut &buf[..]).await,
+            Err(WalError::ChecksumMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn hello_and_segment_frames_round_trip() {
+        let hello = Hello {
+            follower_id: 7,
+            wal_id: 0xfeed,
+            last_lsn: 42,
+            segment_id: 3,
+            segment_len: 4096,
+        };
+        assert_eq!(Hello::from_bytes(&hello.to_bytes()).unwrap(), hello);
+        assert!(Hello::from_bytes(&hello.to_bytes()[..24]).is_err());
+
+        let frame = SegmentFrame {
+            segment_id: 3,
+            offset: 53,
+            first_lsn: 43,
+            last_lsn: 45,
+            bytes: &[1, 2, 3],
+        };
+        let payload = frame.encode();
+        assert_eq!(SegmentFrame::decode(&payload).unwrap(), frame);
+        assert_eq!(frame.end(), 56);
+
+        // LSNs out of order, or only one of them 0
+        let mut reversed = payload.clone();
+        reversed[16..24].copy_from_slice(&46u64.to_le_bytes());
+        assert!(SegmentFrame::decode(&reversed).is_err());
+        let mut half_footer = payload.clone();
+        half_footer[16..24].copy_from_slice(&0u64.to_le_bytes());
+        assert!(SegmentFrame::decode(&half_footer).is_err());
+        assert!(SegmentFrame::decode(&payload[..SegmentFrame::PREFIX_SIZE]).is_err());
+    }
+}
@@ -1,16 +1,24 @@
 //! WAL segment files and rotation manager.
 //!
-//! Each segment is a sequential log file containing serialized WAL entries
-//! with CRC32-checked headers. The `SegmentManager` handles rotation when
-//! segments exceed the configured maximum size.
-
-use std::io::Write;
+//! Each segment is a sequential log file: a [`SegmentHeader`] identifying
+//! the WAL and segment, then serialized WAL entries with CRC32-checked
+//! headers — or compressed and/or encrypted frames of them — and, once
+//! sealed, a [`SegmentFooter`] with a sparse LSN index and a whole-segment
+//! checksum.
+//! The `SegmentManager` handles rotation when segments exceed the configured
+//! maximum size.
+
+use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
 
+use crate::codec::Codec;
+use crate::compression::{CompressedFrameHeader, Compression, CompressionMetrics};
 use crate::entry::WalEntryHeader;
 use crate::error::WalError;
 use crate::invariants::{debug_assert_segment_id_monotonic, debug_assert_segment_size};
-use crate::io::{IoEngine, FileHandle};
+use crate::io::{IoEngine, FileHandle, ReadHandle, SyncHandle};
 
 /// Metadata for a sealed (immutable) segment.
 #[derive(Debug, Clone)]
@@ -23,6 +31,196 @@ pub struct SegmentMeta {
     pub last_lsn: u64,
 }
 
+/// Fixed header at the start of a segment file.
+///
+/// Written with the segment's first entry, so an empty segment file has no
+/// header. Segments written before headers existed start directly with an
+/// entry and are still read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SegmentHeader {
+    pub version: u8,
+    /// ID of the WAL the segment belongs to (see [`SegmentManager::wal_id`]).
+    pub wal_id: u128,
+    pub segment_id: u64,
+    /// LSN of the segment's first entry.
+    pub first_lsn: u64,
+    /// Creation time in seconds since the Unix epoch.
+    pub created_secs: u64,
+}
+
+impl SegmentHeader {
+    pub const MAGIC: [u8; 8] = *b"RWALSEG\0";
+    pub const VERSION: u8 = 1;
+    /// Encoded size: magic (8) + version (1) + WAL ID (16) + segment ID (8)
+    /// + first LSN (8) + creation time (8) + CRC32 (4).
+    pub const SIZE: usize = 53;
+
+    pub fn new(wal_id: u128, segment_id: u64, first_lsn: u64, created_secs: u64) -> Self {
+        Self {
+            version: Self::VERSION,
+            wal_id,
+            segment_id,
+            first_lsn,
+            created_secs,
+        }
+    }
+
+    /// Encodes the header, with a CRC32 of the preceding fields at the end.
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut buf = [0u8; Self::SIZE];
+        buf[0..8].copy_from_slice(&Self::MAGIC);
+        buf[8] = self.version;
+        buf[9..25].copy_from_slice(&self.wal_id.to_le_bytes());
+        buf[25..33].copy_from_slice(&self.segment_id.to_le_bytes());
+        buf[33..41].copy_from_slice(&self.first_lsn.to_le_bytes());
+        buf[41..49].copy_from_slice(&self.created_secs.to_le_bytes());
+        let checksum = crc32fast::hash(&buf[..49]);
+        buf[49..53].copy_from_slice(&checksum.to_le_bytes());
+        buf
+    }
+
+    /// Decodes and validates a header.
+    pub fn from_bytes(buf: &[u8; Self::SIZE]) -> Result<Self, WalError> {
+        if buf[0..8] != Self::MAGIC {
+            return Err(WalError::InvalidSegment("bad segment magic".into()));
+        }
+        let expected = u32::from_le_bytes(buf[49..53].try_into().unwrap());
+        let actual = crc32fast::hash(&buf[..49]);
+        if expected != actual {
+            return Err(WalError::ChecksumMismatch { expected, actual });
+        }
+        let version = buf[8];
+        if version != Self::VERSION {
+            return Err(WalError::InvalidSegment(format!(
+                "unknown segment format version {version}"
+            )));
+        }
+        Ok(Self {
+            version,
+            wal_id: u128::from_le_bytes(buf[9..25].try_into().unwrap()),
+            segment_id: u64::from_le_bytes(buf[25..33].try_into().unwrap()),
+            first_lsn: u64::from_le_bytes(buf[33..41].try_into().unwrap()),
+            created_secs: u64::from_le_bytes(buf[41..49].try_into().unwrap()),
+        })
+    }
+}
+
+/// Footer appended to a segment when it is sealed.
+///
+/// Laid out as the index — `(lsn, offset)` pairs, 16 bytes each — followed
+/// by a fixed trailer ending in [`MAGIC`](Self::MAGIC), so it is found by
+/// reading the last [`TRAILER_SIZE`](Self::TRAILER_SIZE) bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentFooter {
+    pub entry_count: u64,
+    pub first_lsn: u64,
+    pub last_lsn: u64,
+    /// Length of the segment header and entries; the footer starts here.
+    pub data_len: u64,
+    /// CRC32 of the first `data_len` bytes of the file.
+    pub checksum: u32,
+    /// Sparse index of `(lsn, offset)` for the first entry and then roughly
+    /// every [`INDEX_INTERVAL`](Self::INDEX_INTERVAL) bytes, in LSN order.
+    /// An indexed compressed frame is listed under its first LSN.
+    pub index: Vec<(u64, u64)>,
+}
+
+impl SegmentFooter {
+    pub const MAGIC: [u8; 8] = *b"RWALEND\0";
+    /// Trailer size: index length (4) + entry count (8) + first LSN (8)
+    /// + last LSN (8) + data length (8) + segment CRC32 (4)
+    /// + footer CRC32 (4) + magic (8).
+    pub const TRAILER_SIZE: usize = 52;
+    /// Minimum distance in bytes between indexed entries.
+    pub const INDEX_INTERVAL: u64 = 64 * 1024;
+
+    /// Total encoded size of the footer.
+    pub fn encoded_len(&self) -> usize {
+        self.index.len() * 16 + Self::TRAILER_SIZE
+    }
+
+    /// Encodes the footer. Its own CRC32 covers the index and trailer fields.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.encoded_len());
+        for (lsn, offset) in &self.index {
+            buf.extend_from_slice(&lsn.to_le_bytes());
+            buf.extend_from_slice(&offset.to_le_bytes());
+        }
+        buf.extend_from_slice(&(self.index.len() as u32).to_le_bytes());
+        buf.extend_from_slice(&self.entry_count.to_le_bytes());
+        buf.extend_from_slice(&self.first_lsn.to_le_bytes());
+        buf.extend_from_slice(&self.last_lsn.to_le_bytes());
+        buf.extend_from_slice(&self.data_len.to_le_bytes());
+        buf.extend_from_slice(&self.checksum.to_le_bytes());
+        let footer_checksum = crc32fast::hash(&buf);
+        buf.extend_from_slice(&footer_checksum.to_le_bytes());
+        buf.extend_from_slice(&Self::MAGIC);
+        buf
+    }
+
+    /// Reads the footer at the end of a file of `file_len` bytes. Returns
+    /// `None` if the file has no valid footer (unsealed, or written before
+    /// footers existed).
+    pub fn read_from<R: Read + Seek>(file: &mut R, file_len: u64) -> Result<Option<Self>, WalError> {
+        let trailer_size = Self::TRAILER_SIZE as u64;
+        if file_len < (SegmentHeader::SIZE as u64) + trailer_size {
+            return Ok(None);
+        }
+        file.seek(SeekFrom::Start(file_len - trailer_size))?;
+        let mut trailer = [0u8; Self::TRAILER_SIZE];
+        file.read_exact(&mut trailer)?;
+        if trailer[44..52] != Self::MAGIC {
+            return Ok(None);
+        }
+
+        let field = |at: usize| u64::from_le_bytes(trailer[at..at + 8].try_into().unwrap());
+        let index_len = u64::from(u32::from_le_bytes(trailer[0..4].try_into().unwrap()));
+        let footer_len = index_len * 16 + trailer_size;
+        let data_len = field(28);
+        if footer_len > file_len || data_len != file_len - footer_len {
+            return Ok(None);
+        }
+
+        let mut buf = vec![0u8; (index_len * 16) as usize];
+        file.seek(SeekFrom::Start(data_len))?;
+        file.read_exact(&mut buf)?;
+        buf.extend_from_slice(&trailer[..40]);
+        let expected = u32::from_le_bytes(trailer[40..44].try_into().unwrap());
+        if crc32fast::hash(&buf) != expected {
+            return Ok(None);
+        }
+
+        let index = buf[..(index_len * 16) as usize]
+            .chunks_exact(16)
+            .map(|pair| {
+                (
+                    u64::from_le_bytes(pair[0..8].try_into().unwrap()),
+                    u64::from_le_bytes(pair[8..16].try_into().unwrap()),
+                )
+            })
+            .collect();
+        Ok(Some(Self {
+            entry_count: field(4),
+            first_lsn: field(12),
+            last_lsn: field(20),
+            data_len,
+            checksum: u32::from_le_bytes(trailer[36..40].try_into().unwrap()),
+            index,
+        }))
+    }
+
+    /// Returns the offset to start reading from to reach the entry with the
+    /// given LSN: the last indexed entry at or before it, or the first entry.
+    pub fn offset_for(&self, lsn: u64) -> u64 {
+        let i = self.index.partition_point(|&(indexed, _)| indexed <= lsn);
+        if i == 0 {
+            SegmentHeader::SIZE as u64
+        } else {
+            self.index[i - 1].1
+        }
+    }
+}
+
 /// An active segment file being written to.
 pub struct Segment<IO: IoEngine> {
     pub(crate) id: u64,
@@ -33,11 +231,26 @@ pub struct Segment<IO: IoEngine> {
     pub(crate) entry_count: u64,
     pub(crate) first_lsn: u64,
     pub(crate) last_lsn: u64,
+    pub(crate) wal_id: u128,
+    pub(crate) created_secs: u64,
+    /// Running CRC32 of everything written, for the footer. `None` if the
+    /// file already held data when opened, which then gets no footer.
+    checksum: Option<crc32fast::Hasher>,
+    /// Sparse `(lsn, offset)` index for the footer.
+    index: Vec<(u64, u64)>,
 }
 
 impl<IO: IoEngine> Segment<IO> {
-    /// Opens or creates a segment file via the given I/O engine.
-    pub fn open(id: u64, dir: &Path, max_size: u64, direct_io: bool, io: &IO) -> Result<Self, WalError> {
+    /// Opens or creates a segment file of the WAL `wal_id` via the given
+    /// I/O engine.
+    pub fn open(
+        id: u64,
+        dir: &Path,
+        max_size: u64,
+        direct_io: bool,
+        wal_id: u128,
+        io: &IO,
+    ) -> Result<Self, WalError> {
         let path = segment_path(dir, id);
         let file = io.open_append(&path, direct_io)?;
         let size = file.metadata_len()?;
@@ -51,30 +264,102 @@ impl<IO: IoEngine> Segment<IO> {
             entry_count: 0,
             first_lsn: 0,
             last_lsn: 0,
+            wal_id,
+            created_secs: io.now_secs(),
+            checksum: (size == 0).then(crc32fast::Hasher::new),
+            index: Vec::new(),
         })
     }
 
-    /// Appends a serialized entry (header + data) to this segment.
+    /// Appends an entry encoded with `codec` (version-2 header + data) to
+    /// this segment, preceded by the segment header if this is the file's
+    /// first entry.
     ///
-    /// Returns the number of bytes written (header + data).
-    pub fn append_entry(&mut self, lsn: u64, data: &[u8]) -> Result<usize, WalError> {
-        let header = WalEntryHeader::new(data);
-        let header_bytes = header.to_bytes();
-        let total = WalEntryHeader::SIZE + data.len();
+    /// Returns the number of bytes written.
+    pub fn append_entry(
+        &mut self,
+        lsn: u64,
+        writer_id: u32,
+        codec: Codec,
+        data: &[u8],
+    ) -> Result<usize, WalError> {
+        let start = self.size;
+        self.begin_record(lsn)?;
+        let header = WalEntryHeader::new_with_codec(lsn, writer_id, codec, data);
+        self.write(&header.to_bytes())?;
+        self.write(data)?;
+        Ok(self.end_record(start, (lsn, lsn), 1))
+    }
 
-        self.file.write_all(&header_bytes)?;
-        self.file.write_all(data)?;
+    /// Appends a frame — compressed, encrypted, or both — holding the
+    /// `entry_count` entries with LSNs `lsns`, written as the concatenation
+    /// of `parts` and preceded by the segment header if this is the file's
+    /// first record.
+    ///
+    /// Returns the number of bytes written.
+    pub fn append_frame(
+        &mut self,
+        lsns: (u64, u64),
+        entry_count: u64,
+        parts: &[&[u8]],
+    ) -> Result<usize, WalError> {
+        let start = self.size;
+        self.begin_record(lsns.0)?;
+        for part in parts {
+            self.write(part)?;
+        }
+        Ok(self.end_record(start, lsns, entry_count))
+    }
 
-        self.size += total as u64;
-        self.entry_count += 1;
+    /// Returns the offset the next record will be written at, after the
+    /// segment header if the file is still empty.
+    pub fn next_record_offset(&self) -> u64 {
+        if self.size == 0 {
+            SegmentHeader::SIZE as u64
+        } else {
+            self.size
+        }
+    }
+
+    /// Writes the segment header before the first record, and indexes the
+    /// record starting at `lsn` if it is far enough past the last one.
+    fn begin_record(&mut self, lsn: u64) -> Result<(), WalError> {
+        if self.size == 0 {
+            let header = SegmentHeader::new(self.wal_id, self.id, lsn, self.created_secs);
+            self.write(&header.to_bytes())?;
+        }
+        if self
+            .index
+            .last()
+            .is_none_or(|&(_, offset)| self.size - offset >= SegmentFooter::INDEX_INTERVAL)
+        {
+            self.index.push((lsn, self.size));
+        }
+        Ok(())
+    }
+
+    /// Counts the entries of a record written from `start`, returning its
+    /// size.
+    fn end_record(&mut self, start: u64, lsns: (u64, u64), entry_count: u64) -> usize {
+        let total = (self.size - start) as usize;
+        self.entry_count += entry_count;
         if self.first_lsn == 0 {
-            self.first_lsn = lsn;
+            self.first_lsn = lsns.0;
         }
-        self.last_lsn = lsn;
+        self.last_lsn = lsns.1;
 
         debug_assert_segment_size!(self.size, self.max_size + total as u64);
 
-        Ok(total)
+        total
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), WalError> {
+        self.file.write_all(bytes)?;
+        if let Some(checksum) = &mut self.checksum {
+            checksum.update(bytes);
+        }
+        self.size += bytes.len() as u64;
+        Ok(())
     }
 
     /// Returns `true` if the segment has reached or exceeded its max size.
@@ -98,18 +383,33 @@ impl<IO: IoEngine> Segment<IO> {
         Ok(())
     }
 
-    /// Flushes the `BufWriter` buffer and returns a cloned file handle
+    /// Flushes the `BufWriter` buffer and returns a cloned sync handle
     /// for background `sync_all()` via `spawn_blocking`.
     ///
-    /// The clone shares the same underlying OS file descriptor (`dup()`),
-    /// so `sync_all()` on the clone flushes the same inode.
-    pub fn flush_and_clone_fd(&mut self) -> Result<std::fs::File, WalError> {
+    /// For real files the clone shares the same underlying OS file
+    /// descriptor (`dup()`), so `sync_all()` on the clone flushes the same
+    /// inode.
+    pub fn flush_and_clone_fd(&mut self) -> Result<Box<dyn SyncHandle>, WalError> {
         self.file.flush()?;
-        Ok(self.file.try_clone_file()?)
+        Ok(self.file.try_clone_sync()?)
     }
 
-    /// Seals this segment, returning its metadata. The file is fsynced and closed.
+    /// Seals this segment, returning its metadata. A segment with entries
+    /// gets a [`SegmentFooter`]; the file is then fsynced and closed.
     pub fn seal(mut self) -> Result<SegmentMeta, WalError> {
+        if self.entry_count > 0
+            && let Some(checksum) = self.checksum.take()
+        {
+            let footer = SegmentFooter {
+                entry_count: self.entry_count,
+                first_lsn: self.first_lsn,
+                last_lsn: self.last_lsn,
+                data_len: self.size,
+                checksum: checksum.finalize(),
+                index: std::mem::take(&mut self.index),
+            };
+            self.write(&footer.to_bytes())?;
+        }
         self.fsync()?;
         Ok(SegmentMeta {
             id: self.id,
@@ -130,6 +430,10 @@ pub struct SegmentManager<IO: IoEngine> {
     active: Segment<IO>,
     sealed: Vec<SegmentMeta>,
     next_segment_id: u64,
+    wal_id: u128,
+    codec: Codec,
+    compression: Compression,
+    compression_metrics: Arc<CompressionMetrics>,
     io: IO,
 }
 
@@ -142,8 +446,9 @@ impl<IO: IoEngine> SegmentManager<IO> {
         let mut segment_ids = discover_segment_ids(dir, &io)?;
         segment_ids.sort_unstable();
 
+        let wal_id = read_or_create_wal_id(dir, &segment_ids, &io)?;
         let next_id = segment_ids.last().map_or(1, |&id| id + 1);
-        let active = Segment::open(next_id, dir, max_segment_size, direct_io, &io)?;
+        let active = Segment::open(next_id, dir, max_segment_size, direct_io, wal_id, &io)?;
 
         Ok(Self {
             dir: dir.to_path_buf(),
@@ -152,25 +457,105 @@ impl<IO: IoEngine> SegmentManager<IO> {
             active,
             sealed: Vec::new(),
             next_segment_id: next_id + 1,
+            wal_id,
+            codec: Codec::Bincode,
+            compression: Compression::None,
+            compression_metrics: Arc::default(),
             io,
         })
     }
 
     /// Writes a batch of pre-serialized entries to the active segment.
     ///
-    /// Each entry in `batch` is `(lsn, serialized_data)`.
-    /// Rotates the segment if it would exceed the size limit.
-    pub fn write_batch(&mut self, batch: &[(u64, Vec<u8>)]) -> Result<(), WalError> {
-        for (lsn, data) in batch {
+    /// Each entry in `batch` is `(lsn, writer_id, serialized_data)`, encoded
+    /// with the [`codec`](Self::codec). Rotates the segment if it would
+    /// exceed the size limit.
+    ///
+    /// With a [`compression`](Self::compression) set, the batch is written
+    /// as one compressed frame (so a segment may overrun its size limit by
+    /// one frame), unless compressing does not make it smaller. With an
+    /// I/O engine that [encrypts](IoEngine::encryption), the batch — its
+    /// entries or their compressed frame — is always written as one
+    /// encrypted frame.
+    pub fn write_batch(&mut self, batch: &[(u64, u32, Vec<u8>)]) -> Result<(), WalError> {
+        if self.compression != Compression::None || self.io.encryption().is_some() {
+            return self.write_frame(batch);
+        }
+        self.write_entries(batch)
+    }
+
+    fn write_entries(&mut self, batch: &[(u64, u32, Vec<u8>)]) -> Result<(), WalError> {
+        for (lsn, writer_id, data) in batch {
             // Rotate before writing if current segment is full
             if self.active.needs_rotation() {
                 self.rotate()?;
             }
-            self.active.append_entry(*lsn, data)?;
+            self.active.append_entry(*lsn, *writer_id, self.codec, data)?;
         }
         Ok(())
     }
 
+    fn write_frame(&mut self, batch: &[(u64, u32, Vec<u8>)]) -> Result<(), WalError> {
+        let (Some(first), Some(last)) = (batch.first(), batch.last()) else {
+            return Ok(());
+        };
+        let mut entries = Vec::with_capacity(
+            batch.iter().map(|(_, _, data)| WalEntryHeader::SIZE + data.len()).sum(),
+        );
+        for (lsn, writer_id, data) in batch {
+            let header = WalEntryHeader::new_with_codec(*lsn, *writer_id, self.codec, data);
+            entries.extend_from_slice(&header.to_bytes());
+            entries.extend_from_slice(data);
+        }
+
+        // The records to write: the entries, or a compressed frame of them
+        let mut records = None;
+        if self.compression != Compression::None {
+            let started = Instant::now();
+            let payload = self.compression.compress(&entries)?;
+            let elapsed = started.elapsed();
+            let written = CompressedFrameHeader::SIZE + payload.len();
+            if written < entries.len() {
+                let header = CompressedFrameHeader::new(self.compression, entries.len() as u64, &payload);
+                let mut frame = Vec::with_capacity(written);
+                frame.extend_from_slice(&header.to_bytes());
+                frame.extend_from_slice(&payload);
+                records = Some(frame);
+                self.compression_metrics.record(entries.len(), written, elapsed);
+            } else {
+                self.compression_metrics.record(entries.len(), entries.len(), elapsed);
+            }
+        }
+
+        let Some(encryption) = self.io.encryption() else {
+            return match records {
+                Some(frame) => {
+                    if self.active.needs_rotation() {
+                        self.rotate()?;
+                    }
+                    self.active
+                        .append_frame((first.0, last.0), batch.len() as u64, &[&frame])?;
+                    Ok(())
+                }
+                None => self.write_entries(batch),
+            };
+        };
+        let encryption = encryption.clone();
+        let records = records.unwrap_or(entries);
+
+        if self.active.needs_rotation() {
+            self.rotate()?;
+        }
+        let offset = self.active.next_record_offset();
+        let (header, ciphertext) = encryption.seal_frame(self.wal_id, self.active.id, offset, &records)?;
+        self.active.append_frame(
+            (first.0, last.0),
+            batch.len() as u64,
+            &[&header.to_bytes(), &ciphertext],
+        )?;
+        Ok(())
+    }
+
     /// Flushes and fsyncs the active segment (data + metadata).
     pub fn fsync(&mut self) -> Result<(), WalError> {
         self.active.fsync()
@@ -187,9 +572,9 @@ impl<IO: IoEngine> SegmentManager<IO> {
         Ok(())
     }
 
-    /// Flushes the active segment's `BufWriter` and returns a cloned file
+    /// Flushes the active segment's `BufWriter` and returns a cloned sync
     /// handle for background `sync_all()`.
-    pub fn flush_and_clone_fd(&mut self) -> Result<std::fs::File, WalError> {
+    pub fn flush_and_clone_fd(&mut self) -> Result<Box<dyn SyncHandle>, WalError> {
         self.active.flush_and_clone_fd()
     }
 
@@ -199,7 +584,8 @@ impl<IO: IoEngine> SegmentManager<IO> {
         debug_assert_segment_id_monotonic!(self.active.id, new_id);
         self.next_segment_id += 1;
 
-        let new_segment = Segment::open(new_id, &self.dir, self.max_segment_size, self.direct_io, &self.io)?;
+        let new_segment =
+            Segment::open(new_id, &self.dir, self.max_segment_size, self.direct_io, self.wal_id, &self.io)?;
         let old_segment = std::mem::replace(&mut self.active, new_segment);
         let meta = old_segment.seal()?;
         self.sealed.push(meta);
@@ -211,6 +597,39 @@ impl<IO: IoEngine> SegmentManager<IO> {
         &self.sealed
     }
 
+    /// Returns the ID of this WAL, stamped into every segment header.
+    ///
+    /// Kept in the `wal.id` file of the WAL directory, created on first open.
+    pub fn wal_id(&self) -> u128 {
+        self.wal_id
+    }
+
+    /// Returns the codec recorded in the headers of entries written from
+    /// now on (bincode unless set).
+    pub fn codec(&self) -> Codec {
+        self.codec
+    }
+
+    /// Sets the codec recorded for the entries of later batches.
+    pub fn set_codec(&mut self, codec: Codec) {
+        self.codec = codec;
+    }
+
+    /// Returns the compression applied to later batches (none unless set).
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    /// Sets the compression applied to later batches.
+    pub fn set_compression(&mut self, compression: Compression) {
+        self.compression = compression;
+    }
+
+    /// Returns the totals of the batches compressed so far.
+    pub(crate) fn compression_metrics(&self) -> Arc<CompressionMetrics> {
+        Arc::clone(&self.compression_metrics)
+    }
+
     /// Returns the ID of the active segment.
     pub fn active_segment_id(&self) -> u64 {
         self.active.id
@@ -262,3 +681,141 @@ pub fn discover_segment_ids<IO: IoEngine>(dir: &Path, io: &IO) -> Result<Vec<u64
     }
     Ok(ids)
 }
+
+/// Returns the path of the file holding the WAL ID.
+fn wal_id_path(dir: &Path) -> PathBuf {
+    dir.join("wal.id")
+}
+
+/// Reads the WAL ID of a directory, or `None` if it has none yet.
+pub(crate) fn read_wal_id<IO: IoEngine>(dir: &Path, io: &IO) -> Result<Option<u128>, WalError> {
+    let path = wal_id_path(dir);
+    match io.read_file_bytes(&path) {
+        Ok(data) => {
+            let bytes: [u8; 16] = data.as_slice().try_into().map_err(|_| {
+                WalError::InvalidSegment(format!("malformed WAL ID file {}", path.display()))
+            })?;
+            Ok(Some(u128::from_le_bytes(bytes)))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Returns the directory's WAL ID, creating it if missing. A lost ID file
+/// is restored from the newest segment header so existing segments still
+/// match.
+fn read_or_create_wal_id<IO: IoEngine>(
+    dir: &Path,
+    segment_ids: &[u64],
+    io: &IO,
+) -> Result<u128, WalError> {
+    if let Some(id) = read_wal_id(dir, io)? {
+        return Ok(id);
+    }
+    let mut id = None;
+    for &seg_id in segment_ids.iter().rev() {
+        if let Some(header) = read_segment_header(dir, seg_id, io)? {
+            id = Some(header.wal_id);
+            break;
+        }
+    }
+    let id = id.unwrap_or_else(rand::random);
+    write_wal_id(dir, id, io)?;
+    Ok(id)
+}
+
+/// Records `id` as the directory's WAL ID.
+pub(crate) fn write_wal_id<IO: IoEngine>(dir: &Path, id: u128, io: &IO) -> Result<(), WalError> {
+    io.write_file_bytes(&wal_id_path(dir), &id.to_le_bytes())?;
+    Ok(())
+}
+
+/// Reads a segment's header, or `None` if the file is missing, empty,
+/// torn, or predates segment headers.
+pub fn read_segment_header<IO: IoEngine>(
+    dir: &Path,
+    id: u64,
+    io: &IO,
+) -> Result<Option<SegmentHeader>, WalError> {
+    let mut file = match io.open_read(&segment_path(dir, id)) {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let mut buf = [0u8; SegmentHeader::SIZE];
+    if file.read_exact(&mut buf).is_err() {
+        return Ok(None);
+    }
+    Ok(SegmentHeader::from_bytes(&buf).ok())
+}
+
+/// Reads a sealed segment's footer without decoding its entries, or `None`
+/// if the file is missing or has no valid footer.
+pub fn read_segment_footer<IO: IoEngine>(
+    dir: &Path,
+    id: u64,
+    io: &IO,
+) -> Result<Option<SegmentFooter>, WalError> {
+    let mut file = match io.open_read(&segment_path(dir, id)) {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let file_len = file.metadata_len()?;
+    SegmentFooter::read_from(&mut file, file_len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn segment_header_roundtrip() {
+        let header = SegmentHeader::new(0xfeed_beef, 7, 42, 1_700_000_000);
+        let bytes = header.to_bytes();
+        assert_eq!(SegmentHeader::from_bytes(&bytes).unwrap(), header);
+
+        let mut corrupt = bytes;
+        corrupt[30] ^= 1;
+        assert!(matches!(
+            SegmentHeader::from_bytes(&corrupt),
+            Err(WalError::ChecksumMismatch { .. })
+        ));
+
+        let mut foreign = bytes;
+        foreign[0] = b'X';
+        assert!(matches!(
+            SegmentHeader::from_bytes(&foreign),
+            Err(WalError::InvalidSegment(_))
+        ));
+    }
+
+    #[test]
+    fn segment_footer_roundtrip_and_lookup() {
+        let footer = SegmentFooter {
+            entry_count: 300,
+            first_lsn: 10,
+            last_lsn: 309,
+            data_len: 200_000,
+            checksum: 0xabcd,
+            index: vec![(10, 53), (120, 65_600), (230, 131_200)],
+        };
+        let mut file = vec![0u8; footer.data_len as usize];
+        file.extend_from_slice(&footer.to_bytes());
+        let len = file.len() as u64;
+        let read = SegmentFooter::read_from(&mut std::io::Cursor::new(&file), len).unwrap();
+        assert_eq!(read, Some(footer.clone()));
+
+        assert_eq!(footer.offset_for(5), SegmentHeader::SIZE as u64);
+        assert_eq!(footer.offset_for(10), 53);
+        assert_eq!(footer.offset_for(229), 65_600);
+        assert_eq!(footer.offset_for(309), 131_200);
+
+        // A damaged footer reads as absent
+        let at = footer.data_len as usize + 3;
+        file[at] ^= 1;
+        let read = SegmentFooter::read_from(&mut std::io::Cursor::new(&file), len).unwrap();
+        assert_eq!(read, None);
+    }
+}
@@ -0,0 +1,686 @@
+//! Live tailing of committed transactions.
+//!
+//! A [`Subscription`] replays the committed transactions of the segment
+//! files from a start LSN, then follows new ones as the flusher makes them
+//! durable. The flusher never waits for a subscriber: it hands each written
+//! batch to a shared, bounded [`TailHub`] buffer and publishes the durable
+//! LSN after each sync. A subscriber that falls behind the buffer reads
+//! the entries it missed from the segment files, then switches back.
+
+use std::collections::{HashMap, VecDeque};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use futures_core::Stream;
+use serde::de::DeserializeOwned;
+
+use crate::codec::{Codec, CustomCodecs};
+use crate::entry::WalEntry;
+use crate::error::{FlushFailure, WalError};
+use crate::invariants::debug_assert_tail_durable;
+use crate::io::IoEngine;
+use crate::recovery::{
+    read_checkpoint_marks, read_segment_first_lsn, truncate_segments_before, RecoveredTransaction,
+    RecoveryAction, SegmentCursor,
+};
+use crate::segment::{discover_segment_ids, read_wal_id};
+use crate::wal::CommitRegistry;
+
+/// Maximum number of entries read from a segment file per refill.
+const SEGMENT_READ_BATCH: usize = 256;
+
+/// Entries written by the flusher, shared with the subscriptions.
+///
+/// Holds the durable LSN — every entry at or below it is synced to the
+/// segment files — and, while anyone is subscribed, the most recently
+/// written entries up to `capacity` bytes.
+pub(crate) struct TailHub {
+    state: Mutex<TailState>,
+    capacity: usize,
+    /// Held while truncating and while a subscription starts, so segments
+    /// a new subscription needs are not removed under it.
+    truncation: Mutex<()>,
+}
+
+struct TailState {
+    durable_lsn: u64,
+    /// Written entries, in LSN order.
+    entries: VecDeque<TailEntry>,
+    bytes: usize,
+    /// Every entry written with an LSN at or above `floor` is in `entries`
+    /// (`u64::MAX` until a batch is buffered).
+    floor: u64,
+    /// Next LSN each subscription reads, by subscription ID. Truncation
+    /// keeps the segments holding them.
+    positions: HashMap<u64, u64>,
+    wakers: HashMap<u64, Waker>,
+    next_id: u64,
+    closed: bool,
+    failure: Option<FlushFailure>,
+}
+
+/// What a subscription found when it asked for more entries.
+enum Fetch {
+    /// Entries were buffered, or its position moved.
+    Ready,
+    /// It is caught up; the waker is registered.
+    Pending,
+    /// It is caught up and the WAL stopped, after a failure if any.
+    Closed(Option<FlushFailure>),
+}
+
+impl TailHub {
+    pub(crate) fn new(durable_lsn: u64, capacity: usize) -> Self {
+        Self {
+            state: Mutex::new(TailState {
+                durable_lsn,
+                entries: VecDeque::new(),
+                bytes: 0,
+                floor: u64::MAX,
+                positions: HashMap::new(),
+                wakers: HashMap::new(),
+                next_id: 0,
+                closed: false,
+                failure: None,
+            }),
+            capacity,
+            truncation: Mutex::new(()),
+        }
+    }
+
+    /// Takes the entries of a batch the flusher has written with `codec`, if
+    /// anyone is subscribed, dropping the oldest buffered ones past the
+    /// capacity.
+    pub(crate) fn publish(&self, batch: &mut Vec<(u64, u32, Vec<u8>)>, codec: Codec) {
+        let mut state = self.state.lock().unwrap();
+        if state.positions.is_empty() {
+            return;
+        }
+        if state.entries.is_empty() {
+            state.floor = batch.first().map_or(u64::MAX, |&(lsn, _, _)| lsn);
+        }
+        for (lsn, _, data) in batch.drain(..) {
+            state.bytes += data.len();
+            let data = Arc::new(data);
+            state.entries.push_back(TailEntry { lsn, codec, data });
+        }
+        while state.bytes > self.capacity {
+            let Some(entry) = state.entries.pop_front() else {
+                break;
+            };
+            state.bytes -= entry.data.len();
+            state.floor = entry.lsn + 1;
+        }
+    }
+
+    /// Records that every entry up to `lsn` is durable and wakes the
+    /// subscriptions.
+    pub(crate) fn advance(&self, lsn: u64) {
+        let wakers = {
+            let mut state = self.state.lock().unwrap();
+            state.durable_lsn = state.durable_lsn.max(lsn);
+            std::mem::take(&mut state.wakers)
+        };
+        wakers.into_values().for_each(Waker::wake);
+    }
+
+    /// Ends the subscriptions once they have read every durable entry: the
+    /// flusher stopped, or `failure` poisoned the WAL.
+    pub(crate) fn close(&self, failure: Option<FlushFailure>) {
+        let wakers = {
+            let mut state = self.state.lock().unwrap();
+            state.closed = true;
+            if state.failure.is_none() {
+                state.failure = failure;
+            }
+            std::mem::take(&mut state.wakers)
+        };
+        wakers.into_values().for_each(Waker::wake);
+    }
+
+    /// Removes the segments below `lsn`, keeping those a subscription has
+    /// yet to read.
+    pub(crate) fn truncate_before<IO: IoEngine>(
+        &self,
+        dir: &Path,
+        lsn: u64,
+        io: &IO,
+    ) -> Result<usize, WalError> {
+        let _truncation = self.truncation.lock().unwrap();
+        let held = self
+            .state
+            .lock()
+            .unwrap()
+            .positions
+            .values()
+            .fold(lsn, |lsn, &position| lsn.min(position));
+        truncate_segments_before(dir, held, io)
+    }
+
+    fn register(&self, position: u64) -> u64 {
+        let mut state = self.state.lock().unwrap();
+        let id = state.next_id;
+        state.next_id += 1;
+        state.positions.insert(id, position);
+        id
+    }
+
+    fn unregister(&self, id: u64) {
+        let mut state = self.state.lock().unwrap();
+        state.positions.remove(&id);
+        state.wakers.remove(&id);
+        if state.positions.is_empty() {
+            state.entries.clear();
+            state.bytes = 0;
+            state.floor = u64::MAX;
+        }
+    }
+
+    /// The highest LSN durable so far.
+    pub(crate) fn durable_lsn(&self) -> u64 {
+        self.state.lock().unwrap().durable_lsn
+    }
+
+    /// Returns the durable LSN once it reaches `next_lsn`, registering
+    /// `waker` until then.
+    fn wait_durable(&self, id: u64, next_lsn: u64, waker: &Waker) -> (Fetch, u64) {
+        let mut state = self.state.lock().unwrap();
+        state.positions.insert(id, next_lsn);
+        let durable_lsn = state.durable_lsn;
+        if next_lsn <= durable_lsn {
+            return (Fetch::Ready, durable_lsn);
+        }
+        if state.closed {
+            return (Fetch::Closed(state.failure.clone()), durable_lsn);
+        }
+        state.wakers.insert(id, waker.clone());
+        (Fetch::Pending, durable_lsn)
+    }
+
+    /// Moves the durable entries from `next_lsn` on into `pending` if the
+    /// buffer holds them, advancing `next_lsn`. Registers `waker` when
+    /// there is nothing to read yet.
+    fn fetch(
+        &self,
+        id: u64,
+        next_lsn: &mut u64,
+        pending: &mut VecDeque<TailEntry>,
+        waker: &Waker,
+    ) -> (Fetch, u64) {
+        let mut state = self.state.lock().unwrap();
+        state.positions.insert(id, *next_lsn);
+        let durable_lsn = state.durable_lsn;
+        if *next_lsn > durable_lsn {
+            if state.closed {
+                return (Fetch::Closed(state.failure.clone()), durable_lsn);
+            }
+            state.wakers.insert(id, waker.clone());
+            return (Fetch::Pending, durable_lsn);
+        }
+        if state.floor > *next_lsn {
+            // Behind the buffer: the caller reads the segment files
+            return (Fetch::Ready, durable_lsn);
+        }
+        let start = state.entries.partition_point(|entry| entry.lsn < *next_lsn);
+        for entry in state.entries.range(start..) {
+            if entry.lsn > durable_lsn {
+                break;
+            }
+            // INV-WAL-10: Verify only durable entries reach subscribers
+            debug_assert_tail_durable!(entry.lsn, durable_lsn);
+            pending.push_back(entry.clone());
+        }
+        // LSNs up to the durable one missing from the buffer were never written
+        *next_lsn = durable_lsn + 1;
+        state.positions.insert(id, *next_lsn);
+        (Fetch::Ready, durable_lsn)
+    }
+}
+
+/// A durable entry, as encoded in the segment files.
+#[derive(Clone)]
+pub(crate) struct TailEntry {
+    pub(crate) lsn: u64,
+    pub(crate) codec: Codec,
+    pub(crate) data: Arc<Vec<u8>>,
+}
+
+/// Where a cursor continues reading the segment files.
+#[derive(Clone, Copy)]
+struct Resume {
+    segment_id: u64,
+    /// Offset of the next entry, or `None` to look it up.
+    offset: Option<u64>,
+    /// The cursor's `next_lsn` when the position was recorded.
+    lsn: u64,
+}
+
+/// Reads durable entries in LSN order: from the [`TailHub`] buffer when it
+/// holds them, from the segment files otherwise.
+///
+/// Its position holds back truncation until it is dropped.
+pub(crate) struct TailCursor<IO: IoEngine> {
+    id: u64,
+    registry: Arc<CommitRegistry>,
+    dir: PathBuf,
+    io: IO,
+    wal_id: Option<u128>,
+    /// LSN of the next entry to fetch.
+    next_lsn: u64,
+    resume: Option<Resume>,
+    /// Fetched entries not yet returned.
+    pending: VecDeque<TailEntry>,
+}
+
+impl<IO: IoEngine> TailCursor<IO> {
+    /// Opens a cursor at the LSN `start` returns, which is computed while
+    /// truncation is held off.
+    ///
+    /// Fails with [`WalError::Truncated`] if the segment holding that LSN was
+    /// truncated.
+    pub(crate) fn open_with(
+        registry: Arc<CommitRegistry>,
+        dir: &Path,
+        io: IO,
+        start: impl FnOnce(&IO) -> Result<u64, WalError>,
+    ) -> Result<Self, WalError> {
+        let wal_id = read_wal_id(dir, &io)?;
+        let truncation = registry.tail.truncation.lock().unwrap();
+        let lsn = start(&io)?.max(1);
+        let (next_lsn, resume) = match locate(dir, wal_id, &io, lsn)? {
+            Some((segment_id, first_lsn)) if first_lsn > lsn && segment_id > 1 => {
+                return Err(WalError::Truncated {
+                    requested: lsn,
+                    oldest: first_lsn,
+                });
+            }
+            Some((segment_id, _)) => {
+                let resume = Resume {
+                    segment_id,
+                    offset: None,
+                    lsn,
+                };
+                (lsn, Some(resume))
+            }
+            None => (lsn, None),
+        };
+        let id = registry.tail.register(next_lsn);
+        drop(truncation);
+        Ok(Self {
+            id,
+            registry,
+            dir: dir.to_path_buf(),
+            io,
+            wal_id,
+            next_lsn,
+            resume,
+            pending: VecDeque::new(),
+        })
+    }
+
+    /// Returns the next durable entry, waiting for one with `cx`. Ends with
+    /// `None` once the WAL has shut down, or with `WalError::Poisoned`.
+    pub(crate) fn poll_next_entry(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<TailEntry>, WalError>> {
+        loop {
+            if let Some(entry) = self.pending.pop_front() {
+                return Poll::Ready(Ok(Some(entry)));
+            }
+            let next_lsn = self.next_lsn;
+            let (fetch, durable_lsn) =
+                self.registry
+                    .tail
+                    .fetch(self.id, &mut self.next_lsn, &mut self.pending, cx.waker());
+            match fetch {
+                Fetch::Ready if self.next_lsn == next_lsn => {
+                    // Not buffered any more (or yet): read the segment files
+                    if let Err(e) = self.read_segments(durable_lsn) {
+                        return Poll::Ready(Err(e));
+                    }
+                }
+                Fetch::Ready => {}
+                Fetch::Pending => return Poll::Pending,
+                Fetch::Closed(Some(failure)) => {
+                    return Poll::Ready(Err(WalError::Poisoned(failure)));
+                }
+                Fetch::Closed(None) => return Poll::Ready(Ok(None)),
+            }
+        }
+    }
+
+    /// Returns the durable LSN once it reaches the cursor's position,
+    /// without fetching entries: for readers of the segment files' bytes.
+    /// Ends with `None` once the WAL has shut down, or with
+    /// `WalError::Poisoned`.
+    pub(crate) fn poll_durable(&mut self, cx: &mut Context<'_>) -> Poll<Result<Option<u64>, WalError>> {
+        match self.registry.tail.wait_durable(self.id, self.next_lsn, cx.waker()) {
+            (Fetch::Ready, durable_lsn) => Poll::Ready(Ok(Some(durable_lsn))),
+            (Fetch::Pending, _) => Poll::Pending,
+            (Fetch::Closed(Some(failure)), _) => Poll::Ready(Err(WalError::Poisoned(failure))),
+            (Fetch::Closed(None), _) => Poll::Ready(Ok(None)),
+        }
+    }
+
+    /// Moves the cursor's position to `lsn`: everything before it has been
+    /// read.
+    pub(crate) fn skip_to(&mut self, lsn: u64) {
+        self.next_lsn = lsn;
+        self.resume = None;
+        self.pending.clear();
+    }
+
+    /// Reads up to [`SEGMENT_READ_BATCH`] durable entries from `next_lsn`
+    /// on from the segment files into `pending`.
+    fn read_segments(&mut self, durable_lsn: u64) -> Result<(), WalError> {
+        let (segment_id, offset) = match self.resume.take() {
+            Some(resume) if resume.lsn == self.next_lsn => (resume.segment_id, resume.offset),
+            _ => match locate(&self.dir, self.wal_id, &self.io, self.next_lsn)? {
+                Some((segment_id, _)) => (segment_id, None),
+                None => {
+                    self.next_lsn = durable_lsn + 1;
+                    return Ok(());
+                }
+            },
+        };
+        let Some(mut cursor) = SegmentCursor::open(&self.dir, segment_id, self.wal_id, &self.io)?
+        else {
+            // Truncated after being read to the end; locate again
+            return Ok(());
+        };
+        match offset {
+            Some(offset) => cursor.seek_to(offset)?,
+            None => {
+                if let Some(offset) = cursor.footer().map(|f| f.offset_for(self.next_lsn)) {
+                    cursor.seek_to(offset)?;
+                }
+            }
+        }
+
+        let mut read = 0;
+        loop {
+            let offset = cursor.offset();
+            let Some((header, data)) = cursor.next_entry() else {
+                break;
+            };
+            if header.lsn < self.next_lsn {
+                continue;
+            }
+            if header.lsn > durable_lsn || read == SEGMENT_READ_BATCH {
+                if header.lsn > durable_lsn {
+                    // LSNs up to the durable one not read so far were never written
+                    self.next_lsn = self.next_lsn.max(durable_lsn + 1);
+                }
+                self.resume = Some(Resume {
+                    segment_id,
+                    offset: Some(offset),
+                    lsn: self.next_lsn,
+                });
+                return Ok(());
+            }
+            self.pending.push_back(TailEntry {
+                lsn: header.lsn,
+                codec: header.codec,
+                data: Arc::new(data),
+            });
+            self.next_lsn = header.lsn + 1;
+            read += 1;
+        }
+
+        // End of the segment (or its valid prefix): go on to the next one
+        cursor.check_readable()?;
+        let mut segment_ids = discover_segment_ids(&self.dir, &self.io)?;
+        segment_ids.sort_unstable();
+        match segment_ids.into_iter().find(|&id| id > segment_id) {
+            Some(next) => {
+                self.resume = Some(Resume {
+                    segment_id: next,
+                    offset: None,
+                    lsn: self.next_lsn,
+                });
+            }
+            // Every durable entry is in the files read so far
+            None => self.next_lsn = self.next_lsn.max(durable_lsn + 1),
+        }
+        Ok(())
+    }
+}
+
+impl<IO: IoEngine> Drop for TailCursor<IO> {
+    fn drop(&mut self) {
+        self.registry.tail.unregister(self.id);
+    }
+}
+
+/// A live stream of committed transactions, from [`Wal::subscribe`](crate::Wal::subscribe).
+///
+/// Yields each transaction whose `Commit` record is at or after the start
+/// LSN, in commit order (INV-WAL-09), once that record is durable
+/// (INV-WAL-10), with all its entries. Aborted transactions are skipped.
+/// Entries written before LSNs were persisted (version 1) are not replayed.
+///
+/// The stream ends when the WAL shuts down or is poisoned, after yielding
+/// every durable commit; [`error`](Self::error) then says why it ended
+/// early, if it did. While the subscription lives, checkpoint truncation
+/// keeps the segments it has yet to read.
+pub struct Subscription<K, V, IO: IoEngine> {
+    cursor: TailCursor<IO>,
+    from_lsn: u64,
+    /// Entries of transactions without a `Commit`/`Abort` record yet, with
+    /// their first LSN.
+    open: HashMap<u64, (u64, Vec<WalEntry<K, V>>)>,
+    /// Transactions open before this LSN were left by an earlier instance of
+    /// the WAL and never finish; they are dropped once it is reached (0 when
+    /// done).
+    abandoned_before: u64,
+    custom_codecs: CustomCodecs,
+    error: Option<WalError>,
+    done: bool,
+    _marker: PhantomData<fn() -> (K, V)>,
+}
+
+// Never pinned structurally: every field is moved freely between polls.
+impl<K, V, IO: IoEngine> Unpin for Subscription<K, V, IO> {}
+
+impl<K, V, IO: IoEngine> Subscription<K, V, IO> {
+    /// Starts a subscription at `from_lsn`, for a WAL instance whose first
+    /// LSN is `opened_lsn` and whose custom codecs are `custom_codecs`.
+    ///
+    /// Reads from the checkpoint's low-water mark (INV-WAL-12), so the
+    /// transactions open at `from_lsn` are read from their first entry.
+    /// Below the checkpoint no such mark is known: the log is read from its
+    /// start, and if segments were truncated this fails with
+    /// [`WalError::Truncated`] rather than yield transactions missing their
+    /// first entries.
+    pub(crate) fn new(
+        registry: Arc<CommitRegistry>,
+        dir: &Path,
+        io: IO,
+        from_lsn: u64,
+        opened_lsn: u64,
+        custom_codecs: CustomCodecs,
+    ) -> Result<Self, WalError> {
+        let from_lsn = from_lsn.max(1);
+        let mut checkpoint_lsn = 0;
+        let cursor = TailCursor::open_with(registry, dir, io, |io| {
+            let marks = read_checkpoint_marks(dir, io)?;
+            checkpoint_lsn = marks.lsn;
+            Ok(if from_lsn > marks.lsn { marks.low_water.min(from_lsn) } else { 1 })
+        })
+        .map_err(|e| match e {
+            WalError::Truncated { oldest, .. } => WalError::Truncated {
+                requested: from_lsn,
+                oldest: oldest.max(checkpoint_lsn + 1),
+            },
+            e => e,
+        })?;
+        Ok(Self {
+            cursor,
+            from_lsn,
+            open: HashMap::new(),
+            abandoned_before: opened_lsn,
+            custom_codecs,
+            error: None,
+            done: false,
+            _marker: PhantomData,
+        })
+    }
+
+    /// The error that ended the stream early, if any.
+    pub fn error(&self) -> Option<&WalError> {
+        self.error.as_ref()
+    }
+}
+
+impl<K, V, IO> Subscription<K, V, IO>
+where
+    K: DeserializeOwned,
+    V: DeserializeOwned,
+    IO: IoEngine,
+{
+    /// Adds a fetched entry to its transaction, returning the transaction
+    /// when this is its `Commit` record.
+    fn apply(&mut self, entry: &TailEntry) -> Result<Option<RecoveredTransaction<K, V>>, WalError> {
+        let lsn = entry.lsn;
+        if self.abandoned_before > 0 && lsn >= self.abandoned_before {
+            // Whatever is still open was left by an earlier instance
+            self.open.clear();
+            self.abandoned_before = 0;
+        }
+        let entry: WalEntry<K, V> = self.custom_codecs.decode(entry.codec, &entry.data)?;
+        let tx_id = entry.tx_id();
+        if entry.is_commit() {
+            let (first_lsn, entries) = self.open.remove(&tx_id).unwrap_or((lsn, Vec::new()));
+            if lsn < self.from_lsn {
+                return Ok(None);
+            }
+            return Ok(Some(RecoveredTransaction {
+                tx_id,
+                action: RecoveryAction::Commit,
+                entries,
+                first_lsn,
+                last_lsn: lsn,
+            }));
+        }
+        if entry.is_abort() {
+            self.open.remove(&tx_id);
+        } else {
+            self.open.entry(tx_id).or_insert_with(|| (lsn, Vec::new())).1.push(entry);
+        }
+        Ok(None)
+    }
+}
+
+impl<K, V, IO> Stream for Subscription<K, V, IO>
+where
+    K: DeserializeOwned,
+    V: DeserializeOwned,
+    IO: IoEngine,
+{
+    type Item = RecoveredTransaction<K, V>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        while !this.done {
+            let result = match this.cursor.poll_next_entry(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Ok(None)) => Ok(None),
+                Poll::Ready(Ok(Some(entry))) => match this.apply(&entry) {
+                    Ok(None) => continue,
+                    result => result,
+                },
+                Poll::Ready(Err(e)) => Err(e),
+            };
+            match result {
+                Ok(Some(tx)) => return Poll::Ready(Some(tx)),
+                Ok(None) => this.done = true,
+                Err(e) => {
+                    this.error = Some(e);
+                    this.done = true;
+                }
+            }
+        }
+        Poll::Ready(None)
+    }
+}
+
+/// Finds the segment to read for `lsn`: the last one whose first LSN is at
+/// or below it, or the oldest one if the log starts after it. Returns its
+/// ID and first LSN (0 if unknown), or `None` if there are no segments.
+pub(crate) fn locate<IO: IoEngine>(
+    dir: &Path,
+    wal_id: Option<u128>,
+    io: &IO,
+    lsn: u64,
+) -> Result<Option<(u64, u64)>, WalError> {
+    let mut segment_ids = discover_segment_ids(dir, io)?;
+    segment_ids.sort_unstable();
+    let mut found = None;
+    for segment_id in segment_ids {
+        let first_lsn = read_segment_first_lsn(dir, segment_id, wal_id, io)?;
+        if first_lsn > lsn {
+            found = found.or(Some((segment_id, first_lsn)));
+            break;
+        }
+        if first_lsn > 0 || found.is_none() {
+            found = Some((segment_id, first_lsn));
+        }
+    }
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn batch(lsns: std::ops::RangeInclusive<u64>) -> Vec<(u64, u32, Vec<u8>)> {
+        lsns.map(|lsn| (lsn, 0, vec![0u8; 10])).collect()
+    }
+
+    #[test]
+    fn hub_hands_out_durable_entries_and_evicts_oldest() {
+        let hub = TailHub::new(0, 30);
+        let mut pending = VecDeque::new();
+
+        // Nothing is buffered without a subscriber
+        hub.publish(&mut batch(1..=2), Codec::Bincode);
+        let id = hub.register(1);
+        let mut next_lsn = 1;
+        hub.publish(&mut batch(3..=4), Codec::Bincode);
+        hub.advance(4);
+        let (fetch, durable) = hub.fetch(id, &mut next_lsn, &mut pending, Waker::noop());
+        assert!(matches!(fetch, Fetch::Ready) && durable == 4);
+        assert_eq!(next_lsn, 1, "LSNs 1-2 must come from the segments");
+
+        // Only entries at or below the durable LSN are handed out
+        next_lsn = 3;
+        hub.publish(&mut batch(5..=5), Codec::Bincode);
+        hub.fetch(id, &mut next_lsn, &mut pending, Waker::noop());
+        assert_eq!(pending.iter().map(|e| e.lsn).collect::<Vec<_>>(), [3, 4]);
+        assert_eq!(next_lsn, 5);
+        assert!(matches!(
+            hub.fetch(id, &mut next_lsn, &mut pending, Waker::noop()).0,
+            Fetch::Pending
+        ));
+
+        // Past the capacity the oldest entries go
+        hub.publish(&mut batch(6..=7), Codec::Bincode);
+        hub.advance(7);
+        pending.clear();
+        let mut behind = 4;
+        hub.fetch(id, &mut behind, &mut pending, Waker::noop());
+        assert!(pending.is_empty() && behind == 4);
+        hub.fetch(id, &mut next_lsn, &mut pending, Waker::noop());
+        assert_eq!(pending.iter().map(|e| e.lsn).collect::<Vec<_>>(), [5, 6, 7]);
+
+        hub.close(None);
+        assert!(matches!(
+            hub.fetch(id, &mut next_lsn, &mut pending, Waker::noop()).0,
+            Fetch::Closed(None)
+        ));
+    }
+}
@@ -2,31 +2,170 @@
 
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
 use std::time::Duration;
 
 use serde::{de::DeserializeOwned, Serialize};
 use tokio::sync::{oneshot, Notify};
 use tokio::task::JoinHandle;
 
+use crate::codec::CustomCodecs;
+use crate::compression::{CompressionMetrics, CompressionStats};
 use crate::config::WalConfig;
 use crate::config::SyncMode;
-use crate::error::WalError;
-use crate::io::{IoEngine, RealIo};
+use crate::error::{FlushFailure, FlushStage, WalError};
+use crate::invariants::debug_assert_fail_stop;
+use crate::io::{IoEngine, RealIo, SyncHandle};
 use crate::recovery;
+use crate::replication::{Quorum, ReplicationSource};
 use crate::segment::SegmentManager;
+use crate::subscription::{Subscription, TailHub};
 use crate::writer::{Envelope, WalWriterFactory};
 use ringmpsc_rs::Config as RingConfig;
 use ringmpsc_stream::{channel_with_stream_config, RingReceiver, StreamConfig, StreamExt};
 
+/// Receives the outcome of the batch holding a commit.
+pub(crate) type CommitWaiter = oneshot::Sender<Result<(), WalError>>;
+
 /// Shared state used by the flusher to notify commit waiters.
+///
+/// Holds the fail-stop state (INV-WAL-08): the first batch that failed, by
+/// flush sequence number. Writers check it before every append; the flusher
+/// stops writing once it is set. Completed batches also advance the durable
+/// LSN that subscriptions and followers read up to, and in replicated commit
+/// mode wait in the quorum for follower acknowledgements.
 pub(crate) struct CommitRegistry {
-    _private: (),
+    failure: OnceLock<(u64, FlushFailure)>,
+    /// Sequence number of the next batch allowed to complete, for syncs that
+    /// run off the flusher (pipelined modes).
+    next_to_complete: Mutex<u64>,
+    turn: Condvar,
+    pub(crate) tail: TailHub,
+    pub(crate) quorum: Quorum,
 }
 
 impl CommitRegistry {
     pub(crate) fn new() -> Self {
-        Self { _private: () }
+        Self {
+            failure: OnceLock::new(),
+            next_to_complete: Mutex::new(0),
+            turn: Condvar::new(),
+            tail: TailHub::new(0, 0),
+            quorum: Quorum::new(0, None),
+        }
+    }
+
+    /// Replaces the subscription buffer.
+    pub(crate) fn with_tail(mut self, tail: TailHub) -> Self {
+        self.tail = tail;
+        self
+    }
+
+    /// Makes commits wait for follower acknowledgements.
+    pub(crate) fn with_quorum(mut self, quorum: Quorum) -> Self {
+        self.quorum = quorum;
+        self
+    }
+
+    /// Returns the failure that poisoned the WAL, if any.
+    pub(crate) fn failure(&self) -> Option<&FlushFailure> {
+        self.failure.get().map(|(_, failure)| failure)
+    }
+
+    /// Returns `WalError::Poisoned` once a flush has failed.
+    pub(crate) fn check(&self) -> Result<(), WalError> {
+        match self.failure() {
+            Some(failure) => Err(WalError::Poisoned(failure.clone())),
+            None => Ok(()),
+        }
+    }
+
+    /// Tells the waiters of batch `seq` whether it is durable; on success
+    /// `result` holds the batch's last LSN.
+    ///
+    /// A failed batch poisons the WAL and its waiters get `FlushFailed`. A
+    /// batch that synced after an earlier batch failed is not acknowledged
+    /// either: its waiters get `Poisoned`. A durable batch's waiters are
+    /// handed to the quorum, which acknowledges them once enough followers
+    /// have (at once without replication).
+    pub(crate) fn complete(
+        &self,
+        seq: u64,
+        result: Result<u64, FlushFailure>,
+        waiters: Vec<CommitWaiter>,
+    ) {
+        let mut durable_lsn = None;
+        let failed: Option<(fn(FlushFailure) -> WalError, FlushFailure)> = match result {
+            Err(failure) => {
+                let _ = self.failure.set((seq, failure.clone()));
+                self.tail.close(self.failure().cloned());
+                Some((WalError::FlushFailed, failure))
+            }
+            Ok(last_lsn) => {
+                let failed = self
+                    .failure
+                    .get()
+                    .filter(|(failed_seq, _)| *failed_seq < seq)
+                    .map(|(_, failure)| (WalError::Poisoned as fn(_) -> _, failure.clone()));
+                if failed.is_none() {
+                    self.tail.advance(last_lsn);
+                    durable_lsn = Some(last_lsn);
+                }
+                failed
+            }
+        };
+        // INV-WAL-08: Verify no batch is acknowledged after an earlier failure
+        debug_assert_fail_stop!(
+            failed.is_none(),
+            seq,
+            self.failure.get().map(|(failed_seq, _)| *failed_seq)
+        );
+        match (failed, durable_lsn) {
+            (Some((error, failure)), _) => {
+                for waiter in waiters {
+                    let _ = waiter.send(Err(error(failure.clone())));
+                }
+            }
+            (None, Some(lsn)) => self.quorum.release_after(lsn, waiters),
+            (None, None) => unreachable!("a durable batch has a last LSN"),
+        }
+    }
+
+    /// Like [`complete`](Self::complete), but first waits for every earlier
+    /// batch to complete, so a failure is seen by all later batches.
+    /// Blocks; called from the sync threads of the pipelined modes.
+    pub(crate) fn complete_in_order(
+        &self,
+        seq: u64,
+        result: Result<u64, FlushFailure>,
+        waiters: Vec<CommitWaiter>,
+    ) {
+        let mut next = self.next_to_complete.lock().unwrap();
+        while *next < seq {
+            next = self.turn.wait(next).unwrap();
+        }
+        self.complete(seq, result, waiters);
+        *next = seq + 1;
+        self.turn.notify_all();
+    }
+}
+
+/// Health of a WAL, from [`Wal::health`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WalHealth {
+    /// Appends and commits are accepted.
+    Healthy,
+    /// A flush failed. Every append and commit returns
+    /// [`WalError::Poisoned`] until the WAL is shut down, recovered and
+    /// reopened.
+    Poisoned(FlushFailure),
+}
+
+impl WalHealth {
+    /// Returns `true` if the WAL accepts appends.
+    #[must_use]
+    pub fn is_healthy(&self) -> bool {
+        matches!(self, Self::Healthy)
     }
 }
 
@@ -44,12 +183,19 @@ pub struct Wal<IO: IoEngine = RealIo> {
     flusher_handle: Option<JoinHandle<()>>,
     checkpoint_handle: Option<JoinHandle<()>>,
     next_lsn: Arc<AtomicU64>,
+    /// First LSN this instance writes: transactions still open below it
+    /// were left by an earlier instance and can never finish.
+    opened_lsn: u64,
+    commit_registry: Arc<CommitRegistry>,
+    compression_metrics: Arc<CompressionMetrics>,
+    custom_codecs: CustomCodecs,
     io: IO,
 }
 
 impl<IO: IoEngine> Wal<IO> {
     /// Opens or creates a WAL at the configured directory.
     ///
+    /// LSNs continue from the last entry in the existing log.
     /// Spawns a background flusher task on the tokio runtime.
     /// Returns the `Wal` handle and a `WalWriterFactory` for
     /// registering per-writer ring buffers.
@@ -58,38 +204,70 @@ impl<IO: IoEngine> Wal<IO> {
         K: Serialize + DeserializeOwned + Send + 'static,
         V: Serialize + DeserializeOwned + Send + 'static,
     {
+        if !config.custom_codecs.contains(config.codec) {
+            return Err(config.codec.error("no codec registered under this ID"));
+        }
+
         let ring_config = RingConfig::new(
             config.ring_bits,
             config.max_writers,
             config.enable_metrics,
         );
-        let stream_config = StreamConfig::default()
-            .with_poll_interval(config.flush_interval)
-            .with_batch_hint(config.batch_hint);
+        let stream_config = StreamConfig::default().with_batch_hint(config.batch_hint);
 
         let (sender_factory, receiver) =
-            channel_with_stream_config::<Envelope<K, V>>(ring_config, stream_config);
+            channel_with_stream_config::<Envelope>(ring_config, stream_config);
 
-        let segment_mgr = SegmentManager::open(&config.dir, config.max_segment_size, config.direct_io, io.clone())?;
+        let mut segment_mgr = SegmentManager::open(&config.dir, config.max_segment_size, config.direct_io, io.clone())?;
+        segment_mgr.set_codec(config.codec);
+        segment_mgr.set_compression(config.compression);
+        let compression_metrics = segment_mgr.compression_metrics();
+
+        // Resume LSNs after the last one on disk (or the checkpoint, if
+        // truncation left no entries behind)
+        let last_lsn = recovery::last_lsn(&config.dir, &io)?
+            .max(recovery::read_checkpoint(&config.dir, &io)?);
 
         let shutdown_notify = Arc::new(Notify::new());
-        let next_lsn = Arc::new(AtomicU64::new(1));
-        let commit_registry = Arc::new(CommitRegistry::new());
+        let next_lsn = Arc::new(AtomicU64::new(last_lsn + 1));
+        let commit_registry = Arc::new(
+            CommitRegistry::new()
+                .with_tail(TailHub::new(last_lsn, config.subscriber_buffer_bytes))
+                .with_quorum(Quorum::new(config.replication_acks, config.replication_timeout)),
+        );
 
         let flusher_handle = {
             let shutdown_notify = Arc::clone(&shutdown_notify);
             let next_lsn = Arc::clone(&next_lsn);
-            tokio::spawn(flusher_task(
-                receiver,
-                segment_mgr,
-                shutdown_notify,
-                next_lsn,
-                config.batch_hint,
-                config.sync_mode,
-            ))
+            let registry = Arc::clone(&commit_registry);
+            tokio::spawn(async move {
+                let flush = async {
+                    flusher_task(
+                        receiver,
+                        segment_mgr,
+                        shutdown_notify,
+                        next_lsn,
+                        config.batch_hint,
+                        config.sync_mode,
+                        Arc::clone(&registry),
+                    )
+                    .await;
+                    // Nothing more becomes durable: end the subscriptions and
+                    // fail the commits still waiting for followers
+                    registry.tail.close(None);
+                    registry.quorum.close();
+                };
+                // Fails commits past the replication timeout until the close
+                tokio::join!(flush, registry.quorum.expire());
+            })
         };
 
-        let writer_factory = WalWriterFactory::new(sender_factory, commit_registry);
+        let writer_factory = WalWriterFactory::new(
+            sender_factory,
+            Arc::clone(&commit_registry),
+            config.codec,
+            config.custom_codecs.clone(),
+        );
 
         Ok((
             Self {
@@ -98,6 +276,10 @@ impl<IO: IoEngine> Wal<IO> {
                 flusher_handle: Some(flusher_handle),
                 checkpoint_handle: None,
                 next_lsn,
+                opened_lsn: last_lsn + 1,
+                commit_registry,
+                compression_metrics,
+                custom_codecs: config.custom_codecs,
                 io,
             },
             writer_factory,
@@ -105,10 +287,36 @@ impl<IO: IoEngine> Wal<IO> {
     }
 
     /// Returns the current (next-to-assign) LSN.
+    ///
+    /// LSNs are persisted in each entry header, so this is comparable with
+    /// checkpoint LSNs and [`RecoveredTransaction::last_lsn`](crate::RecoveredTransaction::last_lsn)
+    /// across reopens.
     pub fn current_lsn(&self) -> u64 {
         self.next_lsn.load(Ordering::Relaxed)
     }
 
+    /// Returns whether the WAL still accepts appends.
+    ///
+    /// A failed write or fsync poisons the WAL (fail-stop): the batch's
+    /// commits get [`WalError::FlushFailed`], and every later append or
+    /// commit gets [`WalError::Poisoned`] with the same [`FlushFailure`].
+    /// Shut down, run [`recover`](crate::recover) and reopen to continue.
+    #[must_use]
+    pub fn health(&self) -> WalHealth {
+        match self.commit_registry.failure() {
+            Some(failure) => WalHealth::Poisoned(failure.clone()),
+            None => WalHealth::Healthy,
+        }
+    }
+
+    /// Returns the compression ratio and CPU time of the batches written
+    /// since the WAL was opened (all zero without
+    /// [`WalConfig::compression`]).
+    #[must_use]
+    pub fn compression_stats(&self) -> CompressionStats {
+        self.compression_metrics.snapshot()
+    }
+
     /// Initiates graceful shutdown.
     ///
     /// Signals the flusher to drain remaining entries and stop.
@@ -142,7 +350,10 @@ impl<IO: IoEngine> Wal<IO> {
     ///
     /// Scans all segment files, finds the highest committed `tx_id`,
     /// writes a checkpoint file, and removes old segment files whose
-    /// entries are all below the new checkpoint.
+    /// entries are all below the new checkpoint's low-water mark: segments
+    /// holding entries of a transaction still open are kept (INV-WAL-12).
+    /// Transactions a previous instance left open cannot finish and do not
+    /// hold segments back.
     ///
     /// Returns the new checkpoint LSN, or `WalError::NoNewCheckpoints`
     /// if no new committed transactions exist beyond the current checkpoint.
@@ -151,11 +362,67 @@ impl<IO: IoEngine> Wal<IO> {
         K: DeserializeOwned + Send + 'static,
         V: DeserializeOwned + Send + 'static,
     {
-        let lsn = recovery::checkpoint::<K, V, IO>(&self.dir, &self.io)?;
-        let _ = recovery::truncate_segments_before(&self.dir, lsn, &self.io);
+        let lsn = recovery::checkpoint_live_from::<K, V, IO>(
+            &self.dir,
+            &self.io,
+            self.opened_lsn,
+            &self.custom_codecs,
+        )?;
+        let _ = self
+            .commit_registry
+            .tail
+            .truncate_before(&self.dir, lsn, &self.io);
         Ok(lsn)
     }
 
+    /// Subscribes to committed transactions from `from_lsn` on.
+    ///
+    /// The returned [`Subscription`] stream first replays, from the segment
+    /// files, the transactions whose `Commit` record is at or after
+    /// `from_lsn`, then yields each new one once its batch is durable. Pass
+    /// [`current_lsn`](Self::current_lsn) to follow only new commits, or the
+    /// `last_lsn` of the last transaction handled plus one to resume.
+    ///
+    /// Slow subscribers never hold up the flusher: past
+    /// [`WalConfig::subscriber_buffer_bytes`] behind, they read from the
+    /// segment files instead. Checkpoint truncation keeps the segments a
+    /// live subscription has not read yet.
+    ///
+    /// Replay starts at the checkpoint's low-water mark, so transactions
+    /// open at `from_lsn` come with the entries written before it. Fails
+    /// with [`WalError::Truncated`] if those entries may have been
+    /// truncated: `from_lsn` is at or below a checkpoint after truncation.
+    pub fn subscribe<K, V>(&self, from_lsn: u64) -> Result<Subscription<K, V, IO>, WalError>
+    where
+        K: DeserializeOwned,
+        V: DeserializeOwned,
+    {
+        Subscription::new(
+            Arc::clone(&self.commit_registry),
+            &self.dir,
+            self.io.clone(),
+            from_lsn,
+            self.opened_lsn,
+            self.custom_codecs.clone(),
+        )
+    }
+
+    /// Returns the primary side of replication, to serve [`Follower`](crate::Follower)s.
+    ///
+    /// Followers receive the segment bytes after the end of their copy,
+    /// then follow new batches as they become durable. With
+    /// [`WalConfig::replication_acks`] set to N, commits return only once N
+    /// followers have acknowledged them; commits still waiting at shutdown
+    /// fail with [`WalError::NotReplicated`] (they are durable locally).
+    ///
+    /// ```ignore
+    /// let listener = tokio::net::TcpListener::bind("0.0.0.0:7070").await?;
+    /// tokio::spawn(wal.replication_source().listen(listener, |e| eprintln!("replication: {e}")));
+    /// ```
+    pub fn replication_source(&self) -> ReplicationSource<IO> {
+        ReplicationSource::new(Arc::clone(&self.commit_registry), &self.dir, self.io.clone())
+    }
+
     /// Starts a background task that periodically checkpoints committed
     /// transactions and truncates old segment files.
     ///
@@ -174,6 +441,9 @@ impl<IO: IoEngine> Wal<IO> {
         let dir = self.dir.clone();
         let shutdown_notify = Arc::clone(&self.shutdown_notify);
         let io = self.io.clone();
+        let registry = Arc::clone(&self.commit_registry);
+        let opened_lsn = self.opened_lsn;
+        let custom_codecs = self.custom_codecs.clone();
 
         self.checkpoint_handle = Some(tokio::spawn(async move {
             let mut ticker = tokio::time::interval(interval);
@@ -183,9 +453,9 @@ impl<IO: IoEngine> Wal<IO> {
                 tokio::select! {
                     _ = ticker.tick() => {
                         // Best-effort checkpoint — ignore NoNewCheckpoints
-                        match recovery::checkpoint::<K, V, IO>(&dir, &io) {
+                        match recovery::checkpoint_live_from::<K, V, IO>(&dir, &io, opened_lsn, &custom_codecs) {
                             Ok(lsn) => {
-                                let _ = recovery::truncate_segments_before(&dir, lsn, &io);
+                                let _ = registry.tail.truncate_before(&dir, lsn, &io);
                             }
                             Err(WalError::NoNewCheckpoints) => {}
                             Err(e) => {
@@ -202,118 +472,182 @@ impl<IO: IoEngine> Wal<IO> {
     }
 }
 
+/// The flusher's side of group commit: numbers batches and completes their
+/// waiters through the shared [`CommitRegistry`].
+struct GroupCommit {
+    registry: Arc<CommitRegistry>,
+    next_seq: u64,
+}
+
+impl GroupCommit {
+    /// Writes `batch` to the active segment (may rotate).
+    ///
+    /// Returns the batch's sequence number and LSN range. Once the WAL is
+    /// poisoned nothing more is written: the batch is dropped and its waiters
+    /// get `Poisoned`. A failed write poisons the WAL (INV-WAL-08).
+    fn write<IO: IoEngine>(
+        &mut self,
+        segment_mgr: &mut SegmentManager<IO>,
+        batch: &mut Vec<(u64, u32, Vec<u8>)>,
+        commit_waiters: &mut Vec<CommitWaiter>,
+    ) -> Option<(u64, (u64, u64))> {
+        let lsns = (batch.first()?.0, batch.last()?.0);
+        if let Some(failure) = self.registry.failure() {
+            for waiter in commit_waiters.drain(..) {
+                let _ = waiter.send(Err(WalError::Poisoned(failure.clone())));
+            }
+            batch.clear();
+            return None;
+        }
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        let result = segment_mgr.write_batch(batch);
+        if result.is_ok() {
+            // Subscribers read the entries once the batch is durable
+            self.registry.tail.publish(batch, segment_mgr.codec());
+        }
+        batch.clear();
+        if let Err(e) = result {
+            let failure =
+                FlushFailure::new(FlushStage::Write, &e, segment_mgr.active_segment_id(), lsns);
+            self.registry
+                .complete(seq, Err(failure), std::mem::take(commit_waiters));
+            return None;
+        }
+        Some((seq, lsns))
+    }
+
+    /// Fails batch `seq`, whose sync could not be started.
+    ///
+    /// Completion waits for every earlier batch's sync (batch order), so it
+    /// runs on a blocking thread tracked by `in_flight`, never on the
+    /// flusher task.
+    fn fail_sync(
+        &self,
+        seq: u64,
+        error: &WalError,
+        segment_id: u64,
+        lsns: (u64, u64),
+        waiters: Vec<CommitWaiter>,
+        in_flight: &mut tokio::task::JoinSet<()>,
+    ) {
+        let failure = FlushFailure::new(FlushStage::Sync, error, segment_id, lsns);
+        let registry = Arc::clone(&self.registry);
+        in_flight.spawn_blocking(move || registry.complete_in_order(seq, Err(failure), waiters));
+    }
+}
+
+/// A written batch whose fsync runs off the flusher (pipelined modes).
+struct SyncJob {
+    fd: Box<dyn SyncHandle>,
+    waiters: Vec<CommitWaiter>,
+    seq: u64,
+    segment_id: u64,
+    lsns: (u64, u64),
+}
+
+impl SyncJob {
+    /// Syncs the batch and completes its waiters in batch order.
+    fn run(mut self, registry: &CommitRegistry, data_only: bool) {
+        let result = if data_only {
+            self.fd.sync_data()
+        } else {
+            self.fd.sync_all()
+        };
+        let result = result.map(|()| self.lsns.1).map_err(|e| {
+            FlushFailure::new(FlushStage::Sync, &WalError::Io(e), self.segment_id, self.lsns)
+        });
+        registry.complete_in_order(self.seq, result, self.waiters);
+    }
+}
+
 /// Pipelined flush: write batch, spawn fire-and-forget background fsync
 /// that notifies commit waiters directly when fsync completes.
 /// The flusher returns immediately and can start writing the next batch.
 #[allow(clippy::unused_async)]
 async fn pipelined_flush<IO: IoEngine>(
     segment_mgr: &mut SegmentManager<IO>,
-    batch: &mut Vec<(u64, Vec<u8>)>,
-    commit_waiters: &mut Vec<oneshot::Sender<()>>,
+    batch: &mut Vec<(u64, u32, Vec<u8>)>,
+    commit_waiters: &mut Vec<CommitWaiter>,
+    commits: &mut GroupCommit,
     in_flight: &mut tokio::task::JoinSet<()>,
     sync_mode: SyncMode,
 ) {
-    if batch.is_empty() {
+    let Some((seq, lsns)) = commits.write(segment_mgr, batch, commit_waiters) else {
         return;
-    }
-
-    // Write batch to active segment (may rotate)
-    if let Err(e) = segment_mgr.write_batch(batch) {
-        eprintln!("ringwal: segment write error: {e}");
-        batch.clear();
-        commit_waiters.clear();
-        return;
-    }
+    };
+    let segment_id = segment_mgr.active_segment_id();
+    let waiters = std::mem::take(commit_waiters);
 
     // Flush BufWriter + dup() the fd for background sync
     match segment_mgr.flush_and_clone_fd() {
         Ok(fd) => {
-            let waiters = std::mem::take(commit_waiters);
+            let job = SyncJob { fd, waiters, seq, segment_id, lsns };
+            let registry = Arc::clone(&commits.registry);
             let use_data_sync = sync_mode == SyncMode::PipelinedDataOnly;
             // Notify waiters directly from the blocking thread — no flusher
             // involvement needed. This is the key: the flusher can immediately
             // start writing the next batch while fsync runs in the background.
-            in_flight.spawn_blocking(move || {
-                let result = if use_data_sync {
-                    fd.sync_data()
-                } else {
-                    fd.sync_all()
-                };
-                if let Err(e) = result {
-                    eprintln!("ringwal: pipelined fsync error: {e}");
-                }
-                for tx in waiters {
-                    let _ = tx.send(());
-                }
-            });
-        }
-        Err(e) => {
-            eprintln!("ringwal: flush_and_clone_fd error: {e}");
-            commit_waiters.clear();
+            in_flight.spawn_blocking(move || job.run(&registry, use_data_sync));
         }
+        Err(e) => commits.fail_sync(seq, &e, segment_id, lsns, waiters, in_flight),
     }
-
-    batch.clear();
 }
 
 /// Dedicated-thread flush: write batch, send fd + waiters over bounded channel
 /// to a dedicated OS thread that performs fsync and notifies waiters.
 fn dedicated_flush<IO: IoEngine>(
     segment_mgr: &mut SegmentManager<IO>,
-    batch: &mut Vec<(u64, Vec<u8>)>,
-    commit_waiters: &mut Vec<oneshot::Sender<()>>,
-    tx: &std::sync::mpsc::SyncSender<(std::fs::File, Vec<oneshot::Sender<()>>)>,
+    batch: &mut Vec<(u64, u32, Vec<u8>)>,
+    commit_waiters: &mut Vec<CommitWaiter>,
+    commits: &mut GroupCommit,
+    tx: &std::sync::mpsc::SyncSender<SyncJob>,
+    in_flight: &mut tokio::task::JoinSet<()>,
 ) {
-    if batch.is_empty() {
-        return;
-    }
-
-    if let Err(e) = segment_mgr.write_batch(batch) {
-        eprintln!("ringwal: segment write error: {e}");
-        batch.clear();
-        commit_waiters.clear();
+    let Some((seq, lsns)) = commits.write(segment_mgr, batch, commit_waiters) else {
         return;
-    }
+    };
+    let segment_id = segment_mgr.active_segment_id();
+    let waiters = std::mem::take(commit_waiters);
 
     match segment_mgr.flush_and_clone_fd() {
         Ok(fd) => {
-            let waiters = std::mem::take(commit_waiters);
             // Bounded channel (depth 4) provides natural backpressure —
             // blocks if 4 fsyncs are already in-flight.
-            if let Err(e) = tx.send((fd, waiters)) {
-                eprintln!("ringwal: dedicated channel send error: {e}");
+            if let Err(std::sync::mpsc::SendError(job)) =
+                tx.send(SyncJob { fd, waiters, seq, segment_id, lsns })
+            {
+                let error = WalError::Io(std::io::Error::other("fsync thread exited"));
+                commits.fail_sync(seq, &error, segment_id, lsns, job.waiters, in_flight);
             }
         }
-        Err(e) => {
-            eprintln!("ringwal: flush_and_clone_fd error: {e}");
-            commit_waiters.clear();
-        }
+        Err(e) => commits.fail_sync(seq, &e, segment_id, lsns, waiters, in_flight),
     }
-
-    batch.clear();
 }
 
 /// Dedicated fsync thread handle: bounded sender + join handle.
 type DedicatedHandle = (
-    std::sync::mpsc::SyncSender<(std::fs::File, Vec<oneshot::Sender<()>>)>,
+    std::sync::mpsc::SyncSender<SyncJob>,
     Option<std::thread::JoinHandle<()>>,
 );
 
-/// Background task: drains entries from the ring receiver, serialises them,
-/// writes to the segment manager, fsyncs, and notifies commit waiters.
-async fn flusher_task<K, V, IO: IoEngine>(
-    mut receiver: RingReceiver<Envelope<K, V>>,
+/// Background task: drains encoded entries from the ring receiver, writes
+/// them to the segment manager, fsyncs, and notifies commit waiters.
+async fn flusher_task<IO: IoEngine>(
+    mut receiver: RingReceiver<Envelope>,
     mut segment_mgr: SegmentManager<IO>,
     shutdown_notify: Arc<Notify>,
     next_lsn: Arc<AtomicU64>,
     batch_hint: usize,
     sync_mode: SyncMode,
-) where
-    K: Serialize + DeserializeOwned + Send + 'static,
-    V: Serialize + DeserializeOwned + Send + 'static,
-{
-    let mut batch: Vec<(u64, Vec<u8>)> = Vec::with_capacity(batch_hint);
-    let mut commit_waiters: Vec<oneshot::Sender<()>> = Vec::new();
+    registry: Arc<CommitRegistry>,
+) {
+    let mut batch: Vec<(u64, u32, Vec<u8>)> = Vec::with_capacity(batch_hint);
+    let mut commit_waiters: Vec<CommitWaiter> = Vec::new();
+    let mut commits = GroupCommit {
+        registry: Arc::clone(&registry),
+        next_seq: 0,
+    };
     // JoinSet tracks in-flight pipelined fsyncs for shutdown cleanup only.
     // During normal operation, spawned tasks notify waiters directly.
     let mut in_flight: tokio::task::JoinSet<()> = tokio::task::JoinSet::new();
@@ -321,16 +655,10 @@ async fn flusher_task<K, V, IO: IoEngine>(
     // Dedicated fsync thread for PipelinedDedicated mode.
     // The bounded channel (depth 4) provides natural backpressure.
     let mut dedicated: Option<DedicatedHandle> = if sync_mode == SyncMode::PipelinedDedicated {
-        let (tx, rx) =
-            std::sync::mpsc::sync_channel::<(std::fs::File, Vec<oneshot::Sender<()>>)>(4);
+        let (tx, rx) = std::sync::mpsc::sync_channel::<SyncJob>(4);
         let handle = std::thread::spawn(move || {
-            while let Ok((fd, waiters)) = rx.recv() {
-                if let Err(e) = fd.sync_all() {
-                    eprintln!("ringwal: dedicated fsync error: {e}");
-                }
-                for w in waiters {
-                    let _ = w.send(());
-                }
+            while let Ok(job) = rx.recv() {
+                job.run(&registry, false);
             }
         });
         Some((tx, Some(handle)))
@@ -363,10 +691,10 @@ async fn flusher_task<K, V, IO: IoEngine>(
                         if is_pipelined {
                             if sync_mode == SyncMode::PipelinedDedicated {
                                 if let Some((ref tx, _)) = dedicated {
-                                    dedicated_flush(&mut segment_mgr, &mut batch, &mut commit_waiters, tx);
+                                    dedicated_flush(&mut segment_mgr, &mut batch, &mut commit_waiters, &mut commits, tx, &mut in_flight);
                                 }
                             } else {
-                                pipelined_flush(&mut segment_mgr, &mut batch, &mut commit_waiters, &mut in_flight, sync_mode).await;
+                                pipelined_flush(&mut segment_mgr, &mut batch, &mut commit_waiters, &mut commits, &mut in_flight, sync_mode).await;
                             }
                             // Shutdown: drain in-flight JoinSet fsyncs
                             while in_flight.join_next().await.is_some() {}
@@ -378,7 +706,7 @@ async fn flusher_task<K, V, IO: IoEngine>(
                                 }
                             }
                         } else {
-                            flush_and_notify(&mut segment_mgr, &mut batch, &mut commit_waiters, sync_mode).await;
+                            flush_and_notify(&mut segment_mgr, &mut batch, &mut commit_waiters, &mut commits, sync_mode).await;
                         }
                         return;
                     }
@@ -400,10 +728,10 @@ async fn flusher_task<K, V, IO: IoEngine>(
                 if is_pipelined {
                     if sync_mode == SyncMode::PipelinedDedicated {
                         if let Some((ref tx, _)) = dedicated {
-                            dedicated_flush(&mut segment_mgr, &mut batch, &mut commit_waiters, tx);
+                            dedicated_flush(&mut segment_mgr, &mut batch, &mut commit_waiters, &mut commits, tx, &mut in_flight);
                         }
                     } else {
-                        pipelined_flush(&mut segment_mgr, &mut batch, &mut commit_waiters, &mut in_flight, sync_mode).await;
+                        pipelined_flush(&mut segment_mgr, &mut batch, &mut commit_waiters, &mut commits, &mut in_flight, sync_mode).await;
                     }
                     while in_flight.join_next().await.is_some() {}
                     if let Some((tx, handle)) = dedicated.take() {
@@ -413,7 +741,7 @@ async fn flusher_task<K, V, IO: IoEngine>(
                         }
                     }
                 } else {
-                    flush_and_notify(&mut segment_mgr, &mut batch, &mut commit_waiters, sync_mode).await;
+                    flush_and_notify(&mut segment_mgr, &mut batch, &mut commit_waiters, &mut commits, sync_mode).await;
                 }
                 return;
             }
@@ -423,103 +751,65 @@ async fn flusher_task<K, V, IO: IoEngine>(
             if is_pipelined {
                 if sync_mode == SyncMode::PipelinedDedicated {
                     if let Some((ref tx, _)) = dedicated {
-                        dedicated_flush(&mut segment_mgr, &mut batch, &mut commit_waiters, tx);
+                        dedicated_flush(&mut segment_mgr, &mut batch, &mut commit_waiters, &mut commits, tx, &mut in_flight);
                     }
                 } else {
-                    pipelined_flush(&mut segment_mgr, &mut batch, &mut commit_waiters, &mut in_flight, sync_mode).await;
+                    pipelined_flush(&mut segment_mgr, &mut batch, &mut commit_waiters, &mut commits, &mut in_flight, sync_mode).await;
                 }
             } else {
-                flush_and_notify(&mut segment_mgr, &mut batch, &mut commit_waiters, sync_mode).await;
+                flush_and_notify(&mut segment_mgr, &mut batch, &mut commit_waiters, &mut commits, sync_mode).await;
             }
         }
     }
 }
 
 /// Extracts an envelope into the batch and commit waiters.
-fn collect_envelope<K, V>(
-    envelope: Envelope<K, V>,
+fn collect_envelope(
+    envelope: Envelope,
     next_lsn: &AtomicU64,
-    batch: &mut Vec<(u64, Vec<u8>)>,
-    commit_waiters: &mut Vec<oneshot::Sender<()>>,
-) where
-    K: Serialize,
-    V: Serialize,
-{
+    batch: &mut Vec<(u64, u32, Vec<u8>)>,
+    commit_waiters: &mut Vec<CommitWaiter>,
+) {
     let lsn = next_lsn.fetch_add(1, Ordering::Relaxed);
     match envelope {
-        Envelope::Entry(entry) => {
-            if let Ok(data) = bincode::serialize(&entry) {
-                batch.push((lsn, data));
-            }
-        }
-        Envelope::CommitBarrier { entry, tx } => {
-            if let Ok(data) = bincode::serialize(&entry) {
-                batch.push((lsn, data));
-            }
+        Envelope::Entry { writer_id, data } => batch.push((lsn, writer_id, data)),
+        Envelope::CommitBarrier { writer_id, data, tx } => {
+            batch.push((lsn, writer_id, data));
             commit_waiters.push(tx);
         }
     }
 }
 
 /// Writes a batch to the segment manager, optionally fsyncs, and notifies commit waiters.
+///
+/// A failed write, flush or fsync fails the batch's waiters with the error
+/// and poisons the WAL (INV-WAL-08).
 async fn flush_and_notify<IO: IoEngine>(
     segment_mgr: &mut SegmentManager<IO>,
-    batch: &mut Vec<(u64, Vec<u8>)>,
-    commit_waiters: &mut Vec<oneshot::Sender<()>>,
+    batch: &mut Vec<(u64, u32, Vec<u8>)>,
+    commit_waiters: &mut Vec<CommitWaiter>,
+    commits: &mut GroupCommit,
     sync_mode: SyncMode,
 ) {
-    if batch.is_empty() {
-        return;
-    }
-
-    // Write batch to active segment (may rotate)
-    if let Err(e) = segment_mgr.write_batch(batch) {
-        eprintln!("ringwal: segment write error: {e}");
-        batch.clear();
-        commit_waiters.clear();
+    let Some((seq, lsns)) = commits.write(segment_mgr, batch, commit_waiters) else {
         return;
-    }
+    };
 
-    match sync_mode {
-        SyncMode::Full => {
-            // Fsync the active segment — guarantees durability
-            if let Err(e) = segment_mgr.fsync() {
-                eprintln!("ringwal: fsync error: {e}");
-                batch.clear();
-                commit_waiters.clear();
-                return;
-            }
-        }
-        SyncMode::DataOnly => {
-            // Fsync data only (skip metadata) — faster on Linux/ext4
-            if let Err(e) = segment_mgr.fsync_data() {
-                eprintln!("ringwal: fsync_data error: {e}");
-                batch.clear();
-                commit_waiters.clear();
-                return;
-            }
-        }
+    let result = match sync_mode {
+        // Fsync the active segment — guarantees durability
+        SyncMode::Full => segment_mgr.fsync(),
+        // Fsync data only (skip metadata) — faster on Linux/ext4
+        SyncMode::DataOnly => segment_mgr.fsync_data(),
         SyncMode::Background => {
             // Offload fsync to Tokio's blocking thread pool.
             // flush_and_clone_fd() flushes the BufWriter and dup()s the fd;
             // sync_all() on the clone syncs the same inode.
             match segment_mgr.flush_and_clone_fd() {
-                Ok(fd) => {
-                    let result = tokio::task::spawn_blocking(move || fd.sync_all())
-                        .await;
-                    if !matches!(result, Ok(Ok(()))) {
-                        eprintln!("ringwal: background fsync error");
-                        batch.clear();
-                        commit_waiters.clear();
-                        return;
-                    }
-                }
-                Err(e) => {
-                    eprintln!("ringwal: flush_and_clone_fd error: {e}");
-                    batch.clear();
-                    commit_waiters.clear();
-                    return;
-                }
+                Ok(mut fd) => tokio::task::spawn_blocking(move || fd.sync_all())
+                    .await
+                    .unwrap_or_else(|e| Err(std::io::Error::other(e)))
+                    .map_err(WalError::Io),
+                Err(e) => Err(e),
             }
         }
         SyncMode::Pipelined => {
@@ -531,17 +821,65 @@ async fn flush_and_notify<IO: IoEngine>(
         SyncMode::PipelinedDedicated => {
             unreachable!("pipelined-dedicated mode handled in flusher_task")
         }
-        SyncMode::None => {
-            // Flush BufWriter to kernel buffer but skip fsync —
-            // faster but data may be lost on crash.
-            let _ = segment_mgr.flush();
-        }
-    }
+        // Flush BufWriter to kernel buffer but skip fsync —
+        // faster but data may be lost on crash.
+        SyncMode::None => segment_mgr.flush(),
+    };
 
     // Notify all commit waiters — group commit
-    for waiter in commit_waiters.drain(..) {
-        let _ = waiter.send(());
+    let result = result.map(|()| lsns.1).map_err(|e| {
+        FlushFailure::new(FlushStage::Sync, &e, segment_mgr.active_segment_id(), lsns)
+    });
+    commits
+        .registry
+        .complete(seq, result, std::mem::take(commit_waiters));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failure(seq: u64) -> FlushFailure {
+        let error = WalError::Io(std::io::Error::other("boom"));
+        FlushFailure::new(FlushStage::Sync, &error, 1, (seq, seq))
+    }
+
+    fn waiter() -> (CommitWaiter, oneshot::Receiver<Result<(), WalError>>) {
+        oneshot::channel()
+    }
+
+    #[test]
+    fn registry_poisons_on_failure() {
+        let registry = CommitRegistry::new();
+        let (tx0, mut rx0) = waiter();
+        let (tx1, mut rx1) = waiter();
+        registry.complete(0, Ok(0), vec![tx0]);
+        registry.complete(1, Err(failure(1)), vec![tx1]);
+
+        assert!(matches!(rx0.try_recv(), Ok(Ok(()))));
+        assert!(matches!(rx1.try_recv(), Ok(Err(WalError::FlushFailed(f))) if f == failure(1)));
+        assert_eq!(registry.failure(), Some(&failure(1)));
+        assert!(matches!(registry.check(), Err(WalError::Poisoned(_))));
     }
 
-    batch.clear();
+    #[test]
+    fn registry_rejects_batches_synced_after_failure() {
+        let registry = Arc::new(CommitRegistry::new());
+        let (tx2, mut rx2) = waiter();
+        let (tx1, mut rx1) = waiter();
+        let (tx0, mut rx0) = waiter();
+
+        // Batch 2 syncs first but must wait for batches 0 and 1.
+        let later = {
+            let registry = Arc::clone(&registry);
+            std::thread::spawn(move || registry.complete_in_order(2, Ok(2), vec![tx2]))
+        };
+        registry.complete_in_order(0, Ok(0), vec![tx0]);
+        registry.complete_in_order(1, Err(failure(1)), vec![tx1]);
+        later.join().unwrap();
+
+        assert!(matches!(rx0.try_recv(), Ok(Ok(()))));
+        assert!(matches!(rx1.try_recv(), Ok(Err(WalError::FlushFailed(_)))));
+        assert!(matches!(rx2.try_recv(), Ok(Err(WalError::Poisoned(f))) if f == failure(1)));
+    }
 }
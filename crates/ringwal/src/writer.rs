@@ -1,27 +1,33 @@
 //! WAL writer — one per producer, wraps a `RingSender`.
 
+use std::marker::PhantomData;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
 use serde::Serialize;
 use tokio::sync::oneshot;
 
+use crate::codec::{Codec, CustomCodecs};
 use crate::entry::WalEntry;
 use crate::error::WalError;
-use crate::wal::CommitRegistry;
+use crate::wal::{CommitRegistry, CommitWaiter};
 use ringmpsc_stream::{RingSender, SenderFactory, StreamError};
 
 /// Internal envelope sent through the ring buffer.
 ///
-/// Wraps a `WalEntry` with an optional commit-notification channel used
-/// by the flusher to signal durability back to the writer.
-pub(crate) enum Envelope<K, V> {
-    Entry(WalEntry<K, V>),
+/// Wraps an encoded `WalEntry` with the sending writer's ID (persisted in
+/// the entry header) and an optional commit-notification channel used by
+/// the flusher to signal durability back to the writer. Writers encode
+/// before sending, so an encoding error reaches the caller.
+pub(crate) enum Envelope {
+    Entry { writer_id: u32, data: Vec<u8> },
     /// A commit entry bundled with a oneshot sender that the flusher
-    /// will fire after the batch containing this commit is fsynced.
+    /// will fire after the batch containing this commit is fsynced, or
+    /// with the error that failed it.
     CommitBarrier {
-        entry: WalEntry<K, V>,
-        tx: oneshot::Sender<()>,
+        writer_id: u32,
+        data: Vec<u8>,
+        tx: CommitWaiter,
     },
 }
 
@@ -43,8 +49,11 @@ pub(crate) fn reset_tx_id(min: u64) {
 /// Each call to `register()` allocates a dedicated SPSC ring buffer for
 /// the writer, ensuring zero contention with other writers.
 pub struct WalWriterFactory<K, V> {
-    factory: Arc<SenderFactory<Envelope<K, V>>>,
+    factory: Arc<SenderFactory<Envelope>>,
     commit_registry: Arc<CommitRegistry>,
+    codec: Codec,
+    custom_codecs: CustomCodecs,
+    _marker: PhantomData<fn(K, V)>,
 }
 
 impl<K, V> WalWriterFactory<K, V>
@@ -53,12 +62,17 @@ where
     V: Serialize + Send + 'static,
 {
     pub(crate) fn new(
-        factory: SenderFactory<Envelope<K, V>>,
+        factory: SenderFactory<Envelope>,
         commit_registry: Arc<CommitRegistry>,
+        codec: Codec,
+        custom_codecs: CustomCodecs,
     ) -> Self {
         Self {
             factory: Arc::new(factory),
             commit_registry,
+            codec,
+            custom_codecs,
+            _marker: PhantomData,
         }
     }
 
@@ -75,6 +89,10 @@ where
         })?;
         Ok(WalWriter {
             sender,
+            commit_registry: Arc::clone(&self.commit_registry),
+            codec: self.codec,
+            custom_codecs: self.custom_codecs.clone(),
+            _marker: PhantomData,
         })
     }
 
@@ -95,6 +113,9 @@ impl<K, V> Clone for WalWriterFactory<K, V> {
         Self {
             factory: Arc::clone(&self.factory),
             commit_registry: Arc::clone(&self.commit_registry),
+            codec: self.codec,
+            custom_codecs: self.custom_codecs.clone(),
+            _marker: PhantomData,
         }
     }
 }
@@ -102,7 +123,11 @@ impl<K, V> Clone for WalWriterFactory<K, V> {
 /// A WAL writer handle. Each writer has a dedicated SPSC ring buffer —
 /// writes are lock-free with zero contention against other writers.
 pub struct WalWriter<K, V> {
-    sender: RingSender<Envelope<K, V>>,
+    sender: RingSender<Envelope>,
+    commit_registry: Arc<CommitRegistry>,
+    codec: Codec,
+    custom_codecs: CustomCodecs,
+    _marker: PhantomData<fn(K, V)>,
 }
 
 impl<K, V> WalWriter<K, V>
@@ -110,13 +135,25 @@ where
     K: Serialize + Send + 'static,
     V: Serialize + Send + 'static,
 {
+    /// Returns this writer's ID, recorded in the header of every entry it
+    /// appends.
+    #[must_use]
+    pub fn id(&self) -> u32 {
+        self.sender.id() as u32
+    }
+
     /// Appends an entry to the WAL.
     ///
     /// Applies backpressure (async wait) if the ring buffer is full.
     /// The entry is _not_ durable until a subsequent `commit()` completes.
+    /// Returns `WalError::Codec` if the entry cannot be encoded with the
+    /// configured codec (nothing is appended), and `WalError::Poisoned`
+    /// once a flush has failed.
     pub async fn append(&self, entry: WalEntry<K, V>) -> Result<(), WalError> {
+        self.commit_registry.check()?;
+        let data = self.custom_codecs.encode(self.codec, &entry)?;
         self.sender
-            .send(Envelope::Entry(entry))
+            .send(Envelope::Entry { writer_id: self.id(), data })
             .await
             .map_err(|_| WalError::Closed)
     }
@@ -124,30 +161,37 @@ where
     /// Sends a commit marker for `tx_id` and waits until the batch
     /// containing it has been fsynced to disk (group commit).
     ///
-    /// Returns `Ok(())` once durability is guaranteed.
+    /// Returns `Ok(())` once durability is guaranteed. If the batch could
+    /// not be written or synced, returns `WalError::FlushFailed` with the
+    /// underlying error; the WAL is then poisoned and the outcome of the
+    /// transaction is decided by recovery.
     pub async fn commit(&self, tx_id: u64) -> Result<(), WalError> {
+        self.commit_registry.check()?;
         let (tx, rx) = oneshot::channel();
-        let entry = WalEntry::Commit {
+        let entry = WalEntry::<K, V>::Commit {
             tx_id,
             timestamp: WalEntry::<K, V>::new_timestamp(),
         };
+        let data = self.custom_codecs.encode(self.codec, &entry)?;
         self.sender
-            .send(Envelope::CommitBarrier { entry, tx })
+            .send(Envelope::CommitBarrier { writer_id: self.id(), data, tx })
             .await
             .map_err(|_| WalError::Closed)?;
 
         // Wait for flusher to fsync and notify us
-        rx.await.map_err(|_| WalError::Closed)
+        rx.await.unwrap_or(Err(WalError::Closed))
     }
 
     /// Sends an abort marker for `tx_id`. Does not wait for durability.
     pub async fn abort(&self, tx_id: u64) -> Result<(), WalError> {
-        let entry = WalEntry::Abort {
+        self.commit_registry.check()?;
+        let entry = WalEntry::<K, V>::Abort {
             tx_id,
             timestamp: WalEntry::<K, V>::new_timestamp(),
         };
+        let data = self.custom_codecs.encode(self.codec, &entry)?;
         self.sender
-            .send(Envelope::Entry(entry))
+            .send(Envelope::Entry { writer_id: self.id(), data })
             .await
             .map_err(|_| WalError::Closed)
     }
@@ -1,8 +1,12 @@
 //! Integration tests for ringwal.
 
 use ringwal::{
-    recover, read_checkpoint, write_checkpoint,
-    RealIo, RecoveryAction, SyncMode, Transaction, Wal, WalConfig, WalEntry,
+    checkpoint, erased_serde, next_tx_id, recover, Cipher, Codec, CodecError, CompressedFrameHeader,
+    Compression, CustomCodec, EncryptedFrameHeader, EncryptedIo, Encryption, EntryVisitor, KeyRing,
+    read_checkpoint, truncate_segments_before, write_checkpoint,
+    read_segment_footer, read_segment_header, ByteWalEntry, RealIo, RecoveryAction,
+    RecoveryReader, SegmentHeader, SyncMode, Transaction, Wal, WalConfig, WalEntry,
+    WalEntryHeader, WalError,
 };
 use ringwal_store::{recover_into_store, InMemoryStore};
 use std::sync::Arc;
@@ -330,6 +334,283 @@ async fn recover_into_store_replays_committed() {
     assert!(store.get(&"dead".to_string()).is_none());
 }
 
+#[tokio::test]
+async fn recovery_returns_commit_order() {
+    let tmp = TempDir::new().unwrap();
+    let config = test_config(tmp.path());
+    let (mut wal, factory) = Wal::open::<String, Vec<u8>>(config, RealIo).unwrap();
+    let writers: Vec<_> = (0..3).map(|_| factory.register().unwrap()).collect();
+
+    // Writers take turns overwriting the same key; the last commit wins.
+    let mut order = Vec::new();
+    for round in 0..30u8 {
+        let mut tx = Transaction::new();
+        tx.insert("shared".to_string(), vec![round]);
+        tx.insert(format!("key-{round}"), vec![round]);
+        order.push(tx.id);
+        tx.commit(&writers[round as usize % writers.len()]).await.unwrap();
+    }
+
+    wal.shutdown().await.unwrap();
+
+    let (recovered, _) = recover::<String, Vec<u8>, _>(tmp.path(), &RealIo).unwrap();
+    let committed: Vec<u64> = recovered
+        .iter()
+        .filter(|tx| tx.action == RecoveryAction::Commit)
+        .map(|tx| tx.tx_id)
+        .collect();
+    assert_eq!(committed, order);
+    for tx in &recovered {
+        assert!(matches!(&tx.entries[0], WalEntry::Insert { key, .. } if key == "shared"));
+    }
+
+    let mut store = InMemoryStore::<String, Vec<u8>>::new();
+    recover_into_store::<String, Vec<u8>, _, _>(tmp.path(), &mut store, &RealIo).unwrap();
+    assert_eq!(store.get(&"shared".to_string()), Some(vec![29]));
+}
+
+fn insert(tx_id: u64, key: &str) -> WalEntry<String, Vec<u8>> {
+    WalEntry::Insert {
+        tx_id,
+        timestamp: 0,
+        key: key.to_string(),
+        value: vec![7u8; 64],
+    }
+}
+
+#[tokio::test]
+async fn recovery_reader_spills_open_transactions() {
+    let tmp = TempDir::new().unwrap();
+    let (mut wal, factory) = Wal::open::<String, Vec<u8>>(test_config(tmp.path()), RealIo).unwrap();
+    let long_writer = factory.register().unwrap();
+    let writer = factory.register().unwrap();
+
+    // A long transaction stays open while others commit around it, and a
+    // second one never finishes.
+    let long = next_tx_id();
+    let unfinished = next_tx_id();
+    for i in 0..4 {
+        long_writer.append(insert(long, &format!("long-{i}"))).await.unwrap();
+        long_writer.append(insert(unfinished, &format!("open-{i}"))).await.unwrap();
+        let mut tx = Transaction::new();
+        tx.insert(format!("short-{i}"), b"v".to_vec());
+        tx.commit(&writer).await.unwrap();
+    }
+    long_writer.commit(long).await.unwrap();
+    wal.shutdown().await.unwrap();
+
+    let (expected, expected_stats) = recover::<String, Vec<u8>, _>(tmp.path(), &RealIo).unwrap();
+
+    // A 1-byte threshold spills every open entry as soon as it is read
+    let mut reader = RecoveryReader::<String, Vec<u8>, _>::new(tmp.path(), &RealIo)
+        .unwrap()
+        .with_spill_threshold(1);
+    let streamed: Vec<_> = reader.by_ref().map(Result::unwrap).collect();
+    let stats = reader.into_stats();
+
+    assert_eq!(streamed.len(), expected.len());
+    for (got, want) in streamed.iter().zip(&expected) {
+        assert_eq!(got.tx_id, want.tx_id);
+        assert_eq!(got.action, want.action);
+        assert_eq!(got.last_lsn, want.last_lsn);
+        assert_eq!(got.entries, want.entries);
+    }
+    assert_eq!(stats.committed, expected_stats.committed);
+    assert_eq!(stats.incomplete, 1);
+
+    let long_tx = streamed.iter().find(|tx| tx.tx_id == long).unwrap();
+    assert_eq!(long_tx.action, RecoveryAction::Commit);
+    assert_eq!(long_tx.entries.len(), 4);
+    assert_eq!(streamed.last().unwrap().tx_id, unfinished);
+    assert_eq!(streamed.last().unwrap().entries.len(), 4);
+
+    // Spill files are gone once their transactions are yielded
+    let leftovers = std::fs::read_dir(tmp.path())
+        .unwrap()
+        .filter_map(Result::ok)
+        .filter(|e| e.file_name().to_string_lossy().starts_with("recovery-spill"))
+        .count();
+    assert_eq!(leftovers, 0);
+}
+
+#[tokio::test]
+async fn recovery_reader_skips_checkpointed_segments() {
+    let tmp = TempDir::new().unwrap();
+    let config = test_config(tmp.path()).with_max_segment_size(4096);
+    let (mut wal, factory) = Wal::open::<String, Vec<u8>>(config, RealIo).unwrap();
+    let writer = factory.register().unwrap();
+
+    for i in 0..30 {
+        let mut tx = Transaction::new();
+        tx.insert(format!("before-{i}"), vec![0u8; 512]);
+        tx.commit(&writer).await.unwrap();
+    }
+    let checkpoint_lsn = checkpoint::<String, Vec<u8>, _>(tmp.path(), &RealIo).unwrap();
+
+    let mut after = Vec::new();
+    for i in 0..20 {
+        let mut tx = Transaction::new();
+        tx.insert(format!("after-{i}"), vec![0u8; 512]);
+        after.push(tx.id);
+        tx.commit(&writer).await.unwrap();
+    }
+    wal.shutdown().await.unwrap();
+
+    let mut reader = RecoveryReader::<String, Vec<u8>, _>::new(tmp.path(), &RealIo)
+        .unwrap()
+        .with_start_lsn(checkpoint_lsn);
+    let committed: Vec<u64> = reader
+        .by_ref()
+        .map(Result::unwrap)
+        .filter(|tx| tx.action == RecoveryAction::Commit)
+        .map(|tx| tx.tx_id)
+        .collect();
+
+    assert_eq!(committed, after);
+    assert!(reader.stats().segments_skipped > 0);
+}
+
+/// A transaction open at the start LSN is read from its first entry, even
+/// when that lies in a segment wholly below the start LSN.
+#[tokio::test]
+async fn recovery_reader_keeps_segments_of_open_transactions() {
+    let tmp = TempDir::new().unwrap();
+    let config = test_config(tmp.path()).with_max_segment_size(4096);
+    let (mut wal, factory) = Wal::open::<String, Vec<u8>>(config, RealIo).unwrap();
+    let open_writer = factory.register().unwrap();
+    let writer = factory.register().unwrap();
+
+    let a = next_tx_id();
+    open_writer.append(insert(a, "a-head")).await.unwrap();
+    for i in 0..30 {
+        let mut tx = Transaction::new();
+        tx.insert(format!("key-{i}"), vec![0u8; 512]);
+        tx.commit(&writer).await.unwrap();
+    }
+    let checkpoint_lsn = checkpoint::<String, Vec<u8>, _>(tmp.path(), &RealIo).unwrap();
+    open_writer.append(insert(a, "a-tail")).await.unwrap();
+    open_writer.commit(a).await.unwrap();
+    wal.shutdown().await.unwrap();
+
+    for start_lsn in [checkpoint_lsn, checkpoint_lsn - 1] {
+        let committed: Vec<_> = RecoveryReader::<String, Vec<u8>, _>::new(tmp.path(), &RealIo)
+            .unwrap()
+            .with_start_lsn(start_lsn)
+            .map(Result::unwrap)
+            .filter(|tx| tx.action == RecoveryAction::Commit && tx.tx_id == a)
+            .collect();
+        assert_eq!(committed.len(), 1);
+        assert_eq!(committed[0].entries.len(), 2, "start LSN {start_lsn}");
+    }
+}
+
+#[tokio::test]
+async fn sealed_segments_have_header_and_footer() {
+    let tmp = TempDir::new().unwrap();
+    let config = test_config(tmp.path()).with_max_segment_size(4096);
+    let (mut wal, factory) = Wal::open::<String, Vec<u8>>(config, RealIo).unwrap();
+    let writer = factory.register().unwrap();
+    for i in 0..20 {
+        let mut tx = Transaction::new();
+        tx.insert(format!("key{i}"), vec![0u8; 512]);
+        tx.commit(&writer).await.unwrap();
+    }
+    wal.shutdown().await.unwrap();
+
+    let wal_id = u128::from_le_bytes(
+        std::fs::read(tmp.path().join("wal.id")).unwrap().try_into().unwrap(),
+    );
+    let header = read_segment_header(tmp.path(), 1, &RealIo).unwrap().unwrap();
+    assert_eq!(header.wal_id, wal_id);
+    assert_eq!((header.segment_id, header.first_lsn), (1, 1));
+
+    // The footer describes the sealed segment without decoding it
+    let footer = read_segment_footer(tmp.path(), 1, &RealIo).unwrap().unwrap();
+    let bytes = std::fs::read(tmp.path().join("wal-00000001.log")).unwrap();
+    assert_eq!(footer.data_len as usize + footer.encoded_len(), bytes.len());
+    assert_eq!(footer.checksum, crc32fast::hash(&bytes[..footer.data_len as usize]));
+    assert_eq!(footer.first_lsn, 1);
+    assert_eq!(footer.index[0], (1, SegmentHeader::SIZE as u64));
+    assert_eq!(footer.offset_for(footer.last_lsn), SegmentHeader::SIZE as u64);
+    let next = read_segment_header(tmp.path(), 2, &RealIo).unwrap().unwrap();
+    assert_eq!(next.first_lsn, footer.last_lsn + 1);
+
+    // The active segment is not sealed
+    let last = std::fs::read_dir(tmp.path())
+        .unwrap()
+        .filter(|e| e.as_ref().unwrap().file_name().to_string_lossy().starts_with("wal-"))
+        .count() as u64;
+    assert!(read_segment_footer(tmp.path(), last, &RealIo).unwrap().is_none());
+
+    let (_, stats) = recover::<String, Vec<u8>, _>(tmp.path(), &RealIo).unwrap();
+    assert_eq!(stats.committed, 20);
+    assert_eq!(stats.checksum_failures, 0);
+}
+
+/// A sealed segment that does not match its footer's checksum yields none
+/// of its entries, even though each entry's own checksum holds.
+#[tokio::test]
+async fn sealed_segment_checksum_checked_before_entries() {
+    let tmp = TempDir::new().unwrap();
+    let config = test_config(tmp.path()).with_max_segment_size(4096);
+    let (mut wal, factory) = Wal::open::<String, Vec<u8>>(config, RealIo).unwrap();
+    let writer = factory.register().unwrap();
+    for i in 0..20 {
+        let mut tx = Transaction::new();
+        tx.insert(format!("key{i}"), vec![0u8; 512]);
+        tx.commit(&writer).await.unwrap();
+    }
+    wal.shutdown().await.unwrap();
+
+    let mut footer = read_segment_footer(tmp.path(), 1, &RealIo).unwrap().unwrap();
+    footer.checksum ^= 1;
+    let path = tmp.path().join("wal-00000001.log");
+    let mut bytes = std::fs::read(&path).unwrap();
+    bytes.truncate(footer.data_len as usize);
+    bytes.extend_from_slice(&footer.to_bytes());
+    std::fs::write(&path, bytes).unwrap();
+
+    let (recovered, stats) = recover::<String, Vec<u8>, _>(tmp.path(), &RealIo).unwrap();
+    assert_eq!(stats.checksum_failures, 1);
+    assert!(!recovered.is_empty());
+    assert!(recovered.iter().all(|tx| tx.first_lsn > footer.last_lsn));
+}
+
+#[tokio::test]
+async fn foreign_and_corrupt_segments_detected() {
+    async fn write_wal(dir: &std::path::Path) {
+        let config = test_config(dir).with_max_segment_size(4096);
+        let (mut wal, factory) = Wal::open::<String, Vec<u8>>(config, RealIo).unwrap();
+        let writer = factory.register().unwrap();
+        for i in 0..10 {
+            let mut tx = Transaction::new();
+            tx.insert(format!("key{i}"), vec![0u8; 512]);
+            tx.commit(&writer).await.unwrap();
+        }
+        wal.shutdown().await.unwrap();
+    }
+    let ours = TempDir::new().unwrap();
+    let theirs = TempDir::new().unwrap();
+    write_wal(ours.path()).await;
+    write_wal(theirs.path()).await;
+
+    // A segment from another WAL is rejected, not replayed
+    let stray = ours.path().join("wal-00000099.log");
+    std::fs::copy(theirs.path().join("wal-00000001.log"), &stray).unwrap();
+    let err = recover::<String, Vec<u8>, _>(ours.path(), &RealIo).unwrap_err();
+    assert!(matches!(err, WalError::InvalidSegment(_)), "{err}");
+    std::fs::remove_file(&stray).unwrap();
+
+    // Corruption inside a sealed segment fails its whole-segment checksum
+    let path = ours.path().join("wal-00000001.log");
+    let mut bytes = std::fs::read(&path).unwrap();
+    let footer = read_segment_footer(ours.path(), 1, &RealIo).unwrap().unwrap();
+    bytes[footer.data_len as usize - 1] ^= 0xff;
+    std::fs::write(&path, &bytes).unwrap();
+    let (_, stats) = recover::<String, Vec<u8>, _>(ours.path(), &RealIo).unwrap();
+    assert_eq!(stats.checksum_failures, 1);
+}
+
 // ── Pipelined fsync tests ────────────────────────────────────────────────────
 
 #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
@@ -461,3 +742,1023 @@ async fn pipelined_dedicated_multi_writer_commits() {
     assert_eq!(stats.aborted, 0);
     assert_eq!(store.len(), 200);
 }
+
+// ── LSN persistence tests ────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn lsn_persisted_and_resumed_on_reopen() {
+    let tmp = TempDir::new().unwrap();
+    let (mut wal, factory) = Wal::open::<String, Vec<u8>>(test_config(tmp.path()), RealIo).unwrap();
+    let writer = factory.register().unwrap();
+    let mut tx = Transaction::new();
+    tx.insert("k".to_string(), b"v".to_vec());
+    tx.commit(&writer).await.unwrap();
+    wal.shutdown().await.unwrap();
+    let first_run_end = wal.current_lsn();
+
+    // The first entry's header carries its LSN and writer ID
+    let bytes = std::fs::read(tmp.path().join("wal-00000001.log")).unwrap();
+    let header = WalEntryHeader::read_from(&mut &bytes[SegmentHeader::SIZE..]).unwrap();
+    assert_eq!((header.version, header.lsn), (2, 1));
+    assert_eq!(header.writer_id, writer.id());
+
+    let (recovered, stats) = recover::<String, Vec<u8>, _>(tmp.path(), &RealIo).unwrap();
+    assert_eq!(stats.max_lsn, first_run_end - 1);
+    assert_eq!(recovered[0].last_lsn, first_run_end - 1);
+
+    // The second run continues the sequence instead of restarting at 1
+    let (mut wal, factory) = Wal::open::<String, Vec<u8>>(test_config(tmp.path()), RealIo).unwrap();
+    assert_eq!(wal.current_lsn(), first_run_end);
+    let writer = factory.register().unwrap();
+    let mut tx = Transaction::new();
+    tx.insert("k2".to_string(), b"v2".to_vec());
+    let second_id = tx.id;
+    tx.commit(&writer).await.unwrap();
+    wal.shutdown().await.unwrap();
+
+    let (recovered, _) = recover::<String, Vec<u8>, _>(tmp.path(), &RealIo).unwrap();
+    let second = recovered.iter().find(|t| t.tx_id == second_id).unwrap();
+    assert!(second.last_lsn > first_run_end);
+}
+
+/// A transaction that began before a rotation and is still open at the
+/// checkpoint keeps its first segment (INV-WAL-12).
+#[tokio::test]
+async fn checkpoint_keeps_segments_of_open_transactions() {
+    let tmp = TempDir::new().unwrap();
+    let config = test_config(tmp.path()).with_max_segment_size(4096);
+    let (mut wal, factory) = Wal::open::<String, Vec<u8>>(config, RealIo).unwrap();
+    let open_writer = factory.register().unwrap();
+    let writer = factory.register().unwrap();
+
+    // A starts in segment 1; the commits around it rotate past it.
+    let a = next_tx_id();
+    open_writer.append(insert(a, "a-head")).await.unwrap();
+    for i in 0..30 {
+        let mut tx = Transaction::new();
+        tx.insert(format!("key-{i}"), vec![0u8; 512]);
+        tx.commit(&writer).await.unwrap();
+    }
+    let lsn = wal.checkpoint::<String, Vec<u8>>().unwrap();
+    assert!(lsn > 0);
+    assert!(tmp.path().join("wal-00000001.log").exists(), "A's first segment was truncated");
+
+    open_writer.append(insert(a, "a-tail")).await.unwrap();
+    open_writer.commit(a).await.unwrap();
+    wal.shutdown().await.unwrap();
+
+    let (recovered, _) = recover::<String, Vec<u8>, _>(tmp.path(), &RealIo).unwrap();
+    let a_tx = recovered.iter().find(|t| t.tx_id == a).unwrap();
+    assert_eq!(a_tx.action, RecoveryAction::Commit);
+    assert_eq!(a_tx.entries.len(), 2);
+    assert!(a_tx.first_lsn < lsn && a_tx.last_lsn > lsn);
+
+    // Once A has committed, the next checkpoint releases its segment.
+    wal.checkpoint::<String, Vec<u8>>().unwrap();
+    assert!(!tmp.path().join("wal-00000001.log").exists());
+}
+
+/// A transaction left open by an earlier instance can never commit, so it
+/// does not hold truncation back.
+#[tokio::test]
+async fn checkpoint_releases_transactions_abandoned_by_a_crash() {
+    let tmp = TempDir::new().unwrap();
+    let config = test_config(tmp.path()).with_max_segment_size(4096);
+    let (mut wal, factory) = Wal::open::<String, Vec<u8>>(config.clone(), RealIo).unwrap();
+    let writer = factory.register().unwrap();
+    writer.append(insert(next_tx_id(), "abandoned")).await.unwrap();
+    for i in 0..30 {
+        let mut tx = Transaction::new();
+        tx.insert(format!("key-{i}"), vec![0u8; 512]);
+        tx.commit(&writer).await.unwrap();
+    }
+    wal.shutdown().await.unwrap();
+
+    let (mut wal, factory) = Wal::open::<String, Vec<u8>>(config, RealIo).unwrap();
+    let writer = factory.register().unwrap();
+    let mut tx = Transaction::new();
+    tx.insert("after".to_string(), b"v".to_vec());
+    tx.commit(&writer).await.unwrap();
+    wal.checkpoint::<String, Vec<u8>>().unwrap();
+    wal.shutdown().await.unwrap();
+    assert!(!tmp.path().join("wal-00000001.log").exists());
+}
+
+#[tokio::test]
+async fn checkpoint_and_truncation_use_lsns() {
+    let tmp = TempDir::new().unwrap();
+    let config = test_config(tmp.path()).with_max_segment_size(4096);
+    let (mut wal, factory) = Wal::open::<String, Vec<u8>>(config, RealIo).unwrap();
+    let writer = factory.register().unwrap();
+    for i in 0..40 {
+        let mut tx = Transaction::new();
+        tx.insert(format!("key-{i}"), vec![0u8; 512]);
+        tx.commit(&writer).await.unwrap();
+    }
+    wal.shutdown().await.unwrap();
+
+    let (recovered, _) = recover::<String, Vec<u8>, _>(tmp.path(), &RealIo).unwrap();
+    let last_commit = recovered.iter().map(|t| t.last_lsn).max().unwrap();
+    let lsn = wal.checkpoint::<String, Vec<u8>>().unwrap();
+    assert_eq!(lsn, last_commit);
+    assert_eq!(read_checkpoint(tmp.path(), &RealIo).unwrap(), last_commit);
+
+    // Everything before the checkpoint was truncated; the last segment stays.
+    let (_, stats) = recover::<String, Vec<u8>, _>(tmp.path(), &RealIo).unwrap();
+    assert!(stats.committed >= 1 && stats.committed < 40, "{stats:?}");
+    assert_eq!(stats.max_lsn, last_commit);
+
+    // Reopening after truncation still resumes past the checkpoint.
+    let (mut wal, _factory) =
+        Wal::open::<String, Vec<u8>>(test_config(tmp.path()), RealIo).unwrap();
+    assert_eq!(wal.current_lsn(), last_commit + 1);
+    wal.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn version1_segments_still_recover() {
+    let tmp = TempDir::new().unwrap();
+    let entries: Vec<ByteWalEntry> = vec![
+        WalEntry::Insert { tx_id: 7, timestamp: 0, key: "old".into(), value: b"v1".to_vec() },
+        WalEntry::Commit { tx_id: 7, timestamp: 0 },
+    ];
+    let mut bytes = Vec::new();
+    for entry in &entries {
+        let data = bincode::serialize(entry).unwrap();
+        let header = WalEntryHeader::new_v1(&data);
+        bytes.extend_from_slice(&header.to_bytes()[..WalEntryHeader::V1_SIZE]);
+        bytes.extend_from_slice(&data);
+    }
+    std::fs::write(tmp.path().join("wal-00000001.log"), bytes).unwrap();
+    // Legacy checkpoint: a bare u64 transaction ID
+    std::fs::write(tmp.path().join("checkpoint"), 7u64.to_le_bytes()).unwrap();
+    assert_eq!(read_checkpoint(tmp.path(), &RealIo).unwrap(), 0);
+
+    // Append version-2 entries after the version-1 segment
+    let (mut wal, factory) = Wal::open::<String, Vec<u8>>(test_config(tmp.path()), RealIo).unwrap();
+    assert_eq!(wal.current_lsn(), 1);
+    let writer = factory.register().unwrap();
+    let mut tx = Transaction::new();
+    tx.insert("new".to_string(), b"v2".to_vec());
+    tx.commit(&writer).await.unwrap();
+    wal.shutdown().await.unwrap();
+
+    let (recovered, stats) = recover::<String, Vec<u8>, _>(tmp.path(), &RealIo).unwrap();
+    assert_eq!(stats.committed, 2);
+    assert_eq!(stats.checksum_failures + stats.partial_writes, 0);
+    let old = recovered.iter().find(|t| t.tx_id == 7).unwrap();
+    assert_eq!(old.last_lsn, 0);
+
+    // The version-1 segment goes with the first checkpoint past it.
+    let lsn = wal.checkpoint::<String, Vec<u8>>().unwrap();
+    assert!(lsn > 0);
+    let (mut wal, _factory) =
+        Wal::open::<String, Vec<u8>>(test_config(tmp.path()), RealIo).unwrap();
+    wal.shutdown().await.unwrap();
+    assert_eq!(truncate_segments_before(tmp.path(), lsn + 1, &RealIo).unwrap(), 2);
+    assert!(!tmp.path().join("wal-00000001.log").exists());
+}
+
+async fn next_commit<S>(subscription: &mut S) -> Option<ringwal::RecoveredTransaction<String, Vec<u8>>>
+where
+    S: ringmpsc_stream::StreamExt<Item = ringwal::RecoveredTransaction<String, Vec<u8>>> + Unpin,
+{
+    tokio::time::timeout(std::time::Duration::from_secs(5), subscription.next())
+        .await
+        .expect("subscription stalled")
+}
+
+#[tokio::test]
+async fn subscribe_replays_then_follows_commits() {
+    let tmp = TempDir::new().unwrap();
+    let (mut wal, factory) =
+        Wal::open::<String, Vec<u8>>(test_config(tmp.path()), RealIo).unwrap();
+    let writer = factory.register().unwrap();
+
+    let mut expected = Vec::new();
+    for i in 0..3 {
+        let mut tx = Transaction::new();
+        tx.insert(format!("old-{i}"), b"v".to_vec());
+        expected.push(tx.id);
+        tx.commit(&writer).await.unwrap();
+    }
+    let mut subscription = wal.subscribe::<String, Vec<u8>>(0).unwrap();
+
+    // Live commits, with an abort in between that is never yielded
+    let mut aborted = Transaction::new();
+    aborted.insert("gone".into(), b"v".to_vec());
+    aborted.abort(&writer).await.unwrap();
+    for i in 0..3 {
+        let mut tx = Transaction::new();
+        tx.insert(format!("new-{i}"), b"v".to_vec());
+        expected.push(tx.id);
+        tx.commit(&writer).await.unwrap();
+    }
+
+    let mut seen = Vec::new();
+    let mut lsns = Vec::new();
+    while seen.len() < expected.len() {
+        let tx = next_commit(&mut subscription).await.unwrap();
+        assert_eq!(tx.action, RecoveryAction::Commit);
+        assert_eq!(tx.entries.len(), 1);
+        assert!(tx.last_lsn > lsns.last().copied().unwrap_or(0));
+        lsns.push(tx.last_lsn);
+        seen.push(tx.tx_id);
+    }
+    assert_eq!(seen, expected);
+
+    // Resuming after the third commit replays only the later ones
+    let mut resumed = wal.subscribe::<String, Vec<u8>>(lsns[2] + 1).unwrap();
+
+    wal.shutdown().await.unwrap();
+    let mut rest = Vec::new();
+    while let Some(tx) = next_commit(&mut resumed).await {
+        rest.push(tx.tx_id);
+    }
+    assert_eq!(rest, expected[3..]);
+    assert!(resumed.error().is_none());
+    assert!(next_commit(&mut subscription).await.is_none());
+}
+
+#[tokio::test]
+async fn slow_subscriber_reads_segments_and_holds_truncation() {
+    let tmp = TempDir::new().unwrap();
+    // No in-memory buffer: every entry comes from the segment files
+    let config = test_config(tmp.path())
+        .with_max_segment_size(4096)
+        .with_subscriber_buffer_bytes(0);
+    let (mut wal, factory) = Wal::open::<String, Vec<u8>>(config, RealIo).unwrap();
+    let writer = factory.register().unwrap();
+
+    let mut subscription = wal.subscribe::<String, Vec<u8>>(wal.current_lsn()).unwrap();
+    let mut expected = Vec::new();
+    for i in 0..40 {
+        let mut tx = Transaction::new();
+        tx.insert(format!("key-{i}"), vec![0u8; 512]);
+        expected.push(tx.id);
+        tx.commit(&writer).await.unwrap();
+    }
+
+    // The subscriber has read nothing yet, so no segment may go
+    let lsn = wal.checkpoint::<String, Vec<u8>>().unwrap();
+    assert!(read_segment_header(tmp.path(), 1, &RealIo).unwrap().is_some());
+
+    let mut seen = Vec::new();
+    while seen.len() < expected.len() {
+        seen.push(next_commit(&mut subscription).await.unwrap().tx_id);
+    }
+    assert_eq!(seen, expected);
+
+    // Once it has moved on, truncation catches up
+    let mut tx = Transaction::new();
+    tx.insert("last".into(), vec![0u8; 512]);
+    tx.commit(&writer).await.unwrap();
+    assert!(wal.checkpoint::<String, Vec<u8>>().unwrap() > lsn);
+    assert!(read_segment_header(tmp.path(), 1, &RealIo).unwrap().is_none());
+    assert!(matches!(
+        wal.subscribe::<String, Vec<u8>>(1),
+        Err(WalError::Truncated { requested: 1, .. })
+    ));
+    wal.shutdown().await.unwrap();
+}
+
+/// Resuming past a checkpoint yields a transaction that began before it,
+/// in an earlier segment, with all its entries.
+#[tokio::test]
+async fn subscription_replays_transactions_open_at_start() {
+    let tmp = TempDir::new().unwrap();
+    let config = test_config(tmp.path()).with_max_segment_size(4096);
+    let (mut wal, factory) = Wal::open::<String, Vec<u8>>(config, RealIo).unwrap();
+    let open_writer = factory.register().unwrap();
+    let writer = factory.register().unwrap();
+
+    let a = next_tx_id();
+    open_writer.append(insert(a, "a-head")).await.unwrap();
+    for i in 0..30 {
+        let mut tx = Transaction::new();
+        tx.insert(format!("key-{i}"), vec![0u8; 512]);
+        tx.commit(&writer).await.unwrap();
+    }
+    let lsn = wal.checkpoint::<String, Vec<u8>>().unwrap();
+    open_writer.append(insert(a, "a-tail")).await.unwrap();
+    open_writer.commit(a).await.unwrap();
+
+    let mut subscription = wal.subscribe::<String, Vec<u8>>(lsn + 1).unwrap();
+    let tx = next_commit(&mut subscription).await.unwrap();
+    assert_eq!(tx.tx_id, a);
+    assert_eq!(tx.entries.len(), 2);
+
+    // At or below the checkpoint, truncated segments may have held entries
+    // of what is replayed
+    std::mem::drop(subscription);
+    let mut tx = Transaction::new();
+    tx.insert("last".into(), vec![0u8; 512]);
+    tx.commit(&writer).await.unwrap();
+    let lsn = wal.checkpoint::<String, Vec<u8>>().unwrap();
+    assert!(!tmp.path().join("wal-00000001.log").exists());
+    assert!(matches!(
+        wal.subscribe::<String, Vec<u8>>(lsn),
+        Err(WalError::Truncated { oldest, .. }) if oldest == lsn + 1
+    ));
+    wal.shutdown().await.unwrap();
+}
+
+fn committed_ids(dir: &std::path::Path) -> Vec<u64> {
+    let (recovered, _) = recover::<String, Vec<u8>, _>(dir, &RealIo).unwrap();
+    recovered
+        .into_iter()
+        .filter(|tx| tx.action == RecoveryAction::Commit)
+        .map(|tx| tx.tx_id)
+        .collect()
+}
+
+#[tokio::test]
+async fn replicated_commits_wait_for_follower_over_loopback() {
+    let primary_dir = TempDir::new().unwrap();
+    let follower_dir = TempDir::new().unwrap();
+    let config = test_config(primary_dir.path()).with_replication_acks(1);
+    let (mut wal, factory) = Wal::open::<String, Vec<u8>>(config, RealIo).unwrap();
+    let factory = Arc::new(factory);
+    let writer = factory.register().unwrap();
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let source = wal.replication_source();
+    let listening = tokio::spawn(source.clone().listen(listener, |_| {}));
+
+    // Without a follower the commit is durable locally but does not return
+    let mut first = Transaction::new();
+    first.insert("first".into(), b"v".to_vec());
+    let first_id = first.id;
+    let mut pending = tokio::spawn(async move { first.commit(&writer).await });
+    assert!(
+        tokio::time::timeout(std::time::Duration::from_millis(100), &mut pending)
+            .await
+            .is_err()
+    );
+
+    // The follower catches up from the segment files and acknowledges it
+    let mut follower =
+        ringwal::Follower::open(test_config(follower_dir.path()), RealIo).unwrap();
+    let follower_id = follower.id();
+    let following = tokio::spawn(async move {
+        let result = follower.connect(addr).await;
+        (follower, result)
+    });
+    tokio::time::timeout(std::time::Duration::from_secs(5), pending)
+        .await
+        .expect("commit not acknowledged")
+        .unwrap()
+        .unwrap();
+
+    // Concurrent writers are acknowledged as the follower keeps up
+    let mut handles = Vec::new();
+    for w in 0..2 {
+        let factory = Arc::clone(&factory);
+        handles.push(tokio::spawn(async move {
+            let writer = factory.register().unwrap();
+            for i in 0..10 {
+                let mut tx = Transaction::new();
+                tx.insert(format!("w{w}-{i}"), vec![w as u8; 64]);
+                tx.commit(&writer).await.unwrap();
+            }
+        }));
+    }
+    for handle in handles {
+        handle.await.unwrap();
+    }
+    let last_lsn = wal.current_lsn() - 1;
+    assert_eq!(source.follower_lsns(), [(follower_id, last_lsn)]);
+
+    // Shutting the primary down ends the stream cleanly
+    wal.shutdown().await.unwrap();
+    let (follower, result) = tokio::time::timeout(std::time::Duration::from_secs(5), following)
+        .await
+        .expect("follower did not stop")
+        .unwrap();
+    result.unwrap();
+    assert_eq!(follower.last_lsn(), last_lsn);
+    listening.abort();
+
+    let primary = committed_ids(primary_dir.path());
+    assert_eq!(primary.len(), 21);
+    assert_eq!(primary[0], first_id);
+    assert_eq!(committed_ids(follower_dir.path()), primary);
+}
+
+/// Serves `follower` from `wal` over an in-memory stream until either side
+/// ends.
+async fn follow(
+    wal: &Wal<RealIo>,
+    follower: &mut ringwal::Follower<RealIo>,
+) -> (Result<(), WalError>, Result<(), WalError>) {
+    let (primary_end, follower_end) = tokio::io::duplex(64 * 1024);
+    let serving = tokio::spawn(wal.replication_source().serve(primary_end));
+    let following = follower.run(follower_end).await;
+    (serving.await.unwrap(), following)
+}
+
+#[tokio::test]
+async fn follower_copies_segments_and_must_match_the_primary() {
+    let primary_dir = TempDir::new().unwrap();
+    let follower_dir = TempDir::new().unwrap();
+    let config = test_config(primary_dir.path())
+        .with_max_segment_size(4096)
+        .with_compression(Compression::Lz4)
+        .with_replication_acks(1);
+    let (mut wal, factory) = Wal::open::<String, Vec<u8>>(config, RealIo).unwrap();
+    let writer = factory.register().unwrap();
+
+    let mut follower =
+        ringwal::Follower::open(test_config(follower_dir.path()), RealIo).unwrap();
+    let (primary_end, follower_end) = tokio::io::duplex(64 * 1024);
+    let serving = tokio::spawn(wal.replication_source().serve(primary_end));
+    let following = tokio::spawn(async move {
+        let result = follower.run(follower_end).await;
+        (follower, result)
+    });
+    for i in 0..40 {
+        let mut tx = Transaction::new();
+        let value = (0..200u32).map(|j| (j * 7919 + i * 104_729).to_le_bytes()[1]).collect();
+        tx.insert(format!("key-{i}"), value);
+        tx.commit(&writer).await.unwrap();
+    }
+    let last_lsn = wal.current_lsn() - 1;
+    wal.shutdown().await.unwrap();
+    serving.await.unwrap().unwrap();
+    let (mut follower, result) = following.await.unwrap();
+    result.unwrap();
+    assert_eq!(follower.last_lsn(), last_lsn);
+
+    // The copy holds the primary's bytes: whole sealed segments, and a
+    // prefix of the last one
+    let mut sealed = 0;
+    for entry in std::fs::read_dir(follower_dir.path()).unwrap() {
+        let name = entry.unwrap().file_name().into_string().unwrap();
+        let Some(id) = name.strip_prefix("wal-").and_then(|rest| rest.strip_suffix(".log")) else {
+            continue;
+        };
+        let copy = std::fs::read(follower_dir.path().join(&name)).unwrap();
+        let original = std::fs::read(primary_dir.path().join(&name)).unwrap();
+        assert!(original.starts_with(&copy), "{name} differs from the primary's");
+        if read_segment_footer(follower_dir.path(), id.parse().unwrap(), &RealIo).unwrap().is_some() {
+            assert_eq!(copy.len(), original.len());
+            sealed += 1;
+        }
+    }
+    assert!(sealed >= 2, "only {sealed} sealed segments copied");
+    assert_eq!(committed_ids(follower_dir.path()), committed_ids(primary_dir.path()));
+
+    // A primary of another WAL refuses the follower
+    let other_dir = TempDir::new().unwrap();
+    let (mut other, _factory) =
+        Wal::open::<String, Vec<u8>>(test_config(other_dir.path()), RealIo).unwrap();
+    let (served, followed) = follow(&other, &mut follower).await;
+    assert!(matches!(served, Err(WalError::Replication(_))));
+    assert!(matches!(followed, Err(WalError::Replication(_))));
+    other.shutdown().await.unwrap();
+
+    // So does one of the same WAL that is behind the follower
+    let behind_dir = TempDir::new().unwrap();
+    std::fs::copy(primary_dir.path().join("wal.id"), behind_dir.path().join("wal.id")).unwrap();
+    let (mut behind, _factory) =
+        Wal::open::<String, Vec<u8>>(test_config(behind_dir.path()), RealIo).unwrap();
+    let (served, followed) = follow(&behind, &mut follower).await;
+    match served {
+        Err(WalError::Replication(message)) => assert!(message.contains("ahead"), "{message}"),
+        other => panic!("expected a Replication error, got {other:?}"),
+    }
+    assert!(matches!(followed, Err(WalError::Replication(_))));
+    behind.shutdown().await.unwrap();
+    assert_eq!(follower.last_lsn(), last_lsn);
+}
+
+#[tokio::test]
+async fn replicated_commit_fails_after_the_replication_timeout() {
+    let tmp = TempDir::new().unwrap();
+    let config = test_config(tmp.path())
+        .with_replication_acks(1)
+        .with_replication_timeout(std::time::Duration::from_millis(100));
+    let (mut wal, factory) = Wal::open::<String, Vec<u8>>(config, RealIo).unwrap();
+    let writer = factory.register().unwrap();
+
+    // No follower is connected: the commit gives up instead of waiting
+    // until shutdown, but stays durable locally
+    let mut tx = Transaction::new();
+    tx.insert("k".into(), b"v".to_vec());
+    let id = tx.id;
+    let started = std::time::Instant::now();
+    let result = tokio::time::timeout(std::time::Duration::from_secs(5), tx.commit(&writer))
+        .await
+        .expect("commit waited past the replication timeout");
+    assert!(matches!(
+        result,
+        Err(WalError::NotReplicated { acked: 0, required: 1, .. })
+    ));
+    assert!(started.elapsed() >= std::time::Duration::from_millis(100));
+
+    wal.shutdown().await.unwrap();
+    assert_eq!(committed_ids(tmp.path()), vec![id]);
+}
+
+#[tokio::test]
+async fn record_larger_than_a_frame_is_shipped_in_parts() {
+    let primary_dir = TempDir::new().unwrap();
+    let follower_dir = TempDir::new().unwrap();
+    let config = test_config(primary_dir.path())
+        .with_codec(Codec::Raw)
+        .with_replication_acks(1);
+    let (mut wal, factory) = Wal::open::<String, Vec<u8>>(config, RealIo).unwrap();
+    let writer = factory.register().unwrap();
+
+    let mut follower =
+        ringwal::Follower::open(test_config(follower_dir.path()), RealIo).unwrap();
+    let (primary_end, follower_end) = tokio::io::duplex(1024 * 1024);
+    let serving = tokio::spawn(wal.replication_source().serve(primary_end));
+    let following = tokio::spawn(async move {
+        let result = follower.run(follower_end).await;
+        (follower, result)
+    });
+
+    // Over the 64 MiB frame limit: acknowledged once all of it arrived
+    let mut big = Transaction::new();
+    big.insert("big".into(), vec![0x5A; 65 * 1024 * 1024]);
+    big.commit(&writer).await.unwrap();
+    let mut small = Transaction::new();
+    small.insert("small".into(), b"v".to_vec());
+    small.commit(&writer).await.unwrap();
+
+    let last_lsn = wal.current_lsn() - 1;
+    wal.shutdown().await.unwrap();
+    serving.await.unwrap().unwrap();
+    let (follower, result) = following.await.unwrap();
+    result.unwrap();
+    assert_eq!(follower.last_lsn(), last_lsn);
+    assert_eq!(committed_ids(follower_dir.path()), committed_ids(primary_dir.path()));
+}
+
+#[tokio::test]
+async fn follower_open_cuts_a_torn_tail_in_place() {
+    let primary_dir = TempDir::new().unwrap();
+    let follower_dir = TempDir::new().unwrap();
+    let (mut wal, factory) =
+        Wal::open::<String, Vec<u8>>(test_config(primary_dir.path()), RealIo).unwrap();
+    let writer = factory.register().unwrap();
+    let mut follower =
+        ringwal::Follower::open(test_config(follower_dir.path()), RealIo).unwrap();
+    let (primary_end, follower_end) = tokio::io::duplex(64 * 1024);
+    let serving = tokio::spawn(wal.replication_source().serve(primary_end));
+    let following = tokio::spawn(async move {
+        let result = follower.run(follower_end).await;
+        (follower, result)
+    });
+    for i in 0..3 {
+        let mut tx = Transaction::new();
+        tx.insert(format!("key-{i}"), b"v".to_vec());
+        tx.commit(&writer).await.unwrap();
+    }
+    wal.shutdown().await.unwrap();
+    serving.await.unwrap().unwrap();
+    let (follower, result) = following.await.unwrap();
+    result.unwrap();
+    let last_lsn = follower.last_lsn();
+    drop(follower);
+
+    // A crash mid-append leaves half a record behind the copy
+    let segment = follower_dir.path().join("wal-00000001.log");
+    let copied = std::fs::read(&segment).unwrap();
+    let mut file = std::fs::OpenOptions::new().append(true).open(&segment).unwrap();
+    std::io::Write::write_all(&mut file, &[0xAB; 37]).unwrap();
+    drop(file);
+
+    let follower = ringwal::Follower::open(test_config(follower_dir.path()), RealIo).unwrap();
+    assert_eq!(follower.last_lsn(), last_lsn);
+    assert_eq!(std::fs::read(&segment).unwrap(), copied);
+}
+
+#[tokio::test]
+async fn codecs_can_change_between_opens() {
+    let tmp = TempDir::new().unwrap();
+    let codecs = [Codec::Bincode, Codec::Postcard, Codec::Json, Codec::Raw];
+    let mut expected = Vec::new();
+    for codec in codecs {
+        let config = test_config(tmp.path()).with_codec(codec);
+        let (mut wal, factory) = Wal::open::<String, Vec<u8>>(config, RealIo).unwrap();
+        let writer = factory.register().unwrap();
+        let mut tx = Transaction::new();
+        tx.insert(format!("{codec:?}"), vec![codec.id(); 3]);
+        tx.delete(format!("{codec:?}-old"), b"old".to_vec());
+        expected.push(tx.id);
+        tx.commit(&writer).await.unwrap();
+        wal.shutdown().await.unwrap();
+    }
+
+    // Every entry is decoded with the codec recorded in its header
+    let (recovered, stats) = recover::<String, Vec<u8>, _>(tmp.path(), &RealIo).unwrap();
+    assert_eq!(stats.committed, codecs.len());
+    assert_eq!(recovered.iter().map(|tx| tx.tx_id).collect::<Vec<_>>(), expected);
+    for (tx, codec) in recovered.iter().zip(codecs) {
+        match &tx.entries[0] {
+            WalEntry::Insert { key, value, .. } => {
+                assert_eq!(key, &format!("{codec:?}"));
+                assert_eq!(value, &vec![codec.id(); 3]);
+            }
+            other => panic!("expected an insert, got {other:?}"),
+        }
+        assert!(matches!(&tx.entries[1], WalEntry::Delete { key, .. } if key.ends_with("-old")));
+    }
+}
+
+#[tokio::test]
+async fn raw_codec_rejects_non_byte_types() {
+    let tmp = TempDir::new().unwrap();
+    let config = test_config(tmp.path()).with_codec(Codec::Raw);
+    let (mut wal, factory) = Wal::open::<u64, Vec<u8>>(config, RealIo).unwrap();
+    let writer = factory.register().unwrap();
+
+    let mut tx = Transaction::new();
+    tx.insert(7, b"v".to_vec());
+    assert!(matches!(
+        tx.commit(&writer).await,
+        Err(WalError::Codec { codec: Codec::Raw, .. })
+    ));
+    wal.shutdown().await.unwrap();
+}
+
+/// JSON through `erased_serde`, as an application would plug in a format.
+struct JsonCustom;
+
+impl CustomCodec for JsonCustom {
+    fn encode(&self, entry: &dyn erased_serde::Serialize) -> Result<Vec<u8>, CodecError> {
+        Ok(serde_json::to_vec(entry)?)
+    }
+
+    fn decode(&self, data: &[u8], visit: &mut EntryVisitor<'_>) -> Result<(), CodecError> {
+        let mut deserializer = serde_json::Deserializer::from_slice(data);
+        visit(&mut <dyn erased_serde::Deserializer>::erase(&mut deserializer))?;
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn custom_codecs_write_and_read_entries_under_their_id() {
+    let tmp = TempDir::new().unwrap();
+    let config = test_config(tmp.path())
+        .with_custom_codec(8, Arc::new(JsonCustom))
+        .with_codec(Codec::Custom(8));
+    let custom_codecs = config.custom_codecs.clone();
+    let (mut wal, factory) = Wal::open::<String, Vec<u8>>(config, RealIo).unwrap();
+    let writer = factory.register().unwrap();
+    let mut subscription = wal.subscribe::<String, Vec<u8>>(wal.current_lsn()).unwrap();
+
+    let mut tx = Transaction::new();
+    tx.insert("k".into(), b"v".to_vec());
+    let tx_id = tx.id;
+    tx.commit(&writer).await.unwrap();
+    assert_eq!(next_commit(&mut subscription).await.unwrap().tx_id, tx_id);
+    assert!(wal.checkpoint::<String, Vec<u8>>().is_ok());
+    drop(subscription);
+    wal.shutdown().await.unwrap();
+
+    // Entries name their codec: without it they are not mistaken for corrupt
+    assert!(matches!(
+        recover::<String, Vec<u8>, _>(tmp.path(), &RealIo),
+        Err(WalError::Codec { codec: Codec::Custom(8), .. })
+    ));
+    let recovered = RecoveryReader::<String, Vec<u8>, _>::new(tmp.path(), &RealIo)
+        .unwrap()
+        .with_custom_codecs(custom_codecs)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(recovered.len(), 1);
+    assert!(matches!(
+        &recovered[0].entries[0],
+        WalEntry::Insert { key, value, .. } if key == "k" && value == b"v"
+    ));
+
+    // Selecting a codec that is not registered fails the open
+    let config = test_config(tmp.path()).with_codec(Codec::Custom(9));
+    assert!(matches!(
+        Wal::open::<String, Vec<u8>>(config, RealIo),
+        Err(WalError::Codec { codec: Codec::Custom(9), .. })
+    ));
+}
+
+fn json_document(i: usize) -> Vec<u8> {
+    format!(r#"{{"id":{i},"kind":"order","status":"pending","items":[{{"sku":"A-1","qty":1}},{{"sku":"A-2","qty":2}}]}}"#)
+        .into_bytes()
+}
+
+#[tokio::test]
+async fn compressed_batches_recover_and_report_stats() {
+    for compression in [Compression::Lz4, Compression::Zstd { level: 3 }] {
+        let tmp = TempDir::new().unwrap();
+        let config = test_config(tmp.path())
+            .with_max_segment_size(8192)
+            .with_compression(compression);
+        let (mut wal, factory) = Wal::open::<String, Vec<u8>>(config, RealIo).unwrap();
+        let writer = factory.register().unwrap();
+        let mut expected = Vec::new();
+        for i in 0..100 {
+            let mut tx = Transaction::new();
+            for j in 0..4 {
+                tx.insert(format!("order-{i}-{j}"), json_document(i * 4 + j));
+            }
+            expected.push(tx.id);
+            tx.commit(&writer).await.unwrap();
+        }
+        let stats = wal.compression_stats();
+        let mut subscription = wal.subscribe::<String, Vec<u8>>(0).unwrap();
+        wal.shutdown().await.unwrap();
+
+        assert!(stats.batches > 0);
+        assert!(stats.ratio() > 2.0, "{compression:?}: {stats:?}");
+        assert!(stats.written_bytes < stats.uncompressed_bytes);
+
+        // Each batch is one frame right after the segment header
+        let bytes = std::fs::read(tmp.path().join("wal-00000001.log")).unwrap();
+        assert!(CompressedFrameHeader::is_frame(bytes[SegmentHeader::SIZE + 12]));
+        assert_eq!(bytes[SegmentHeader::SIZE + 12] >> 4, compression.id());
+
+        let (recovered, stats) = recover::<String, Vec<u8>, _>(tmp.path(), &RealIo).unwrap();
+        assert_eq!(stats.checksum_failures + stats.partial_writes, 0);
+        assert_eq!(recovered.iter().map(|tx| tx.tx_id).collect::<Vec<_>>(), expected);
+        match &recovered[99].entries[3] {
+            WalEntry::Insert { key, value, .. } => {
+                assert_eq!(key, "order-99-3");
+                assert_eq!(value, &json_document(399));
+            }
+            other => panic!("expected an insert, got {other:?}"),
+        }
+
+        // A subscription reading the segments resumes inside frames
+        let mut seen = Vec::new();
+        while let Some(tx) = next_commit(&mut subscription).await {
+            seen.push(tx.tx_id);
+        }
+        assert_eq!(seen, expected);
+    }
+}
+
+#[tokio::test]
+async fn compression_can_change_between_opens() {
+    let tmp = TempDir::new().unwrap();
+    let mut expected = Vec::new();
+    for compression in [Compression::None, Compression::Lz4, Compression::Zstd { level: 1 }, Compression::None] {
+        let config = test_config(tmp.path()).with_compression(compression);
+        let (mut wal, factory) = Wal::open::<String, Vec<u8>>(config, RealIo).unwrap();
+        let writer = factory.register().unwrap();
+        for i in 0..5 {
+            let mut tx = Transaction::new();
+            tx.insert(format!("{compression:?}-{i}"), json_document(i));
+            expected.push(tx.id);
+            tx.commit(&writer).await.unwrap();
+        }
+        wal.shutdown().await.unwrap();
+    }
+
+    // Plain entries and frames of either kind are read back in LSN order,
+    // by recovery and by a subscription reading the segments
+    assert_eq!(committed_ids(tmp.path()), expected);
+    let (mut wal, _factory) = Wal::open::<String, Vec<u8>>(test_config(tmp.path()), RealIo).unwrap();
+    let mut subscription = wal.subscribe::<String, Vec<u8>>(0).unwrap();
+    wal.shutdown().await.unwrap();
+    let mut seen = Vec::new();
+    while let Some(tx) = next_commit(&mut subscription).await {
+        seen.push(tx.tx_id);
+    }
+    assert_eq!(seen, expected);
+}
+
+#[tokio::test]
+async fn corrupt_frame_stops_recovery() {
+    let tmp = TempDir::new().unwrap();
+    let config = test_config(tmp.path()).with_compression(Compression::Lz4);
+    let (mut wal, factory) = Wal::open::<String, Vec<u8>>(config, RealIo).unwrap();
+    let writer = factory.register().unwrap();
+    for i in 0..2 {
+        let mut tx = Transaction::new();
+        for j in 0..4 {
+            tx.insert(format!("{i}-{j}"), json_document(j));
+        }
+        tx.commit(&writer).await.unwrap();
+    }
+    wal.shutdown().await.unwrap();
+
+    // Flip a byte in the last frame's payload
+    let path = tmp.path().join("wal-00000001.log");
+    let mut bytes = std::fs::read(&path).unwrap();
+    assert!(CompressedFrameHeader::is_frame(bytes[SegmentHeader::SIZE + 12]));
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0xff;
+    std::fs::write(&path, &bytes).unwrap();
+
+    let (recovered, stats) = recover::<String, Vec<u8>, _>(tmp.path(), &RealIo).unwrap();
+    assert_eq!(stats.checksum_failures, 1);
+    assert_eq!(stats.committed, 1);
+    assert_eq!(recovered[0].entries.len(), 4);
+}
+
+fn encrypted_io(cipher: Cipher, keys: &Arc<KeyRing>) -> EncryptedIo<RealIo> {
+    EncryptedIo::new(RealIo, Encryption::new(cipher, keys.clone()))
+}
+
+/// Commits `count` transactions of four JSON documents each, keyed
+/// `{prefix}{i}-{j}`.
+async fn commit_documents<IO: ringwal::IoEngine>(config: WalConfig, io: IO, prefix: &str, count: usize) {
+    let (mut wal, factory) = Wal::open::<String, Vec<u8>>(config, io).unwrap();
+    let writer = factory.register().unwrap();
+    for i in 0..count {
+        let mut tx = Transaction::new();
+        for j in 0..4 {
+            tx.insert(format!("{prefix}{i}-{j}"), json_document(j));
+        }
+        tx.commit(&writer).await.unwrap();
+    }
+    wal.shutdown().await.unwrap();
+}
+
+/// Offsets of the encrypted frames of a segment file.
+fn encrypted_frames(bytes: &[u8]) -> Vec<usize> {
+    let mut frames = Vec::new();
+    let mut offset = SegmentHeader::SIZE;
+    while offset + EncryptedFrameHeader::SIZE <= bytes.len()
+        && EncryptedFrameHeader::is_frame(bytes[offset + 12])
+    {
+        frames.push(offset);
+        let length = u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap());
+        offset += EncryptedFrameHeader::SIZE + length as usize;
+    }
+    frames
+}
+
+#[tokio::test]
+async fn encrypted_segments_recover_with_each_cipher() {
+    for (cipher, compression) in [
+        (Cipher::Aes256Gcm, Compression::None),
+        (Cipher::ChaCha20Poly1305, Compression::Lz4),
+    ] {
+        let tmp = TempDir::new().unwrap();
+        let keys = Arc::new(KeyRing::new(1, [0x5a; 32]));
+        let io = encrypted_io(cipher, &keys);
+        let config = test_config(tmp.path())
+            .with_max_segment_size(8192)
+            .with_compression(compression);
+        commit_documents(config.clone(), io.clone(), "secret-", 50).await;
+
+        // Nothing written is readable without the key
+        let segments: Vec<_> = std::fs::read_dir(tmp.path())
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "log"))
+            .collect();
+        assert!(segments.len() > 1, "{cipher:?}");
+        for path in &segments {
+            let bytes = std::fs::read(path).unwrap();
+            assert!(!bytes.windows(7).any(|w| w == b"secret-"), "{cipher:?}");
+            assert!(!bytes.windows(5).any(|w| w == b"order"), "{cipher:?}");
+            assert!(!encrypted_frames(&bytes).is_empty());
+        }
+
+        let (recovered, stats) = recover::<String, Vec<u8>, _>(tmp.path(), &io).unwrap();
+        assert_eq!(stats.committed, 50, "{cipher:?}");
+        assert_eq!((stats.checksum_failures, stats.tampered, stats.partial_writes), (0, 0, 0));
+        assert_eq!(recovered[49].entries.len(), 4);
+
+        // Reopening continues the log, and subscriptions decrypt too
+        let (mut wal, _factory) = Wal::open::<String, Vec<u8>>(config, io).unwrap();
+        let mut sub = wal.subscribe::<String, Vec<u8>>(1).unwrap();
+        let tx = next_commit(&mut sub).await.unwrap();
+        assert_eq!(tx.entries.len(), 4);
+        drop(sub);
+        wal.shutdown().await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn rotated_keys_keep_older_segments_readable() {
+    let tmp = TempDir::new().unwrap();
+    let keys = Arc::new(KeyRing::new(1, [1; 32]));
+    let io = encrypted_io(Cipher::Aes256Gcm, &keys);
+    commit_documents(test_config(tmp.path()), io.clone(), "a", 3).await;
+    keys.rotate(2, [2; 32]);
+    commit_documents(test_config(tmp.path()), io.clone(), "b", 3).await;
+
+    let (recovered, stats) = recover::<String, Vec<u8>, _>(tmp.path(), &io).unwrap();
+    assert_eq!(stats.committed, 6);
+    assert_eq!(recovered.len(), 6);
+
+    // Without the retired key, or without encryption, the log cannot be
+    // read — rather than seeming to end early
+    let only_new = Arc::new(KeyRing::new(2, [2; 32]));
+    assert!(matches!(
+        recover::<String, Vec<u8>, _>(tmp.path(), &encrypted_io(Cipher::Aes256Gcm, &only_new)),
+        Err(WalError::Encryption(_))
+    ));
+    assert!(matches!(
+        recover::<String, Vec<u8>, _>(tmp.path(), &RealIo),
+        Err(WalError::Encryption(_))
+    ));
+    assert!(matches!(
+        Wal::open::<String, Vec<u8>>(test_config(tmp.path()), RealIo),
+        Err(WalError::Encryption(_))
+    ));
+}
+
+#[tokio::test]
+async fn recovery_spill_files_are_encrypted() {
+    let tmp = TempDir::new().unwrap();
+    let keys = Arc::new(KeyRing::new(1, [5; 32]));
+    let io = encrypted_io(Cipher::Aes256Gcm, &keys);
+    let (mut wal, factory) = Wal::open::<String, Vec<u8>>(test_config(tmp.path()), io.clone()).unwrap();
+    let long_writer = factory.register().unwrap();
+    let writer = factory.register().unwrap();
+    let long = next_tx_id();
+    for i in 0..3 {
+        long_writer.append(insert(long, &format!("secret-{i}"))).await.unwrap();
+        let mut tx = Transaction::new();
+        tx.insert(format!("short-{i}"), b"v".to_vec());
+        tx.commit(&writer).await.unwrap();
+    }
+    long_writer.commit(long).await.unwrap();
+    wal.shutdown().await.unwrap();
+
+    // Look at the long transaction's spill file while it is still open
+    let path = tmp.path().join(format!("recovery-spill-{long}.tmp"));
+    let mut reader = RecoveryReader::<String, Vec<u8>, _>::new(tmp.path(), &io)
+        .unwrap()
+        .with_spill_threshold(1);
+    let mut spilled = None;
+    let mut recovered = Vec::new();
+    for tx in reader.by_ref() {
+        if spilled.is_none() && path.exists() {
+            spilled = Some(std::fs::read(&path).unwrap());
+        }
+        recovered.push(tx.unwrap());
+    }
+    let spilled = spilled.expect("the long transaction was never spilled");
+    assert!(!spilled.windows(6).any(|w| w == b"secret"));
+
+    let long_tx = recovered.iter().find(|tx| tx.tx_id == long).unwrap();
+    assert_eq!(long_tx.action, RecoveryAction::Commit);
+    assert!(matches!(&long_tx.entries[2], WalEntry::Insert { key, .. } if key == "secret-2"));
+}
+
+#[tokio::test]
+async fn tampering_is_reported_apart_from_torn_writes() {
+    let tmp = TempDir::new().unwrap();
+    let keys = Arc::new(KeyRing::new(1, [9; 32]));
+    let io = encrypted_io(Cipher::ChaCha20Poly1305, &keys);
+    commit_documents(test_config(tmp.path()), io.clone(), "k", 2).await;
+
+    let path = tmp.path().join("wal-00000001.log");
+    let original = std::fs::read(&path).unwrap();
+    let frames = encrypted_frames(&original);
+    assert!(frames.len() >= 2);
+
+    // Modify the last frame's ciphertext and fix up its checksum
+    let last = *frames.last().unwrap();
+    let mut bytes = original.clone();
+    bytes[last + EncryptedFrameHeader::SIZE] ^= 1;
+    let length = u64::from_le_bytes(bytes[last..last + 8].try_into().unwrap()) as usize;
+    let mut crc = crc32fast::Hasher::new();
+    crc.update(&bytes[last + 12..last + EncryptedFrameHeader::SIZE + length]);
+    bytes[last + 8..last + 12].copy_from_slice(&crc.finalize().to_le_bytes());
+    std::fs::write(&path, &bytes).unwrap();
+    let mut reader = RecoveryReader::<String, Vec<u8>, _>::new(tmp.path(), &io).unwrap();
+    assert!(matches!(reader.by_ref().last(), Some(Err(WalError::Tampered(_)))));
+    assert_eq!((reader.stats().tampered, reader.stats().checksum_failures), (1, 0));
+
+    // Without the checksum fix-up it reads as corruption
+    bytes[last + 8] ^= 1;
+    std::fs::write(&path, &bytes).unwrap();
+    let (_, stats) = recover::<String, Vec<u8>, _>(tmp.path(), &io).unwrap();
+    assert_eq!((stats.tampered, stats.checksum_failures), (0, 1));
+
+    // A torn last frame is a partial write
+    std::fs::write(&path, &original[..last + EncryptedFrameHeader::SIZE + 4]).unwrap();
+    let (_, stats) = recover::<String, Vec<u8>, _>(tmp.path(), &io).unwrap();
+    assert_eq!((stats.tampered, stats.partial_writes), (0, 1));
+}
+
+#[tokio::test]
+async fn encrypted_checkpoint_roundtrip_and_tampering() {
+    let tmp = TempDir::new().unwrap();
+    let keys = Arc::new(KeyRing::new(1, [3; 32]));
+    let io = encrypted_io(Cipher::Aes256Gcm, &keys);
+
+    // A plaintext checkpoint is still read, then replaced by an encrypted one
+    write_checkpoint(tmp.path(), 7, &RealIo).unwrap();
+    assert_eq!(read_checkpoint(tmp.path(), &io).unwrap(), 7);
+    write_checkpoint(tmp.path(), 42, &io).unwrap();
+    assert_eq!(read_checkpoint(tmp.path(), &io).unwrap(), 42);
+    let path = tmp.path().join("checkpoint");
+    let mut bytes = std::fs::read(&path).unwrap();
+    assert!(!bytes.windows(8).any(|w| w == 42u64.to_le_bytes()));
+
+    assert!(matches!(read_checkpoint(tmp.path(), &RealIo), Err(WalError::Encryption(_))));
+    let last = bytes.len() - 1;
+    bytes[last] ^= 1;
+    std::fs::write(&path, &bytes).unwrap();
+    assert!(matches!(read_checkpoint(tmp.path(), &io), Err(WalError::Tampered(_))));
+}
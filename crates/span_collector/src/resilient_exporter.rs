@@ -620,7 +620,7 @@ mod tests {
             base,
             CircuitBreakerConfig {
                 failure_threshold: 3,
-                reset_timeout: Duration::from_secs(60),
+                reset_timeout: Duration::from_mins(1),
                 success_threshold: 1,
             },
         );